@@ -0,0 +1,308 @@
+//! Players for any [`GameState`]: a human at the terminal, minimax for tic-tac-toe at a chosen
+//! [`Skill`], perfect Nim play, uniformly random moves, and Monte Carlo tree search. Front ends hold one [`Agent`] per
+//! side, so any pair can play each other.
+
+use crate::impartial::{Nim, Take};
+use crate::mcts::{Mcts, Playout};
+use crate::search::{DepthLimitedSearch, GameState, ZeroEvaluator};
+use crate::tictactoe::{Board, SolutionTable};
+
+use std::fmt::Display;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Chooses moves for one side of a game
+pub trait Agent<G: GameState> {
+    /// One of the legal moves of `state`, which must not be a finished game
+    fn choose_move(&mut self, state: &G) -> G::Move;
+}
+
+/// Reads moves from a reader, by default standard input, asking again until the move is legal
+pub struct HumanAgent<R> {
+    reader: R,
+}
+
+impl HumanAgent<std::io::StdinLock<'static>> {
+    pub fn stdin() -> Self {
+        HumanAgent {
+            reader: std::io::stdin().lock(),
+        }
+    }
+}
+
+impl<R: BufRead> HumanAgent<R> {
+    pub fn new(reader: R) -> Self {
+        HumanAgent { reader }
+    }
+}
+
+impl<G, R> Agent<G> for HumanAgent<R>
+where
+    G: GameState,
+    G::Move: FromStr + Display,
+    R: BufRead,
+{
+    /// Panics if the reader ends before a legal move is read
+    fn choose_move(&mut self, state: &G) -> G::Move {
+        let moves = state.legal_moves();
+        loop {
+            let mut input_buffer = String::new();
+            match self.reader.read_line(&mut input_buffer) {
+                Ok(0) | Err(_) => panic!("No more input"),
+                Ok(_) => {}
+            }
+            match input_buffer.trim().parse::<G::Move>() {
+                Ok(x) if moves.contains(&x) => return x,
+                Ok(x) => println!("Invalid move: {} is not legal", x),
+                Err(_) => println!("Invalid move: {}", input_buffer.trim()),
+            }
+        }
+    }
+}
+
+/// How well a [`MinimaxAgent`] plays
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Skill {
+    /// Always the exact minimax move
+    Perfect,
+    /// A random legal move with this probability, and otherwise the minimax move
+    Epsilon(f64),
+    /// The best move searching only this many moves ahead, blind to wins and losses beyond that
+    Depth(usize),
+}
+
+impl FromStr for Skill {
+    type Err = String;
+
+    /// `easy`, `medium`, `hard` or `perfect`, or a skill given exactly as `epsilon=0.3` or
+    /// `depth=2`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "easy" => Ok(Skill::Epsilon(0.5)),
+            "medium" => Ok(Skill::Depth(2)),
+            "hard" => Ok(Skill::Epsilon(0.1)),
+            "perfect" => Ok(Skill::Perfect),
+            _ => match s.split_once('=') {
+                Some(("epsilon", x)) => match x.parse::<f64>() {
+                    Ok(x) if (0. ..=1.).contains(&x) => Ok(Skill::Epsilon(x)),
+                    _ => Err(format!("Invalid epsilon: {}", x)),
+                },
+                Some(("depth", x)) => x
+                    .parse()
+                    .map(Skill::Depth)
+                    .map_err(|_| format!("Invalid depth: {}", x)),
+                _ => Err(format!("Unknown skill: {}", s)),
+            },
+        }
+    }
+}
+
+/// Plays minimax moves at a [`Skill`], from a solution table shared across games
+pub struct MinimaxAgent {
+    pub table: SolutionTable,
+    pub skill: Skill,
+    rng: StdRng,
+}
+
+impl Default for MinimaxAgent {
+    fn default() -> Self {
+        MinimaxAgent::new(SolutionTable::default())
+    }
+}
+
+impl MinimaxAgent {
+    /// Perfect play with the table
+    pub fn new(table: SolutionTable) -> Self {
+        MinimaxAgent {
+            table,
+            skill: Skill::Perfect,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    pub fn with_skill(mut self, skill: Skill) -> Self {
+        self.skill = skill;
+        self
+    }
+
+    /// Seeds the random moves of [`Skill::Epsilon`]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+}
+
+impl Agent<Board> for MinimaxAgent {
+    fn choose_move(&mut self, state: &Board) -> usize {
+        match self.skill {
+            Skill::Perfect => self.table.solve(state),
+            Skill::Epsilon(epsilon) => match self.rng.gen::<f64>() < epsilon {
+                true => *state
+                    .valid_moves()
+                    .choose(&mut self.rng)
+                    .expect("No legal moves"),
+                false => self.table.solve(state),
+            },
+            Skill::Depth(depth) => DepthLimitedSearch::new(ZeroEvaluator, depth)
+                .best_move(state)
+                .map(|x| x.0)
+                .expect("No legal moves"),
+        }
+    }
+}
+
+/// Plays Nim perfectly, leaving a nim sum of 0 when it can and otherwise taking a single item from
+/// the largest pile, to leave the opponent the most room to go wrong
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NimAgent;
+
+impl Agent<Nim> for NimAgent {
+    fn choose_move(&mut self, state: &Nim) -> Take {
+        state.winning_move().unwrap_or_else(|| {
+            let (pile, _) = state
+                .piles
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, x)| **x)
+                .filter(|(_, x)| **x > 0)
+                .expect("No legal moves");
+            Take { pile, count: 1 }
+        })
+    }
+}
+
+/// Plays uniformly random legal moves
+pub struct RandomAgent {
+    rng: StdRng,
+}
+
+impl RandomAgent {
+    /// Seeded from entropy if `seed` is None
+    pub fn new(seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        RandomAgent { rng }
+    }
+}
+
+impl<G: GameState> Agent<G> for RandomAgent {
+    fn choose_move(&mut self, state: &G) -> G::Move {
+        *state
+            .legal_moves()
+            .choose(&mut self.rng)
+            .expect("No legal moves")
+    }
+}
+
+/// Plays the most visited move of a Monte Carlo tree search
+pub struct MctsAgent<P> {
+    pub mcts: Mcts<P>,
+}
+
+impl<P> MctsAgent<P> {
+    pub fn new(mcts: Mcts<P>) -> Self {
+        MctsAgent { mcts }
+    }
+}
+
+impl<G: GameState, P: Playout<G>> Agent<G> for MctsAgent<P> {
+    fn choose_move(&mut self, state: &G) -> G::Move {
+        self.mcts.best_move(state).expect("No legal moves")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcts::{MctsConfig, RandomPlayout};
+    use crate::search::Outcome;
+
+    /// Plays a game of tic-tac-toe to the end
+    fn play(x: &mut dyn Agent<Board>, o: &mut dyn Agent<Board>) -> Option<Outcome> {
+        let mut board = Board::default();
+        while board.outcome().is_none() {
+            let action = match board.current_player() {
+                0 => x.choose_move(&board),
+                _ => o.choose_move(&board),
+            };
+            board.act(action).unwrap();
+        }
+        board.outcome()
+    }
+
+    #[test]
+    fn test_agents() {
+        let mut minimax = MinimaxAgent::default();
+        let mut other = MinimaxAgent::default();
+        assert_eq!(play(&mut minimax, &mut other), Some(Outcome::Draw));
+
+        // Random play never beats minimax
+        let mut random = RandomAgent::new(Some(0));
+        for _ in 0..10 {
+            assert_ne!(play(&mut random, &mut minimax), Some(Outcome::Win(0)));
+            assert_ne!(play(&mut minimax, &mut random), Some(Outcome::Win(1)));
+        }
+
+        let config = MctsConfig {
+            iterations: 2000,
+            seed: Some(0),
+            ..Default::default()
+        };
+        let mut mcts = MctsAgent::new(Mcts::new(config, RandomPlayout));
+        let board: Board = "XX2OO5678".parse().unwrap();
+        assert_eq!(mcts.choose_move(&board), 2);
+    }
+
+    #[test]
+    fn test_skill() {
+        assert_eq!("easy".parse(), Ok(Skill::Epsilon(0.5)));
+        assert_eq!("depth=3".parse(), Ok(Skill::Depth(3)));
+        assert_eq!("epsilon=0.25".parse(), Ok(Skill::Epsilon(0.25)));
+        assert!("epsilon=2".parse::<Skill>().is_err());
+        assert!("expert".parse::<Skill>().is_err());
+
+        let win: Board = "XX2OO5678".parse().unwrap();
+        let block: Board = "XX2O45678".parse().unwrap();
+        let mut shallow = MinimaxAgent::default().with_skill(Skill::Depth(1));
+        assert_eq!(shallow.choose_move(&win), 2);
+        let mut deeper = MinimaxAgent::default().with_skill(Skill::Depth(2));
+        assert_eq!(deeper.choose_move(&block), 2);
+
+        // Never random at epsilon 0, always at epsilon 1
+        let mut perfect = MinimaxAgent::default().with_skill(Skill::Epsilon(0.));
+        let mut random = MinimaxAgent::default()
+            .with_skill(Skill::Epsilon(1.))
+            .with_seed(0);
+        let mut minimax = MinimaxAgent::default();
+        assert_eq!(play(&mut perfect, &mut minimax), Some(Outcome::Draw));
+        let moves: Vec<usize> = (0..20).map(|_| random.choose_move(&block)).collect();
+        assert!(moves.iter().any(|x| *x != 2));
+        assert!(moves.iter().all(|x| block.valid_moves().contains(x)));
+    }
+
+    #[test]
+    fn test_nim_agent() {
+        // Wins every game from a position with a nonzero nim sum
+        let nim = Nim::new(vec![3, 4, 5]);
+        let mut random = RandomAgent::new(Some(0));
+        for _ in 0..20 {
+            let agents: [&mut dyn Agent<Nim>; 2] = [&mut NimAgent, &mut random];
+            let (_, outcome) = crate::arena::play_game(&nim, agents);
+            assert_eq!(outcome, Outcome::Win(0));
+        }
+    }
+
+    #[test]
+    fn test_human_agent() {
+        // Skips unparseable and illegal moves
+        let mut human = HumanAgent::new("a\n0\n9\n4\n".as_bytes());
+        let board: Board = "X12345678".parse().unwrap();
+        assert_eq!(human.choose_move(&board), 4);
+    }
+}
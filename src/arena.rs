@@ -0,0 +1,165 @@
+//! Head-to-head matches between two [`Agent`]s, alternating who moves first, with confidence
+//! intervals on the results so that a new agent can be checked against a known baseline.
+
+use crate::agent::Agent;
+use crate::search::{GameState, Outcome};
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// z-score of a two-sided 95% confidence interval
+const Z_95: f64 = 1.959964;
+
+/// A game played in the arena
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord<M> {
+    /// Which of the two agents moved first, 0 or 1
+    pub first: usize,
+    pub moves: Vec<M>,
+    pub outcome: Outcome,
+}
+
+/// Results of a match from the point of view of the first agent
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArenaStats {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl ArenaStats {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, counting a draw as half a win
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games().max(1) as f64
+    }
+
+    /// 95% Wilson score interval for the proportion of games ending with `count` of them
+    pub fn interval(&self, count: usize) -> (f64, f64) {
+        let n = self.games() as f64;
+        if n == 0. {
+            return (0., 1.);
+        }
+        let p = count as f64 / n;
+        let z2 = Z_95 * Z_95;
+        let denominator = 1. + z2 / n;
+        let center = (p + z2 / (2. * n)) / denominator;
+        let half_width = Z_95 * (p * (1. - p) / n + z2 / (4. * n * n)).sqrt() / denominator;
+        ((center - half_width).max(0.), (center + half_width).min(1.))
+    }
+}
+
+impl Display for ArenaStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, count) in [
+            ("Wins", self.wins),
+            ("Draws", self.draws),
+            ("Losses", self.losses),
+        ] {
+            let (low, high) = self.interval(count);
+            writeln!(
+                f,
+                "{:<7} {:>6} ({:.1}% to {:.1}%)",
+                name,
+                count,
+                low * 100.,
+                high * 100.
+            )?;
+        }
+        write!(f, "Score   {:.3}", self.score())
+    }
+}
+
+/// Plays a game from `state` to the end, `agents[0]` moving first
+pub fn play_game<G: GameState>(
+    state: &G,
+    agents: [&mut dyn Agent<G>; 2],
+) -> (Vec<G::Move>, Outcome) {
+    let mut state = state.clone();
+    let mut moves = vec![];
+    let first = state.current_player();
+    loop {
+        if let Some(outcome) = state.outcome() {
+            return (moves, outcome);
+        }
+        let agent = (state.current_player() + 2 - first) % 2;
+        let action = agents[agent].choose_move(&state);
+        if state.play(action).is_err() {
+            // An illegal move forfeits the game
+            let winner = 1 - state.current_player();
+            return (moves, Outcome::Win(winner));
+        }
+        moves.push(action);
+    }
+}
+
+/// Plays `games` games from `state` between two agents, `a` moving first in even games and `b` in
+/// odd ones, and returns the results for `a` along with every game
+pub fn run<G: GameState>(
+    state: &G,
+    a: &mut dyn Agent<G>,
+    b: &mut dyn Agent<G>,
+    games: usize,
+) -> (ArenaStats, Vec<GameRecord<G::Move>>) {
+    let mut stats = ArenaStats::default();
+    let mut records = Vec::with_capacity(games);
+    for game in 0..games {
+        let first = game % 2;
+        let agents: [&mut dyn Agent<G>; 2] = match first {
+            0 => [&mut *a, &mut *b],
+            _ => [&mut *b, &mut *a],
+        };
+        let (moves, outcome) = play_game(state, agents);
+        // Player index of `a` in this game
+        let player_a = (state.current_player() + first) % 2;
+        match outcome {
+            Outcome::Win(x) if x == player_a => stats.wins += 1,
+            Outcome::Win(_) => stats.losses += 1,
+            Outcome::Draw => stats.draws += 1,
+        }
+        records.push(GameRecord {
+            first,
+            moves,
+            outcome,
+        });
+    }
+    (stats, records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{MinimaxAgent, RandomAgent};
+    use crate::tictactoe::Board;
+
+    #[test]
+    fn test_arena() {
+        let mut minimax = MinimaxAgent::default();
+        let mut random = RandomAgent::new(Some(0));
+        let (stats, records) = run(&Board::default(), &mut minimax, &mut random, 20);
+        assert_eq!(stats.games(), 20);
+        assert_eq!(stats.losses, 0);
+        assert!(stats.wins > 0);
+        assert_eq!(records.len(), 20);
+        assert_eq!(records[1].first, 1);
+
+        // Replaying the moves gives the recorded result
+        for record in &records {
+            let mut board = Board::default();
+            for action in &record.moves {
+                board.act(*action).unwrap();
+            }
+            assert_eq!(board.outcome(), Some(record.outcome));
+        }
+
+        let (low, high) = stats.interval(stats.wins);
+        let p = stats.wins as f64 / 20.;
+        assert!(low <= p && p <= high && low < high);
+        assert!(stats.interval(0).0 < 1e-9);
+        assert!(stats.interval(0).1 > 0.);
+    }
+}
@@ -0,0 +1,355 @@
+//! Sealed-bid auctions of one item with independent private values: each bidder's value is drawn
+//! independently from a common distribution, and the highest bid wins, ties split at random.
+//!
+//! In a first-price auction the winner pays their bid, so bidders shade their bids below their
+//! values, and in a second-price auction they pay the second highest bid, which makes bidding the
+//! value a dominant strategy. By revenue equivalence both raise the same expected revenue in
+//! equilibrium: with `n` bidders and values uniform on [0, 1], first-price bidders bid
+//! `(n - 1) / n` of their value and both auctions raise `(n - 1) / (n + 1)`.
+//!
+//! Values and bids are discretized onto grids. With finitely many values a first-price auction
+//! has no pure symmetric equilibrium, since a bidder whose opponents all bid the same with a
+//! value can outbid them by one step, so [`Auction::solve`] iterates best responses to the
+//! average of the previous ones, as in fictitious play, and the average converges to a mixed
+//! equilibrium. [`Auction::max_gain`] verifies a strategy by how much any value could gain by
+//! deviating.
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use ndarray::*;
+use rand::Rng;
+
+/// Rule deciding what the winner pays
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuctionFormat {
+    FirstPrice,
+    SecondPrice,
+}
+
+impl FromStr for AuctionFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first-price" => Ok(AuctionFormat::FirstPrice),
+            "second-price" => Ok(AuctionFormat::SecondPrice),
+            _ => Err(format!("Unknown auction format: {}", s)),
+        }
+    }
+}
+
+impl Display for AuctionFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuctionFormat::FirstPrice => write!(f, "first-price"),
+            AuctionFormat::SecondPrice => write!(f, "second-price"),
+        }
+    }
+}
+
+/// Probability of each bid (columns) made with each value (rows), shared by every bidder
+pub type BidStrategy = Array<f64, Ix2>;
+
+/// Sealed-bid auction with values and bids on grids
+#[derive(Debug, Clone, PartialEq)]
+pub struct Auction {
+    pub format: AuctionFormat,
+    pub bidders: usize,
+    /// Possible values of each bidder
+    pub values: Array<f64, Ix1>,
+    /// Probability of each value
+    pub probabilities: Array<f64, Ix1>,
+    /// Allowed bids, in increasing order
+    pub bids: Array<f64, Ix1>,
+}
+
+/// Outcome of iterating best responses
+#[derive(Debug, Clone, PartialEq)]
+pub struct BestResponseResult {
+    /// Average of the best responses
+    pub strategy: BidStrategy,
+    pub iterations: usize,
+    /// Largest gain of any value from deviating from the average
+    pub max_gain: f64,
+}
+
+/// Expected outcome of an auction when every bidder follows a strategy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AuctionReport {
+    pub format: AuctionFormat,
+    /// Expected payment of the winner
+    pub revenue: f64,
+    /// Expected value less payment of each bidder
+    pub surplus: f64,
+    /// Largest gain of any value from deviating
+    pub max_gain: f64,
+}
+
+impl Display for AuctionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<13} revenue {:.4}, bidder surplus {:.4}, max gain from deviating {:.4}",
+            self.format, self.revenue, self.surplus, self.max_gain
+        )
+    }
+}
+
+impl Auction {
+    /// Values uniform on `values` evenly spaced points of [0, 1], and bids on `bids` evenly
+    /// spaced points of [0, 1]
+    pub fn uniform(format: AuctionFormat, bidders: usize, values: usize, bids: usize) -> Self {
+        Auction {
+            format,
+            bidders,
+            values: Array::linspace(0., 1., values),
+            probabilities: Array::from_elem(values, 1. / values as f64),
+            bids: Array::linspace(0., 1., bids),
+        }
+    }
+
+    /// The highest bid not above each value
+    pub fn truthful(&self) -> BidStrategy {
+        let mut strategy = Array::zeros((self.values.len(), self.bids.len()));
+        for (x, value) in self.values.iter().enumerate() {
+            let bid = self
+                .bids
+                .iter()
+                .rposition(|y| y <= value)
+                .unwrap_or_default();
+            strategy[[x, bid]] = 1.;
+        }
+        strategy
+    }
+
+    /// Probability of each bid of a single bidder following the strategy
+    pub fn bid_distribution(&self, strategy: &BidStrategy) -> Array<f64, Ix1> {
+        self.probabilities.dot(strategy)
+    }
+
+    /// Expected value less payment of a bidder with each value (rows) making each bid (columns),
+    /// against opponents bidding with the probabilities in `opponents`
+    pub fn utilities(&self, opponents: &Array<f64, Ix1>) -> Array<f64, Ix2> {
+        let others = self.bidders as i32 - 1;
+        let mut win = Array::zeros(self.bids.len());
+        let mut payment = Array::zeros(self.bids.len());
+        // Probability that every opponent bids below the current bid, and the expected price paid
+        // to outbid them all in a second-price auction
+        let (mut below, mut outbid) = (0., 0.);
+        for (bid, tied) in opponents.iter().enumerate() {
+            let at_most = below + tied;
+            // Each of the tied bidders is equally likely to win
+            win[bid] = match *tied > 0. {
+                true => {
+                    (at_most.powi(others + 1) - f64::powi(below, others + 1))
+                        / (tied * (others + 1) as f64)
+                }
+                false => f64::powi(below, others),
+            };
+            payment[bid] = match self.format {
+                AuctionFormat::FirstPrice => self.bids[bid] * win[bid],
+                AuctionFormat::SecondPrice => {
+                    outbid + self.bids[bid] * (win[bid] - f64::powi(below, others))
+                }
+            };
+            outbid += self.bids[bid] * (at_most.powi(others) - f64::powi(below, others));
+            below = at_most;
+        }
+        Array::from_shape_fn((self.values.len(), self.bids.len()), |(x, bid)| {
+            self.values[x] * win[bid] - payment[bid]
+        })
+    }
+
+    /// Best bid for each value against opponents following the strategy, the lowest on ties
+    pub fn best_response(&self, strategy: &BidStrategy) -> Vec<usize> {
+        let utilities = self.utilities(&self.bid_distribution(strategy));
+        utilities
+            .rows()
+            .into_iter()
+            .map(|row| {
+                let mut best = 0;
+                for (bid, utility) in row.iter().enumerate() {
+                    if *utility > row[best] + 1e-12 {
+                        best = bid;
+                    }
+                }
+                best
+            })
+            .collect()
+    }
+
+    /// Largest gain of a bidder with any value from deviating from the strategy when everyone else
+    /// follows it; zero exactly at an equilibrium
+    pub fn max_gain(&self, strategy: &BidStrategy) -> f64 {
+        let utilities = self.utilities(&self.bid_distribution(strategy));
+        let expected = (&utilities * strategy).sum_axis(Axis(1));
+        utilities
+            .rows()
+            .into_iter()
+            .zip(&expected)
+            .map(|(row, x)| row.fold(f64::NEG_INFINITY, |acc, y| acc.max(*y)) - x)
+            .fold(0., f64::max)
+    }
+
+    /// Averages best responses to the previous average, starting from truthful bidding, until no
+    /// value gains more than `target` by deviating or after `max_iterations`
+    pub fn solve(&self, max_iterations: usize, target: f64) -> BestResponseResult {
+        let mut counts = self.truthful();
+        let mut strategy = counts.clone();
+        let mut iterations = 0;
+        let mut max_gain = self.max_gain(&strategy);
+        while iterations < max_iterations && max_gain > target {
+            for (x, bid) in self.best_response(&strategy).into_iter().enumerate() {
+                counts[[x, bid]] += 1.;
+            }
+            iterations += 1;
+            strategy = &counts / (iterations + 1) as f64;
+            max_gain = self.max_gain(&strategy);
+        }
+        BestResponseResult {
+            strategy,
+            iterations,
+            max_gain,
+        }
+    }
+
+    /// Expected payment of the winner when everyone follows the strategy
+    pub fn revenue(&self, strategy: &BidStrategy) -> f64 {
+        let n = self.bidders as i32;
+        let mut cumulative = 0.;
+        // Probability that the price is at most each bid
+        let mut previous = 0.;
+        let mut revenue = 0.;
+        for (bid, probability) in self.bids.iter().zip(self.bid_distribution(strategy)) {
+            cumulative += probability;
+            let at_most = match self.format {
+                AuctionFormat::FirstPrice => f64::powi(cumulative, n),
+                AuctionFormat::SecondPrice => {
+                    f64::powi(cumulative, n)
+                        + n as f64 * f64::powi(cumulative, n - 1) * (1. - cumulative)
+                }
+            };
+            revenue += bid * (at_most - previous);
+            previous = at_most;
+        }
+        revenue
+    }
+
+    /// Revenue, bidder surplus and distance from equilibrium of a strategy
+    pub fn report(&self, strategy: &BidStrategy) -> AuctionReport {
+        let utilities = self.utilities(&self.bid_distribution(strategy));
+        let surplus = self
+            .probabilities
+            .dot(&(&utilities * strategy).sum_axis(Axis(1)));
+        AuctionReport {
+            format: self.format,
+            revenue: self.revenue(strategy),
+            surplus,
+            max_gain: self.max_gain(strategy),
+        }
+    }
+
+    /// Average revenue over `rounds` auctions with values and bids drawn at random
+    pub fn simulate(&self, strategy: &BidStrategy, rounds: usize, rng: &mut impl Rng) -> f64 {
+        let mut total = 0.;
+        for _ in 0..rounds {
+            let mut offers: Vec<f64> = (0..self.bidders)
+                .map(|_| {
+                    let value = sample(self.probabilities.view(), rng);
+                    self.bids[sample(strategy.row(value), rng)]
+                })
+                .collect();
+            // Ties are split at random, but the price doesn't depend on who wins them
+            offers.sort_by(|a, b| b.total_cmp(a));
+            total += match self.format {
+                AuctionFormat::FirstPrice => offers[0],
+                AuctionFormat::SecondPrice => offers.get(1).copied().unwrap_or_default(),
+            };
+        }
+        total / rounds as f64
+    }
+}
+
+/// Index drawn with the given probabilities
+fn sample(probabilities: ArrayView1<f64>, rng: &mut impl Rng) -> usize {
+    let mut remaining: f64 = rng.gen();
+    for (x, probability) in probabilities.iter().enumerate() {
+        remaining -= probability;
+        if remaining < 0. {
+            return x;
+        }
+    }
+    probabilities.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_second_price() {
+        // Truthful bidding is an equilibrium, found without any iterations
+        let auction = Auction::uniform(AuctionFormat::SecondPrice, 3, 21, 21);
+        let result = auction.solve(100, 0.);
+        assert_eq!(result.iterations, 0);
+        assert_eq!(result.strategy, auction.truthful());
+        assert_eq!(result.max_gain, 0.);
+        assert_eq!(
+            auction.best_response(&result.strategy),
+            (0..21).collect::<Vec<_>>()
+        );
+
+        // Overbidding loses money when it wins against higher values
+        let overbid = Array::from_shape_fn((21, 21), |(x, y)| match y == (x + 2).min(20) {
+            true => 1.,
+            false => 0.,
+        });
+        assert!(auction.max_gain(&overbid) > 0.);
+    }
+
+    #[test]
+    fn test_first_price() {
+        // Equilibrium bids average close to (n - 1) / n of the value
+        for bidders in [2, 3] {
+            let auction = Auction::uniform(AuctionFormat::FirstPrice, bidders, 21, 201);
+            let result = auction.solve(5000, 5e-3);
+            assert!(result.max_gain <= 5e-3, "{}", result.max_gain);
+            let shading = (bidders - 1) as f64 / bidders as f64;
+            let average = result.strategy.dot(&auction.bids);
+            for (value, bid) in auction.values.iter().zip(&average) {
+                assert!((bid - shading * value).abs() < 0.05, "{}", average);
+            }
+
+            // Truthful bidding never gains anything
+            let truthful = auction.truthful();
+            assert!(auction.report(&truthful).surplus.abs() < 1e-9);
+            assert!(auction.max_gain(&truthful) > 0.);
+        }
+    }
+
+    #[test]
+    fn test_revenue_equivalence() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for bidders in [2, 4] {
+            let expected = (bidders - 1) as f64 / (bidders + 1) as f64;
+            for format in [AuctionFormat::FirstPrice, AuctionFormat::SecondPrice] {
+                let auction = Auction::uniform(format, bidders, 41, 201);
+                let strategy = auction.solve(5000, 5e-3).strategy;
+                let report = auction.report(&strategy);
+                assert!((report.revenue - expected).abs() < 0.03, "{}", report);
+                let simulated = auction.simulate(&strategy, 20000, &mut rng);
+                assert!((simulated - report.revenue).abs() < 0.01, "{}", simulated);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("first-price".parse(), Ok(AuctionFormat::FirstPrice));
+        assert_eq!(AuctionFormat::SecondPrice.to_string(), "second-price");
+        assert!("dutch".parse::<AuctionFormat>().is_err());
+    }
+}
@@ -0,0 +1,268 @@
+//! Backward induction on perfect-information game trees, such as the ultimatum game, the
+//! centipede game or entry deterrence, built with the same [`TreeBuilder`] as the CFR games.
+//!
+//! Every action node must give each state its own infoset, so the acting player always knows
+//! where they are. Working up from the terminal nodes, each player picks the action with the
+//! highest payout given the choices already made below, and chance nodes average their outcomes.
+//! The result is a subgame perfect equilibrium in pure strategies, found in one pass over the
+//! tree instead of iterating regrets. Ties go to the first legal action, so the action order
+//! decides between equally good equilibria.
+//!
+//! [`TreeBuilder`]: crate::cfr::TreeBuilder
+
+use crate::cfr::{Float, Node, NodeRef, StrategyProfile, TreeError};
+
+use ndarray::*;
+use std::collections::BTreeMap;
+
+/// Subgame perfect equilibrium of a perfect-information tree
+#[derive(Debug, Default)]
+pub struct SubgamePerfect {
+    /// Expected payout of each player, over the root's state probabilities
+    pub payoffs: Array<Float, Ix1>,
+    /// Payouts indexed by player, state at the root
+    pub state_payoffs: Array<Float, Ix2>,
+    /// Pure strategy of every action node, keyed like [`StrategyProfile::from_tree`], so that
+    /// [`crate::cfr::load_strategies`] can load it back into the tree
+    pub profile: StrategyProfile,
+    /// Probability of reaching each terminal node on the equilibrium path, keyed by path; only
+    /// chance makes there be more than one
+    pub outcomes: BTreeMap<String, Float>,
+}
+
+/// Finds a subgame perfect equilibrium by backward induction
+pub fn solve(root: &dyn Node) -> Result<SubgamePerfect, TreeError> {
+    let mut result = SubgamePerfect::default();
+    let path = root.name();
+    result.state_payoffs = induce(root, &path, &mut result.profile)?;
+    let reach = root.state_probabilities().to_owned();
+    result.payoffs = result.state_payoffs.dot(&reach);
+    play(root, &path, &reach, &result.profile, &mut result.outcomes);
+    Ok(result)
+}
+
+/// Payouts indexed by player, state of the subtree, filling in the profile on the way up
+fn induce(
+    node: &dyn Node,
+    path: &str,
+    profile: &mut StrategyProfile,
+) -> Result<Array<Float, Ix2>, TreeError> {
+    let child_payouts = |children: &Vec<Box<dyn Node>>, profile: &mut StrategyProfile| {
+        children
+            .iter()
+            .map(|child| {
+                induce(
+                    child.as_ref(),
+                    &format!("{}/{}", path, child.name()),
+                    profile,
+                )
+            })
+            .collect::<Result<Vec<_>, TreeError>>()
+    };
+    match node.as_node_ref() {
+        NodeRef::Terminal(x) => Ok(x.payouts.clone()),
+        NodeRef::Chance(x) => Ok(child_payouts(&x.children, profile)?
+            .into_iter()
+            .zip(x.outcome_probabilities.rows())
+            .fold(Array::zeros(x.evs.raw_dim()), |f, (payouts, p)| {
+                f + payouts * p
+            })),
+        NodeRef::Action(x) => {
+            let imperfect = TreeError::ImperfectInformation {
+                path: path.to_string(),
+            };
+            if x.hidden || x.infosets.iter().any(|states| states.len() > 1) {
+                return Err(imperfect);
+            }
+            let children = child_payouts(&x.children, profile)?;
+            let mut payouts = Array::zeros(x.evs.raw_dim());
+            let mut strategy = Array::zeros(x.avg_strategy.raw_dim());
+            for (infoset, states) in x.infosets.iter().enumerate() {
+                let Some(&state) = states.first() else {
+                    continue;
+                };
+                let legal = |action: &usize| {
+                    x.action_mask
+                        .as_ref()
+                        .is_none_or(|mask| mask[[*action, infoset]])
+                };
+                let best = (0..children.len())
+                    .filter(legal)
+                    .fold(None, |best, action| {
+                        let value = children[action][[x.player, state]];
+                        match best {
+                            Some((_, best_value)) if best_value >= value => best,
+                            _ => Some((action, value)),
+                        }
+                    });
+                if let Some((action, _)) = best {
+                    strategy[[action, infoset]] = 1.;
+                    payouts
+                        .column_mut(state)
+                        .assign(&children[action].column(state));
+                }
+            }
+            profile.strategies.insert(path.to_string(), strategy);
+            Ok(payouts)
+        }
+        NodeRef::Arena(_) => Err(TreeError::Flattened {
+            path: path.to_string(),
+        }),
+    }
+}
+
+/// Adds the probability of reaching each terminal node when following the profile, given the
+/// probability of reaching each state of the node
+fn play(
+    node: &dyn Node,
+    path: &str,
+    reach: &Array<Float, Ix1>,
+    profile: &StrategyProfile,
+    outcomes: &mut BTreeMap<String, Float>,
+) {
+    let (children, probabilities): (_, Array<Float, Ix2>) = match node.as_node_ref() {
+        NodeRef::Terminal(_) => {
+            let probability = reach.sum();
+            if probability > 0. {
+                *outcomes.entry(path.to_string()).or_default() += probability;
+            }
+            return;
+        }
+        NodeRef::Chance(x) => (&x.children, x.outcome_probabilities.clone()),
+        NodeRef::Action(x) => {
+            let strategy = &profile.strategies[path];
+            let mut probabilities = Array::zeros((x.children.len(), reach.len()));
+            for (infoset, states) in x.infosets.iter().enumerate() {
+                for state in states {
+                    probabilities
+                        .column_mut(*state)
+                        .assign(&strategy.column(infoset));
+                }
+            }
+            (&x.children, probabilities)
+        }
+        NodeRef::Arena(_) => return,
+    };
+    for (child, p) in children.iter().zip(probabilities.rows()) {
+        let child_reach = reach * &p;
+        if child_reach.sum() > 0. {
+            let child_path = format!("{}/{}", path, child.name());
+            play(child.as_ref(), &child_path, &child_reach, profile, outcomes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::*;
+
+    #[test]
+    fn test_ultimatum() {
+        // The proposer offers 0 to 4 of 4 coins, which the responder accepts or rejects
+        let root = (0..=4)
+            .fold(TreeBuilder::action("propose", 0), |root, offer| {
+                let offer = offer as Float;
+                root.child(
+                    TreeBuilder::action(&format!("offer {}", offer), 1)
+                        .terminal("accept", array![[4. - offer], [offer]])
+                        .terminal("reject", array![[0.], [0.]]),
+                )
+            })
+            .build()
+            .unwrap();
+        let equilibrium = solve(root.as_ref()).unwrap();
+        // Accepting nothing ties with rejecting, and ties go to the first action
+        assert_eq!(equilibrium.payoffs, array![4., 0.]);
+        assert_eq!(
+            equilibrium.profile.strategies["propose"],
+            array![[1.], [0.], [0.], [0.], [0.]]
+        );
+        assert_eq!(
+            equilibrium.profile.strategies["propose/offer 2"],
+            array![[1.], [0.]]
+        );
+        assert_eq!(
+            equilibrium.outcomes.into_iter().collect::<Vec<_>>(),
+            vec![("propose/offer 0/accept".to_string(), 1.)]
+        );
+    }
+
+    #[test]
+    fn test_centipede() {
+        // Players alternate between taking the larger share of a pot that doubles at every pass
+        let rounds = 6;
+        let take = |round: usize| {
+            let pot = (1 << (round + 2)) as Float;
+            Array::from_shape_fn((2, 1), |(player, _)| match player == round % 2 {
+                true => pot * 0.8,
+                false => pot * 0.2,
+            })
+        };
+        let last = rounds - 1;
+        let mut node = TreeBuilder::action(&format!("round {}", last), last % 2)
+            .terminal("take", take(last))
+            .terminal("pass", take(rounds));
+        for round in (0..last).rev() {
+            node = TreeBuilder::action(&format!("round {}", round), round % 2)
+                .terminal("take", take(round))
+                .child(node.clone());
+        }
+        let mut root = node.build().unwrap();
+        let equilibrium = solve(root.as_ref()).unwrap();
+        assert_eq!(equilibrium.payoffs, array![3.2, 0.8]);
+        assert_eq!(equilibrium.profile.strategies.len(), rounds);
+        assert!(equilibrium
+            .profile
+            .strategies
+            .values()
+            .all(|x| x == array![[1.], [0.]]));
+
+        // Neither player gains from deviating in any subgame, so not from the root either
+        load_strategies(root.as_mut(), &equilibrium.profile).unwrap();
+        assert!(unilateral_gains(root.as_ref())
+            .iter()
+            .all(|x| x.abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_entry_deterrence() {
+        // The incumbent has high costs with probability 0.3, in which case fighting an entrant
+        // costs more than sharing the market, and the entrant sees the incumbent's costs
+        let incumbent = |name: &str, fight: Float| {
+            TreeBuilder::action(name, 0)
+                .terminal("stay out", array![[0.], [3.]])
+                .child(
+                    TreeBuilder::action("enter", 1)
+                        .terminal("fight", array![[-1.], [fight]])
+                        .terminal("accommodate", array![[1.], [1.]]),
+                )
+        };
+        let root = TreeBuilder::chance("costs")
+            .outcome_probabilities(array![[0.3], [0.7]])
+            .child(incumbent("high", -2.))
+            .child(incumbent("low", 2.))
+            .build()
+            .unwrap();
+        let equilibrium = solve(root.as_ref()).unwrap();
+        assert!((equilibrium.payoffs[0] - 0.3).abs() < 1e-6);
+        assert!((equilibrium.payoffs[1] - 2.4).abs() < 1e-6);
+        assert_eq!(equilibrium.outcomes.len(), 2);
+        assert!((equilibrium.outcomes["costs/high/enter/accommodate"] - 0.3).abs() < 1e-6);
+        assert!((equilibrium.outcomes["costs/low/stay out"] - 0.7).abs() < 1e-6);
+
+        // Not knowing the incumbent's costs makes it a game of imperfect information
+        let root = TreeBuilder::action("enter", 0)
+            .infosets(vec![vec![0, 1]])
+            .terminal("stay out", array![[0., 0.], [3., 3.]])
+            .terminal("accommodate", array![[1., 1.], [1., 1.]])
+            .build()
+            .unwrap();
+        assert_eq!(
+            solve(root.as_ref()).unwrap_err(),
+            TreeError::ImperfectInformation {
+                path: "enter".to_string()
+            }
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! Two player bargaining: the players agree on one of a set of feasible payoff pairs, or get the
+//! disagreement point if they don't agree.
+//!
+//! The Nash bargaining solution maximizes the product of the players' gains over disagreement,
+//! and is the only solution that is Pareto optimal, symmetric, independent of how each player's
+//! payoffs are scaled and unaffected by removing unchosen points. The Kalai-Smorodinsky solution
+//! replaces the last axiom with monotonicity, giving each player the same fraction of the most
+//! they could get in any individually rational agreement: it's the point on the line from
+//! disagreement to that ideal point. The feasible set is given as a finite list of points, such as
+//! a discretized Pareto frontier, so both solutions are the best of those points, and the
+//! Kalai-Smorodinsky one is only as close to the line as the discretization allows.
+
+/// Feasible payoff pairs and the payoffs if the players don't agree
+#[derive(Clone, Debug, PartialEq)]
+pub struct BargainingProblem {
+    pub feasible: Vec<[f64; 2]>,
+    pub disagreement: [f64; 2],
+}
+
+impl BargainingProblem {
+    pub fn new(feasible: Vec<[f64; 2]>, disagreement: [f64; 2]) -> Self {
+        BargainingProblem {
+            feasible,
+            disagreement,
+        }
+    }
+
+    /// Feasible points at least as good as disagreement for both players
+    pub fn individually_rational(&self) -> impl Iterator<Item = &[f64; 2]> {
+        self.feasible
+            .iter()
+            .filter(|x| (0..2).all(|player| x[player] >= self.disagreement[player]))
+    }
+
+    /// Most each player gets in any individually rational point, or None if there isn't one
+    pub fn ideal_point(&self) -> Option<[f64; 2]> {
+        self.individually_rational().fold(None, |acc, x| match acc {
+            None => Some(*x),
+            Some(best) => Some([best[0].max(x[0]), best[1].max(x[1])]),
+        })
+    }
+
+    /// Feasible point maximizing the product of the players' gains over disagreement, or None if
+    /// no point gives both players more
+    pub fn nash_solution(&self) -> Option<[f64; 2]> {
+        self.best_by(|gains| gains[0] * gains[1])
+    }
+
+    /// Feasible point maximizing the smaller of the players' gains as a fraction of their gain at
+    /// the ideal point, or None if no point gives both players more than disagreement
+    pub fn kalai_smorodinsky(&self) -> Option<[f64; 2]> {
+        let ideal = self.ideal_point()?;
+        let range = [0, 1].map(|player| ideal[player] - self.disagreement[player]);
+        self.best_by(|gains| (gains[0] / range[0]).min(gains[1] / range[1]))
+    }
+
+    /// Individually rational point with both gains positive maximizing `score` of the gains,
+    /// the first on ties
+    fn best_by(&self, score: impl Fn([f64; 2]) -> f64) -> Option<[f64; 2]> {
+        let mut best: Option<([f64; 2], f64)> = None;
+        for point in self.individually_rational() {
+            let gains = [0, 1].map(|player| point[player] - self.disagreement[player]);
+            if gains.iter().any(|x| *x <= 0.) {
+                continue;
+            }
+            let value = score(gains);
+            if best.is_none_or(|(_, x)| value > x) {
+                best = Some((*point, value));
+            }
+        }
+        best.map(|(point, _)| point)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Points on the frontier `second = f(first)` for `first` evenly spaced in [0, 1]
+    fn frontier(f: impl Fn(f64) -> f64, steps: usize) -> Vec<[f64; 2]> {
+        (0..=steps)
+            .map(|x| {
+                let first = x as f64 / steps as f64;
+                [first, f(first)]
+            })
+            .collect()
+    }
+
+    fn assert_close(point: [f64; 2], expected: [f64; 2], tolerance: f64) {
+        assert!(
+            (0..2).all(|x| (point[x] - expected[x]).abs() < tolerance),
+            "{:?}",
+            point
+        );
+    }
+
+    #[test]
+    fn test_split_a_dollar() {
+        // Symmetric problems are split evenly
+        let problem = BargainingProblem::new(frontier(|x| 1. - x, 100), [0., 0.]);
+        assert_close(problem.nash_solution().unwrap(), [0.5, 0.5], 1e-9);
+        assert_close(problem.kalai_smorodinsky().unwrap(), [0.5, 0.5], 1e-9);
+
+        // A better outside option gets a bigger share
+        let problem = BargainingProblem::new(frontier(|x| 1. - x, 100), [0.2, 0.]);
+        assert_eq!(problem.ideal_point(), Some([1., 0.8]));
+        assert_close(problem.nash_solution().unwrap(), [0.6, 0.4], 1e-9);
+
+        // Nobody gains over disagreement
+        let problem = BargainingProblem::new(frontier(|x| 1. - x, 100), [0.5, 0.5]);
+        assert_eq!(problem.nash_solution(), None);
+        assert_eq!(problem.kalai_smorodinsky(), None);
+        let problem = BargainingProblem::new(vec![], [0., 0.]);
+        assert_eq!(problem.ideal_point(), None);
+    }
+
+    #[test]
+    fn test_concave_frontier() {
+        // On second = 1 - first², Nash maximizes first - first³ at first = 1/√3, while
+        // Kalai-Smorodinsky equalizes the shares of the ideal point (1, 1) where first = 1 - first²
+        let feasible = frontier(|x| 1. - x * x, 10000);
+        let problem = BargainingProblem::new(feasible.clone(), [0., 0.]);
+        let nash = 1. / 3f64.sqrt();
+        assert_close(problem.nash_solution().unwrap(), [nash, 2. / 3.], 1e-3);
+        let ks = (5f64.sqrt() - 1.) / 2.;
+        assert_close(problem.kalai_smorodinsky().unwrap(), [ks, ks], 1e-3);
+
+        // Rescaling a player's payoffs rescales both solutions with them
+        let scaled = BargainingProblem::new(
+            feasible.iter().map(|x| [x[0], 3. * x[1] + 1.]).collect(),
+            [0., 1.],
+        );
+        for (solve, expected) in [
+            (BargainingProblem::nash_solution as fn(&_) -> _, nash),
+            (BargainingProblem::kalai_smorodinsky, ks),
+        ] {
+            let point = solve(&scaled).unwrap();
+            assert!((point[0] - expected).abs() < 1e-3);
+            assert!((point[1] - (3. * (1. - point[0] * point[0]) + 1.)).abs() < 1e-9);
+            assert_eq!(solve(&problem).unwrap()[0], point[0]);
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Two player Bayesian games: before playing, each player privately learns their type, drawn
+//! from a common prior over pairs of types, and the payoffs depend on both types and both actions.
+//!
+//! A strategy picks an action, or a mixture, for each of the player's types, and a Bayes-Nash
+//! equilibrium is a Nash equilibrium of the induced normal-form game whose pure strategies are
+//! those choices, with payoffs averaged over the prior. [`BayesianGame::to_normal_form`] builds
+//! that game for the solvers in [`crate::normal_form`], with a number of strategies exponential in
+//! the number of types. [`BayesianGame::build_tree`] instead builds a CFR tree whose states are
+//! the pairs of types, dealt by the root's state distribution as the cards are in Liar's Dice, in
+//! which each player only sees their own type, so its size only grows with the number of types.
+
+use crate::cfr::{Float, Node, TreeBuilder, TreeError};
+use crate::normal_form::NormalFormGame;
+
+use ndarray::*;
+
+/// Two player game of incomplete information with a common prior over types
+#[derive(Clone, Debug, PartialEq)]
+pub struct BayesianGame {
+    /// Probability of each pair of types, indexed by the first player's type, the second's
+    pub prior: Array<f64, Ix2>,
+    /// Indexed by player, the first player's type, the second's, the first player's action, the
+    /// second's
+    pub payoffs: Array<f64, Ix5>,
+    /// Type names of each player
+    pub types: [Vec<String>; 2],
+    /// Action names of each player
+    pub actions: [Vec<String>; 2],
+}
+
+impl BayesianGame {
+    /// Game with types and actions named by their index
+    pub fn new(prior: Array<f64, Ix2>, payoffs: Array<f64, Ix5>) -> Self {
+        let (_, first_types, second_types, rows, columns) = payoffs.dim();
+        let names = |n: usize| -> Vec<String> { (0..n).map(|x| x.to_string()).collect() };
+        BayesianGame {
+            prior,
+            payoffs,
+            types: [names(first_types), names(second_types)],
+            actions: [names(rows), names(columns)],
+        }
+    }
+
+    /// Number of types and actions of the player
+    fn dims(&self, player: usize) -> (usize, usize) {
+        (self.types[player].len(), self.actions[player].len())
+    }
+
+    /// Action of each type in the player's pure strategy `index` of the induced normal form, the
+    /// first type's action the most significant digit
+    pub fn pure_strategy(&self, player: usize, mut index: usize) -> Vec<usize> {
+        let (types, actions) = self.dims(player);
+        let mut result = vec![0; types];
+        for x in result.iter_mut().rev() {
+            *x = index % actions;
+            index /= actions;
+        }
+        result
+    }
+
+    /// Normal-form game with a pure strategy for every choice of action by every type of the
+    /// player, and payoffs averaged over the prior
+    pub fn to_normal_form(&self) -> NormalFormGame {
+        let count = |player: usize| {
+            let (types, actions) = self.dims(player);
+            actions.pow(types as u32)
+        };
+        let [first, second] = [0, 1].map(|player| {
+            Array::from_shape_fn((count(0), count(1)), |(i, j)| {
+                let (rows, columns) = (self.pure_strategy(0, i), self.pure_strategy(1, j));
+                self.prior
+                    .indexed_iter()
+                    .map(|((x, y), p)| p * self.payoffs[[player, x, y, rows[x], columns[y]]])
+                    .sum()
+            })
+        });
+        NormalFormGame::bimatrix(first, second)
+    }
+
+    /// Probability of each action of each type (rows) of the player when playing a mixed
+    /// strategy of the induced normal form
+    pub fn behavior_strategy(&self, player: usize, strategy: &Array<f64, Ix1>) -> Array<f64, Ix2> {
+        let (types, actions) = self.dims(player);
+        let mut result = Array::zeros((types, actions));
+        for (index, probability) in strategy.iter().enumerate() {
+            for (x, action) in self.pure_strategy(player, index).into_iter().enumerate() {
+                result[[x, action]] += probability;
+            }
+        }
+        result
+    }
+
+    /// Expected payoff of each player when each type plays the mixed strategies given by the
+    /// rows of `strategies`
+    pub fn expected_payoffs(&self, strategies: [&Array<f64, Ix2>; 2]) -> [f64; 2] {
+        [0, 1].map(|player| {
+            self.prior
+                .indexed_iter()
+                .map(|((x, y), p)| {
+                    let payoffs: ArrayView2<f64> = self.payoffs.slice(s![player, x, y, .., ..]);
+                    p * strategies[0]
+                        .row(x)
+                        .dot(&payoffs.dot(&strategies[1].row(y)))
+                })
+                .sum()
+        })
+    }
+
+    /// Builds a CFR tree with a state per pair of types, the first player's type most
+    /// significant, in which the first player acts on their type and the second on theirs
+    /// without seeing the first player's action
+    pub fn build_tree(&self) -> Result<Box<dyn Node>, TreeError> {
+        let (first_types, rows) = self.dims(0);
+        let (second_types, columns) = self.dims(1);
+        let states = first_types * second_types;
+        let infosets = [
+            (0..first_types)
+                .map(|x| (0..second_types).map(|y| x * second_types + y).collect())
+                .collect::<Vec<Vec<usize>>>(),
+            (0..second_types)
+                .map(|y| (0..first_types).map(|x| x * second_types + y).collect())
+                .collect(),
+        ];
+        (0..rows)
+            .fold(
+                TreeBuilder::action("root", 0)
+                    .infosets(infosets[0].clone())
+                    .hidden(),
+                |root, row| {
+                    let node = (0..columns).fold(
+                        TreeBuilder::action(&self.actions[0][row], 1).infosets(infosets[1].clone()),
+                        |node, column| {
+                            let payouts = Array::from_shape_fn((2, states), |(player, state)| {
+                                let (x, y) = (state / second_types, state % second_types);
+                                self.payoffs[[player, x, y, row, column]] as Float
+                            });
+                            node.terminal(&self.actions[1][column], payouts)
+                        },
+                    );
+                    root.child(node)
+                },
+            )
+            .state_probabilities(self.prior.iter().map(|x| *x as Float).collect())
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::*;
+
+    /// Battle of the sexes where the second player either wants to meet the first or to avoid
+    /// them, each with probability 1/2
+    fn bayesian_battle_of_the_sexes() -> BayesianGame {
+        let meet = [array![[2., 0.], [0., 1.]], array![[1., 0.], [0., 2.]]];
+        let avoid = [array![[2., 0.], [0., 1.]], array![[0., 2.], [1., 0.]]];
+        let payoffs = Array::from_shape_fn((2, 1, 2, 2, 2), |(player, _, y, i, j)| match y {
+            0 => meet[player][[i, j]],
+            _ => avoid[player][[i, j]],
+        });
+        BayesianGame::new(array![[0.5, 0.5]], payoffs)
+    }
+
+    #[test]
+    fn test_normal_form() {
+        let game = bayesian_battle_of_the_sexes();
+        assert_eq!(game.pure_strategy(1, 1), [0, 1]);
+        let normal_form = game.to_normal_form();
+        assert_eq!(normal_form.shape(), [2, 4]);
+        // The first player's first action against the second player meeting or avoiding them
+        assert_eq!(normal_form.payoffs[[0, 0, 1]], 1.);
+        assert_eq!(normal_form.payoffs[[1, 0, 1]], 1.5);
+
+        // The only pure Bayes-Nash equilibrium has the first player choose their favourite, and
+        // the second player's types meet and avoid them
+        let pure: Vec<_> = normal_form
+            .support_enumeration()
+            .into_iter()
+            .filter(|x| x.supports().iter().all(|y| y.len() == 1))
+            .collect();
+        assert_eq!(pure.len(), 1);
+        let strategies = [0, 1].map(|x| game.behavior_strategy(x, &pure[0].strategies[x]));
+        assert_eq!(strategies[0], array![[1., 0.]]);
+        assert_eq!(strategies[1], array![[1., 0.], [0., 1.]]);
+        assert_eq!(
+            game.expected_payoffs([&strategies[0], &strategies[1]]),
+            pure[0].payoffs
+        );
+    }
+
+    #[test]
+    fn test_cfr() {
+        // Zero-sum, where the first player knows which of two matrices is being played and the
+        // second doesn't
+        let matrices = [array![[1., -1.], [-1., 1.]], array![[3., 0.], [-2., 1.]]];
+        let payoffs = Array::from_shape_fn((2, 2, 1, 2, 2), |(player, x, _, i, j)| {
+            let payoff = matrices[x][[i, j]];
+            match player {
+                0 => payoff,
+                _ => -payoff,
+            }
+        });
+        let game = BayesianGame::new(array![[0.3], [0.7]], payoffs);
+        let value = game.to_normal_form().solve_zero_sum().unwrap().value;
+
+        let mut solver = CfrSolver::new(
+            game.build_tree().unwrap(),
+            SolverConfig {
+                variant: CfrVariant::CfrPlus,
+                max_iterations: 5000,
+                ..Default::default()
+            },
+        );
+        let result = solver.solve();
+        assert!(result.exploitability < 1e-2, "{}", result.exploitability);
+        let root = &solver.root;
+        let cfr_value = root.avg_strategy_payouts().dot(&root.state_probabilities())[0];
+        assert!(
+            (cfr_value - value as Float).abs() < 1e-2,
+            "{} {}",
+            cfr_value,
+            value
+        );
+    }
+}
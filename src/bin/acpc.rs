@@ -0,0 +1,70 @@
+use game_theory_rs::cfr::*;
+use game_theory_rs::logging::LogArgs;
+use game_theory_rs::poker::acpc::*;
+
+use std::io::BufReader;
+use std::net::TcpStream;
+use std::path::PathBuf;
+
+use clap::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(help = "Preflop tree written by push-fold --save")]
+    tree: PathBuf,
+
+    #[arg(help = "Host of the ACPC dealer")]
+    host: String,
+
+    #[arg(help = "Port the dealer assigned this agent")]
+    port: u16,
+
+    #[arg(default_value = "100", long, help = "Big blind in chips")]
+    big_blind: u64,
+
+    #[arg(
+        default_value = "20000",
+        long,
+        help = "Chips each player starts every hand with"
+    )]
+    stack: u64,
+
+    #[arg(
+        default_value = "1",
+        long,
+        help = "Position of the small blind (1 in the competition's reverse blinds games)"
+    )]
+    sb_position: usize,
+
+    #[arg(long, help = "Seed for sampling actions")]
+    seed: Option<u64>,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.log.init();
+    if args.sb_position > 1 {
+        return Err("--sb-position must be 0 or 1".into());
+    }
+    let config = AcpcConfig {
+        big_blind: args.big_blind,
+        stack: args.stack,
+        sb_position: args.sb_position,
+    };
+    let player = AcpcPlayer::new(load_tree(&args.tree)?, config);
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let stream = TcpStream::connect((args.host.as_str(), args.port))?;
+    stream.set_nodelay(true)?;
+    let hands = player.play(BufReader::new(stream.try_clone()?), stream, &mut rng)?;
+    println!("Played {} hands", hands);
+    Ok(())
+}
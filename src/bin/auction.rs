@@ -0,0 +1,70 @@
+use game_theory_rs::auction::*;
+use game_theory_rs::logging::LogArgs;
+
+use clap::*;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(default_value = "2", short, long, help = "Number of bidders")]
+    bidders: usize,
+
+    #[arg(
+        default_value = "21",
+        long,
+        help = "Number of values, evenly spaced on [0, 1] and equally likely"
+    )]
+    values: usize,
+
+    #[arg(
+        default_value = "201",
+        long,
+        help = "Number of allowed bids, evenly spaced on [0, 1]"
+    )]
+    bids: usize,
+
+    #[arg(
+        default_value = "5000",
+        short,
+        long,
+        help = "Maximum number of best response iterations"
+    )]
+    iter: usize,
+
+    #[arg(
+        default_value = "0.001",
+        long,
+        help = "Stop once no value gains more than this by deviating"
+    )]
+    target: f64,
+
+    #[arg(long, help = "Print the average bid made with each value")]
+    bid_function: bool,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() {
+    let args = Args::parse();
+    args.log.init();
+    println!(
+        "{} bidders with values uniform on [0, 1], revenue equivalence predicts {:.4}",
+        args.bidders,
+        (args.bidders - 1) as f64 / (args.bidders + 1) as f64
+    );
+    for format in [AuctionFormat::FirstPrice, AuctionFormat::SecondPrice] {
+        let auction = Auction::uniform(format, args.bidders, args.values, args.bids);
+        let result = auction.solve(args.iter, args.target);
+        println!(
+            "{} ({} iterations)",
+            auction.report(&result.strategy),
+            result.iterations
+        );
+        if args.bid_function {
+            let average = result.strategy.dot(&auction.bids);
+            for (value, bid) in auction.values.iter().zip(&average) {
+                println!("  {:.3}: {:.3}", value, bid);
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use game_theory_rs::connect4::*;
+use game_theory_rs::logging::LogArgs;
+use game_theory_rs::mcts::{GreedyPlayout, Mcts, MctsConfig};
+use game_theory_rs::search::DepthLimitedSearch;
+
+use std::path::PathBuf;
+
+use clap::*;
+use tracing::info;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(
+        default_value = "",
+        help = "Columns played so far, numbered from 1, e.g. 4453"
+    )]
+    moves: String,
+
+    #[arg(
+        long,
+        help = "Search this many moves ahead with a heuristic evaluation instead of solving"
+    )]
+    depth: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Run this many iterations of Monte Carlo tree search instead of solving"
+    )]
+    mcts: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Transposition table file to start the solver from, updated after solving"
+    )]
+    table: Option<PathBuf>,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.log.init();
+    let position: Position = args.moves.parse()?;
+    println!("{position}");
+    if position.last_move_won() {
+        println!("The game is over: the last player to move won");
+        return Ok(());
+    }
+
+    if let Some(depth) = args.depth {
+        let mut search = DepthLimitedSearch::new(ThreatEvaluator, depth);
+        if let Some((column, score)) = search.best_move(&position) {
+            println!("Score: {}", score);
+            println!("Best move: {}", column + 1);
+        }
+        info!(nodes = search.nodes, "Search finished");
+        return Ok(());
+    }
+
+    if let Some(iterations) = args.mcts {
+        let config = MctsConfig {
+            iterations,
+            ..Default::default()
+        };
+        let mut mcts = Mcts::new(config, GreedyPlayout);
+        let stats = mcts.analyze(&position);
+        for stats in &stats {
+            println!(
+                "Column {}: {} visits, {:.3} mean result",
+                stats.action + 1,
+                stats.visits,
+                stats.value
+            );
+        }
+        if let Some(best) = stats.iter().max_by_key(|x| x.visits) {
+            println!("Best move: {}", best.action + 1);
+        }
+        return Ok(());
+    }
+
+    let mut solver = match &args.table {
+        Some(path) if path.is_file() => Solver::load(path)?,
+        _ => Solver::default(),
+    };
+    match solver.best_move(&position) {
+        Some((column, score)) => {
+            println!("Score: {}", score);
+            println!("Best move: {}", column + 1);
+        }
+        None => {
+            println!("The game is over: draw");
+            return Ok(());
+        }
+    }
+    for column in position.valid_moves() {
+        let score = if position.is_winning_move(column) {
+            (BOARD_SIZE as i32 + 1 - position.moves() as i32) / 2
+        } else {
+            let mut child = position;
+            child.play(column)?;
+            -solver.solve(&child)
+        };
+        println!("Column {}: {}", column + 1, score);
+    }
+    info!(nodes = solver.nodes, "Search finished");
+    if let Some(path) = &args.table {
+        solver.save(path)?;
+    }
+    Ok(())
+}
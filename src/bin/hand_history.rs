@@ -0,0 +1,134 @@
+use game_theory_rs::cfr::*;
+use game_theory_rs::logging::LogArgs;
+use game_theory_rs::poker::hand_history::*;
+use game_theory_rs::poker::push_fold::*;
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use clap::*;
+use tracing::info;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(help = "Hand history file")]
+    path: PathBuf,
+
+    #[arg(
+        default_value = "json",
+        long,
+        help = "Format of the hand history (json, pokerstars)"
+    )]
+    format: String,
+
+    #[arg(default_value = "0.5", short, long, help = "Small blind")]
+    sb: f64,
+
+    #[arg(
+        default_value = "0.5",
+        long,
+        help = "Round stacks to a multiple of this many big blinds, solving once per stack"
+    )]
+    stack_step: f64,
+
+    #[arg(
+        default_value = "1000",
+        short,
+        long,
+        help = "Number of CFR iterations per stack"
+    )]
+    iter: u64,
+
+    #[arg(long, help = "Print every decision, not just the total")]
+    decisions: bool,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.log.init();
+    if args.stack_step <= 0. {
+        return Err("--stack-step must be positive".into());
+    }
+    let text = std::fs::read_to_string(&args.path)?;
+    let decisions = match args.format.as_str() {
+        "json" => parse_json(&text)?,
+        "pokerstars" => parse_pokerstars(&text),
+        _ => return Err(format!("Unknown hand history format: {}", args.format).into()),
+    };
+
+    // Decisions at the same rounded stack and ante share a solve
+    let key = |x: &Decision| {
+        let stack = (x.stack / args.stack_step).round() * args.stack_step;
+        (
+            (stack * 1000.).round() as i64,
+            (x.ante * 1000.).round() as i64,
+        )
+    };
+    let mut groups: BTreeMap<(i64, i64), Vec<&Decision>> = BTreeMap::new();
+    for decision in &decisions {
+        groups.entry(key(decision)).or_default().push(decision);
+    }
+
+    let tables = PreflopTables::load()?;
+    let config = SolverConfig {
+        variant: CfrVariant::CfrPlus,
+        max_iterations: args.iter,
+        ..Default::default()
+    };
+    let (mut total, mut count) = (0., 0);
+    for ((stack, ante), decisions) in groups {
+        let (stack, ante) = (stack as f64 / 1000., ante as f64 / 1000.);
+        let root = tables.build_tree(
+            stack,
+            ante,
+            AnteStructure::Each,
+            args.sb,
+            &[SbAction::Shove, SbAction::Fold],
+            &Rake::default(),
+        )?;
+        let mut solver = CfrSolver::new(root, config.clone());
+        let result = solver.solve();
+        info!(
+            stack,
+            ante,
+            iterations = result.iterations,
+            "Solved to {:.3} mbb/hand",
+            result.exploitability * 1000.
+        );
+        let reports = solver.report();
+        for decision in decisions {
+            let ev = evaluate(decision, &reports)?;
+            if args.decisions {
+                println!(
+                    "Hand {}: {:?} {} at {} bb, {:?} (played {:.3}): EV {:.3}, strategy {:.3}, lost {:.3} bb",
+                    decision.hand,
+                    decision.seat,
+                    decision.cards,
+                    decision.stack,
+                    decision.action,
+                    ev.frequency,
+                    ev.ev,
+                    ev.strategy_ev,
+                    ev.loss()
+                );
+            }
+            total += ev.loss();
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        println!("No push/fold decisions found");
+        return Ok(());
+    }
+    println!(
+        "Lost {:.3} bb over {} decisions ({:.1} mbb per decision)",
+        total,
+        count,
+        total / count as Float * 1000.
+    );
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use game_theory_rs::hex::*;
+use game_theory_rs::logging::LogArgs;
+use game_theory_rs::mcts::{Mcts, MctsConfig, RandomPlayout};
+use game_theory_rs::search::DepthLimitedSearch;
+
+use std::io::stdin;
+
+use clap::*;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(default_value = "7", long, help = "Cells on each side of the board")]
+    size: usize,
+
+    #[arg(
+        default_value = "X",
+        long,
+        help = "Side played from the terminal: X, O, or none for the computer against itself"
+    )]
+    human: String,
+
+    #[arg(
+        long,
+        help = "Search this many moves ahead with alpha-beta instead of Monte Carlo tree search"
+    )]
+    depth: Option<usize>,
+
+    #[arg(
+        default_value = "10000",
+        long,
+        help = "Monte Carlo tree search iterations per move"
+    )]
+    iterations: usize,
+
+    #[arg(
+        long,
+        help = "Play winning moves found by the exact solver when there are any, for boards up to 5x5"
+    )]
+    solve: bool,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.log.init();
+    let human = match args.human.as_str() {
+        "X" | "x" => Some(0),
+        "O" | "o" => Some(1),
+        _ => None,
+    };
+
+    let mut search = args
+        .depth
+        .map(|x| DepthLimitedSearch::new(BridgeEvaluator, x));
+    let config = MctsConfig {
+        iterations: args.iterations,
+        ..Default::default()
+    };
+    let mut mcts = Mcts::new(config, RandomPlayout);
+    let mut solver = Solver::default();
+
+    let mut board = Board::new(args.size)?;
+    println!("X joins top and bottom, O joins left and right");
+    println!("{board}");
+    while board.winner().is_none() {
+        if Some(board.turn()) == human {
+            println!("Your move, e.g. a1:");
+            let mut input_buffer = String::new();
+            let _ = stdin().read_line(&mut input_buffer);
+            if let Err(e) = board.cell(&input_buffer).and_then(|x| board.act(x)) {
+                println!("Invalid move: {}", e);
+                continue;
+            }
+        } else {
+            let mut index = match args.solve {
+                true => solver.winning_move(&board),
+                false => None,
+            };
+            if index.is_none() {
+                index = match &mut search {
+                    Some(search) => search.best_move(&board).map(|x| x.0),
+                    None => mcts.best_move(&board),
+                };
+            }
+            match index {
+                Some(index) => {
+                    let player = ["X", "O"][board.turn()];
+                    println!("{} plays {}", player, board.cell_name(index));
+                    let _ = board.act(index);
+                }
+                None => break,
+            }
+        }
+        println!("{board}");
+    }
+
+    if let Some(winner) = board.winner() {
+        println!("{} wins", ["X", "O"][winner]);
+    }
+    Ok(())
+}
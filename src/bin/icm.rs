@@ -0,0 +1,220 @@
+use game_theory_rs::icm::*;
+use game_theory_rs::logging::LogArgs;
+
+use std::path::PathBuf;
+
+use clap::*;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(short, global = true, num_args = 1.., help = "Stack sizes")]
+    stacks: Vec<f64>,
+
+    #[arg(short, global = true, num_args = 1.., help = "Payouts")]
+    payouts: Vec<f64>,
+
+    #[arg(
+        default_value = "mh",
+        long,
+        help = "Place probability model: Malmuth-Harville (mh) or Malmuth-Weitzman (mw)"
+    )]
+    model: IcmModel,
+
+    #[arg(
+        long,
+        num_args = 1..,
+        help = "Bounty on each player's head, for progressive knockout equity"
+    )]
+    bounties: Option<Vec<f64>>,
+
+    #[arg(
+        long,
+        help = "Simulate this many hands of blinds before applying ICM (FGS)"
+    )]
+    fgs: Option<usize>,
+
+    #[arg(
+        default_value = "0.5,1",
+        long,
+        value_delimiter = ',',
+        num_args = 2,
+        help = "Small and big blind for --fgs"
+    )]
+    blinds: Vec<f64>,
+
+    #[arg(
+        default_value = "0",
+        long,
+        help = "Ante posted by every player for --fgs"
+    )]
+    ante: f64,
+
+    #[arg(default_value = "0", long, help = "Player on the button for --fgs")]
+    button: usize,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// ICM equity of calling an all in against folding
+    Diff {
+        #[arg(long, help = "Index of the player deciding whether to call")]
+        caller: usize,
+
+        #[arg(long, help = "Index of the player who went all in")]
+        better: usize,
+
+        #[arg(
+            default_value = "0",
+            long,
+            help = "Chips already in the middle, such as blinds and antes, on top of the stacks"
+        )]
+        pot: f64,
+
+        #[arg(long, help = "Caller's chance to win the all in, to price the call")]
+        equity: Option<f64>,
+    },
+    /// Bubble factor of every player against every other
+    Factors {
+        #[arg(long, help = "Write the matrix to this CSV file")]
+        csv: Option<PathBuf>,
+
+        #[arg(
+            long,
+            help = "Write the matrix with the stacks and payouts to this JSON file"
+        )]
+        json: Option<PathBuf>,
+    },
+    /// Equity each player gains per chip added to each player's stack
+    Gradient,
+    /// Split the remaining payouts by ICM, by chips, or a blend of the two
+    Deal {
+        #[arg(
+            default_value = "0.5",
+            long,
+            help = "Weight of the ICM chop in the blend, the rest going to the chip chop"
+        )]
+        icm_weight: f64,
+
+        #[arg(long, help = "Print the deal as JSON")]
+        json: bool,
+    },
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.log.init();
+    if args.stacks.is_empty() || args.payouts.is_empty() {
+        return Err("Both -s and -p are required".into());
+    }
+    check_tournament(&args.stacks, &args.payouts)?;
+    match args.command {
+        None => {
+            let place_probabilities = args
+                .model
+                .place_probabilities(&args.stacks, args.payouts.len());
+            let icm = equities(&place_probabilities, &args.payouts);
+
+            println!("Place probabilities:");
+            place_probabilities.iter().for_each(|x| println!("{:?}", x));
+            println!("ICM:");
+            println!("{:?}", icm);
+            if let Some(bounties) = &args.bounties {
+                if bounties.len() != args.stacks.len() {
+                    return Err("--bounties needs a bounty for every player".into());
+                }
+                let equities = pko_equities(&args.stacks, &args.payouts, bounties)?;
+                println!("PKO (payouts and bounties):");
+                println!("{:?}", equities);
+            }
+            if let Some(hands) = args.fgs {
+                if args.button >= args.stacks.len() {
+                    return Err("--button must be one of the players".into());
+                }
+                let config = FgsConfig {
+                    sb: args.blinds[0],
+                    bb: args.blinds[1],
+                    ante: args.ante,
+                    button: args.button,
+                    hands,
+                };
+                let equities = fgs(&args.stacks, &args.payouts, &config)?;
+                println!("FGS ({} hands):", hands);
+                println!("{:?}", equities);
+            }
+        }
+        Some(Command::Diff {
+            caller,
+            better,
+            pot,
+            equity,
+        }) => {
+            let n = args.stacks.len();
+            if caller >= n || better >= n || caller == better {
+                return Err("--caller and --better must be two different players".into());
+            }
+            let equities = AllInEquities::new(&args.stacks, &args.payouts, caller, better, pot);
+            let amount = args.stacks[caller].min(args.stacks[better]);
+            println!("Fold: {:.4}", equities.fold);
+            println!("Call and win: {:.4}", equities.win);
+            println!("Call and lose: {:.4}", equities.lose);
+            println!(
+                "Required equity: {:.1}% (chip EV {:.1}%)",
+                equities.required_equity() * 100.,
+                amount / (2. * amount + pot) * 100.
+            );
+            if let Some(equity) = equity {
+                let call = equities.call(equity);
+                println!(
+                    "Call: {:.4} ({:+.4} over folding)",
+                    call,
+                    call - equities.fold
+                );
+            }
+        }
+        Some(Command::Factors { csv, json }) => {
+            let factors = BubbleFactors::new(&args.stacks, &args.payouts);
+            println!("Bubble factors (row player against column player):");
+            println!("{}", factors);
+            if let Some(path) = &csv {
+                factors.write_csv(path)?;
+            }
+            if let Some(path) = &json {
+                std::fs::write(path, serde_json::to_string_pretty(&factors)?)?;
+            }
+        }
+        Some(Command::Gradient) => {
+            println!("Equity per chip (row player, per chip added to the column player):");
+            for row in equity_gradient(&args.stacks, &args.payouts) {
+                let row: Vec<String> = row.iter().map(|x| format!("{:>12.3e}", x)).collect();
+                println!("{}", row.join(" "));
+            }
+        }
+        Some(Command::Deal { icm_weight, json }) => {
+            if !(0. ..=1.).contains(&icm_weight) {
+                return Err("--icm-weight must be between 0 and 1".into());
+            }
+            let deal = Deal::new(&args.stacks, &args.payouts, icm_weight);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&deal)?);
+                return Ok(());
+            }
+            println!(
+                "{:>8} {:>12} {:>12} {:>12} {:>12}",
+                "player", "stack", "icm", "chip chop", "blend"
+            );
+            for (i, stack) in deal.stacks.iter().enumerate() {
+                println!(
+                    "{:>8} {:>12.2} {:>12.2} {:>12.2} {:>12.2}",
+                    i, stack, deal.icm[i], deal.chip_chop[i], deal.blend[i]
+                );
+            }
+        }
+    }
+    Ok(())
+}
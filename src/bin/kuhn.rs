@@ -0,0 +1,260 @@
+use game_theory_rs::cfr::*;
+use game_theory_rs::logging::LogArgs;
+
+use std::path::PathBuf;
+
+use clap::*;
+use ndarray::*;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(
+        default_value = "1000000",
+        short,
+        long,
+        help = "Number of CFR iterations"
+    )]
+    iter: u64,
+
+    #[arg(
+        default_value = "vanilla",
+        long,
+        help = "CFR variant (vanilla, cfr+, linear)"
+    )]
+    variant: CfrVariant,
+
+    #[arg(short, long, help = "Stop once exploitability drops below this value")]
+    target: Option<Float>,
+
+    #[arg(
+        default_value = "1000",
+        long,
+        help = "Iterations between exploitability checks"
+    )]
+    check_every: u64,
+
+    #[arg(long, help = "Update one player's regrets per iteration")]
+    alternating: bool,
+
+    #[arg(
+        long,
+        help = "Average strategy weighting (uniform, linear, last-K); defaults to the variant's"
+    )]
+    averaging: Option<AveragingScheme>,
+
+    #[arg(
+        long,
+        help = "Mix EPSILON[:DECAY] of uniform play into the current strategy, decaying by DECAY per iteration"
+    )]
+    exploration: Option<Exploration>,
+
+    #[arg(long, help = "Write exploitability at every check to this CSV file")]
+    convergence: Option<PathBuf>,
+
+    #[arg(long, help = "Print the EV of every infoset and action")]
+    report: bool,
+
+    #[arg(
+        default_value = "3",
+        long,
+        help = "Number of cards in the deck, from 2 to 12"
+    )]
+    cards: usize,
+
+    #[arg(default_value = "1", long, help = "Bet size, in antes")]
+    bet: Float,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+/// Card names from the highest card down, for decks of up to 12 cards
+const CARD_NAMES: [&str; 12] = ["K", "Q", "J", "T", "9", "8", "7", "6", "5", "4", "3", "2"];
+
+/// Kuhn poker with a deck of `n_cards` cards, indexed from the highest, and bets of `bet` after
+/// antes of 1. States are every ordered pair of distinct cards, by the first player's card then
+/// the second's; both players' infosets are ordered by the card they hold.
+fn build_kuhn_tree(n_cards: usize, bet: Float) -> Box<dyn Node> {
+    // With 3 cards: KQ KJ QK QJ JK JQ
+    let states: Vec<(usize, usize)> = (0..n_cards)
+        .flat_map(|a| (0..n_cards).filter(move |b| *b != a).map(move |b| (a, b)))
+        .collect();
+    let infosets = |player: usize| -> Vec<Vec<usize>> {
+        (0..n_cards)
+            .map(|card| {
+                (0..states.len())
+                    .filter(|i| [states[*i].0, states[*i].1][player] == card)
+                    .collect()
+            })
+            .collect()
+    };
+    let (p1_infosets, p2_infosets) = (infosets(0), infosets(1));
+    // First player's payouts at showdown when each player has put in `pot`
+    let showdown = |pot: Float| -> Array<Float, Ix1> {
+        states
+            .iter()
+            .map(|(a, b)| if a < b { pot } else { -pot })
+            .collect()
+    };
+    let constant = |x: Float| Array::from_elem(states.len(), x);
+
+    TreeBuilder::action("root", 0)
+        .infosets(p1_infosets.clone())
+        .child(
+            TreeBuilder::action("b", 1)
+                .infosets(p2_infosets.clone())
+                .zero_sum("bc", showdown(1. + bet))
+                .zero_sum("bf", constant(1.)),
+        )
+        .child(
+            TreeBuilder::action("x", 1)
+                .infosets(p2_infosets)
+                .child(
+                    TreeBuilder::action("xb", 0)
+                        .infosets(p1_infosets)
+                        .zero_sum("bc", showdown(1. + bet))
+                        .zero_sum("bf", constant(-1.)),
+                )
+                .zero_sum("xx", showdown(1.)),
+        )
+        .build()
+        .unwrap()
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.log.init();
+    if !(2..=CARD_NAMES.len()).contains(&args.cards) || args.bet <= 0. {
+        return Err("--cards must be from 2 to 12 and --bet positive".into());
+    }
+    let cards = &CARD_NAMES[..args.cards];
+    let config = SolverConfig {
+        variant: args.variant,
+        max_iterations: args.iter,
+        target_exploitability: args.target,
+        check_interval: args.check_every,
+        alternating: args.alternating,
+        averaging: args.averaging,
+        record_convergence: args.convergence.is_some(),
+        frozen_players: Vec::new(),
+        exploration: args.exploration,
+    };
+    let mut solver = CfrSolver::new(build_kuhn_tree(args.cards, args.bet), config);
+    let result = solver.solve();
+    let root = &solver.root;
+
+    let root_strategy = root.avg_strategy().unwrap();
+    println!("{}", cards.join(" / "));
+    println!("Root");
+    println!("Bet: {:.3}", root_strategy.slice(s![0, ..]));
+    println!("Check: {:.3}", root_strategy.slice(s![1, ..]));
+
+    let b_strategy = root.children().unwrap()[0].avg_strategy().unwrap();
+    println!("b");
+    println!("Call: {:.3}", b_strategy.slice(s![0, ..]));
+    println!("Fold: {:.3}", b_strategy.slice(s![1, ..]));
+
+    let x_strategy = root.children().unwrap()[1].avg_strategy().unwrap();
+    println!("x");
+    println!("Bet: {:.3}", x_strategy.slice(s![0, ..]));
+    println!("Check: {:.3}", x_strategy.slice(s![1, ..]));
+
+    let x_b_strategy = root.children().unwrap()[1].children().unwrap()[0]
+        .avg_strategy()
+        .unwrap();
+    println!("x_b");
+    println!("Call: {:.3}", x_b_strategy.slice(s![0, ..]));
+    println!("Fold: {:.3}", x_b_strategy.slice(s![1, ..]));
+
+    println!(
+        "Exploitability: {:.6} after {} iterations",
+        result.exploitability, result.iterations
+    );
+    if args.target.is_some() && !result.converged {
+        println!("Target exploitability not reached");
+    }
+
+    if let Some(path) = &args.convergence {
+        solver.write_convergence_csv(path)?;
+    }
+
+    if args.report {
+        // Both players' infosets are ordered by the card they hold
+        println!();
+        for infoset in solver.report() {
+            println!(
+                "{} {} (reach {:.3}): EV {:.3}",
+                infoset.node, cards[infoset.infoset], infoset.reach, infoset.ev
+            );
+            for action in infoset.actions {
+                println!(
+                    "  {}: EV {:.3}, frequency {:.3}",
+                    action.action, action.ev, action.frequency
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kuhn_exploitability() {
+        let mut root = build_kuhn_tree(3, 1.);
+        let config = SolverConfig {
+            variant: CfrVariant::CfrPlus,
+            ..Default::default()
+        };
+        let initial = exploitability(root.as_ref());
+
+        for _ in 0..1000 {
+            root.update_probabilities();
+            root.update_ev();
+            root.update_strategy(&config, None);
+        }
+
+        assert!(initial > 0.1);
+        assert!(exploitability(root.as_ref()) < 0.005);
+        // Game value is 1/18 for the second player at equilibrium
+        assert!((best_response_value(root.as_ref(), 1) - 1. / 18.).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_kuhn_alternating() {
+        let mut solver = CfrSolver::new(
+            build_kuhn_tree(3, 1.),
+            SolverConfig {
+                variant: CfrVariant::CfrPlus,
+                max_iterations: 2000,
+                alternating: true,
+                ..Default::default()
+            },
+        );
+        let result = solver.solve();
+        assert!(result.exploitability < 0.005);
+    }
+
+    #[test]
+    fn test_kuhn_bet_size() {
+        let mut solver = CfrSolver::new(
+            build_kuhn_tree(4, 2.),
+            SolverConfig {
+                variant: CfrVariant::CfrPlus,
+                max_iterations: 5000,
+                ..Default::default()
+            },
+        );
+        let result = solver.solve();
+        assert!(result.exploitability < 0.01);
+
+        // Checked to, the second player bets their lowest card often enough that calling a bet of
+        // 2 into a pot of 2 breaks even: bet / (2 + bet) as often as the highest card
+        let x = &solver.root.children().unwrap()[1];
+        let bets = x.avg_strategy().unwrap().row(0).to_owned();
+        assert!(bets[0] > 0.95);
+        assert!((bets[3] - 0.5).abs() < 0.05);
+    }
+}
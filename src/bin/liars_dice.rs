@@ -0,0 +1,120 @@
+use game_theory_rs::cfr::*;
+use game_theory_rs::liars_dice::*;
+use game_theory_rs::logging::LogArgs;
+
+use std::path::PathBuf;
+
+use clap::*;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(
+        default_value = "100000",
+        short,
+        long,
+        help = "Number of CFR iterations"
+    )]
+    iter: u64,
+
+    #[arg(
+        default_value = "cfr+",
+        long,
+        help = "CFR variant (vanilla, cfr+, linear)"
+    )]
+    variant: CfrVariant,
+
+    #[arg(short, long, help = "Stop once exploitability drops below this value")]
+    target: Option<Float>,
+
+    #[arg(
+        default_value = "1000",
+        long,
+        help = "Iterations between exploitability checks"
+    )]
+    check_every: u64,
+
+    #[arg(long, help = "Update one player's regrets per iteration")]
+    alternating: bool,
+
+    #[arg(
+        long,
+        help = "Average strategy weighting (uniform, linear, last-K); defaults to the variant's"
+    )]
+    averaging: Option<AveragingScheme>,
+
+    #[arg(long, help = "Write exploitability at every check to this CSV file")]
+    convergence: Option<PathBuf>,
+
+    #[arg(long, help = "Print the EV of every infoset and action")]
+    report: bool,
+
+    #[arg(default_value = "1", long, help = "Dice rolled by each player")]
+    dice: usize,
+
+    #[arg(default_value = "6", long, help = "Faces of each die, from 2 to 9")]
+    faces: usize,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.log.init();
+    if args.dice == 0 || !(2..=9).contains(&args.faces) {
+        return Err("--dice must be nonzero and --faces from 2 to 9".into());
+    }
+    let game = LiarsDiceConfig {
+        dice: args.dice,
+        faces: args.faces,
+    };
+    let rolls = rolls(&game);
+    let config = SolverConfig {
+        variant: args.variant,
+        max_iterations: args.iter,
+        target_exploitability: args.target,
+        check_interval: args.check_every,
+        alternating: args.alternating,
+        averaging: args.averaging,
+        record_convergence: args.convergence.is_some(),
+        ..Default::default()
+    };
+    let mut solver = CfrSolver::new(build_liars_dice_tree(&game)?, config);
+    let result = solver.solve();
+
+    let root = &solver.root;
+    let value = root.avg_strategy_payouts().dot(&root.state_probabilities())[0];
+    println!("Game value for the first player: {:.4}", value);
+    println!(
+        "Exploitability: {:.6} after {} iterations",
+        result.exploitability, result.iterations
+    );
+    if args.target.is_some() && !result.converged {
+        println!("Target exploitability not reached");
+    }
+
+    if let Some(path) = &args.convergence {
+        solver.write_convergence_csv(path)?;
+    }
+
+    if args.report {
+        // Infosets are ordered by the roll the acting player sees
+        println!();
+        for infoset in solver.report() {
+            println!(
+                "{} {} (reach {:.3}): EV {:.3}",
+                infoset.node,
+                roll_name(&rolls[infoset.infoset]),
+                infoset.reach,
+                infoset.ev
+            );
+            for action in infoset.actions {
+                println!(
+                    "  {}: EV {:.3}, frequency {:.3}",
+                    action.action, action.ev, action.frequency
+                );
+            }
+        }
+    }
+    Ok(())
+}
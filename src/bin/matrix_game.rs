@@ -0,0 +1,98 @@
+use game_theory_rs::cfr::*;
+use game_theory_rs::logging::LogArgs;
+use game_theory_rs::matrix_game::*;
+
+use std::path::PathBuf;
+
+use clap::*;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(
+        default_value = "10000",
+        short,
+        long,
+        help = "Number of CFR iterations"
+    )]
+    iter: u64,
+
+    #[arg(
+        default_value = "cfr+",
+        long,
+        help = "CFR variant (vanilla, cfr+, linear)"
+    )]
+    variant: CfrVariant,
+
+    #[arg(short, long, help = "Stop once exploitability drops below this value")]
+    target: Option<Float>,
+
+    #[arg(
+        default_value = "1000",
+        long,
+        help = "Iterations between exploitability checks"
+    )]
+    check_every: u64,
+
+    #[arg(long, help = "Update one player's regrets per iteration")]
+    alternating: bool,
+
+    #[arg(
+        long,
+        help = "Average strategy weighting (uniform, linear, last-K); defaults to the variant's"
+    )]
+    averaging: Option<AveragingScheme>,
+
+    #[arg(long, help = "Write exploitability at every check to this CSV file")]
+    convergence: Option<PathBuf>,
+
+    #[arg(
+        default_value = "rps",
+        long,
+        help = "rps, biased-rps, or the first player's payouts in a zero-sum game, e.g. \"1,-1;-1,1\""
+    )]
+    game: MatrixGame,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.log.init();
+    let config = SolverConfig {
+        variant: args.variant,
+        max_iterations: args.iter,
+        target_exploitability: args.target,
+        check_interval: args.check_every,
+        alternating: args.alternating,
+        averaging: args.averaging,
+        record_convergence: args.convergence.is_some(),
+        ..Default::default()
+    };
+    let mut solver = CfrSolver::new(args.game.build_tree()?, config);
+    let result = solver.solve();
+
+    let root = &solver.root;
+    let value = root.avg_strategy_payouts().dot(&root.state_probabilities())[0];
+    println!("Game value for the first player: {:.4}", value);
+    println!(
+        "Exploitability: {:.6} after {} iterations",
+        result.exploitability, result.iterations
+    );
+    if args.target.is_some() && !result.converged {
+        println!("Target exploitability not reached");
+    }
+
+    if let Some(path) = &args.convergence {
+        solver.write_convergence_csv(path)?;
+    }
+
+    for (player, strategy) in avg_strategies(solver.root.as_ref()).iter().enumerate() {
+        println!();
+        println!("Player {}:", player + 1);
+        for (action, frequency) in args.game.actions[player].iter().zip(strategy) {
+            println!("  {}: {:.3}", action, frequency);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use game_theory_rs::error::SolverError;
+use game_theory_rs::logging::LogArgs;
+use game_theory_rs::poker::multiway::*;
+use game_theory_rs::poker::preflop::*;
+
+use std::time::Instant;
+
+use clap::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use tracing::{debug, info};
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(
+        long,
+        help = "Also compute the equity table, which takes several minutes"
+    )]
+    equities: bool,
+
+    #[arg(
+        long,
+        help = "Also compute three-way equities from this many sampled deals per matchup"
+    )]
+    three_way: Option<usize>,
+
+    #[arg(default_value = "0", long, help = "Seed for sampling three-way deals")]
+    seed: u64,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+/// Progress callback for the matchups of `table`: a progress bar with an ETA on stderr, or log
+/// events with `--log-json`, where a bar would break up the JSON lines. The bar is hidden by `-q`,
+/// and also when stderr isn't a terminal.
+fn progress(table: &'static str, log: &LogArgs) -> Box<dyn Fn(usize, usize) + Sync> {
+    if log.log_json {
+        return Box::new(log_progress(table));
+    }
+    let bar = match log.quiet {
+        0 => ProgressBar::new(0),
+        _ => ProgressBar::hidden(),
+    };
+    bar.set_style(
+        ProgressStyle::with_template("{msg}: {wide_bar} {pos}/{len} matchups, ETA {eta}").unwrap(),
+    );
+    bar.set_message(table);
+    // Called once per finished matchup, from rayon's threads
+    Box::new(move |done, total| {
+        bar.set_length(total as u64);
+        bar.inc(1);
+        if done == total {
+            bar.finish_and_clear();
+            info!(
+                table,
+                elapsed = bar.elapsed().as_secs_f64(),
+                "Computed {} matchups",
+                total
+            );
+        }
+    })
+}
+
+/// Progress callback logging how many matchups of `table` are done, at every percent since
+/// matchups finish hundreds of times a second: every 10% at info level and the rest at debug level.
+/// It runs on rayon's threads, so the table is a field of each event rather than a span.
+fn log_progress(table: &'static str) -> impl Fn(usize, usize) + Sync {
+    let start = Instant::now();
+    move |done, total| {
+        let percent = done * 100 / total;
+        if percent == (done - 1) * 100 / total && done < total {
+            return;
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        let eta = elapsed / done as f64 * (total - done) as f64;
+        match percent % 10 {
+            0 => info!(table, done, total, elapsed, eta, "{}% of matchups", percent),
+            _ => debug!(table, done, total, elapsed, eta, "{}% of matchups", percent),
+        }
+    }
+}
+
+fn main() -> Result<(), SolverError> {
+    let args = Args::parse();
+    args.log.init();
+    if args.equities {
+        build_matchup_equities(progress("equities", &args.log))?;
+    }
+    if let Some(n_samples) = args.three_way {
+        build_three_way_equities(
+            n_samples,
+            args.seed,
+            progress("three-way equities", &args.log),
+        )?;
+    }
+    info!("Computing matchup probabilities");
+    build_matchup_probabilities()?;
+    Ok(())
+}
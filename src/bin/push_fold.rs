@@ -0,0 +1,783 @@
+use game_theory_rs::cfr::*;
+use game_theory_rs::error::SolverError;
+#[cfg(feature = "gpu")]
+use game_theory_rs::gpu::GpuArenaTree;
+use game_theory_rs::icm;
+use game_theory_rs::logging::LogArgs;
+use game_theory_rs::poker::combos;
+use game_theory_rs::poker::push_fold::*;
+use game_theory_rs::poker::range::*;
+use game_theory_rs::poker::three_handed;
+
+use std::fs::File;
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use clap::*;
+use ndarray::*;
+use tracing::{info, info_span};
+
+#[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Args {
+    #[arg(default_value = "10.0", help = "Stack size")]
+    stack_size: f64,
+
+    #[arg(default_value = "0.125", short, long, help = "Ante")]
+    ante: f64,
+
+    #[arg(
+        default_value = "each",
+        long,
+        help = "Who posts the ante: every player (each), or only the big blind (bb) or button (button)"
+    )]
+    ante_structure: AnteStructure,
+
+    #[arg(default_value = "0.5", short, long, help = "Small blind")]
+    sb: f64,
+
+    #[arg(
+        default_value = "0",
+        long,
+        help = "Fraction of the pot taken as rake, e.g. 0.05"
+    )]
+    rake: f64,
+
+    #[arg(long, help = "Most rake taken from a pot, in big blinds")]
+    rake_cap: Option<f64>,
+
+    #[arg(long, help = "Only rake pots that see a flop")]
+    no_flop_no_drop: bool,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Scale each player's losses by their bubble factor, e.g. 1.4,1.2 for the SB and BB"
+    )]
+    bubble_factors: Option<Vec<f64>>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Derive bubble factors from ICM with these stacks, the SB's and BB's first"
+    )]
+    icm_stacks: Option<Vec<f64>>,
+
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Tournament payouts for --icm-stacks, e.g. 0.5,0.3,0.2"
+    )]
+    icm_payouts: Option<Vec<f64>>,
+
+    #[arg(default_value = "100", short, long, help = "Number of CFR iterations")]
+    iter: u64,
+
+    #[arg(
+        default_value = "vanilla",
+        long,
+        help = "CFR variant (vanilla, cfr+, linear)"
+    )]
+    variant: CfrVariant,
+
+    #[arg(
+        short,
+        long,
+        help = "Stop once exploitability drops below this value, in mbb/hand"
+    )]
+    target: Option<Float>,
+
+    #[arg(
+        default_value = "10",
+        long,
+        help = "Iterations between exploitability checks"
+    )]
+    check_every: u64,
+
+    #[arg(long, help = "Update one player's regrets per iteration")]
+    alternating: bool,
+
+    #[arg(
+        long,
+        help = "Average strategy weighting (uniform, linear, last-K); defaults to the variant's"
+    )]
+    averaging: Option<AveragingScheme>,
+
+    #[arg(
+        long,
+        help = "Mix EPSILON[:DECAY] of uniform play into the current strategy, decaying by DECAY per iteration"
+    )]
+    exploration: Option<Exploration>,
+
+    #[arg(long, help = "Write the solved tree to this file")]
+    save: Option<PathBuf>,
+
+    #[arg(long, help = "Seed the solve from a tree written by --save")]
+    warm_start: Option<PathBuf>,
+
+    #[arg(
+        default_value = "10.0",
+        long,
+        help = "Number of iterations the warm start strategy counts for"
+    )]
+    warm_start_weight: Float,
+
+    #[arg(long, help = "Periodically write the solver state to this file")]
+    checkpoint: Option<PathBuf>,
+
+    #[arg(default_value = "100", long, help = "Iterations between checkpoints")]
+    checkpoint_every: u64,
+
+    #[arg(
+        long,
+        help = "Also write a checkpoint after this many seconds since the last one"
+    )]
+    checkpoint_seconds: Option<u64>,
+
+    #[arg(long, help = "Continue a solve from a file written by --checkpoint")]
+    resume: Option<PathBuf>,
+
+    #[arg(long, help = "Solve on a flattened, arena-backed copy of the tree")]
+    arena: bool,
+
+    #[cfg(feature = "gpu")]
+    #[arg(
+        long,
+        help = "Run the probability and EV passes of the arena on the GPU; implies --arena"
+    )]
+    gpu: bool,
+
+    #[arg(long, help = "Write exploitability at every check to this CSV file")]
+    convergence: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Drop actions played less often than this and renormalize"
+    )]
+    threshold: Option<Float>,
+
+    #[arg(long, help = "Play only the most frequent action of each hand")]
+    purify: bool,
+
+    #[arg(
+        long,
+        help = "Write the EV of every hand and action to this file as JSON"
+    )]
+    report: Option<PathBuf>,
+
+    #[arg(long, help = "Hold this seat's strategy fixed (sb, bb) and exploit it")]
+    freeze: Option<Seat>,
+
+    #[arg(
+        long,
+        help = "Fixed strategy of the frozen seat as a range, e.g. \"22+,ATs+:0.5,KQo\""
+    )]
+    frozen_range: Option<String>,
+
+    #[arg(
+        long,
+        help = "Take the frozen seat's strategy from a tree written by --save"
+    )]
+    frozen_tree: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Fix the BB's calling range, e.g. \"22+,A7s+,KQs\", and print the SB's best response instead of solving"
+    )]
+    villain_call_range: Option<String>,
+
+    #[arg(
+        default_value = "shove,fold",
+        long,
+        value_delimiter = ',',
+        help = "Opening actions of the SB (shove, minraise, limp, fold)"
+    )]
+    sb_actions: Vec<SbAction>,
+
+    #[arg(
+        long,
+        help = "Only deal the SB hands in this range, e.g. \"22+,ATs+:0.5,KQo\""
+    )]
+    sb_range: Option<String>,
+
+    #[arg(long, help = "Only deal the BB hands in this range")]
+    bb_range: Option<String>,
+
+    #[arg(
+        long,
+        help = "Print charts in range notation, with the hands played at least this often"
+    )]
+    range: Option<Float>,
+
+    #[arg(
+        long,
+        help = "Print charts as 13x13 grids, shaded by frequency on a terminal"
+    )]
+    grid: bool,
+
+    #[arg(
+        long,
+        help = "Write the SB's first action as a 13x13 SVG heatmap to this file"
+    )]
+    svg: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Solve every stack size in START:END:STEP and print the first SB action's frequencies as CSV"
+    )]
+    sweep: Option<StackSweep>,
+
+    #[arg(
+        long,
+        help = "Solve over the 1326 combos instead of the 169 hand classes, and compare the two"
+    )]
+    combos: bool,
+
+    #[arg(
+        long,
+        value_parser = parse_board,
+        help = "Board cards already dealt, like AhKd7c, to solve shove or fold on that board"
+    )]
+    board: Option<u64>,
+
+    #[arg(long, help = "Solve three-handed (BTN, SB, BB) over sampled deals")]
+    three_handed: bool,
+
+    #[arg(
+        default_value = "100000",
+        long,
+        help = "Number of deals sampled for --three-handed"
+    )]
+    deals: usize,
+
+    #[arg(default_value = "0", long, help = "Seed for sampling deals")]
+    seed: u64,
+
+    #[cfg(feature = "server")]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+#[cfg(feature = "server")]
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Serve the ICM calculator and push-fold solver as a JSON API over HTTP
+    Serve {
+        #[arg(default_value = "127.0.0.1:3000", long, help = "Address to listen on")]
+        addr: std::net::SocketAddr,
+    },
+}
+
+/// Prints a chart of the hands playing an action, given its frequency for every hand, as a list,
+/// in range notation (`--range`) or as a grid (`--grid`)
+fn print_chart(frequencies: ArrayView1<Float>, args: &Args) {
+    let frequencies = &by_class(frequencies, args);
+    let frequencies = frequencies.view();
+    if args.grid {
+        println!(
+            "{}",
+            render_grid(frequencies, std::io::stdout().is_terminal())
+        );
+        return;
+    }
+    if let Some(threshold) = args.range {
+        println!("{}", to_range_notation(frequencies, threshold));
+        println!();
+        return;
+    }
+    frequencies
+        .iter()
+        .enumerate()
+        .map(|(index, strategy)| {
+            let name = Hand::index_to_str(index);
+            if *strategy > 0.999 {
+                print!("{}", name);
+                if index < 168 {
+                    print!(",");
+                }
+            } else if *strategy > 0.001 {
+                print!("{}:{:.3}", name, strategy);
+                if index < 168 {
+                    print!(",");
+                }
+            }
+        })
+        .for_each(drop);
+    println!();
+    println!();
+}
+
+/// Frequencies of an action by hand class, averaging over combos with `--combos`
+fn by_class(frequencies: ArrayView1<Float>, args: &Args) -> Array<Float, Ix1> {
+    match args.combos {
+        true => combos::class_frequencies(frequencies),
+        false => frequencies.to_owned(),
+    }
+}
+
+/// Rake configured by `--rake`, `--rake-cap` and `--no-flop-no-drop`
+fn rake(args: &Args) -> Rake {
+    Rake {
+        fraction: args.rake,
+        cap: args.rake_cap.unwrap_or(f64::INFINITY),
+        no_flop_no_drop: args.no_flop_no_drop,
+    }
+}
+
+/// Bubble factors of each player, given by `--bubble-factors` or derived from `--icm-stacks` and
+/// `--icm-payouts`
+fn bubble_factors(args: &Args) -> Result<Option<Vec<f64>>, Box<dyn std::error::Error>> {
+    let n_players = if args.three_handed { 3 } else { 2 };
+    let factors = match (&args.bubble_factors, &args.icm_stacks, &args.icm_payouts) {
+        (None, None, None) => return Ok(None),
+        (Some(factors), None, None) => factors.clone(),
+        (None, Some(stacks), Some(payouts)) if !args.three_handed => {
+            if stacks.len() < 2 || stacks.iter().any(|x| *x <= 0.) {
+                return Err("--icm-stacks needs at least the SB's and BB's stacks".into());
+            }
+            vec![
+                icm::bubble_factor(stacks, payouts, 0, 1),
+                icm::bubble_factor(stacks, payouts, 1, 0),
+            ]
+        }
+        _ => {
+            return Err(
+                "Use either --bubble-factors, or --icm-stacks with --icm-payouts heads-up".into(),
+            )
+        }
+    };
+    if factors.len() != n_players || factors.iter().any(|x| !(*x > 0. && x.is_finite())) {
+        return Err(format!("Expected {} positive bubble factors", n_players).into());
+    }
+    Ok(Some(factors))
+}
+
+/// Flattens the tree with --arena, and also moves it onto the GPU with --gpu
+fn flatten(root: Box<dyn Node>, args: &Args) -> Result<Box<dyn Node>, Box<dyn std::error::Error>> {
+    #[cfg(feature = "gpu")]
+    if args.gpu {
+        return Ok(Box::new(GpuArenaTree::new(ArenaTree::from_tree(root))?));
+    }
+    Ok(match args.arena {
+        true => Box::new(ArenaTree::from_tree(root)),
+        false => root,
+    })
+}
+
+/// States of the heads-up tree, by combo with `--combos` and by hand class otherwise
+fn load_tables(args: &Args) -> Result<PreflopTables, SolverError> {
+    match (args.combos, args.board) {
+        (true, _) => Ok(combos::combo_tables(&combos::combo_equities()?)),
+        (false, Some(board)) => PreflopTables::on_board(board),
+        (false, None) => PreflopTables::load(),
+    }
+}
+
+/// Applies `--sb-range` and `--bb-range` to a heads-up tree
+fn restrict(root: &mut dyn Node, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    if args.sb_range.is_none() && args.bb_range.is_none() {
+        return Ok(());
+    }
+    let parse = |range: &Option<String>| match range {
+        Some(x) => parse_range(x),
+        None => Ok(Array::ones(169)),
+    };
+    Ok(restrict_ranges(
+        root,
+        &parse(&args.sb_range)?,
+        &parse(&args.bb_range)?,
+    )?)
+}
+
+/// Solves each stack size of the sweep, warm starting from the previous one, and prints the
+/// frequency of the SB's first action for every hand and stack
+fn sweep(
+    args: &Args,
+    config: SolverConfig,
+    sweep: StackSweep,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if args.three_handed || args.resume.is_some() || args.freeze.is_some() {
+        return Err("--sweep is not supported with --three-handed, --resume or --freeze".into());
+    }
+    let tables = load_tables(args)?;
+    let bubble_factors = bubble_factors(args)?;
+    let stacks = sweep.stacks();
+    let mut previous = match &args.warm_start {
+        Some(path) => Some(StrategyProfile::from_tree(load_tree(path)?.as_ref())),
+        None => None,
+    };
+    let mut frequencies = Vec::with_capacity(stacks.len());
+    for stack_size in &stacks {
+        let mut root = tables.build_tree(
+            *stack_size,
+            args.ante,
+            args.ante_structure,
+            args.sb,
+            &args.sb_actions,
+            &rake(args),
+        )?;
+        restrict(root.as_mut(), args)?;
+        if let Some(factors) = &bubble_factors {
+            apply_bubble_factors(root.as_mut(), factors);
+        }
+        if let Some(profile) = &previous {
+            warm_start(root.as_mut(), profile, args.warm_start_weight);
+        }
+        let mut solver = CfrSolver::new(flatten(root, args)?, config.clone());
+        let result = solver.solve();
+        info!(
+            stack = stack_size,
+            iterations = result.iterations,
+            "Solved to {:.3} mbb/hand",
+            result.exploitability * 1000.
+        );
+        frequencies.push(by_class(solver.root.avg_strategy().unwrap().row(0), args));
+        previous = Some(StrategyProfile::from_tree(solver.root.as_ref()));
+    }
+
+    println!(
+        "hand,{}",
+        stacks
+            .iter()
+            .map(f64::to_string)
+            .collect::<Vec<String>>()
+            .join(",")
+    );
+    for hand in 0..169 {
+        println!(
+            "{},{}",
+            Hand::index_to_str(hand),
+            frequencies
+                .iter()
+                .map(|x| format!("{:.3}", x[hand]))
+                .collect::<Vec<String>>()
+                .join(",")
+        );
+    }
+    Ok(())
+}
+
+/// Prints the SB's maximally exploitative shoving range against the BB calling with
+/// `call_range`, and its EV, from a single best response pass rather than a CFR solve
+fn exploit(args: &Args, call_range: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut root = load_tables(args)?.build_tree(
+        args.stack_size,
+        args.ante,
+        args.ante_structure,
+        args.sb,
+        &args.sb_actions,
+        &rake(args),
+    )?;
+    restrict(root.as_mut(), args)?;
+    if let Some(factors) = bubble_factors(args)? {
+        apply_bubble_factors(root.as_mut(), &factors);
+    }
+    let shove = exploit_call_range(root.as_mut(), &parse_range(call_range)?);
+    print_chart(shove.view(), args);
+
+    let reach = root.state_probabilities().to_owned();
+    println!(
+        "SB EV against the calling range: {:.3} bb",
+        root.avg_strategy_payouts().dot(&reach)[0]
+    );
+    if let Some(path) = &args.svg {
+        std::fs::write(path, render_svg(shove.view()))?;
+    }
+    if let Some(path) = &args.save {
+        save_tree(root.as_ref(), path)?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.log.init();
+    #[cfg(feature = "server")]
+    if let Some(Command::Serve { addr }) = &args.command {
+        use game_theory_rs::server::{serve, ServerState};
+        let state = ServerState::load()?;
+        info!("Listening on http://{}", addr);
+        tokio::runtime::Runtime::new()?.block_on(serve(*addr, state))?;
+        return Ok(());
+    }
+    let config = SolverConfig {
+        variant: args.variant,
+        max_iterations: args.iter,
+        // Payouts are in big blinds
+        target_exploitability: args.target.map(|x| x / 1000.),
+        check_interval: args.check_every,
+        alternating: args.alternating,
+        averaging: args.averaging,
+        record_convergence: args.convergence.is_some(),
+        frozen_players: args.freeze.iter().map(Seat::player).collect(),
+        exploration: args.exploration,
+    };
+    if args.three_handed && args.freeze.is_some() {
+        return Err("--freeze is not supported with --three-handed".into());
+    }
+    let push_fold = args.sb_actions.len() == 2
+        && args.sb_actions.contains(&SbAction::Shove)
+        && args.sb_actions.contains(&SbAction::Fold);
+    if args.three_handed && !push_fold {
+        return Err("--sb-actions is not supported with --three-handed".into());
+    }
+    if args.three_handed && (args.sb_range.is_some() || args.bb_range.is_some()) {
+        return Err("--sb-range and --bb-range are not supported with --three-handed".into());
+    }
+    if args.three_handed && rake(&args) != Rake::default() {
+        return Err("Rake is not supported with --three-handed".into());
+    }
+    if !(0. ..1.).contains(&args.rake) || args.rake_cap.is_some_and(|x| x < 0.) {
+        return Err("--rake must be in [0, 1) and --rake-cap at least 0".into());
+    }
+    let ranges = args.sb_range.is_some() || args.bb_range.is_some() || args.frozen_range.is_some();
+    if args.combos && (args.three_handed || ranges) {
+        return Err("--combos is not supported with --three-handed or ranges".into());
+    }
+    if args.board.is_some() && (args.combos || args.three_handed || args.frozen_range.is_some()) {
+        return Err(
+            "--board is not supported with --combos, --three-handed or --frozen-range".into(),
+        );
+    }
+    if args.frozen_range.is_some() && !push_fold {
+        return Err("--frozen-range needs --sb-actions shove,fold; use --frozen-tree".into());
+    }
+    if let Some(x) = args.sweep {
+        return sweep(&args, config, x);
+    }
+    if let Some(call_range) = &args.villain_call_range {
+        if !push_fold || args.three_handed || args.combos || args.freeze.is_some() {
+            return Err(
+                "--villain-call-range is only supported for heads-up push/fold over hand classes"
+                    .into(),
+            );
+        }
+        return exploit(&args, call_range);
+    }
+
+    let mut solver = match &args.resume {
+        Some(path) => {
+            let mut solver = CfrSolver::load_checkpoint(path)?;
+            // Keep the checkpoint's variant and update scheme, but let the stopping criteria change
+            solver.config.max_iterations = config.max_iterations;
+            solver.config.target_exploitability = config.target_exploitability;
+            solver.config.check_interval = config.check_interval;
+            solver.config.record_convergence = config.record_convergence;
+            solver.config.frozen_players = config.frozen_players.clone();
+            #[cfg(feature = "gpu")]
+            if args.gpu {
+                let root = std::mem::replace(&mut solver.root, Box::new(ArenaTree::default()));
+                solver.root = flatten(root, &args)?;
+            }
+            solver
+        }
+        None => {
+            let span = info_span!("build_tree", stack = args.stack_size).entered();
+            let start = Instant::now();
+            let mut root = match args.three_handed {
+                true => three_handed::build_three_handed_tree(
+                    &three_handed::sample_deals(args.deals, args.seed),
+                    args.stack_size,
+                    args.ante,
+                    args.ante_structure,
+                    args.sb,
+                )?,
+                false => load_tables(&args)?.build_tree(
+                    args.stack_size,
+                    args.ante,
+                    args.ante_structure,
+                    args.sb,
+                    &args.sb_actions,
+                    &rake(&args),
+                )?,
+            };
+            if !args.three_handed {
+                restrict(root.as_mut(), &args)?;
+            }
+            if let Some(factors) = bubble_factors(&args)? {
+                info!("Bubble factors: {:?}", factors);
+                apply_bubble_factors(root.as_mut(), &factors);
+            }
+            if let Some(path) = &args.warm_start {
+                let previous = load_tree(path)?;
+                warm_start(
+                    root.as_mut(),
+                    &StrategyProfile::from_tree(previous.as_ref()),
+                    args.warm_start_weight,
+                );
+            }
+            if let Some(seat) = args.freeze {
+                let profile = match (&args.frozen_range, &args.frozen_tree) {
+                    (Some(chart), _) => {
+                        let frequencies = parse_range(chart)?;
+                        StrategyProfile {
+                            strategies: [(
+                                seat.node_path().to_string(),
+                                stack![Axis(0), frequencies, 1. - &frequencies],
+                            )]
+                            .into(),
+                        }
+                    }
+                    (None, Some(path)) => StrategyProfile::from_tree(load_tree(path)?.as_ref()),
+                    (None, None) => {
+                        return Err("--freeze needs --frozen-range or --frozen-tree".into())
+                    }
+                };
+                freeze_strategies(root.as_mut(), &profile, seat.player());
+            }
+            let root = flatten(root, &args)?;
+            info!(elapsed = start.elapsed().as_secs_f64(), "Built tree");
+            drop(span);
+            CfrSolver::new(root, config)
+        }
+    };
+    solver.checkpoint = args.checkpoint.clone().map(|path| CheckpointConfig {
+        path,
+        every_iterations: Some(args.checkpoint_every),
+        every_duration: args.checkpoint_seconds.map(std::time::Duration::from_secs),
+    });
+    let start = Instant::now();
+    let result = solver.solve();
+    let elapsed = start.elapsed();
+
+    // The output below walks the tree's children, so unflatten it first
+    let flattened = match solver.root.as_node_mut() {
+        NodeMut::Arena(x) => Some(std::mem::take(x).into_tree()),
+        _ => None,
+    };
+    if let Some(tree) = flattened {
+        solver.root = tree;
+    }
+
+    let purification = match (args.purify, args.threshold) {
+        (true, _) => Some(Purification::Pure),
+        (false, Some(threshold)) => Some(Purification::Threshold(threshold)),
+        (false, None) => None,
+    };
+    let purified_exploitability = purification.map(|purification| {
+        purify(solver.root.as_mut(), purification);
+        exploitability(solver.root.as_ref())
+    });
+    let root = &solver.root;
+
+    if args.three_handed {
+        // Every action node's infosets are keyed by the acting player's hand
+        let mut stack = vec![root.as_ref()];
+        while let Some(node) = stack.pop() {
+            if let Some(frequencies) = three_handed::class_frequencies(node) {
+                println!("{}:", node.name());
+                print_chart(frequencies.view(), &args);
+            }
+            if let Some(children) = node.children() {
+                stack.extend(children.iter().rev().map(|x| x.as_ref()));
+            }
+        }
+    } else if !push_fold {
+        // One chart per action, since the first action no longer tells the whole story
+        let mut stack = vec![root.as_ref()];
+        while let Some(node) = stack.pop() {
+            if let (Some(strategy), Some(children)) = (node.avg_strategy(), node.children()) {
+                for (action, child) in children.iter().enumerate() {
+                    println!("{} -> {}:", node.name(), child.name());
+                    print_chart(strategy.slice(s![action, ..]), &args);
+                }
+            }
+            if let Some(children) = node.children() {
+                stack.extend(children.iter().rev().map(|x| x.as_ref()));
+            }
+        }
+    } else {
+        print_chart(root.avg_strategy().unwrap().slice(s![0, ..]), &args);
+        print_chart(
+            root.children().unwrap()[0]
+                .avg_strategy()
+                .unwrap()
+                .slice(s![0, ..]),
+            &args,
+        );
+    }
+
+    if let Some(path) = &args.svg {
+        std::fs::write(
+            path,
+            render_svg(by_class(root.avg_strategy().unwrap().row(0), &args).view()),
+        )?;
+    }
+
+    if args.combos {
+        // Solve the same game over hand classes to see what the bucketing loses
+        let mut tree = PreflopTables::load()?.build_tree(
+            args.stack_size,
+            args.ante,
+            args.ante_structure,
+            args.sb,
+            &args.sb_actions,
+            &rake(&args),
+        )?;
+        if let Some(factors) = bubble_factors(&args)? {
+            apply_bubble_factors(tree.as_mut(), &factors);
+        }
+        let mut classes = CfrSolver::new(tree, solver.config.clone());
+        classes.solve();
+        let differences = (by_class(root.avg_strategy().unwrap().row(0), &args)
+            - classes.root.avg_strategy().unwrap().row(0))
+        .mapv(Float::abs);
+        let (hand, largest) =
+            differences
+                .iter()
+                .enumerate()
+                .fold((0, 0.), |f, (i, x)| if *x > f.1 { (i, *x) } else { f });
+        println!(
+            "Largest difference from the hand class solution: {:.3} ({}), mean {:.4}",
+            largest,
+            Hand::index_to_str(hand),
+            differences.mean().unwrap()
+        );
+    }
+
+    println!(
+        "Exploitability: {:.3} mbb/hand after {} iterations",
+        result.exploitability * 1000.,
+        result.iterations
+    );
+    if let Some(seat) = args.freeze {
+        // Exploitability is dominated by the frozen seat; what matters is how well the other seat
+        // exploits it
+        let exploiter = 1 - seat.player();
+        let reach = root.state_probabilities().to_owned();
+        println!(
+            "Exploiting EV: {:.3} bb (best response {:.3} bb)",
+            root.avg_strategy_payouts().dot(&reach)[exploiter],
+            best_response_value(root.as_ref(), exploiter)
+        );
+    }
+    if let Some(x) = purified_exploitability {
+        println!(
+            "Exploitability after purification: {:.3} mbb/hand",
+            x * 1000.
+        );
+    }
+    println!("Solved in {:.2}s", elapsed.as_secs_f64());
+    if args.target.is_some() && !result.converged {
+        println!("Target exploitability not reached");
+    }
+
+    if let Some(path) = &args.convergence {
+        solver.write_convergence_csv(path)?;
+    }
+
+    if let Some(path) = &args.report {
+        let report = solver.report();
+        let writer = std::io::BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, &report)?;
+    }
+
+    if let Some(path) = &args.save {
+        save_tree(solver.root.as_ref(), path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use game_theory_rs::agent::*;
+use game_theory_rs::arena;
+use game_theory_rs::logging::LogArgs;
+use game_theory_rs::mcts::{Mcts, MctsConfig, RandomPlayout};
+use game_theory_rs::search::GameState as _;
+use game_theory_rs::tictactoe::*;
+
+use std::io::stdin;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use clap::*;
+
+/// Who controls one side of the board
+#[derive(Clone, Copy, Debug)]
+enum AgentKind {
+    Human,
+    Minimax,
+    Random,
+    Mcts,
+}
+
+impl FromStr for AgentKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(AgentKind::Human),
+            "minimax" => Ok(AgentKind::Minimax),
+            "random" => Ok(AgentKind::Random),
+            "mcts" => Ok(AgentKind::Mcts),
+            _ => Err(format!("Unknown agent: {}", s)),
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(
+        short = 'x',
+        long,
+        default_value = "human",
+        help = "Agent playing X: human, minimax, random or mcts"
+    )]
+    x_agent: AgentKind,
+
+    #[arg(
+        short = 'o',
+        long,
+        default_value = "minimax",
+        help = "Agent playing O: human, minimax, random or mcts"
+    )]
+    o_agent: AgentKind,
+
+    #[arg(
+        default_value = "10000",
+        long,
+        help = "Monte Carlo tree search iterations per move"
+    )]
+    iterations: usize,
+
+    #[arg(
+        default_value = "perfect",
+        long,
+        help = "Skill of minimax agents: easy, medium, hard, perfect, epsilon=<p> or depth=<n>"
+    )]
+    skill: Skill,
+
+    #[arg(long, help = "Search each minimax move's replies on separate threads")]
+    parallel: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Play the X and O agents against each other, alternating who moves first
+    Arena {
+        #[arg(default_value = "100", long, help = "Number of games")]
+        games: usize,
+
+        #[arg(
+            long,
+            help = "Write every game's moves to this file, one game per line"
+        )]
+        record: Option<PathBuf>,
+    },
+    /// Replay a game, e.g. "X4 O0 X8", printing each position and its minimax value
+    Replay { moves: MoveList },
+}
+
+fn search_config(args: &Args) -> SearchConfig {
+    SearchConfig {
+        parallel: args.parallel,
+        ..SearchConfig::pruned()
+    }
+}
+
+fn build(kind: AgentKind, args: &Args) -> Box<dyn Agent<Board>> {
+    match kind {
+        AgentKind::Human => Box::new(HumanAgent::stdin()),
+        AgentKind::Minimax => Box::new(
+            MinimaxAgent::new(SolutionTable::new(search_config(args))).with_skill(args.skill),
+        ),
+        AgentKind::Random => Box::new(RandomAgent::new(None)),
+        AgentKind::Mcts => {
+            let config = MctsConfig {
+                iterations: args.iterations,
+                ..Default::default()
+            };
+            Box::new(MctsAgent::new(Mcts::new(config, RandomPlayout)))
+        }
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    args.log.init();
+    let mut agents = [build(args.x_agent, &args), build(args.o_agent, &args)];
+
+    if let Some(Command::Replay { moves }) = &args.command {
+        let mut solution = SolutionTable::new(search_config(&args));
+        for i in 0..=moves.0.len() {
+            let board = Board::replay(&moves.0[..i])?;
+            println!("{board}");
+            println!("Value: {}", solution.value(&board));
+        }
+        return Ok(());
+    }
+
+    if let Some(Command::Arena { games, record }) = &args.command {
+        let [a, b] = &mut agents;
+        let (stats, records) = arena::run(&Board::default(), a.as_mut(), b.as_mut(), *games);
+        println!(
+            "{:?} against {:?} over {} games:",
+            args.x_agent, args.o_agent, games
+        );
+        println!("{}", stats);
+        if let Some(path) = record {
+            let lines: Vec<String> = records
+                .iter()
+                .map(|x| MoveList(x.moves.clone()).to_string())
+                .collect();
+            std::fs::write(path, lines.join("\n") + "\n")?;
+        }
+        return Ok(());
+    }
+
+    let kinds = [args.x_agent, args.o_agent];
+    let is_human = |game: &Game| matches!(kinds[game.board().current_player()], AgentKind::Human);
+    let mut game = Game::default();
+    println!("{}", game.board());
+
+    while game.board().state() == GameState::InProgress {
+        if !is_human(&game) {
+            let index = agents[game.board().current_player()].choose_move(game.board());
+            if let Err(e) = game.play(index) {
+                println!("Invalid move: {}", e);
+            }
+            println!("{}", game.board());
+            continue;
+        }
+
+        let mut input_buffer = String::new();
+        if stdin().read_line(&mut input_buffer)? == 0 {
+            break;
+        }
+        match input_buffer.trim() {
+            // Undo and redo as many moves as it takes to get back to a human's turn
+            "u" => {
+                if game.undo().is_none() {
+                    println!("Nothing to undo");
+                }
+                while !is_human(&game) && game.undo().is_some() {}
+            }
+            "r" => {
+                if game.redo().is_none() {
+                    println!("Nothing to redo");
+                }
+                while !is_human(&game) && game.redo().is_some() {}
+            }
+            "h" => println!("{}", game.history()),
+            x => match x.parse::<usize>() {
+                Ok(index) => {
+                    if let Err(e) = game.play(index) {
+                        println!("Invalid move: {}", e);
+                    }
+                }
+                Err(_) => println!(
+                    "Enter a tile from 0 to 8, u to undo, r to redo, or h for the move history"
+                ),
+            },
+        }
+        println!("{}", game.board());
+    }
+
+    match game.board().state() {
+        GameState::Won(tile, line) => println!("{:?} wins along {:?}", tile, line),
+        GameState::Draw => println!("Draw"),
+        GameState::InProgress => {}
+    }
+    println!("{}", game.history());
+    Ok(())
+}
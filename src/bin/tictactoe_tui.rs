@@ -0,0 +1,214 @@
+use game_theory_rs::logging::LogArgs;
+use game_theory_rs::tictactoe::*;
+
+use std::io;
+
+use clap::*;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(
+        default_value = "O",
+        long,
+        help = "Side played by the computer: X, O, or none for two players at the keyboard"
+    )]
+    computer: String,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+struct App {
+    game: Game,
+    cursor: usize,
+    computer: Tile,
+    solution: SolutionTable,
+    message: String,
+}
+
+impl App {
+    fn new(computer: Tile) -> Self {
+        let mut app = App {
+            game: Game::default(),
+            cursor: 4,
+            computer,
+            solution: SolutionTable::new(SearchConfig::pruned()),
+            message: String::new(),
+        };
+        app.computer_move();
+        app
+    }
+
+    fn is_over(&self) -> bool {
+        self.game.board().state() != GameState::InProgress
+    }
+
+    /// Plays for the computer if it's its turn
+    fn computer_move(&mut self) {
+        if !self.is_over() && self.game.board().turn() == self.computer {
+            let index = self.solution.solve(self.game.board());
+            let _ = self.game.play(index);
+        }
+    }
+
+    fn play(&mut self, index: usize) {
+        if self.is_over() {
+            self.message = "The game is over: n for a new game".to_string();
+            return;
+        }
+        match self.game.play(index) {
+            Ok(()) => {
+                self.message.clear();
+                self.computer_move();
+            }
+            Err(e) => self.message = format!("Invalid move: {}", e),
+        }
+    }
+
+    /// Takes back moves until it's a human's turn again
+    fn undo(&mut self) {
+        if self.game.undo().is_none() {
+            self.message = "Nothing to undo".to_string();
+            return;
+        }
+        while self.game.board().turn() == self.computer && self.game.undo().is_some() {}
+        self.message.clear();
+        self.computer_move();
+    }
+
+    /// Replays undone moves until it's a human's turn again
+    fn redo(&mut self) {
+        if self.game.redo().is_none() {
+            self.message = "Nothing to redo".to_string();
+            return;
+        }
+        while self.game.board().turn() == self.computer && self.game.redo().is_some() {}
+        self.message.clear();
+        self.computer_move();
+    }
+
+    fn new_game(&mut self) {
+        self.game = Game::default();
+        self.message.clear();
+        self.computer_move();
+    }
+
+    fn evaluation(&mut self) -> String {
+        let value = self.solution.value(self.game.board());
+        let result = match value {
+            0 => "draw".to_string(),
+            x if x > 0 => "X wins".to_string(),
+            _ => "O wins".to_string(),
+        };
+        format!("Minimax value {}: {}", value, result)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let winning_line = match self.game.board().state() {
+            GameState::Won(_, line) => Some(line),
+            _ => None,
+        };
+        let mut lines = vec![];
+        for row in 0..3 {
+            if row > 0 {
+                lines.push(Line::from("---+---+---"));
+            }
+            let mut spans = vec![];
+            for column in 0..3 {
+                let index = row * 3 + column;
+                if column > 0 {
+                    spans.push(Span::raw("|"));
+                }
+                let text = match self.game.board().get(index) {
+                    Ok(Tile::X) => " X ",
+                    Ok(Tile::O) => " O ",
+                    _ => "   ",
+                };
+                let mut style = Style::default();
+                if winning_line.is_some_and(|x| x.contains(&index)) {
+                    style = style.fg(Color::Green).add_modifier(Modifier::BOLD);
+                }
+                if index == self.cursor && !self.is_over() {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                spans.push(Span::styled(text, style));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        let status = match self.game.board().state() {
+            GameState::Won(tile, _) => format!("{:?} wins", tile),
+            GameState::Draw => "Draw".to_string(),
+            GameState::InProgress => format!("{:?} to move", self.game.board().turn()),
+        };
+        let evaluation = self.evaluation();
+        let [board_area, info_area] =
+            Layout::vertical([Constraint::Length(7), Constraint::Min(0)]).areas(frame.area());
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::default().borders(Borders::ALL).title("Tic-tac-toe")),
+            board_area,
+        );
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(status),
+                Line::from(evaluation),
+                Line::from(self.message.clone()),
+                Line::from(
+                    "Arrows move, Enter plays, 0-8 play a tile, u undoes, r redoes, n restarts, q quits",
+                ),
+            ]),
+            info_area,
+        );
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Left | KeyCode::Char('h') if !self.cursor.is_multiple_of(3) => {
+                    self.cursor -= 1
+                }
+                KeyCode::Right | KeyCode::Char('l') if self.cursor % 3 < 2 => self.cursor += 1,
+                KeyCode::Up | KeyCode::Char('k') if self.cursor >= 3 => self.cursor -= 3,
+                KeyCode::Down | KeyCode::Char('j') if self.cursor < 6 => self.cursor += 3,
+                KeyCode::Enter | KeyCode::Char(' ') => self.play(self.cursor),
+                KeyCode::Char(x) if x.is_ascii_digit() => {
+                    self.play(x.to_digit(10).unwrap_or(0) as usize)
+                }
+                KeyCode::Char('u') => self.undo(),
+                KeyCode::Char('r') => self.redo(),
+                KeyCode::Char('n') => self.new_game(),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    args.log.init();
+    let computer = match args.computer.as_str() {
+        "X" | "x" => Tile::X,
+        "O" | "o" => Tile::O,
+        _ => Tile::Empty,
+    };
+    let mut app = App::new(computer);
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,109 @@
+use game_theory_rs::logging::LogArgs;
+use game_theory_rs::mcts::{GreedyPlayout, Mcts, MctsConfig};
+use game_theory_rs::search::DepthLimitedSearch;
+use game_theory_rs::ultimate_tictactoe::*;
+
+use std::io::stdin;
+
+use clap::*;
+
+#[derive(Parser, Debug)]
+struct Args {
+    #[arg(
+        default_value = "X",
+        long,
+        help = "Side played from the terminal: X, O, or none for the computer against itself"
+    )]
+    human: String,
+
+    #[arg(
+        long,
+        help = "Search this many moves ahead with alpha-beta instead of Monte Carlo tree search"
+    )]
+    depth: Option<usize>,
+
+    #[arg(
+        default_value = "10000",
+        long,
+        help = "Monte Carlo tree search iterations per move"
+    )]
+    iterations: usize,
+
+    #[command(flatten)]
+    log: LogArgs,
+}
+
+/// Parses a move as `board cell` or as a single index `board * 9 + cell`
+fn parse_move(input: &str) -> Option<usize> {
+    let numbers: Vec<usize> = input
+        .split_whitespace()
+        .map(|x| x.parse().ok())
+        .collect::<Option<_>>()?;
+    match numbers[..] {
+        [index] => Some(index),
+        [board, cell] if cell < 9 => Some(board * 9 + cell),
+        _ => None,
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    args.log.init();
+    let human = match args.human.as_str() {
+        "X" | "x" => Tile::X,
+        "O" | "o" => Tile::O,
+        _ => Tile::Empty,
+    };
+
+    let mut search = args
+        .depth
+        .map(|x| DepthLimitedSearch::new(BoardsEvaluator, x));
+    let config = MctsConfig {
+        iterations: args.iterations,
+        ..Default::default()
+    };
+    let mut mcts = Mcts::new(config, GreedyPlayout);
+
+    let mut board = Board::default();
+    println!("{board}");
+    while !board.is_over() {
+        if board.turn() == human {
+            match board.active_board() {
+                Some(x) => println!("Your move on board {} (board cell):", x),
+                None => println!("Your move on any open board (board cell):"),
+            }
+            let mut input_buffer = String::new();
+            let _ = stdin().read_line(&mut input_buffer);
+            match parse_move(&input_buffer) {
+                Some(index) => {
+                    if let Err(e) = board.act(index) {
+                        println!("Invalid move: {}", e);
+                        continue;
+                    }
+                }
+                None => {
+                    println!("Invalid move!");
+                    continue;
+                }
+            }
+        } else {
+            let index = match &mut search {
+                Some(search) => search.best_move(&board).map(|x| x.0),
+                None => mcts.best_move(&board),
+            };
+            match index {
+                Some(index) => {
+                    println!("{:?} plays {} {}", board.turn(), index / 9, index % 9);
+                    let _ = board.act(index);
+                }
+                None => break,
+            }
+        }
+        println!("{board}");
+    }
+
+    match board.winner() {
+        Tile::Empty => println!("Draw"),
+        winner => println!("{:?} wins", winner),
+    }
+}
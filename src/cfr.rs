@@ -1,40 +1,963 @@
+#[cfg(feature = "fs")]
+use crate::error::IoError;
+use crate::parallel::*;
+
 use ndarray::*;
-use rayon::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use siphasher::sip::SipHasher13;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fmt::{Debug, Display};
+#[cfg(feature = "fs")]
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+#[cfg(feature = "fs")]
+use std::io::{BufReader, BufWriter, Write};
+use std::ops::Range;
+#[cfg(feature = "fs")]
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+#[cfg(feature = "fs")]
+use tracing::warn;
+use tracing::{debug, info_span, trace};
+
+/// Precision of probabilities, payouts and regrets in the CFR arrays; the `f32` feature halves the
+/// memory used by large trees at the cost of accuracy
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+/// Rule used to accumulate regrets and average strategies across iterations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum CfrVariant {
+    /// Vanilla CFR: cumulative regrets, uniformly weighted average strategy
+    #[default]
+    Vanilla,
+    /// CFR+: regrets floored at zero after each update, average strategy weighted by iteration
+    CfrPlus,
+    /// Linear CFR: both regrets and average strategy weighted by iteration
+    Linear,
+}
+
+impl FromStr for CfrVariant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vanilla" => Ok(CfrVariant::Vanilla),
+            "cfr+" | "cfr-plus" => Ok(CfrVariant::CfrPlus),
+            "linear" => Ok(CfrVariant::Linear),
+            _ => Err(format!("Unknown CFR variant: {}", s)),
+        }
+    }
+}
+
+/// Weighting of past iterations' strategies in the average strategy
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AveragingScheme {
+    /// Every iteration counts equally
+    Uniform,
+    /// Iteration t counts with weight t, so later iterations dominate
+    Linear,
+    /// Uniform average over only the most recent K iterations
+    LastK(usize),
+}
+
+/// Epsilon-greedy floor on the current strategy: every legal action gets at least `epsilon / n`
+/// probability, with `epsilon` shrinking by a factor of `decay` each iteration
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Exploration {
+    pub epsilon: Float,
+    pub decay: Float,
+}
+
+impl Exploration {
+    /// Exploration rate in iteration `iteration`, counting from 1
+    pub fn epsilon_at(&self, iteration: u64) -> Float {
+        self.epsilon * self.decay.powf(iteration.saturating_sub(1) as Float)
+    }
+}
+
+impl FromStr for Exploration {
+    type Err = String;
+
+    /// Parses `EPSILON` or `EPSILON:DECAY`; without a decay the rate stays constant
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (epsilon, decay) = s.split_once(':').unwrap_or((s, "1"));
+        match (epsilon.parse::<Float>(), decay.parse::<Float>()) {
+            (Ok(epsilon), Ok(decay))
+                if (0. ..=1.).contains(&epsilon) && (0. ..=1.).contains(&decay) =>
+            {
+                Ok(Exploration { epsilon, decay })
+            }
+            _ => Err(format!("Invalid exploration: {}", s)),
+        }
+    }
+}
+
+impl FromStr for AveragingScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(AveragingScheme::Uniform),
+            "linear" => Ok(AveragingScheme::Linear),
+            _ => match s.strip_prefix("last-").map(str::parse::<usize>) {
+                Some(Ok(k)) if k > 0 => Ok(AveragingScheme::LastK(k)),
+                _ => Err(format!("Unknown averaging scheme: {}", s)),
+            },
+        }
+    }
+}
+
+/// Solver options shared by every node in the tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SolverConfig {
+    pub variant: CfrVariant,
+    /// Upper bound on the number of CFR iterations
+    pub max_iterations: u64,
+    /// Stop once exploitability drops below this value, in payout units
+    pub target_exploitability: Option<Float>,
+    /// Number of iterations between exploitability checks
+    pub check_interval: u64,
+    /// Update a single player's regrets per iteration, cycling through the players
+    pub alternating: bool,
+    /// Overrides the variant's averaging scheme
+    #[serde(default)]
+    pub averaging: Option<AveragingScheme>,
+    /// Record exploitability every `check_interval` iterations in [`CfrSolver::convergence`]
+    #[serde(default)]
+    pub record_convergence: bool,
+    /// Players whose strategies are held fixed (see [`freeze_strategies`]), so that the others
+    /// learn a best response to them
+    #[serde(default)]
+    pub frozen_players: Vec<usize>,
+    /// Mixes some uniform play into the current strategy so no infoset is starved of reach early on
+    #[serde(default)]
+    pub exploration: Option<Exploration>,
+}
+
+impl SolverConfig {
+    /// Whether nodes where `node_player` acts accumulate regrets in an iteration that updates
+    /// `player` (or everyone, if `None`)
+    fn updates(&self, node_player: usize, player: Option<usize>) -> bool {
+        player.is_none_or(|x| x == node_player) && !self.frozen_players.contains(&node_player)
+    }
+
+    /// Averaging scheme in effect: the override if set, otherwise the one the variant calls for
+    pub fn averaging(&self) -> AveragingScheme {
+        self.averaging.unwrap_or(match self.variant {
+            CfrVariant::Vanilla => AveragingScheme::Uniform,
+            CfrVariant::CfrPlus | CfrVariant::Linear => AveragingScheme::Linear,
+        })
+    }
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        SolverConfig {
+            variant: CfrVariant::default(),
+            max_iterations: 1000,
+            target_exploitability: None,
+            check_interval: 100,
+            alternating: false,
+            averaging: None,
+            record_convergence: false,
+            frozen_players: Vec::new(),
+            exploration: None,
+        }
+    }
+}
+
+/// Summary of a finished solve
+#[derive(Debug, Clone, Copy)]
+pub struct SolveResult {
+    pub iterations: u64,
+    pub exploitability: Float,
+    /// Whether the target exploitability was reached before the iteration cap
+    pub converged: bool,
+}
+
+/// Progress reported to [`CfrSolver`] callbacks after every iteration
+#[derive(Debug, Clone, Copy)]
+pub struct IterationMetrics {
+    pub iteration: u64,
+    /// Wall-clock time since the start of the current [`CfrSolver::solve`] call
+    pub elapsed: Duration,
+    /// Only measured every `check_interval` iterations, since it walks the whole tree
+    pub exploitability: Option<Float>,
+}
+
+/// Exploitability measured at one point of a solve
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConvergencePoint {
+    pub iteration: u64,
+    /// Seconds since the start of the solve call that measured it
+    pub elapsed: f64,
+    pub exploitability: Float,
+}
+
+pub type IterationCallback = Box<dyn FnMut(&IterationMetrics)>;
+
+/// Where and how often a [`CfrSolver`] writes its state during [`CfrSolver::solve`]
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub struct CheckpointConfig {
+    pub path: PathBuf,
+    /// Write a checkpoint every this many iterations
+    pub every_iterations: Option<u64>,
+    /// Write a checkpoint once this much wall-clock time has passed since the last one
+    pub every_duration: Option<Duration>,
+}
+
+/// Runs CFR iterations over a tree until the target exploitability or the iteration cap is reached
+#[derive(Serialize, Deserialize)]
+pub struct CfrSolver {
+    pub root: Box<dyn Node>,
+    pub config: SolverConfig,
+    pub iterations: u64,
+    /// Not part of the saved state, so a resumed solve picks its own checkpoint settings
+    #[cfg(feature = "fs")]
+    #[serde(skip)]
+    pub checkpoint: Option<CheckpointConfig>,
+    #[serde(skip)]
+    callbacks: Vec<IterationCallback>,
+    /// Exploitability series recorded when `config.record_convergence` is set; kept in
+    /// checkpoints so a resumed solve continues the same curve
+    #[serde(default)]
+    pub convergence: Vec<ConvergencePoint>,
+}
+
+impl CfrSolver {
+    pub fn new(root: Box<dyn Node>, config: SolverConfig) -> Self {
+        CfrSolver {
+            root,
+            config,
+            iterations: 0,
+            #[cfg(feature = "fs")]
+            checkpoint: None,
+            callbacks: Vec::new(),
+            convergence: Vec::new(),
+        }
+    }
+
+    /// Registers a callback to run after every iteration of [`CfrSolver::solve`]. Exploitability
+    /// is measured every `check_interval` iterations once any callback is registered.
+    pub fn on_iteration(&mut self, callback: impl FnMut(&IterationMetrics) + 'static) {
+        self.callbacks.push(Box::new(callback));
+    }
+
+    /// Writes the full solver state (tree, regrets, averages, config, iteration count) to `path`
+    #[cfg(feature = "fs")]
+    pub fn save_checkpoint(&self, path: &Path) -> Result<(), IoError> {
+        // Write to a sibling file first so an interrupted write never clobbers the last good checkpoint
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        let writer = BufWriter::new(File::create(&tmp_path).map_err(IoError::file(&tmp_path))?);
+        serde_json::to_writer(writer, self).map_err(IoError::format(&tmp_path))?;
+        std::fs::rename(&tmp_path, path).map_err(IoError::file(path))
+    }
+
+    /// Restores a solver written by [`CfrSolver::save_checkpoint`]
+    #[cfg(feature = "fs")]
+    pub fn load_checkpoint(path: &Path) -> Result<Self, IoError> {
+        let reader = BufReader::new(File::open(path).map_err(IoError::file(path))?);
+        serde_json::from_reader(reader).map_err(IoError::format(path))
+    }
+
+    /// Writes the recorded convergence curve as CSV
+    #[cfg(feature = "fs")]
+    pub fn write_convergence_csv(&self, path: &Path) -> Result<(), IoError> {
+        let mut writer = BufWriter::new(File::create(path).map_err(IoError::file(path))?);
+        writeln!(writer, "iteration,elapsed,exploitability").map_err(IoError::file(path))?;
+        for point in &self.convergence {
+            writeln!(
+                writer,
+                "{},{},{}",
+                point.iteration, point.elapsed, point.exploitability
+            )
+            .map_err(IoError::file(path))?;
+        }
+        writer.flush().map_err(IoError::file(path))
+    }
+
+    /// Per-infoset EVs and action frequencies of the average strategy; see [`report`]
+    pub fn report(&mut self) -> Vec<InfosetReport> {
+        report(self.root.as_mut())
+    }
+
+    /// Runs a single CFR iteration
+    pub fn step(&mut self) {
+        self.root.update_probabilities();
+        self.root.update_ev();
+        let player = match self.config.alternating {
+            true => Some(self.iterations as usize % self.root.payouts().nrows()),
+            false => None,
+        };
+        self.root.update_strategy(&self.config, player);
+        self.iterations += 1;
+    }
+
+    /// Runs iterations until convergence, checking exploitability every `check_interval` iterations.
+    /// Logs each check and the result at debug level, and the iterations in between at trace level.
+    pub fn solve(&mut self) -> SolveResult {
+        let _span = info_span!(
+            "solve",
+            variant = ?self.config.variant,
+            max_iterations = self.config.max_iterations
+        )
+        .entered();
+        let check_interval = self.config.check_interval.max(1);
+        let start = Instant::now();
+        let result = self.run(check_interval, start);
+        debug!(
+            iterations = result.iterations,
+            exploitability = result.exploitability,
+            converged = result.converged,
+            elapsed = start.elapsed().as_secs_f64(),
+            "Solved"
+        );
+        result
+    }
+
+    /// Iterations of [`CfrSolver::solve`], split out so that every way of stopping is logged
+    fn run(&mut self, check_interval: u64, start: Instant) -> SolveResult {
+        #[cfg(feature = "fs")]
+        let mut last_checkpoint = start;
+        while self.iterations < self.config.max_iterations {
+            self.step();
+
+            #[cfg(feature = "fs")]
+            if let Some(checkpoint) = &self.checkpoint {
+                let due_iterations = checkpoint
+                    .every_iterations
+                    .is_some_and(|n| self.iterations.is_multiple_of(n.max(1)));
+                let due_time = checkpoint
+                    .every_duration
+                    .is_some_and(|d| last_checkpoint.elapsed() >= d);
+                if due_iterations || due_time {
+                    // A failed write shouldn't throw away the progress of a long solve
+                    match self.save_checkpoint(&checkpoint.path) {
+                        Ok(()) => debug!(path = ?checkpoint.path, "Wrote checkpoint"),
+                        Err(e) => {
+                            warn!(path = ?checkpoint.path, "Failed to write checkpoint: {}", e)
+                        }
+                    }
+                    last_checkpoint = Instant::now();
+                }
+            }
+
+            let measure = self.config.target_exploitability.is_some()
+                || self.config.record_convergence
+                || !self.callbacks.is_empty();
+            let metrics = IterationMetrics {
+                iteration: self.iterations,
+                elapsed: start.elapsed(),
+                exploitability: (measure && self.iterations.is_multiple_of(check_interval))
+                    .then(|| exploitability(self.root.as_ref())),
+            };
+            match metrics.exploitability {
+                Some(exploitability) => debug!(
+                    iteration = metrics.iteration,
+                    exploitability,
+                    elapsed = metrics.elapsed.as_secs_f64()
+                ),
+                None => trace!(
+                    iteration = metrics.iteration,
+                    elapsed = metrics.elapsed.as_secs_f64()
+                ),
+            }
+            self.callbacks
+                .iter_mut()
+                .map(|callback| callback(&metrics))
+                .for_each(drop);
+            if let (true, Some(exploitability)) =
+                (self.config.record_convergence, metrics.exploitability)
+            {
+                self.convergence.push(ConvergencePoint {
+                    iteration: metrics.iteration,
+                    elapsed: metrics.elapsed.as_secs_f64(),
+                    exploitability,
+                });
+            }
+
+            if let (Some(target), Some(exploitability)) =
+                (self.config.target_exploitability, metrics.exploitability)
+            {
+                if exploitability < target {
+                    return SolveResult {
+                        iterations: self.iterations,
+                        exploitability,
+                        converged: true,
+                    };
+                }
+            }
+        }
+
+        let exploitability = exploitability(self.root.as_ref());
+        SolveResult {
+            iterations: self.iterations,
+            exploitability,
+            converged: self
+                .config
+                .target_exploitability
+                .is_some_and(|target| exploitability < target),
+        }
+    }
+}
 
 pub trait Node: Debug + Sync + Send + Display {
     fn name(&self) -> String;
-    fn state_probabilities(&self) -> Array<f64, Ix1>;
-    fn payouts(&self) -> Array<f64, Ix1>;
-    fn strategy(&self) -> Option<Array<f64, Ix2>>;
-    fn avg_strategy(&self) -> Option<Array<f64, Ix2>>;
+    fn state_probabilities(&self) -> ArrayView1<'_, Float>;
+    fn payouts(&self) -> ArrayView2<'_, Float>;
+    fn strategy(&self) -> Option<ArrayView2<'_, Float>>;
+    fn avg_strategy(&self) -> Option<ArrayView2<'_, Float>>;
     fn children(&self) -> Option<&Vec<Box<dyn Node>>>;
 
-    fn set_state_probabilities(&mut self, p: Array<f64, Ix1>);
+    /// Borrows the concrete node, for serialization
+    fn as_node_ref(&self) -> NodeRef<'_>;
+
+    /// Mutably borrows the concrete node, for tree-wide passes outside of the CFR updates
+    fn as_node_mut(&mut self) -> NodeMut<'_>;
+
+    fn set_state_probabilities(&mut self, p: Array<Float, Ix1>);
+    /// Sets the probability of reaching each state through each player's own actions, indexed by
+    /// player, state. Terminal nodes don't need them.
+    fn set_player_probabilities(&mut self, _p: Array<Float, Ix2>) {}
     fn update_probabilities(&mut self);
     fn update_ev(&mut self);
-    fn update_strategy(&mut self);
+    /// Accumulates regrets and updates strategies; with `player` set, only that player's nodes are
+    /// updated (alternating updates)
+    fn update_strategy(&mut self, config: &SolverConfig, player: Option<usize>);
+
+    /// Per-state payouts of the given player when best responding to the average strategy,
+    /// given the probability of reaching each state through chance and the other players' actions
+    fn best_response(&self, player: usize, reach: &Array<Float, Ix1>) -> Array<Float, Ix1>;
+
+    /// Per-state payouts, indexed by player and state, when every player follows the average strategy
+    fn avg_strategy_payouts(&self) -> Array<Float, Ix2>;
+}
+
+/// Turns payouts summed over children, weighted by the children's state probabilities, back into
+/// per-state EVs at the parent
+fn conditional_evs(
+    weighted_payouts: Array<Float, Ix2>,
+    state_probabilities: &Array<Float, Ix1>,
+) -> Array<Float, Ix2> {
+    weighted_payouts
+        / state_probabilities
+            .iter()
+            .map(|x| match x {
+                0. => 1.,
+                _ => *x,
+            })
+            .collect::<Array<Float, Ix1>>()
+}
+
+/// Each player's reach probabilities as passed down from a parent, or all ones at the root, which
+/// leaves them empty
+fn player_probabilities(
+    player_probabilities: &Array<Float, Ix2>,
+    n_players: usize,
+    n_states: usize,
+) -> Array<Float, Ix2> {
+    match player_probabilities.is_empty() {
+        true => Array::ones((n_players, n_states)),
+        false => player_probabilities.clone(),
+    }
+}
+
+/// Expected payout of the given player when best responding to the average strategy
+pub fn best_response_value(root: &dyn Node, player: usize) -> Float {
+    let reach = root.state_probabilities().to_owned();
+    (root.best_response(player, &reach) * &reach).sum()
+}
+
+/// Gain of each player from switching alone to a best response against the average strategy
+/// profile, in payout units; the profile is an epsilon-equilibrium for every epsilon at least the
+/// largest
+pub fn unilateral_gains(root: &dyn Node) -> Array<Float, Ix1> {
+    let reach = root.state_probabilities().to_owned();
+    let avg_strategy_values = root.avg_strategy_payouts().dot(&reach);
+    Array::from_shape_fn(avg_strategy_values.len(), |player| {
+        best_response_value(root, player) - avg_strategy_values[player]
+    })
+}
+
+/// Average gain of each player's best response over the average strategy profile, in payout units;
+/// zero exactly at a Nash equilibrium
+pub fn exploitability(root: &dyn Node) -> Float {
+    let gains = unilateral_gains(root);
+    gains.sum() / gains.len() as Float
+}
+
+/// Concrete node borrowed from a trait object; serializes as an externally tagged enum
+#[derive(Serialize)]
+pub enum NodeRef<'a> {
+    Action(&'a ActionNode),
+    Terminal(&'a TerminalNode),
+    Chance(&'a ChanceNode),
+    Arena(&'a ArenaTree),
+}
+
+/// Concrete node mutably borrowed from a trait object
+pub enum NodeMut<'a> {
+    Action(&'a mut ActionNode),
+    Terminal(&'a mut TerminalNode),
+    Chance(&'a mut ChanceNode),
+    Arena(&'a mut ArenaTree),
+}
+
+/// Calls `f` on every action node in the tree, along with its path of node names from the root
+pub fn visit_action_nodes_mut(
+    node: &mut dyn Node,
+    path: &str,
+    f: &mut impl FnMut(&mut ActionNode, &str),
+) {
+    let children = match node.as_node_mut() {
+        NodeMut::Action(x) => {
+            f(x, path);
+            &mut x.children
+        }
+        NodeMut::Chance(x) => &mut x.children,
+        NodeMut::Terminal(_) => return,
+        NodeMut::Arena(x) => {
+            let paths = x.paths(path);
+            x.nodes
+                .iter_mut()
+                .zip(paths)
+                .map(|(node, path)| {
+                    if let ArenaNode::Action(node) = node {
+                        f(node, &path);
+                    }
+                })
+                .for_each(drop);
+            return;
+        }
+    };
+    children
+        .iter_mut()
+        .map(|child| {
+            let child_path = format!("{}/{}", path, child.name());
+            visit_action_nodes_mut(child.as_mut(), &child_path, f);
+        })
+        .for_each(drop);
+}
+
+/// Owned counterpart of [`NodeRef`], used to deserialize boxed nodes
+#[derive(Deserialize)]
+enum NodeData {
+    Action(Box<ActionNode>),
+    Terminal(Box<TerminalNode>),
+    Chance(Box<ChanceNode>),
+    Arena(Box<ArenaTree>),
+}
+
+impl Serialize for dyn Node + '_ {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_node_ref().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Box<dyn Node> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match NodeData::deserialize(deserializer)? {
+            NodeData::Action(x) => x,
+            NodeData::Terminal(x) => x,
+            NodeData::Chance(x) => x,
+            NodeData::Arena(x) => x,
+        })
+    }
+}
+
+/// Writes a tree, including regrets, average strategies and iteration counts, as JSON
+#[cfg(feature = "fs")]
+pub fn save_tree(root: &dyn Node, path: &Path) -> Result<(), IoError> {
+    let writer = BufWriter::new(File::create(path).map_err(IoError::file(path))?);
+    serde_json::to_writer(writer, root).map_err(IoError::format(path))
+}
+
+/// Reads a tree previously written by [`save_tree`]
+#[cfg(feature = "fs")]
+pub fn load_tree(path: &Path) -> Result<Box<dyn Node>, IoError> {
+    let reader = BufReader::new(File::open(path).map_err(IoError::file(path))?);
+    serde_json::from_reader(reader).map_err(IoError::format(path))
+}
+
+/// Seeds a fresh tree from a previously computed strategy profile (e.g. a solve at a nearby stack
+/// size), as if each matching action node had already played it for `weight` iterations
+pub fn warm_start(root: &mut dyn Node, profile: &StrategyProfile, weight: Float) {
+    let root_name = root.name();
+    let matching_strategy = |node: &ActionNode, path: &str| {
+        profile
+            .strategies
+            .get(path)
+            .filter(|x| x.dim() == node.strategy.dim())
+            .cloned()
+    };
+
+    // Play the profile once so that reach probabilities and EVs reflect it
+    visit_action_nodes_mut(root, &root_name, &mut |node, path| {
+        if let Some(strategy) = matching_strategy(node, path) {
+            node.strategy = strategy;
+        }
+    });
+    root.update_probabilities();
+    root.update_ev();
+
+    visit_action_nodes_mut(root, &root_name, &mut |node, path| {
+        if let Some(strategy) = matching_strategy(node, path) {
+            node.warm_start(&strategy, weight);
+        }
+    });
+}
+
+/// Sets both the current and average strategy of `player`'s action nodes to the profile's, where
+/// it has an entry of the right shape. With the player in [`SolverConfig::frozen_players`], the
+/// strategy then stays fixed while everyone else converges to a best response against it.
+pub fn freeze_strategies(root: &mut dyn Node, profile: &StrategyProfile, player: usize) {
+    let root_name = root.name();
+    visit_action_nodes_mut(root, &root_name, &mut |node, path| {
+        if let Some(strategy) = profile
+            .strategies
+            .get(path)
+            .filter(|x| node.player == player && x.dim() == node.strategy.dim())
+        {
+            node.strategy = strategy.clone();
+            node.avg_strategy = strategy.clone();
+        }
+    });
+}
+
+/// Sets the average strategy of every action node to the profile's, so that [`unilateral_gains`]
+/// measures how far an imported or hand-made profile is from equilibrium. Every action node needs
+/// an entry of the right shape whose probabilities in each infoset sum to 1.
+pub fn load_strategies(root: &mut dyn Node, profile: &StrategyProfile) -> Result<(), TreeError> {
+    let root_name = root.name();
+    let mut result = Ok(());
+    visit_action_nodes_mut(root, &root_name, &mut |node, path| {
+        if result.is_err() {
+            return;
+        }
+        let strategy = match profile.strategies.get(path) {
+            Some(x) if x.dim() == node.avg_strategy.dim() => x,
+            Some(x) => {
+                result = Err(TreeError::ShapeMismatch {
+                    path: path.to_string(),
+                    expected: node.avg_strategy.shape().to_vec(),
+                    found: x.shape().to_vec(),
+                });
+                return;
+            }
+            None => {
+                result = Err(TreeError::MissingStrategy {
+                    path: path.to_string(),
+                });
+                return;
+            }
+        };
+        let totals = strategy.sum_axis(Axis(0));
+        if let Some(infoset) = totals.iter().position(|x| (x - 1.).abs() > 1e-4) {
+            result = Err(TreeError::InvalidStrategy {
+                path: path.to_string(),
+                infoset,
+            });
+            return;
+        }
+        node.avg_strategy = strategy.clone();
+    });
+    result
+}
+
+/// Post-processing applied to solved average strategies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Purification {
+    /// Drop actions played with probability below the threshold and renormalize
+    Threshold(Float),
+    /// Play the most frequent action of each infoset with probability 1
+    Pure,
+}
+
+/// Applies `purification` to one strategy, indexed by action, infoset
+pub fn purify_strategy(
+    strategy: &Array<Float, Ix2>,
+    purification: Purification,
+) -> Array<Float, Ix2> {
+    let mut result = strategy.clone();
+    result
+        .columns_mut()
+        .into_iter()
+        .map(|mut column| {
+            let (argmax, max) =
+                column
+                    .iter()
+                    .enumerate()
+                    .fold((0, Float::MIN), |(argmax, max), (index, x)| {
+                        match max >= *x {
+                            true => (argmax, max),
+                            false => (index, *x),
+                        }
+                    });
+            match purification {
+                // The most frequent action always survives, so every infoset keeps a valid strategy
+                Purification::Threshold(threshold) if max >= threshold => {
+                    column.map_inplace(|x| {
+                        if *x < threshold {
+                            *x = 0.
+                        }
+                    });
+                    let sum = column.sum();
+                    column /= sum;
+                }
+                _ => {
+                    column.fill(0.);
+                    column[argmax] = 1.;
+                }
+            }
+        })
+        .for_each(drop);
+    result
+}
+
+/// Applies `purification` to the average strategy of every action node
+pub fn purify(root: &mut dyn Node, purification: Purification) {
+    let root_name = root.name();
+    visit_action_nodes_mut(root, &root_name, &mut |node, _| {
+        node.avg_strategy = purify_strategy(&node.avg_strategy, purification);
+    });
+}
+
+/// One action of an [`InfosetReport`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActionReport {
+    /// Name of the child node the action leads to
+    pub action: String,
+    pub ev: Float,
+    pub frequency: Float,
+}
+
+/// Values of one infoset when every player follows the average strategy. EVs are those of the
+/// acting player, conditional on reaching the infoset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InfosetReport {
+    /// Path of node names from the root
+    pub node: String,
+    pub infoset: usize,
+    pub player: usize,
+    /// Probability of reaching the infoset
+    pub reach: Float,
+    pub ev: Float,
+    pub actions: Vec<ActionReport>,
+}
+
+/// Per-infoset EVs and frequencies of every action node under the average strategy profile
+pub fn report(root: &mut dyn Node) -> Vec<InfosetReport> {
+    // Action EVs need each node's children, which an arena only has once unflattened
+    if let NodeMut::Arena(x) = root.as_node_mut() {
+        let mut tree = std::mem::take(x).into_tree();
+        let result = report(tree.as_mut());
+        *x = ArenaTree::from_tree(tree);
+        return result;
+    }
+
+    // Evaluate the average strategy by temporarily playing it. Probabilities and EVs are
+    // recomputed at the start of every iteration, so they don't need restoring afterwards.
+    let root_name = root.name();
+    let swap_strategies = |root: &mut dyn Node| {
+        visit_action_nodes_mut(root, &root_name, &mut |node, _| {
+            std::mem::swap(&mut node.strategy, &mut node.avg_strategy);
+        })
+    };
+    swap_strategies(root);
+    root.update_probabilities();
+    root.update_ev();
+
+    let mut result = Vec::new();
+    visit_action_nodes_mut(root, &root_name, &mut |node, path| {
+        let action_evs = node.action_evs();
+        let evs = node.infoset_evs(node.evs.row(node.player), &node.state_probabilities);
+        let reach = node.infoset_probabilities(&node.state_probabilities);
+        result.extend((0..node.infosets.len()).map(|infoset| {
+            InfosetReport {
+                node: path.to_string(),
+                infoset,
+                player: node.player,
+                reach: reach[infoset],
+                ev: evs[infoset],
+                actions: node
+                    .children
+                    .iter()
+                    .enumerate()
+                    .map(|(action_index, child)| ActionReport {
+                        action: child.name(),
+                        ev: action_evs[[action_index, infoset]],
+                        // The average strategy is in the strategy slot while swapped
+                        frequency: node.strategy[[action_index, infoset]],
+                    })
+                    .collect(),
+            }
+        }));
+    });
+
+    swap_strategies(root);
+    result
+}
+
+/// Average strategies of every action node, keyed by the path of node names from the root
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StrategyProfile {
+    pub strategies: BTreeMap<String, Array<Float, Ix2>>,
+}
+
+impl StrategyProfile {
+    pub fn from_tree(root: &dyn Node) -> Self {
+        let mut result = StrategyProfile::default();
+        result.collect(root, root.name());
+        result
+    }
+
+    fn collect(&mut self, node: &dyn Node, path: String) {
+        if let NodeRef::Arena(x) = node.as_node_ref() {
+            x.nodes
+                .iter()
+                .zip(x.paths(&path))
+                .map(|(node, path)| {
+                    if let Some(avg_strategy) = node.as_node().avg_strategy() {
+                        self.strategies.insert(path, avg_strategy.to_owned());
+                    }
+                })
+                .for_each(drop);
+            return;
+        }
+        if let Some(avg_strategy) = node.avg_strategy() {
+            self.strategies
+                .insert(path.clone(), avg_strategy.to_owned());
+        }
+        if let Some(children) = node.children() {
+            children
+                .iter()
+                .map(|child| self.collect(child.as_ref(), format!("{}/{}", path, child.name())))
+                .for_each(drop);
+        }
+    }
+}
+
+/// 1 for legal actions and 0 for illegal ones
+fn legal_weights(mask: &Array<bool, Ix2>) -> Array<Float, Ix2> {
+    mask.mapv(|x| match x {
+        true => 1.,
+        false => 0.,
+    })
+}
+
+pub type InfosetKey = u64;
+
+/// Hashes anything identifying an infoset (e.g. the acting player's cards and the public actions)
+/// into an [`InfosetKey`]. SipHash-1-3 with fixed keys rather than std's `DefaultHasher`, whose
+/// algorithm may change between Rust releases, since keys are saved in checkpoints.
+pub fn infoset_key<T: Hash + ?Sized>(x: &T) -> InfosetKey {
+    let mut hasher = SipHasher13::new_with_keys(0, 0);
+    x.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Infosets keyed by hash rather than densely enumerated. Infosets are created the first time a
+/// state is inserted under their key, so states that are never inserted take up no space; they
+/// must be unreachable, as the acting player takes no action there.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyedInfosets {
+    indices: HashMap<InfosetKey, usize>,
+    infosets: Vec<Vec<usize>>, // Indexed by infoset, member(state)
+}
+
+impl KeyedInfosets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `state` to the infoset for `key`, creating it if needed, and returns its index
+    pub fn insert(&mut self, key: InfosetKey, state: usize) -> usize {
+        let infosets = &mut self.infosets;
+        let index = *self.indices.entry(key).or_insert_with(|| {
+            infosets.push(Vec::new());
+            infosets.len() - 1
+        });
+        self.infosets[index].push(state);
+        index
+    }
+
+    pub fn get(&self, key: InfosetKey) -> Option<usize> {
+        self.indices.get(&key).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.infosets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.infosets.is_empty()
+    }
+}
+
+impl From<HashMap<InfosetKey, Vec<usize>>> for KeyedInfosets {
+    fn from(map: HashMap<InfosetKey, Vec<usize>>) -> Self {
+        // Sort by key so the infoset order doesn't depend on the map's iteration order
+        let mut entries: Vec<(InfosetKey, Vec<usize>)> = map.into_iter().collect();
+        entries.sort_unstable_by_key(|(key, _)| *key);
+        let mut result = KeyedInfosets::new();
+        for (key, states) in entries {
+            for state in states {
+                result.insert(key, state);
+            }
+        }
+        result
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ActionNode {
     pub name: String,
-    pub state_probabilities: Array<f64, Ix1>, // Indexed by state
-    pub total_probabilities: Array<f64, Ix1>, // Indexed by infoset
-    pub evs: Array<f64, Ix1>,                 // Indexed by state
-    pub infosets: Vec<Vec<usize>>,            // Indexed by infoset, member(state)
-    pub strategy: Array<f64, Ix2>,            // Indexed by action, infoset
-    pub avg_strategy: Array<f64, Ix2>,        // Indexed by action, infoset
-    pub regrets: Array<f64, Ix2>,             // Indexed by action, infoset
+    pub state_probabilities: Array<Float, Ix1>, // Indexed by state
+    pub total_probabilities: Array<Float, Ix1>, // Indexed by infoset
+    pub evs: Array<Float, Ix2>,                 // Indexed by player, state
+    /// Probability of reaching each state through each player's own actions, indexed by player,
+    /// state; empty at the root
+    #[serde(default)]
+    pub player_probabilities: Array<Float, Ix2>,
+    pub infosets: Vec<Vec<usize>>, // Indexed by infoset, member(state)
+    pub strategy: Array<Float, Ix2>, // Indexed by action, infoset
+    pub avg_strategy: Array<Float, Ix2>, // Indexed by action, infoset
+    pub regrets: Array<Float, Ix2>, // Indexed by action, infoset
     pub children: Vec<Box<dyn Node>>,
-    pub sign: i8,        // 1 for positive payout, -1 for negative payout
+    pub player: usize,   // Index of the acting player
     pub iter_count: u64, // CFR iteration count
+    /// (strategy, infoset weights) of recent iterations, kept for last-K averaging
+    #[serde(default)]
+    pub recent_strategies: VecDeque<(Array<Float, Ix2>, Array<Float, Ix1>)>,
+    /// Legal actions, indexed by action, infoset; every action is legal everywhere if `None`
+    #[serde(default)]
+    pub action_mask: Option<Array<bool, Ix2>>,
+    /// Infoset index of each key if the infosets are keyed; see [`KeyedInfosets`]
+    #[serde(default)]
+    pub infoset_keys: Option<HashMap<InfosetKey, usize>>,
+    /// Whether the action taken here is hidden from the player acting next, as in a simultaneous
+    /// move; the children then share one strategy. See [`TreeBuilder::hidden`]
+    #[serde(default)]
+    pub hidden: bool,
 }
 
 impl ActionNode {
-    fn expand_strategy(&self) -> Array<f64, Ix2> {
-        let mut result: Array<f64, Ix2> =
-            Array::zeros((self.children.len(), self.state_probabilities.len()));
+    /// Index of the infoset with the given key, if the infosets are keyed and it exists
+    pub fn infoset_index(&self, key: InfosetKey) -> Option<usize> {
+        self.infoset_keys.as_ref()?.get(&key).copied()
+    }
+
+    pub(crate) fn expand_strategy(&self, strategy: &Array<Float, Ix2>) -> Array<Float, Ix2> {
+        let mut result: Array<Float, Ix2> =
+            Array::zeros((strategy.nrows(), self.state_probabilities.len()));
 
         self.infosets
             .iter()
@@ -45,7 +968,7 @@ impl ActionNode {
                     .map(|state_index| {
                         result
                             .slice_mut(s![.., *state_index])
-                            .assign(&self.strategy.slice(s![.., infoset_index]))
+                            .assign(&strategy.slice(s![.., infoset_index]))
                     })
                     .for_each(drop);
             })
@@ -53,8 +976,11 @@ impl ActionNode {
         result
     }
 
-    fn infoset_probabilities(&self, state_probabilities: &Array<f64, Ix1>) -> Array<f64, Ix1> {
-        let result: Array<f64, Ix1> = self
+    fn infoset_probabilities<S: Data<Elem = Float>>(
+        &self,
+        state_probabilities: &ArrayBase<S, Ix1>,
+    ) -> Array<Float, Ix1> {
+        let result: Array<Float, Ix1> = self
             .infosets
             .iter()
             .map(|x| x.iter().map(|i| state_probabilities[*i]).sum())
@@ -62,12 +988,12 @@ impl ActionNode {
         result
     }
 
-    fn infoset_evs(
+    fn infoset_evs<S: Data<Elem = Float>>(
         &self,
-        evs: &Array<f64, Ix1>,
-        state_probabilities: &Array<f64, Ix1>,
-    ) -> Array<f64, Ix1> {
-        let result: Array<f64, Ix1> = self
+        evs: ArrayView1<Float>,
+        state_probabilities: &ArrayBase<S, Ix1>,
+    ) -> Array<Float, Ix1> {
+        let result: Array<Float, Ix1> = self
             .infosets
             .iter()
             .map(|x| {
@@ -75,7 +1001,7 @@ impl ActionNode {
                     .map(|state_index| evs[*state_index] * state_probabilities[*state_index])
                     .sum()
             })
-            .collect::<Array<f64, Ix1>>()
+            .collect::<Array<Float, Ix1>>()
             / &self
                 .infoset_probabilities(state_probabilities)
                 .iter()
@@ -83,122 +1009,505 @@ impl ActionNode {
                     0. => 1.,
                     _ => *x,
                 })
-                .collect::<Array<f64, Ix1>>();
+                .collect::<Array<Float, Ix1>>();
         result
     }
 
-    fn action_evs(&self) -> Array<f64, Ix2> {
-        let mut result: Array<f64, Ix2> = Array::zeros((self.children.len(), self.infosets.len()));
-        self.children
-            .iter()
+    fn action_evs(&self) -> Array<Float, Ix2> {
+        self.action_evs_from(
+            self.children
+                .iter()
+                .map(|child| (child.payouts(), child.state_probabilities())),
+        )
+    }
+
+    /// Per-infoset EV of each action, given the (payouts, state probabilities) of each child
+    fn action_evs_from<S: Data<Elem = Float>, T: Data<Elem = Float>>(
+        &self,
+        children: impl Iterator<Item = (ArrayBase<S, Ix2>, ArrayBase<T, Ix1>)>,
+    ) -> Array<Float, Ix2> {
+        let mut result: Array<Float, Ix2> = Array::zeros(self.regrets.raw_dim());
+        children
             .enumerate()
-            .map(|(action_index, child)| {
+            .map(|(action_index, (payouts, state_probabilities))| {
                 result
                     .slice_mut(s![action_index, ..])
-                    .assign(&self.infoset_evs(&child.payouts(), &child.state_probabilities()));
+                    .assign(&self.infoset_evs(payouts.row(self.player), &state_probabilities));
             })
             .for_each(drop);
         result
     }
 
-    fn current_regret(&self) -> Array<f64, Ix2> {
-        (self.action_evs() - self.infoset_evs(&self.evs, &self.state_probabilities))
-            * self.sign as f64
+    fn current_regret(&self, action_evs: Array<Float, Ix2>) -> Array<Float, Ix2> {
+        action_evs - self.infoset_evs(self.evs.row(self.player), &self.state_probabilities)
     }
 
-    fn regret_match(&self) -> Array<f64, Ix2> {
-        const EPSILON: f64 = 1e-8;
-        let mut result: Array<f64, Ix2> = Array::zeros((self.children.len(), self.infosets.len()));
-        self.regrets
-            .axis_iter(Axis(1))
-            .enumerate()
-            .map(|(infoset_index, x)| {
-                let nonzero_regrets: Array<f64, Ix1> = x
-                    .iter()
-                    .map(|y| match *y < 0. {
-                        true => 0.,
-                        _ => *y,
-                    })
-                    .collect();
-
-                if nonzero_regrets.sum() == 0. {
-                    result
-                        .slice_mut(s![.., infoset_index])
-                        .assign(&Array::from_elem(
-                            nonzero_regrets.len(),
-                            1. / self.children.len() as f64,
-                        ));
-                } else {
-                    result.slice_mut(s![.., infoset_index]).assign(
-                        &((&nonzero_regrets + EPSILON) / (&nonzero_regrets + EPSILON).sum()),
-                    );
-                }
-            })
-            .for_each(drop);
-        result
+    fn is_legal(&self, action_index: usize, infoset_index: usize) -> bool {
+        self.action_mask
+            .as_ref()
+            .is_none_or(|mask| mask[[action_index, infoset_index]])
     }
-}
 
-impl Display for ActionNode {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "ActionNode {{")?;
-        writeln!(f, "  Name: {}", self.name)?;
-        writeln!(f, "  State probabilities: {}", self.state_probabilities)?;
-        writeln!(f, "  Total probabilities: {}", self.total_probabilities)?;
-        writeln!(f, "  EVs: {}", self.evs)?;
-        writeln!(f, "  Infosets: {:?}", self.infosets)?;
-        writeln!(f, "  Strategy:\n{}", self.strategy)?;
-        writeln!(f, "  Average Strategy:\n{}", self.avg_strategy)?;
-        writeln!(f, "  Regrets: {}", self.regrets)?;
-        writeln!(
-            f,
-            "  Children: {:?}",
-            self.children
-                .iter()
-                .map(|x| x.name())
-                .collect::<Vec<String>>()
-        )?;
-        writeln!(f, "}}")?;
+    fn regret_match(&self) -> Array<Float, Ix2> {
+        const EPSILON: Float = 1e-8;
+        let mut positive_regrets = self.regrets.mapv(|x| x.max(0.));
+        let legal = match &self.action_mask {
+            Some(mask) => {
+                let legal = legal_weights(mask);
+                positive_regrets *= &legal;
+                legal
+            }
+            None => Array::ones(self.regrets.raw_dim()),
+        };
 
-        for child in &self.children {
-            writeln!(f, "{}", child)?;
-        }
-        write!(f, "")
+        // Infosets without positive regret play uniformly over the legal actions
+        let legal_scale =
+            positive_regrets
+                .sum_axis(Axis(0))
+                .mapv(|x| if x == 0. { 1. } else { EPSILON });
+        let weights = positive_regrets + legal * &legal_scale;
+        let totals = weights.sum_axis(Axis(0));
+        weights / &totals
     }
-}
 
-impl Node for ActionNode {
-    fn name(&self) -> String {
-        self.name.clone()
+    /// Mixes `epsilon` of uniform play over the legal actions into the current strategy
+    fn explore(&mut self, epsilon: Float) {
+        let legal = match &self.action_mask {
+            Some(mask) => legal_weights(mask),
+            None => Array::ones(self.strategy.raw_dim()),
+        };
+        let uniform = &legal / &legal.sum_axis(Axis(0));
+        self.strategy = &self.strategy * (1. - epsilon) + uniform * epsilon;
     }
 
-    fn state_probabilities(&self) -> Array<f64, Ix1> {
-        self.state_probabilities.clone()
+    /// Accumulates this iteration's regrets, given the current EV of each action, and folds the
+    /// resulting strategy into the average
+    fn accumulate_regrets(&mut self, config: &SolverConfig, action_evs: Array<Float, Ix2>) {
+        let (regrets, infoset_probabilities) = self.weighted_regrets(action_evs);
+        self.apply_regrets(config, regrets, infoset_probabilities);
     }
 
-    fn payouts(&self) -> Array<f64, Ix1> {
-        self.evs.clone()
+    /// Probability of reaching each infoset through chance and the other players' actions, which
+    /// weights its regrets, and through the acting player's own actions, which weights its share of
+    /// the average strategy. The acting player's own reach is the same for every state of an
+    /// infoset.
+    fn reach_probabilities(&self) -> (Array<Float, Ix1>, Array<Float, Ix1>) {
+        let infoset_probabilities = self.infoset_probabilities(&self.state_probabilities);
+        let own: Array<Float, Ix1> = self
+            .infosets
+            .iter()
+            .map(
+                |x| match (x.first(), self.player_probabilities.is_empty()) {
+                    (Some(i), false) => self.player_probabilities[[self.player, *i]],
+                    _ => 1.,
+                },
+            )
+            .collect();
+        let counterfactual = Zip::from(&infoset_probabilities)
+            .and(&own)
+            .map_collect(|p, own| match *own > 0. {
+                true => p / own,
+                false => 0.,
+            });
+        (counterfactual, own)
     }
 
-    fn set_state_probabilities(&mut self, p: Array<f64, Ix1>) {
-        self.state_probabilities = p;
+    /// This iteration's regrets, given the current EV of each action, weighted by the
+    /// counterfactual reach probability of each infoset, along with the acting player's own reach
+    /// probabilities that weight the average strategy
+    fn weighted_regrets(&self, action_evs: Array<Float, Ix2>) -> WeightedRegrets {
+        let (counterfactual, own) = self.reach_probabilities();
+        (self.current_regret(action_evs) * &counterfactual, own)
     }
 
-    fn update_probabilities(&mut self) {
-        if self.total_probabilities.sum() == 0. {
-            self.total_probabilities = self.infoset_probabilities(&self.state_probabilities);
+    /// Adds regrets from [`ActionNode::weighted_regrets`] and folds the resulting strategy into
+    /// the average
+    fn apply_regrets(
+        &mut self,
+        config: &SolverConfig,
+        regrets: Array<Float, Ix2>,
+        infoset_probabilities: Array<Float, Ix1>,
+    ) {
+        // Iteration t contributes with weight t to the regrets under Linear CFR, and uniformly otherwise
+        let regret_weight = match config.variant {
+            CfrVariant::Linear => self.iter_count as Float,
+            _ => 1.,
+        };
+        self.regrets = &self.regrets + regrets * regret_weight;
+
+        if config.variant == CfrVariant::CfrPlus {
+            self.regrets.map_inplace(|x| match *x > 0. {
+                true => {}
+                false => *x = 0.,
+            });
+        }
+        // Illegal actions never accumulate regret
+        if let Some(mask) = &self.action_mask {
+            Zip::from(&mut self.regrets)
+                .and(mask)
+                .for_each(|regret, legal| {
+                    if !legal {
+                        *regret = 0.
+                    }
+                });
+        }
+
+        self.strategy = self.regret_match();
+
+        match config.averaging() {
+            AveragingScheme::Uniform => self.update_avg_strategy(&infoset_probabilities),
+            AveragingScheme::Linear => {
+                self.update_avg_strategy(&(infoset_probabilities * self.iter_count as Float))
+            }
+            AveragingScheme::LastK(k) => self.update_window_avg_strategy(infoset_probabilities, k),
+        }
+        // Explore only after averaging, so the floor shapes reach probabilities but not the average
+        if let Some(exploration) = &config.exploration {
+            self.explore(exploration.epsilon_at(self.iter_count));
+        }
+        self.iter_count += 1;
+    }
+
+    /// Seeds regrets and the average strategy so that regret matching reproduces `strategy`. Each
+    /// infoset's regrets are scaled by its counterfactual reach probability and the spread of its
+    /// action EVs, which bounds the regret a single iteration can add, so it takes on the order of
+    /// `weight` iterations of contrary regret to move away from the seeded strategy. Expects up to
+    /// date probabilities and EVs.
+    pub fn warm_start(&mut self, strategy: &Array<Float, Ix2>, weight: Float) {
+        let (counterfactual, own) = self.reach_probabilities();
+        let action_evs = self.action_evs();
+        let spreads: Array<Float, Ix1> = action_evs
+            .axis_iter(Axis(1))
+            .map(|x| {
+                x.iter().fold(Float::MIN, |f, y| f.max(*y))
+                    - x.iter().fold(Float::MAX, |f, y| f.min(*y))
+            })
+            .collect();
+
+        self.strategy = strategy.clone();
+        self.regrets = strategy * &(spreads * &counterfactual * weight);
+        self.avg_strategy = strategy.clone();
+        self.total_probabilities = own * weight;
+    }
+
+    /// Best response values given a function computing each child's best response values from
+    /// its reach probabilities
+    fn best_response_from(
+        &self,
+        player: usize,
+        reach: &Array<Float, Ix1>,
+        child_best_response: impl Fn(usize, &Array<Float, Ix1>) -> Array<Float, Ix1> + Sync,
+    ) -> Array<Float, Ix1> {
+        let n_states = self.state_probabilities.len();
+        match self.player == player {
+            true => {
+                // Pick the action with the highest counterfactual value in each infoset
+                let child_values: Vec<Array<Float, Ix1>> = (0..self.strategy.nrows())
+                    .into_par_iter()
+                    .map(|action_index| child_best_response(action_index, reach))
+                    .collect();
+
+                let mut result: Array<Float, Ix1> = Array::zeros(n_states);
+                self.infosets
+                    .iter()
+                    .enumerate()
+                    .map(|(infoset_index, infoset)| {
+                        let (best_action, _) = child_values
+                            .iter()
+                            .map(|values| {
+                                infoset
+                                    .iter()
+                                    .map(|i| reach[*i] * values[*i])
+                                    .sum::<Float>()
+                            })
+                            .enumerate()
+                            .filter(|(action_index, _)| self.is_legal(*action_index, infoset_index))
+                            .fold((0, Float::MIN), |(argmax, max), (index, value)| {
+                                match max >= value {
+                                    true => (argmax, max),
+                                    false => (index, value),
+                                }
+                            });
+                        infoset
+                            .iter()
+                            .map(|i| result[*i] = child_values[best_action][*i])
+                            .for_each(drop);
+                    })
+                    .for_each(drop);
+                result
+            }
+            false => {
+                // Other players follow their average strategy
+                let expanded_strategy = self.expand_strategy(&self.avg_strategy);
+                (0..self.strategy.nrows())
+                    .into_par_iter()
+                    .map(|action_index| {
+                        let action_probabilities = expanded_strategy.slice(s![action_index, ..]);
+                        child_best_response(action_index, &(reach * &action_probabilities))
+                            * action_probabilities
+                    })
+                    .reduce(|| Array::zeros(n_states), |f, x| f + x)
+            }
+        }
+    }
+
+    /// Reach probabilities [`ActionNode::best_response_from`] passes to each child
+    fn child_reaches(&self, player: usize, reach: &Array<Float, Ix1>) -> Vec<Array<Float, Ix1>> {
+        if self.player == player {
+            return vec![reach.clone(); self.children.len()];
+        }
+        let expanded_strategy = self.expand_strategy(&self.avg_strategy);
+        expanded_strategy
+            .rows()
+            .into_iter()
+            .map(|x| reach * &x)
+            .collect()
+    }
+
+    /// Children of a hidden action when `player` acts in them, which must best respond together
+    /// since they share their infosets; empty otherwise
+    fn tied_children(&self, player: usize) -> Vec<&ActionNode> {
+        if !self.hidden {
+            return Vec::new();
+        }
+        self.children
+            .iter()
+            .filter_map(|x| match x.as_node_ref() {
+                NodeRef::Action(x) if x.player == player => Some(x),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Updates the strategies below this node. The children of a hidden action share their
+    /// strategy, so they all add up the regrets of every child.
+    fn update_children_strategies(&mut self, config: &SolverConfig, player: Option<usize>) {
+        if !self.hidden {
+            self.children
+                .par_iter_mut()
+                .map(|x| x.update_strategy(config, player))
+                .for_each(drop);
+            return;
+        }
+        let mut children: Vec<&mut ActionNode> = self
+            .children
+            .iter_mut()
+            .filter_map(|x| match x.as_node_mut() {
+                NodeMut::Action(x) => Some(x),
+                _ => None,
+            })
+            .collect();
+        if children
+            .first()
+            .is_some_and(|x| config.updates(x.player, player))
+        {
+            let total = children
+                .par_iter()
+                .map(|x| x.weighted_regrets(x.action_evs()))
+                .reduce_with(|(a, b), (x, y)| (a + x, b + y));
+            if let Some((regrets, infoset_probabilities)) = total {
+                children
+                    .par_iter_mut()
+                    .map(|x| {
+                        x.apply_regrets(config, regrets.clone(), infoset_probabilities.clone())
+                    })
+                    .for_each(drop);
+            }
+        }
+        children
+            .par_iter_mut()
+            .map(|x| x.update_children_strategies(config, player))
+            .for_each(drop);
+    }
+
+    /// Average strategy payouts given a function computing each child's
+    fn avg_strategy_payouts_from(
+        &self,
+        child_payouts: impl Fn(usize) -> Array<Float, Ix2> + Sync,
+    ) -> Array<Float, Ix2> {
+        let expanded_strategy = self.expand_strategy(&self.avg_strategy);
+        (0..self.strategy.nrows())
+            .into_par_iter()
+            .map(|action_index| {
+                child_payouts(action_index) * expanded_strategy.slice(s![action_index, ..])
+            })
+            .reduce(|| Array::zeros(self.evs.raw_dim()), |f, x| f + x)
+    }
+
+    /// Recomputes the average over the current strategy and the previous `k - 1`, each weighted by
+    /// its infoset reach probabilities
+    fn update_window_avg_strategy(&mut self, weights: Array<Float, Ix1>, k: usize) {
+        self.recent_strategies
+            .push_back((self.strategy.clone(), weights));
+        while self.recent_strategies.len() > k {
+            self.recent_strategies.pop_front();
+        }
+
+        let mut weighted_sum: Array<Float, Ix2> = Array::zeros(self.strategy.raw_dim());
+        let mut total: Array<Float, Ix1> = Array::zeros(self.infosets.len());
+        self.recent_strategies
+            .iter()
+            .map(|(strategy, weights)| {
+                weighted_sum = &weighted_sum + strategy * weights;
+                total = &total + weights;
+            })
+            .for_each(drop);
+
+        Zip::from(self.avg_strategy.columns_mut())
+            .and(weighted_sum.columns())
+            .and(&total)
+            .for_each(|mut avg, sum, total| {
+                // Infosets not reached within the window keep their previous average
+                if *total > 0. {
+                    avg.assign(&(&sum / *total));
+                }
+            });
+        self.total_probabilities = total;
+    }
+
+    /// Folds the current strategy into the running average, weighting each infoset by `weights`
+    fn update_avg_strategy(&mut self, weights: &Array<Float, Ix1>) {
+        Zip::from(self.avg_strategy.columns_mut())
+            .and(self.strategy.columns())
+            .and(&mut self.total_probabilities)
+            .and(weights)
+            .for_each(|mut avg, current, total, weight| {
+                // Infosets that are never reached keep their previous average
+                if *total + *weight > 0. {
+                    avg.assign(&((&avg * *total + &current * *weight) / (*total + *weight)));
+                    *total += *weight;
+                }
+            });
+    }
+}
+
+/// Regrets of an action node, indexed by action, infoset, and the reach probability of each infoset
+/// that weights its average strategy; see [`ActionNode::weighted_regrets`]
+type WeightedRegrets = (Array<Float, Ix2>, Array<Float, Ix1>);
+
+/// Best response values of the children of a hidden action where `player` acts, given the reach
+/// probabilities of each child and a function computing the best response values of each child's
+/// children. The children share their infosets, so each infoset takes the action that does best
+/// summed over all of them.
+fn tied_best_response(
+    children: &[&ActionNode],
+    reaches: &[Array<Float, Ix1>],
+    grandchild_best_response: impl Fn(usize, usize, &Array<Float, Ix1>) -> Array<Float, Ix1> + Sync,
+) -> Vec<Array<Float, Ix1>> {
+    // Indexed by child, action
+    let values: Vec<Vec<Array<Float, Ix1>>> = children
+        .par_iter()
+        .enumerate()
+        .map(|(child_index, child)| {
+            (0..child.strategy.nrows())
+                .map(|action_index| {
+                    grandchild_best_response(child_index, action_index, &reaches[child_index])
+                })
+                .collect()
+        })
+        .collect();
+
+    let first = children[0];
+    let mut result: Vec<Array<Float, Ix1>> =
+        reaches.iter().map(|x| Array::zeros(x.len())).collect();
+    for (infoset_index, infoset) in first.infosets.iter().enumerate() {
+        let (best_action, _) = (0..first.strategy.nrows())
+            .filter(|action_index| first.is_legal(*action_index, infoset_index))
+            .map(|action_index| {
+                let value: Float = (0..children.len())
+                    .map(|child_index| {
+                        infoset
+                            .iter()
+                            .map(|i| {
+                                reaches[child_index][*i] * values[child_index][action_index][*i]
+                            })
+                            .sum::<Float>()
+                    })
+                    .sum();
+                (action_index, value)
+            })
+            .fold((0, Float::MIN), |(argmax, max), (index, value)| {
+                match max >= value {
+                    true => (argmax, max),
+                    false => (index, value),
+                }
+            });
+        for (child_index, values) in values.iter().enumerate() {
+            for i in infoset {
+                result[child_index][*i] = values[best_action][*i];
+            }
+        }
+    }
+    result
+}
+
+impl Display for ActionNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "ActionNode {{")?;
+        writeln!(f, "  Name: {}", self.name)?;
+        writeln!(f, "  State probabilities: {}", self.state_probabilities)?;
+        writeln!(f, "  Total probabilities: {}", self.total_probabilities)?;
+        writeln!(f, "  EVs: {}", self.evs)?;
+        writeln!(f, "  Infosets: {:?}", self.infosets)?;
+        writeln!(f, "  Strategy:\n{}", self.strategy)?;
+        writeln!(f, "  Average Strategy:\n{}", self.avg_strategy)?;
+        writeln!(f, "  Regrets: {}", self.regrets)?;
+        writeln!(
+            f,
+            "  Children: {:?}",
+            self.children
+                .iter()
+                .map(|x| x.name())
+                .collect::<Vec<String>>()
+        )?;
+        writeln!(f, "}}")?;
+
+        for child in &self.children {
+            writeln!(f, "{}", child)?;
         }
+        write!(f, "")
+    }
+}
+
+impl Node for ActionNode {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn state_probabilities(&self) -> ArrayView1<'_, Float> {
+        self.state_probabilities.view()
+    }
 
-        let expanded_strategy = self.expand_strategy();
+    fn payouts(&self) -> ArrayView2<'_, Float> {
+        self.evs.view()
+    }
+
+    fn set_state_probabilities(&mut self, p: Array<Float, Ix1>) {
+        self.state_probabilities = p;
+    }
+
+    fn set_player_probabilities(&mut self, p: Array<Float, Ix2>) {
+        self.player_probabilities = p;
+    }
+
+    fn update_probabilities(&mut self) {
+        let expanded_strategy = self.expand_strategy(&self.strategy);
+        let player_probabilities = player_probabilities(
+            &self.player_probabilities,
+            self.evs.nrows(),
+            self.state_probabilities.len(),
+        );
 
         self.children
             .par_iter_mut()
             .enumerate()
             .map(|(action_index, child)| {
-                child.set_state_probabilities(
-                    self.state_probabilities.clone()
-                        * expanded_strategy.slice(s![action_index, ..]),
-                );
+                let action_probabilities = expanded_strategy.slice(s![action_index, ..]);
+                child.set_state_probabilities(&self.state_probabilities * &action_probabilities);
+                let mut child_player_probabilities = player_probabilities.clone();
+                let mut own = child_player_probabilities.row_mut(self.player);
+                own *= &action_probabilities;
+                child.set_player_probabilities(child_player_probabilities);
                 child.update_probabilities();
             })
             .for_each(drop);
@@ -211,62 +1520,64 @@ impl Node for ActionNode {
             .for_each(drop);
 
         // Compute current node EV from children
-        let n_states = self.state_probabilities.len();
-        self.evs = self
-            .children
-            .iter()
-            .map(|child| child.payouts() * child.state_probabilities())
-            .fold(Array::zeros(n_states), |f, x| f + x)
-            / self
-                .state_probabilities
+        self.evs = conditional_evs(
+            self.children
                 .iter()
-                .map(|x| match x {
-                    0. => 1.,
-                    _ => *x,
-                })
-                .collect::<Array<f64, Ix1>>();
+                .map(|child| &child.payouts() * &child.state_probabilities())
+                .fold(Array::zeros(self.evs.raw_dim()), |f, x| f + x),
+            &self.state_probabilities,
+        );
     }
 
-    fn update_strategy(&mut self) {
-        let infoset_probabilities = self.infoset_probabilities(&self.state_probabilities);
-
-        self.regrets = (&self.regrets + self.current_regret() * &infoset_probabilities)
-            * self.iter_count as f64
-            / (self.iter_count as f64 + 1.);
-
-        self.regrets.map_inplace(|x| match *x > 0. {
-            true => {}
-            false => *x = 0.,
-        });
-
-        self.strategy = self.regret_match();
-
-        self.avg_strategy = (&self.avg_strategy * &self.total_probabilities
-            + &self.strategy * &infoset_probabilities)
-            / (&self.total_probabilities + &infoset_probabilities);
+    fn update_strategy(&mut self, config: &SolverConfig, player: Option<usize>) {
+        if config.updates(self.player, player) {
+            self.accumulate_regrets(config, self.action_evs());
+        }
+        self.update_children_strategies(config, player);
+    }
 
-        self.total_probabilities = (&self.total_probabilities + infoset_probabilities)
-            * self.iter_count as f64
-            / (self.iter_count as f64 + 1.);
-        self.iter_count += 1;
+    fn best_response(&self, player: usize, reach: &Array<Float, Ix1>) -> Array<Float, Ix1> {
+        let children = self.tied_children(player);
+        if children.is_empty() {
+            return self.best_response_from(player, reach, |action_index, reach| {
+                self.children[action_index].best_response(player, reach)
+            });
+        }
+        let values = tied_best_response(
+            &children,
+            &self.child_reaches(player, reach),
+            |child_index, action_index, reach| {
+                children[child_index].children[action_index].best_response(player, reach)
+            },
+        );
+        self.best_response_from(player, reach, |child_index, _| values[child_index].clone())
+    }
 
-        self.children
-            .par_iter_mut()
-            .map(|x| x.update_strategy())
-            .for_each(drop);
+    fn avg_strategy_payouts(&self) -> Array<Float, Ix2> {
+        self.avg_strategy_payouts_from(|action_index| {
+            self.children[action_index].avg_strategy_payouts()
+        })
     }
 
-    fn strategy(&self) -> Option<Array<f64, Ix2>> {
-        Some(self.strategy.clone())
+    fn strategy(&self) -> Option<ArrayView2<'_, Float>> {
+        Some(self.strategy.view())
     }
 
-    fn avg_strategy(&self) -> Option<Array<f64, Ix2>> {
-        Some(self.avg_strategy.clone())
+    fn avg_strategy(&self) -> Option<ArrayView2<'_, Float>> {
+        Some(self.avg_strategy.view())
     }
 
     fn children(&self) -> Option<&Vec<Box<dyn Node>>> {
         Some(&self.children)
     }
+
+    fn as_node_ref(&self) -> NodeRef<'_> {
+        NodeRef::Action(self)
+    }
+
+    fn as_node_mut(&mut self) -> NodeMut<'_> {
+        NodeMut::Action(self)
+    }
 }
 
 impl Display for TerminalNode {
@@ -279,11 +1590,22 @@ impl Display for TerminalNode {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct TerminalNode {
     pub name: String,
-    pub state_probabilities: Array<f64, Ix1>,
-    pub payouts: Array<f64, Ix1>,
+    pub state_probabilities: Array<Float, Ix1>,
+    pub payouts: Array<Float, Ix2>, // Indexed by player, state
+}
+
+impl TerminalNode {
+    /// Terminal node of a two player zero-sum game, given the first player's payouts
+    pub fn zero_sum(name: &str, payouts: Array<Float, Ix1>) -> Self {
+        TerminalNode {
+            name: name.to_string(),
+            state_probabilities: Array::zeros(payouts.len()),
+            payouts: stack![Axis(0), payouts, -&payouts],
+        }
+    }
 }
 
 impl Node for TerminalNode {
@@ -291,15 +1613,15 @@ impl Node for TerminalNode {
         self.name.clone()
     }
 
-    fn state_probabilities(&self) -> Array<f64, Ix1> {
-        self.state_probabilities.clone()
+    fn state_probabilities(&self) -> ArrayView1<'_, Float> {
+        self.state_probabilities.view()
     }
 
-    fn payouts(&self) -> Array<f64, Ix1> {
-        self.payouts.clone()
+    fn payouts(&self) -> ArrayView2<'_, Float> {
+        self.payouts.view()
     }
 
-    fn set_state_probabilities(&mut self, p: Array<f64, Ix1>) {
+    fn set_state_probabilities(&mut self, p: Array<Float, Ix1>) {
         self.state_probabilities = p;
     }
 
@@ -311,16 +1633,24 @@ impl Node for TerminalNode {
         // Nothing to do for terminal nodes
     }
 
-    fn update_strategy(&mut self) {
+    fn update_strategy(&mut self, _config: &SolverConfig, _player: Option<usize>) {
         // Nothing to do for terminal nodes
     }
 
-    fn strategy(&self) -> Option<Array<f64, Ix2>> {
+    fn best_response(&self, player: usize, _reach: &Array<Float, Ix1>) -> Array<Float, Ix1> {
+        self.payouts.row(player).to_owned()
+    }
+
+    fn avg_strategy_payouts(&self) -> Array<Float, Ix2> {
+        self.payouts.clone()
+    }
+
+    fn strategy(&self) -> Option<ArrayView2<'_, Float>> {
         // Terminal nodes have no strategy
         None
     }
 
-    fn avg_strategy(&self) -> Option<Array<f64, Ix2>> {
+    fn avg_strategy(&self) -> Option<ArrayView2<'_, Float>> {
         // Terminal nodes have no strategy
         None
     }
@@ -329,88 +1659,1989 @@ impl Node for TerminalNode {
         // Terminal nodes have no children
         None
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn as_node_ref(&self) -> NodeRef<'_> {
+        NodeRef::Terminal(self)
+    }
 
-    #[test]
-    fn test_expand_strategy() {
-        let root = ActionNode {
-            name: "root".to_string(),
-            state_probabilities: Array::from_elem(6, 1. / 6.),
-            total_probabilities: Array::zeros(3),
-            evs: Array::zeros(6),
-            infosets: vec![vec![0, 1], vec![2, 3], vec![4, 5]],
-            strategy: Array::from_elem((2, 3), 1. / 2.),
-            avg_strategy: Array::from_elem((2, 3), 1. / 2.),
-            regrets: Array::from_elem((2, 3), 0.),
-            children: vec![
-                Box::new(TerminalNode {
-                    name: "a".to_string(),
-                    state_probabilities: Array::from_elem(3, 0.),
-                    payouts: array![3., 2., 1.],
-                }),
-                Box::new(TerminalNode {
-                    name: "b".to_string(),
-                    state_probabilities: Array::from_elem(3, 0.),
-                    payouts: array![1., 2., 3.],
-                }),
-            ],
-            sign: 1,
-            iter_count: 1,
-        };
+    fn as_node_mut(&mut self) -> NodeMut<'_> {
+        NodeMut::Terminal(self)
+    }
+}
+
+impl Display for ChanceNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "ChanceNode {{")?;
+        writeln!(f, "  Name: {}", self.name)?;
+        writeln!(f, "  State probabilities: {}", self.state_probabilities)?;
+        writeln!(f, "  EVs: {}", self.evs)?;
+        writeln!(
+            f,
+            "  Outcome probabilities:\n{}",
+            self.outcome_probabilities
+        )?;
+        writeln!(
+            f,
+            "  Children: {:?}",
+            self.children
+                .iter()
+                .map(|x| x.name())
+                .collect::<Vec<String>>()
+        )?;
+        writeln!(f, "}}")?;
 
-        assert_eq!(root.expand_strategy(), Array::from_elem((2, 6), 1. / 2.));
+        for child in &self.children {
+            writeln!(f, "{}", child)?;
+        }
+        write!(f, "")
     }
+}
 
-    #[test]
-    fn test_update_tree() {
-        let mut root = ActionNode {
-            name: "root".to_string(),
-            state_probabilities: Array::from_elem(3, 1. / 3.),
-            total_probabilities: Array::zeros(3),
-            evs: Array::zeros(3),
-            infosets: vec![vec![0], vec![1], vec![2]],
-            strategy: Array::from_elem((3, 3), 1. / 3.),
-            avg_strategy: Array::from_elem((3, 3), 1. / 3.),
-            regrets: Array::from_elem((3, 3), 0.),
-            children: vec![
-                Box::new(TerminalNode {
-                    name: "a".to_string(),
-                    state_probabilities: Array::from_elem(3, 0.),
-                    payouts: array![3., 2., 3.],
-                }),
-                Box::new(TerminalNode {
-                    name: "b".to_string(),
-                    state_probabilities: Array::from_elem(3, 0.),
-                    payouts: array![1., 2.5, 2.],
-                }),
-                Box::new(TerminalNode {
-                    name: "c".to_string(),
-                    state_probabilities: Array::from_elem(3, 0.),
-                    payouts: array![4., 2., 2.],
-                }),
-            ],
-            sign: 1,
-            iter_count: 1,
-        };
-        println!("{}", root);
+/// Chance event (e.g. a card being dealt) with fixed outcome probabilities
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ChanceNode {
+    pub name: String,
+    pub state_probabilities: Array<Float, Ix1>, // Indexed by state
+    pub evs: Array<Float, Ix2>,                 // Indexed by player, state
+    /// Probability of reaching each state through each player's own actions, indexed by player,
+    /// state; empty at the root
+    #[serde(default)]
+    pub player_probabilities: Array<Float, Ix2>,
+    pub outcome_probabilities: Array<Float, Ix2>, // Indexed by outcome, state
+    pub children: Vec<Box<dyn Node>>,             // Indexed by outcome
+}
 
-        for _ in 0..1 {
-            // Run one iteration of CFR
-            root.update_probabilities();
-            root.update_ev();
-            root.update_strategy();
-            root.update_probabilities();
+impl ChanceNode {
+    /// Chance node whose outcomes are equally likely in every state
+    pub fn uniform(
+        name: &str,
+        n_players: usize,
+        n_states: usize,
+        children: Vec<Box<dyn Node>>,
+    ) -> Self {
+        let n_outcomes = children.len();
+        ChanceNode {
+            name: name.to_string(),
+            state_probabilities: Array::zeros(n_states),
+            evs: Array::zeros((n_players, n_states)),
+            player_probabilities: Array::zeros((0, 0)),
+            outcome_probabilities: Array::from_elem(
+                (n_outcomes, n_states),
+                1. / n_outcomes as Float,
+            ),
+            children,
         }
+    }
 
-        println!("{}", root);
+    /// Best response values given a function computing each child's best response values from
+    /// its reach probabilities
+    fn best_response_from(
+        &self,
+        reach: &Array<Float, Ix1>,
+        child_best_response: impl Fn(usize, &Array<Float, Ix1>) -> Array<Float, Ix1> + Sync,
+    ) -> Array<Float, Ix1> {
+        let n_states = self.state_probabilities.len();
+        (0..self.outcome_probabilities.nrows())
+            .into_par_iter()
+            .map(|outcome_index| {
+                let outcome_probabilities = self.outcome_probabilities.slice(s![outcome_index, ..]);
+                child_best_response(outcome_index, &(reach * &outcome_probabilities))
+                    * outcome_probabilities
+            })
+            .reduce(|| Array::zeros(n_states), |f, x| f + x)
+    }
 
-        println!("{}", root.infoset_probabilities(&root.state_probabilities));
-        println!("{}", root.infoset_evs(&root.evs, &root.state_probabilities));
-        println!("{}", root.action_evs());
-        println!("{}", root.current_regret());
+    /// Average strategy payouts given a function computing each child's
+    fn avg_strategy_payouts_from(
+        &self,
+        child_payouts: impl Fn(usize) -> Array<Float, Ix2> + Sync,
+    ) -> Array<Float, Ix2> {
+        (0..self.outcome_probabilities.nrows())
+            .into_par_iter()
+            .map(|outcome_index| {
+                child_payouts(outcome_index)
+                    * self.outcome_probabilities.slice(s![outcome_index, ..])
+            })
+            .reduce(|| Array::zeros(self.evs.raw_dim()), |f, x| f + x)
+    }
+}
+
+impl Node for ChanceNode {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn state_probabilities(&self) -> ArrayView1<'_, Float> {
+        self.state_probabilities.view()
+    }
+
+    fn payouts(&self) -> ArrayView2<'_, Float> {
+        self.evs.view()
+    }
+
+    fn set_state_probabilities(&mut self, p: Array<Float, Ix1>) {
+        self.state_probabilities = p;
+    }
+
+    fn set_player_probabilities(&mut self, p: Array<Float, Ix2>) {
+        self.player_probabilities = p;
+    }
+
+    fn update_probabilities(&mut self) {
+        // Chance isn't a player, so every player's own reach passes through unchanged
+        let player_probabilities = player_probabilities(
+            &self.player_probabilities,
+            self.evs.nrows(),
+            self.state_probabilities.len(),
+        );
+        self.children
+            .par_iter_mut()
+            .enumerate()
+            .map(|(outcome_index, child)| {
+                child.set_state_probabilities(
+                    &self.state_probabilities
+                        * &self.outcome_probabilities.slice(s![outcome_index, ..]),
+                );
+                child.set_player_probabilities(player_probabilities.clone());
+                child.update_probabilities();
+            })
+            .for_each(drop);
+    }
+
+    fn update_ev(&mut self) {
+        self.children
+            .par_iter_mut()
+            .map(|x| x.update_ev())
+            .for_each(drop);
+
+        // Outcome probabilities are already folded into the children's state probabilities
+        self.evs = conditional_evs(
+            self.children
+                .iter()
+                .map(|child| &child.payouts() * &child.state_probabilities())
+                .fold(Array::zeros(self.evs.raw_dim()), |f, x| f + x),
+            &self.state_probabilities,
+        );
+    }
+
+    fn update_strategy(&mut self, config: &SolverConfig, player: Option<usize>) {
+        self.children
+            .par_iter_mut()
+            .map(|x| x.update_strategy(config, player))
+            .for_each(drop);
+    }
+
+    fn best_response(&self, player: usize, reach: &Array<Float, Ix1>) -> Array<Float, Ix1> {
+        self.best_response_from(reach, |outcome_index, reach| {
+            self.children[outcome_index].best_response(player, reach)
+        })
+    }
+
+    fn avg_strategy_payouts(&self) -> Array<Float, Ix2> {
+        self.avg_strategy_payouts_from(|outcome_index| {
+            self.children[outcome_index].avg_strategy_payouts()
+        })
+    }
+
+    fn strategy(&self) -> Option<ArrayView2<'_, Float>> {
+        // Chance nodes have no strategy
+        None
+    }
+
+    fn avg_strategy(&self) -> Option<ArrayView2<'_, Float>> {
+        // Chance nodes have no strategy
+        None
+    }
+
+    fn children(&self) -> Option<&Vec<Box<dyn Node>>> {
+        Some(&self.children)
+    }
+
+    fn as_node_ref(&self) -> NodeRef<'_> {
+        NodeRef::Chance(self)
+    }
+
+    fn as_node_mut(&mut self) -> NodeMut<'_> {
+        NodeMut::Chance(self)
+    }
+}
+
+/// Concrete node stored inline in an [`ArenaTree`], with its `children` left empty
+#[derive(Debug, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)] // Stored inline so the arena stays one contiguous allocation
+pub(crate) enum ArenaNode {
+    Action(ActionNode),
+    Terminal(TerminalNode),
+    Chance(ChanceNode),
+}
+
+impl ArenaNode {
+    fn as_node(&self) -> &dyn Node {
+        match self {
+            ArenaNode::Action(x) => x,
+            ArenaNode::Terminal(x) => x,
+            ArenaNode::Chance(x) => x,
+        }
+    }
+
+    pub(crate) fn state_probabilities(&self) -> &Array<Float, Ix1> {
+        match self {
+            ArenaNode::Action(x) => &x.state_probabilities,
+            ArenaNode::Terminal(x) => &x.state_probabilities,
+            ArenaNode::Chance(x) => &x.state_probabilities,
+        }
+    }
+
+    pub(crate) fn state_probabilities_mut(&mut self) -> &mut Array<Float, Ix1> {
+        match self {
+            ArenaNode::Action(x) => &mut x.state_probabilities,
+            ArenaNode::Terminal(x) => &mut x.state_probabilities,
+            ArenaNode::Chance(x) => &mut x.state_probabilities,
+        }
+    }
+
+    pub(crate) fn player_probabilities_mut(&mut self) -> Option<&mut Array<Float, Ix2>> {
+        match self {
+            ArenaNode::Action(x) => Some(&mut x.player_probabilities),
+            ArenaNode::Terminal(_) => None,
+            ArenaNode::Chance(x) => Some(&mut x.player_probabilities),
+        }
+    }
+
+    pub(crate) fn payouts(&self) -> &Array<Float, Ix2> {
+        match self {
+            ArenaNode::Action(x) => &x.evs,
+            ArenaNode::Terminal(x) => &x.payouts,
+            ArenaNode::Chance(x) => &x.evs,
+        }
+    }
+}
+
+/// Flattened tree with the same solve API as a boxed one. Nodes are stored breadth-first in a
+/// single vector and each node's children occupy a contiguous range of indices after it, so the
+/// CFR passes are loops over the vector rather than recursion through trait objects. With the
+/// `gpu` feature, [`crate::gpu::GpuArenaTree`] runs the probability and EV passes on the GPU.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ArenaTree {
+    pub(crate) nodes: Vec<ArenaNode>,
+    pub(crate) children: Vec<Range<usize>>, // Indexed by node
+}
+
+impl ArenaTree {
+    pub fn from_tree(root: Box<dyn Node>) -> Self {
+        let mut result = ArenaTree::default();
+        let mut queue = VecDeque::from([root]);
+        while let Some(mut node) = queue.pop_front() {
+            let (node, children) = match node.as_node_mut() {
+                NodeMut::Action(x) => {
+                    let children = std::mem::take(&mut x.children);
+                    (ArenaNode::Action(std::mem::take(x)), children)
+                }
+                NodeMut::Chance(x) => {
+                    let children = std::mem::take(&mut x.children);
+                    (ArenaNode::Chance(std::mem::take(x)), children)
+                }
+                NodeMut::Terminal(x) => (ArenaNode::Terminal(std::mem::take(x)), Vec::new()),
+                NodeMut::Arena(x) => {
+                    queue.push_front(std::mem::take(x).into_tree());
+                    continue;
+                }
+            };
+            // Everything already queued gets its index before this node's children
+            let first_child = result.nodes.len() + queue.len() + 1;
+            result
+                .children
+                .push(first_child..first_child + children.len());
+            result.nodes.push(node);
+            queue.extend(children);
+        }
+        result
+    }
+
+    /// Rebuilds the boxed tree, e.g. to walk it with [`Node::children`]
+    pub fn into_tree(self) -> Box<dyn Node> {
+        let mut built: Vec<Option<Box<dyn Node>>> = (0..self.nodes.len()).map(|_| None).collect();
+        // Children always come after their parent, so building from the back sees them first
+        for (index, node) in self.nodes.into_iter().enumerate().rev() {
+            let children: Vec<Box<dyn Node>> = self.children[index]
+                .clone()
+                .map(|child| built[child].take().unwrap())
+                .collect();
+            built[index] = Some(match node {
+                ArenaNode::Action(mut x) => {
+                    x.children = children;
+                    Box::new(x)
+                }
+                ArenaNode::Chance(mut x) => {
+                    x.children = children;
+                    Box::new(x)
+                }
+                ArenaNode::Terminal(x) => Box::new(x),
+            });
+        }
+        built[0].take().unwrap()
+    }
+
+    /// Path of node names of every node, starting from `root_path`
+    fn paths(&self, root_path: &str) -> Vec<String> {
+        let mut result = vec![root_path.to_string(); self.nodes.len()];
+        for index in 0..self.nodes.len() {
+            for child in self.children[index].clone() {
+                result[child] = format!("{}/{}", result[index], self.nodes[child].as_node().name());
+            }
+        }
+        result
+    }
+
+    fn best_response_at(
+        &self,
+        index: usize,
+        player: usize,
+        reach: &Array<Float, Ix1>,
+    ) -> Array<Float, Ix1> {
+        let first_child = self.children[index].start;
+        let child_best_response = |offset: usize, reach: &Array<Float, Ix1>| {
+            self.best_response_at(first_child + offset, player, reach)
+        };
+        match &self.nodes[index] {
+            ArenaNode::Action(x) if x.hidden => {
+                let children: Vec<&ActionNode> = self.children[index]
+                    .clone()
+                    .filter_map(|child| match &self.nodes[child] {
+                        ArenaNode::Action(child) if child.player == player => Some(child),
+                        _ => None,
+                    })
+                    .collect();
+                if children.is_empty() {
+                    return x.best_response_from(player, reach, child_best_response);
+                }
+                let values = tied_best_response(
+                    &children,
+                    &x.child_reaches(player, reach),
+                    |child_index, action_index, reach| {
+                        let grandchild = self.children[first_child + child_index].start;
+                        self.best_response_at(grandchild + action_index, player, reach)
+                    },
+                );
+                x.best_response_from(player, reach, |child_index, _| values[child_index].clone())
+            }
+            ArenaNode::Action(x) => x.best_response_from(player, reach, child_best_response),
+            ArenaNode::Chance(x) => x.best_response_from(reach, child_best_response),
+            ArenaNode::Terminal(x) => x.best_response(player, reach),
+        }
+    }
+
+    fn avg_strategy_payouts_at(&self, index: usize) -> Array<Float, Ix2> {
+        let first_child = self.children[index].start;
+        let child_payouts = |offset: usize| self.avg_strategy_payouts_at(first_child + offset);
+        match &self.nodes[index] {
+            ArenaNode::Action(x) => x.avg_strategy_payouts_from(child_payouts),
+            ArenaNode::Chance(x) => x.avg_strategy_payouts_from(child_payouts),
+            ArenaNode::Terminal(x) => x.avg_strategy_payouts(),
+        }
+    }
+}
+
+impl Display for ArenaTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for node in &self.nodes {
+            writeln!(f, "{}", node.as_node())?;
+        }
+        write!(f, "")
+    }
+}
+
+impl Node for ArenaTree {
+    fn name(&self) -> String {
+        self.nodes[0].as_node().name()
+    }
+
+    fn state_probabilities(&self) -> ArrayView1<'_, Float> {
+        self.nodes[0].state_probabilities().view()
+    }
+
+    fn payouts(&self) -> ArrayView2<'_, Float> {
+        self.nodes[0].payouts().view()
+    }
+
+    fn set_state_probabilities(&mut self, p: Array<Float, Ix1>) {
+        *self.nodes[0].state_probabilities_mut() = p;
+    }
+
+    fn set_player_probabilities(&mut self, p: Array<Float, Ix2>) {
+        if let Some(x) = self.nodes[0].player_probabilities_mut() {
+            *x = p;
+        }
+    }
+
+    fn update_probabilities(&mut self) {
+        for index in 0..self.nodes.len() {
+            // Children come after the parent, so they can be borrowed mutably alongside it
+            let (head, tail) = self.nodes.split_at_mut(index + 1);
+            let range = &self.children[index];
+            let children = &mut tail[range.start - index - 1..range.end - index - 1];
+
+            let expanded_strategy;
+            // The acting player, whose own reach the children's probabilities scale; none for chance
+            let (state_probabilities, child_probabilities, player_probabilities, player) =
+                match &head[index] {
+                    ArenaNode::Action(x) => {
+                        expanded_strategy = x.expand_strategy(&x.strategy);
+                        (
+                            &x.state_probabilities,
+                            expanded_strategy.view(),
+                            player_probabilities(
+                                &x.player_probabilities,
+                                x.evs.nrows(),
+                                x.state_probabilities.len(),
+                            ),
+                            Some(x.player),
+                        )
+                    }
+                    ArenaNode::Chance(x) => (
+                        &x.state_probabilities,
+                        x.outcome_probabilities.view(),
+                        player_probabilities(
+                            &x.player_probabilities,
+                            x.evs.nrows(),
+                            x.state_probabilities.len(),
+                        ),
+                        None,
+                    ),
+                    ArenaNode::Terminal(_) => continue,
+                };
+            children
+                .par_iter_mut()
+                .enumerate()
+                .map(|(child_index, child)| {
+                    *child.state_probabilities_mut() =
+                        state_probabilities * &child_probabilities.row(child_index);
+                    if let Some(x) = child.player_probabilities_mut() {
+                        let mut probabilities = player_probabilities.clone();
+                        if let Some(player) = player {
+                            let mut own = probabilities.row_mut(player);
+                            own *= &child_probabilities.row(child_index);
+                        }
+                        *x = probabilities;
+                    }
+                })
+                .for_each(drop);
+        }
+    }
+
+    fn update_ev(&mut self) {
+        for index in (0..self.nodes.len()).rev() {
+            let (head, tail) = self.nodes.split_at_mut(index + 1);
+            let range = &self.children[index];
+            let children = &tail[range.start - index - 1..range.end - index - 1];
+
+            let (evs, state_probabilities) = match &mut head[index] {
+                ArenaNode::Action(x) => (&mut x.evs, &x.state_probabilities),
+                ArenaNode::Chance(x) => (&mut x.evs, &x.state_probabilities),
+                ArenaNode::Terminal(_) => continue,
+            };
+            *evs = conditional_evs(
+                children
+                    .iter()
+                    .map(|child| child.payouts() * child.state_probabilities())
+                    .fold(Array::zeros(evs.raw_dim()), |f, x| f + x),
+                state_probabilities,
+            );
+        }
+    }
+
+    fn update_strategy(&mut self, config: &SolverConfig, player: Option<usize>) {
+        // Regrets read the children, so they're all computed before any node is updated
+        let mut regrets: Vec<Option<WeightedRegrets>> = (0..self.nodes.len())
+            .into_par_iter()
+            .map(|index| match &self.nodes[index] {
+                ArenaNode::Action(x) if config.updates(x.player, player) => Some(
+                    x.weighted_regrets(x.action_evs_from(self.children[index].clone().map(
+                        |child| {
+                            (
+                                self.nodes[child].payouts().view(),
+                                self.nodes[child].state_probabilities().view(),
+                            )
+                        },
+                    ))),
+                ),
+                _ => None,
+            })
+            .collect();
+
+        // The children of a hidden action share their strategy, so they all add up the regrets of
+        // every child
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let ArenaNode::Action(ActionNode { hidden: true, .. }) = node {
+                let range = self.children[index].clone();
+                let total = regrets[range.clone()]
+                    .iter()
+                    .flatten()
+                    .cloned()
+                    .reduce(|(a, b), (x, y)| (a + x, b + y));
+                for child in range {
+                    if regrets[child].is_some() {
+                        regrets[child] = total.clone();
+                    }
+                }
+            }
+        }
+
+        self.nodes
+            .par_iter_mut()
+            .zip(regrets)
+            .map(|(node, regrets)| {
+                if let (ArenaNode::Action(x), Some((regrets, infoset_probabilities))) =
+                    (node, regrets)
+                {
+                    x.apply_regrets(config, regrets, infoset_probabilities);
+                }
+            })
+            .for_each(drop);
+    }
+
+    fn best_response(&self, player: usize, reach: &Array<Float, Ix1>) -> Array<Float, Ix1> {
+        self.best_response_at(0, player, reach)
+    }
+
+    fn avg_strategy_payouts(&self) -> Array<Float, Ix2> {
+        self.avg_strategy_payouts_at(0)
+    }
+
+    fn strategy(&self) -> Option<ArrayView2<'_, Float>> {
+        self.nodes[0].as_node().strategy()
+    }
+
+    fn avg_strategy(&self) -> Option<ArrayView2<'_, Float>> {
+        self.nodes[0].as_node().avg_strategy()
+    }
+
+    fn children(&self) -> Option<&Vec<Box<dyn Node>>> {
+        // Children are flattened into the arena; see ArenaTree::into_tree
+        None
+    }
+
+    fn as_node_ref(&self) -> NodeRef<'_> {
+        NodeRef::Arena(self)
+    }
+
+    fn as_node_mut(&mut self) -> NodeMut<'_> {
+        NodeMut::Arena(self)
+    }
+}
+
+/// Inconsistency found by [`TreeBuilder::build`], [`load_strategies`] or
+/// [`crate::backward_induction::solve`], with the "/"-joined path of the offending node
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TreeError {
+    /// No terminal node to infer the player and state counts from
+    #[error("tree has no terminal node")]
+    NoTerminal,
+    /// Action or chance node without children
+    #[error("{path}: node has no children")]
+    NoChildren { path: String },
+    /// Array whose shape disagrees with the player and state counts of the rest of the tree
+    #[error("{path}: expected shape {expected:?}, found {found:?}")]
+    ShapeMismatch {
+        path: String,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+    /// Infosets that don't assign every state to exactly one infoset
+    #[error("{path}: infosets must contain every state exactly once")]
+    InvalidInfosets { path: String },
+    /// Acting player without a row in the payouts
+    #[error("{path}: player {player} has no payouts")]
+    InvalidPlayer { path: String, player: usize },
+    /// Action mask without any legal action in an infoset
+    #[error("{path}: infoset {infoset} has no legal actions")]
+    NoLegalActions { path: String, infoset: usize },
+    /// Hidden action whose children can't share a strategy
+    #[error("{path}: children of a hidden action must be action nodes of one player with the same actions and infosets")]
+    InvalidHidden { path: String },
+    /// Action node without a strategy in a profile
+    #[error("{path}: no strategy in the profile")]
+    MissingStrategy { path: String },
+    /// Strategy whose probabilities in an infoset don't sum to 1
+    #[error("{path}: strategy in infoset {infoset} doesn't sum to 1")]
+    InvalidStrategy { path: String, infoset: usize },
+    /// Action node with an infoset of several states, or a hidden action
+    #[error("{path}: game doesn't have perfect information")]
+    ImperfectInformation { path: String },
+    /// Arena tree where the nodes need to be walked one by one
+    #[error("{path}: arena trees must be rebuilt with ArenaTree::into_tree first")]
+    Flattened { path: String },
+}
+
+#[derive(Debug, Clone)]
+enum BuilderKind {
+    Action {
+        player: usize,
+        infosets: Option<Vec<Vec<usize>>>,
+        action_mask: Option<Array<bool, Ix2>>,
+        infoset_keys: Option<HashMap<InfosetKey, usize>>,
+        hidden: bool,
+    },
+    Chance {
+        outcome_probabilities: Option<Array<Float, Ix2>>,
+    },
+    Terminal {
+        payouts: Array<Float, Ix2>,
+    },
+}
+
+/// Describes a game tree without spelling out every node's arrays. The player and state counts
+/// are inferred from the terminal payouts, and [`TreeBuilder::build`] checks that the rest of the
+/// tree agrees with them before filling in zeroed regrets, EVs and uniform strategies.
+#[derive(Debug, Clone)]
+pub struct TreeBuilder {
+    name: String,
+    kind: BuilderKind,
+    state_probabilities: Option<Array<Float, Ix1>>,
+    children: Vec<TreeBuilder>,
+}
+
+impl TreeBuilder {
+    /// Decision node for `player`. Every state is its own infoset unless [`TreeBuilder::infosets`]
+    /// says otherwise.
+    pub fn action(name: &str, player: usize) -> Self {
+        TreeBuilder {
+            name: name.to_string(),
+            kind: BuilderKind::Action {
+                player,
+                infosets: None,
+                action_mask: None,
+                infoset_keys: None,
+                hidden: false,
+            },
+            state_probabilities: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Chance node, with equally likely outcomes unless [`TreeBuilder::outcome_probabilities`]
+    /// says otherwise
+    pub fn chance(name: &str) -> Self {
+        TreeBuilder {
+            name: name.to_string(),
+            kind: BuilderKind::Chance {
+                outcome_probabilities: None,
+            },
+            state_probabilities: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// Groups the states of an action node into infosets, indexed by infoset, member(state)
+    pub fn infosets(mut self, infosets: Vec<Vec<usize>>) -> Self {
+        if let BuilderKind::Action { infosets: x, .. } = &mut self.kind {
+            *x = Some(infosets);
+        }
+        self
+    }
+
+    /// Uses keyed infosets for an action node. Unlike [`TreeBuilder::infosets`], states may be left
+    /// out, as long as they are unreachable.
+    pub fn keyed_infosets(mut self, keyed: KeyedInfosets) -> Self {
+        if let BuilderKind::Action {
+            infosets,
+            infoset_keys,
+            ..
+        } = &mut self.kind
+        {
+            *infosets = Some(keyed.infosets);
+            *infoset_keys = Some(keyed.indices);
+        }
+        self
+    }
+
+    /// Restricts an action node's legal actions, indexed by action, infoset
+    pub fn action_mask(mut self, mask: Array<bool, Ix2>) -> Self {
+        if let BuilderKind::Action { action_mask: x, .. } = &mut self.kind {
+            *x = Some(mask);
+        }
+        self
+    }
+
+    /// Hides an action node's action from the player acting next, as in a simultaneous move. The
+    /// children must be action nodes of that player with the same actions and infosets, and they
+    /// share one strategy.
+    pub fn hidden(mut self) -> Self {
+        if let BuilderKind::Action { hidden: x, .. } = &mut self.kind {
+            *x = true;
+        }
+        self
+    }
+
+    /// Sets a chance node's outcome probabilities, indexed by outcome, state
+    pub fn outcome_probabilities(mut self, probabilities: Array<Float, Ix2>) -> Self {
+        if let BuilderKind::Chance {
+            outcome_probabilities,
+        } = &mut self.kind
+        {
+            *outcome_probabilities = Some(probabilities);
+        }
+        self
+    }
+
+    /// Sets the initial state distribution. Only used at the root, where it defaults to uniform.
+    pub fn state_probabilities(mut self, probabilities: Array<Float, Ix1>) -> Self {
+        self.state_probabilities = Some(probabilities);
+        self
+    }
+
+    /// Appends a child subtree, in action (or outcome) order
+    pub fn child(mut self, child: TreeBuilder) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Appends a terminal child with payouts indexed by player, state
+    pub fn terminal(self, name: &str, payouts: Array<Float, Ix2>) -> Self {
+        self.child(TreeBuilder {
+            name: name.to_string(),
+            kind: BuilderKind::Terminal { payouts },
+            state_probabilities: None,
+            children: Vec::new(),
+        })
+    }
+
+    /// Appends a terminal child of a two player zero-sum game, given the first player's payouts
+    pub fn zero_sum(self, name: &str, payouts: Array<Float, Ix1>) -> Self {
+        let payouts = stack![Axis(0), payouts, -&payouts];
+        self.terminal(name, payouts)
+    }
+
+    pub fn build(self) -> Result<Box<dyn Node>, TreeError> {
+        let (n_players, n_states) = self.shape().ok_or(TreeError::NoTerminal)?;
+        let state_probabilities = self
+            .state_probabilities
+            .clone()
+            .unwrap_or_else(|| Array::from_elem(n_states, 1. / n_states as Float));
+        let mut root = self.build_node("", n_players, n_states)?;
+        root.set_state_probabilities(state_probabilities);
+        Ok(root)
+    }
+
+    /// (players, states) of the first terminal node found
+    fn shape(&self) -> Option<(usize, usize)> {
+        match &self.kind {
+            BuilderKind::Terminal { payouts } => Some(payouts.dim()),
+            _ => self.children.iter().find_map(|x| x.shape()),
+        }
+    }
+
+    fn build_node(
+        self,
+        parent_path: &str,
+        n_players: usize,
+        n_states: usize,
+    ) -> Result<Box<dyn Node>, TreeError> {
+        let path = match parent_path {
+            "" => self.name.clone(),
+            _ => format!("{}/{}", parent_path, self.name),
+        };
+        let shape_mismatch = |expected: &[usize], found: &[usize]| TreeError::ShapeMismatch {
+            path: path.clone(),
+            expected: expected.to_vec(),
+            found: found.to_vec(),
+        };
+
+        if let Some(x) = &self.state_probabilities {
+            if x.len() != n_states {
+                return Err(shape_mismatch(&[n_states], x.shape()));
+            }
+        }
+        if !matches!(self.kind, BuilderKind::Terminal { .. }) && self.children.is_empty() {
+            return Err(TreeError::NoChildren { path });
+        }
+        let n_children = self.children.len();
+        let children = self
+            .children
+            .into_iter()
+            .map(|x| x.build_node(&path, n_players, n_states))
+            .collect::<Result<Vec<Box<dyn Node>>, TreeError>>()?;
+
+        match self.kind {
+            BuilderKind::Action {
+                player,
+                infosets,
+                action_mask,
+                infoset_keys,
+                hidden,
+            } => {
+                if player >= n_players {
+                    return Err(TreeError::InvalidPlayer { path, player });
+                }
+                if hidden && !can_share_strategy(&children) {
+                    return Err(TreeError::InvalidHidden { path });
+                }
+                let infosets = infosets.unwrap_or_else(|| (0..n_states).map(|x| vec![x]).collect());
+                let mut seen = vec![false; n_states];
+                for state in infosets.iter().flatten() {
+                    if *state >= n_states || seen[*state] {
+                        return Err(TreeError::InvalidInfosets { path });
+                    }
+                    seen[*state] = true;
+                }
+                if infoset_keys.is_none() && seen.contains(&false) {
+                    return Err(TreeError::InvalidInfosets { path });
+                }
+
+                let n_infosets = infosets.len();
+                let legal = match &action_mask {
+                    Some(mask) => {
+                        if mask.dim() != (n_children, n_infosets) {
+                            return Err(shape_mismatch(&[n_children, n_infosets], mask.shape()));
+                        }
+                        if let Some(infoset) = mask
+                            .columns()
+                            .into_iter()
+                            .position(|x| !x.iter().any(|y| *y))
+                        {
+                            return Err(TreeError::NoLegalActions { path, infoset });
+                        }
+                        legal_weights(mask)
+                    }
+                    None => Array::ones((n_children, n_infosets)),
+                };
+                // Uniform over the legal actions
+                let strategy = &legal / &legal.sum_axis(Axis(0));
+
+                Ok(Box::new(ActionNode {
+                    name: self.name,
+                    state_probabilities: Array::zeros(n_states),
+                    total_probabilities: Array::zeros(n_infosets),
+                    evs: Array::zeros((n_players, n_states)),
+                    player_probabilities: Array::zeros((0, 0)),
+                    infosets,
+                    avg_strategy: strategy.clone(),
+                    strategy,
+                    regrets: Array::zeros((n_children, n_infosets)),
+                    children,
+                    player,
+                    iter_count: 1,
+                    recent_strategies: VecDeque::new(),
+                    action_mask,
+                    infoset_keys,
+                    hidden,
+                }))
+            }
+            BuilderKind::Chance {
+                outcome_probabilities,
+            } => {
+                let outcome_probabilities = outcome_probabilities.unwrap_or_else(|| {
+                    Array::from_elem((n_children, n_states), 1. / n_children as Float)
+                });
+                if outcome_probabilities.dim() != (n_children, n_states) {
+                    return Err(shape_mismatch(
+                        &[n_children, n_states],
+                        outcome_probabilities.shape(),
+                    ));
+                }
+                Ok(Box::new(ChanceNode {
+                    name: self.name,
+                    state_probabilities: Array::zeros(n_states),
+                    evs: Array::zeros((n_players, n_states)),
+                    player_probabilities: Array::zeros((0, 0)),
+                    outcome_probabilities,
+                    children,
+                }))
+            }
+            BuilderKind::Terminal { payouts } => {
+                if payouts.dim() != (n_players, n_states) {
+                    return Err(shape_mismatch(&[n_players, n_states], payouts.shape()));
+                }
+                Ok(Box::new(TerminalNode {
+                    name: self.name,
+                    state_probabilities: Array::zeros(n_states),
+                    payouts,
+                }))
+            }
+        }
+    }
+}
+
+/// Whether `nodes` are action nodes of one player with the same actions and infosets
+fn can_share_strategy(nodes: &[Box<dyn Node>]) -> bool {
+    let nodes: Vec<NodeRef> = nodes.iter().map(|x| x.as_node_ref()).collect();
+    match nodes.first() {
+        Some(NodeRef::Action(first)) => nodes.iter().all(|x| {
+            matches!(x, NodeRef::Action(x) if x.player == first.player
+                && x.children.len() == first.children.len()
+                && x.infosets == first.infosets
+                && x.action_mask == first.action_mask)
+        }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Per-infoset regret matching, as it was before being vectorized
+    fn regret_match_reference(node: &ActionNode) -> Array<Float, Ix2> {
+        const EPSILON: Float = 1e-8;
+        let mut result: Array<Float, Ix2> = Array::zeros(node.regrets.raw_dim());
+        node.regrets
+            .axis_iter(Axis(1))
+            .enumerate()
+            .map(|(infoset_index, x)| {
+                let legal: Array<Float, Ix1> = (0..x.len())
+                    .map(
+                        |action_index| match node.is_legal(action_index, infoset_index) {
+                            true => 1.,
+                            false => 0.,
+                        },
+                    )
+                    .collect();
+                let nonzero_regrets: Array<Float, Ix1> = x
+                    .iter()
+                    .map(|y| match *y < 0. {
+                        true => 0.,
+                        _ => *y,
+                    })
+                    .collect::<Array<Float, Ix1>>()
+                    * &legal;
+
+                if nonzero_regrets.sum() == 0. {
+                    // Uniform over the legal actions
+                    result
+                        .slice_mut(s![.., infoset_index])
+                        .assign(&(&legal / legal.sum()));
+                } else {
+                    let weights = nonzero_regrets + &legal * EPSILON;
+                    result
+                        .slice_mut(s![.., infoset_index])
+                        .assign(&(&weights / weights.sum()));
+                }
+            })
+            .for_each(drop);
+        result
+    }
+
+    #[test]
+    fn test_expand_strategy() {
+        let root = ActionNode {
+            name: "root".to_string(),
+            state_probabilities: Array::from_elem(6, 1. / 6.),
+            total_probabilities: Array::zeros(3),
+            evs: Array::zeros((2, 6)),
+            player_probabilities: Array::zeros((0, 0)),
+            infosets: vec![vec![0, 1], vec![2, 3], vec![4, 5]],
+            strategy: Array::from_elem((2, 3), 1. / 2.),
+            avg_strategy: Array::from_elem((2, 3), 1. / 2.),
+            regrets: Array::from_elem((2, 3), 0.),
+            children: vec![
+                Box::new(TerminalNode::zero_sum("a", array![3., 2., 1.])),
+                Box::new(TerminalNode::zero_sum("b", array![1., 2., 3.])),
+            ],
+            player: 0,
+            iter_count: 1,
+            recent_strategies: VecDeque::new(),
+            action_mask: None,
+            infoset_keys: None,
+            hidden: false,
+        };
+
+        assert_eq!(
+            root.expand_strategy(&root.strategy),
+            Array::from_elem((2, 6), 1. / 2.)
+        );
+    }
+
+    #[test]
+    fn test_update_tree() {
+        let mut root = ActionNode {
+            name: "root".to_string(),
+            state_probabilities: Array::from_elem(3, 1. / 3.),
+            total_probabilities: Array::zeros(3),
+            evs: Array::zeros((2, 3)),
+            player_probabilities: Array::zeros((0, 0)),
+            infosets: vec![vec![0], vec![1], vec![2]],
+            strategy: Array::from_elem((3, 3), 1. / 3.),
+            avg_strategy: Array::from_elem((3, 3), 1. / 3.),
+            regrets: Array::from_elem((3, 3), 0.),
+            children: vec![
+                Box::new(TerminalNode::zero_sum("a", array![3., 2., 3.])),
+                Box::new(TerminalNode::zero_sum("b", array![1., 2.5, 2.])),
+                Box::new(TerminalNode::zero_sum("c", array![4., 2., 2.])),
+            ],
+            player: 0,
+            iter_count: 1,
+            recent_strategies: VecDeque::new(),
+            action_mask: None,
+            infoset_keys: None,
+            hidden: false,
+        };
+        println!("{}", root);
+
+        for _ in 0..1 {
+            // Run one iteration of CFR
+            root.update_probabilities();
+            root.update_ev();
+            root.update_strategy(&SolverConfig::default(), None);
+            root.update_probabilities();
+        }
+
+        println!("{}", root);
+
+        println!("{}", root.infoset_probabilities(&root.state_probabilities));
+        println!(
+            "{}",
+            root.infoset_evs(root.evs.row(0), &root.state_probabilities)
+        );
+        println!("{}", root.action_evs());
+        println!("{}", root.current_regret(root.action_evs()));
+    }
+
+    fn build_two_action_node() -> ActionNode {
+        ActionNode {
+            name: "root".to_string(),
+            state_probabilities: Array::from_elem(1, 1.),
+            total_probabilities: Array::zeros(1),
+            evs: Array::zeros((2, 1)),
+            player_probabilities: Array::zeros((0, 0)),
+            infosets: vec![vec![0]],
+            strategy: Array::from_elem((2, 1), 1. / 2.),
+            avg_strategy: Array::from_elem((2, 1), 1. / 2.),
+            regrets: Array::zeros((2, 1)),
+            children: vec![
+                Box::new(TerminalNode::zero_sum("a", array![1.])),
+                Box::new(TerminalNode::zero_sum("b", array![-1.])),
+            ],
+            player: 0,
+            iter_count: 1,
+            recent_strategies: VecDeque::new(),
+            action_mask: None,
+            infoset_keys: None,
+            hidden: false,
+        }
+    }
+
+    #[test]
+    fn test_cfr_plus_floors_regrets() {
+        let mut vanilla = build_two_action_node();
+        let mut cfr_plus = build_two_action_node();
+        let config = SolverConfig {
+            variant: CfrVariant::CfrPlus,
+            ..Default::default()
+        };
+        for _ in 0..3 {
+            vanilla.update_probabilities();
+            vanilla.update_ev();
+            vanilla.update_strategy(&SolverConfig::default(), None);
+
+            cfr_plus.update_probabilities();
+            cfr_plus.update_ev();
+            cfr_plus.update_strategy(&config, None);
+        }
+
+        assert!(vanilla.regrets[[1, 0]] < 0.);
+        assert_eq!(cfr_plus.regrets[[1, 0]], 0.);
+        assert!(cfr_plus.regrets[[0, 0]] > 0.);
+    }
+
+    #[test]
+    fn test_linear_cfr_weights_regrets() {
+        let mut root = build_two_action_node();
+        let config = SolverConfig {
+            variant: CfrVariant::Linear,
+            ..Default::default()
+        };
+        for _ in 0..2 {
+            root.update_probabilities();
+            root.update_ev();
+            root.update_strategy(&config, None);
+        }
+
+        // Regret of -1 at t = 1, then roughly -2 at t = 2 once the strategy is pure
+        assert!((root.regrets[[1, 0]] + 5.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_exploitability() {
+        let mut root = build_two_action_node();
+
+        // Uniform average strategy leaves a full unit on the table for the acting player
+        assert_eq!(best_response_value(&root, 0), 1.);
+        assert_eq!(best_response_value(&root, 1), 0.);
+        assert_eq!(exploitability(&root), 0.5);
+
+        for _ in 0..10 {
+            root.update_probabilities();
+            root.update_ev();
+            root.update_strategy(&SolverConfig::default(), None);
+        }
+        assert!(exploitability(&root) < 1e-6);
+    }
+
+    #[test]
+    fn test_solver_stops_at_target() {
+        let mut solver = CfrSolver::new(
+            Box::new(build_two_action_node()),
+            SolverConfig {
+                max_iterations: 100,
+                target_exploitability: Some(1e-3),
+                check_interval: 5,
+                ..Default::default()
+            },
+        );
+        let result = solver.solve();
+        assert!(result.converged);
+        assert_eq!(result.iterations, 5);
+        assert!(result.exploitability < 1e-3);
+
+        let mut solver = CfrSolver::new(
+            Box::new(build_two_action_node()),
+            SolverConfig {
+                max_iterations: 7,
+                ..Default::default()
+            },
+        );
+        let result = solver.solve();
+        assert!(!result.converged);
+        assert_eq!(result.iterations, 7);
+    }
+
+    #[test]
+    fn test_solver_callbacks() {
+        let mut solver = CfrSolver::new(
+            Box::new(build_two_action_node()),
+            SolverConfig {
+                max_iterations: 6,
+                check_interval: 3,
+                ..Default::default()
+            },
+        );
+        let metrics = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = metrics.clone();
+        solver.on_iteration(move |x| recorded.lock().unwrap().push(*x));
+        solver.solve();
+
+        let metrics = metrics.lock().unwrap();
+        assert_eq!(
+            metrics.iter().map(|x| x.iteration).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4, 5, 6]
+        );
+        assert_eq!(
+            metrics
+                .iter()
+                .map(|x| x.exploitability.is_some())
+                .collect::<Vec<_>>(),
+            vec![false, false, true, false, false, true]
+        );
+        assert!(metrics.windows(2).all(|x| x[0].elapsed <= x[1].elapsed));
+    }
+
+    #[test]
+    fn test_chance_node() {
+        let mut root = ChanceNode {
+            name: "deal".to_string(),
+            state_probabilities: array![0.5, 0.5],
+            evs: Array::zeros((2, 2)),
+            player_probabilities: Array::zeros((0, 0)),
+            outcome_probabilities: array![[0.25, 1.], [0.75, 0.]],
+            children: vec![
+                Box::new(TerminalNode::zero_sum("a", array![4., 2.])),
+                Box::new(TerminalNode::zero_sum("b", array![0., 8.])),
+            ],
+        };
+
+        root.update_probabilities();
+        root.update_ev();
+
+        assert_eq!(root.children[0].state_probabilities(), array![0.125, 0.5]);
+        assert_eq!(root.children[1].state_probabilities(), array![0.375, 0.]);
+        assert_eq!(root.evs.row(0), array![1., 2.]);
+        assert_eq!(best_response_value(&root, 0), 1.5);
+        assert_eq!(exploitability(&root), 0.);
+    }
+
+    #[test]
+    fn test_three_player_node() {
+        // The third player picks between two outcomes that only differ in who else gets paid
+        let mut root = ActionNode {
+            name: "root".to_string(),
+            state_probabilities: Array::from_elem(1, 1.),
+            total_probabilities: Array::zeros(1),
+            evs: Array::zeros((3, 1)),
+            player_probabilities: Array::zeros((0, 0)),
+            infosets: vec![vec![0]],
+            strategy: Array::from_elem((2, 1), 1. / 2.),
+            avg_strategy: Array::from_elem((2, 1), 1. / 2.),
+            regrets: Array::zeros((2, 1)),
+            children: vec![
+                Box::new(TerminalNode {
+                    name: "a".to_string(),
+                    state_probabilities: Array::zeros(1),
+                    payouts: array![[2.], [-3.], [1.]],
+                }),
+                Box::new(TerminalNode {
+                    name: "b".to_string(),
+                    state_probabilities: Array::zeros(1),
+                    payouts: array![[-3.], [2.], [1.5]],
+                }),
+            ],
+            player: 2,
+            iter_count: 1,
+            recent_strategies: VecDeque::new(),
+            action_mask: None,
+            infoset_keys: None,
+            hidden: false,
+        };
+
+        assert_eq!(exploitability(&root), 0.25 / 3.);
+
+        for _ in 0..10 {
+            root.update_probabilities();
+            root.update_ev();
+            root.update_strategy(&SolverConfig::default(), None);
+        }
+
+        assert!(root.avg_strategy[[1, 0]] > 0.99);
+        assert!(exploitability(&root) < 1e-6);
+    }
+
+    #[test]
+    fn test_alternating_updates() {
+        let mut root = build_two_action_node();
+        root.update_probabilities();
+        root.update_ev();
+
+        // Only the second player is updated, so the first player's node is untouched
+        root.update_strategy(&SolverConfig::default(), Some(1));
+        assert_eq!(root.iter_count, 1);
+        assert_eq!(root.regrets, Array::<Float, Ix2>::zeros((2, 1)));
+
+        root.update_strategy(&SolverConfig::default(), Some(0));
+        assert_eq!(root.iter_count, 2);
+        assert!(root.regrets[[0, 0]] > 0.);
+    }
+
+    #[test]
+    fn test_serialize_tree() {
+        let mut root: Box<dyn Node> = Box::new(ChanceNode {
+            name: "deal".to_string(),
+            state_probabilities: Array::from_elem(1, 1.),
+            evs: Array::zeros((2, 1)),
+            player_probabilities: Array::zeros((0, 0)),
+            outcome_probabilities: array![[1.]],
+            children: vec![Box::new(build_two_action_node())],
+        });
+        for _ in 0..3 {
+            root.update_probabilities();
+            root.update_ev();
+            root.update_strategy(&SolverConfig::default(), None);
+        }
+
+        let json = serde_json::to_string(root.as_ref()).unwrap();
+        let loaded: Box<dyn Node> = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(loaded.as_ref()).unwrap(), json);
+
+        let child = &loaded.children().unwrap()[0];
+        assert_eq!(
+            child.avg_strategy(),
+            root.children().unwrap()[0].avg_strategy()
+        );
+        match child.as_node_ref() {
+            NodeRef::Action(x) => assert_eq!(x.iter_count, 4),
+            _ => panic!("Expected an action node"),
+        }
+
+        let profile = StrategyProfile::from_tree(loaded.as_ref());
+        assert_eq!(
+            profile.strategies.keys().collect::<Vec<_>>(),
+            vec!["deal/root"]
+        );
+    }
+
+    #[test]
+    fn test_warm_start() {
+        let mut solved = build_two_action_node();
+        for _ in 0..3 {
+            solved.update_probabilities();
+            solved.update_ev();
+            solved.update_strategy(&SolverConfig::default(), None);
+        }
+        let profile = StrategyProfile::from_tree(&solved);
+
+        let mut root = build_two_action_node();
+        warm_start(&mut root, &profile, 10.);
+        assert_eq!(root.strategy, solved.avg_strategy);
+        assert_eq!(root.avg_strategy, solved.avg_strategy);
+        assert!((root.regret_match() - &root.strategy)
+            .iter()
+            .all(|x| x.abs() < 1e-6));
+        assert_eq!(root.total_probabilities, array![10.]);
+        assert!(exploitability(&root) < 1e-6);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_resume_from_checkpoint() {
+        let config = SolverConfig {
+            variant: CfrVariant::Linear,
+            max_iterations: 8,
+            ..Default::default()
+        };
+        let mut uninterrupted = CfrSolver::new(Box::new(build_two_action_node()), config.clone());
+        uninterrupted.solve();
+
+        let path = std::env::temp_dir().join(format!("cfr_checkpoint_{}.json", std::process::id()));
+        let mut interrupted = CfrSolver::new(
+            Box::new(build_two_action_node()),
+            SolverConfig {
+                max_iterations: 4,
+                ..config
+            },
+        );
+        interrupted.checkpoint = Some(CheckpointConfig {
+            path: path.clone(),
+            every_iterations: Some(4),
+            every_duration: None,
+        });
+        interrupted.solve();
+
+        let mut resumed = CfrSolver::load_checkpoint(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(resumed.iterations, 4);
+        assert!(resumed.checkpoint.is_none());
+        resumed.config.max_iterations = 8;
+        resumed.solve();
+        assert_eq!(
+            resumed.root.avg_strategy(),
+            uninterrupted.root.avg_strategy()
+        );
+    }
+
+    #[test]
+    fn test_tree_builder() {
+        let root = TreeBuilder::action("root", 0)
+            .infosets(vec![vec![0, 1], vec![2]])
+            .child(
+                TreeBuilder::chance("c")
+                    .zero_sum("ca", array![1., 0., -1.])
+                    .zero_sum("cb", array![0., 1., 2.]),
+            )
+            .child(
+                TreeBuilder::action("b", 1)
+                    .zero_sum("bc", array![2., 2., -2.])
+                    .zero_sum("bf", array![1., 1., 1.]),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(root.state_probabilities(), Array::from_elem(3, 1. / 3.));
+        assert_eq!(root.payouts().dim(), (2, 3));
+        assert_eq!(root.strategy().unwrap(), Array::from_elem((2, 2), 0.5));
+        let b = &root.children().unwrap()[1];
+        assert_eq!(b.strategy().unwrap().dim(), (2, 3));
+        assert!(matches!(b.as_node_ref(), NodeRef::Action(x) if x.player == 1));
+        assert!(matches!(
+            root.children().unwrap()[0].as_node_ref(),
+            NodeRef::Chance(x) if x.outcome_probabilities == Array::from_elem((2, 3), 0.5)
+        ));
+    }
+
+    #[test]
+    fn test_tree_builder_errors() {
+        let result = TreeBuilder::action("root", 0)
+            .zero_sum("a", array![1., 2.])
+            .zero_sum("b", array![1., 2., 3.])
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            TreeError::ShapeMismatch {
+                path: "root/b".to_string(),
+                expected: vec![2, 2],
+                found: vec![2, 3],
+            }
+        );
+
+        let result = TreeBuilder::action("root", 0)
+            .infosets(vec![vec![0], vec![0, 1]])
+            .zero_sum("a", array![1., 2.])
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            TreeError::InvalidInfosets {
+                path: "root".to_string()
+            }
+        );
+
+        let result = TreeBuilder::action("root", 2)
+            .zero_sum("a", array![1., 2.])
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            TreeError::InvalidPlayer {
+                path: "root".to_string(),
+                player: 2
+            }
+        );
+
+        let result = TreeBuilder::action("root", 0)
+            .child(TreeBuilder::chance("c"))
+            .build();
+        assert_eq!(result.unwrap_err(), TreeError::NoTerminal);
+    }
+
+    fn build_dealt_game() -> TreeBuilder {
+        // A chance node deals one of two cards to player 0 in each of two states, then player 1
+        // responds without seeing it
+        TreeBuilder::chance("deal")
+            .outcome_probabilities(array![[0.25, 0.75], [0.75, 0.25]])
+            .child(
+                TreeBuilder::action("high", 0)
+                    .child(
+                        TreeBuilder::action("b", 1)
+                            .infosets(vec![vec![0, 1]])
+                            .zero_sum("bc", array![2., 1.])
+                            .zero_sum("bf", array![1., 1.]),
+                    )
+                    .zero_sum("x", array![1., -1.]),
+            )
+            .child(
+                TreeBuilder::action("low", 0)
+                    .child(
+                        TreeBuilder::action("b", 1)
+                            .infosets(vec![vec![0, 1]])
+                            .zero_sum("bc", array![-2., -1.])
+                            .zero_sum("bf", array![1., 1.]),
+                    )
+                    .zero_sum("x", array![-1., 0.]),
+            )
+    }
+
+    #[test]
+    fn test_arena_matches_tree() {
+        let config = SolverConfig {
+            variant: CfrVariant::CfrPlus,
+            max_iterations: 50,
+            alternating: true,
+            ..Default::default()
+        };
+        let mut boxed = CfrSolver::new(build_dealt_game().build().unwrap(), config.clone());
+        let mut arena = CfrSolver::new(
+            Box::new(ArenaTree::from_tree(build_dealt_game().build().unwrap())),
+            config,
+        );
+        boxed.solve();
+        arena.solve();
+
+        let boxed_profile = StrategyProfile::from_tree(boxed.root.as_ref());
+        let arena_profile = StrategyProfile::from_tree(arena.root.as_ref());
+        assert_eq!(boxed_profile.strategies.len(), 4);
+        assert_eq!(
+            boxed_profile.strategies.keys().collect::<Vec<_>>(),
+            arena_profile.strategies.keys().collect::<Vec<_>>()
+        );
+        for (path, strategy) in &boxed_profile.strategies {
+            let difference = strategy - &arena_profile.strategies[path];
+            assert!(difference.iter().all(|x| x.abs() < 1e-12), "{}", path);
+        }
+        assert!(
+            (exploitability(boxed.root.as_ref()) - exploitability(arena.root.as_ref())).abs()
+                < 1e-12
+        );
+
+        // Round trips through serialization and back to a boxed tree
+        let json = serde_json::to_string(arena.root.as_ref()).unwrap();
+        let mut loaded: Box<dyn Node> = serde_json::from_str(&json).unwrap();
+        let tree = match loaded.as_node_mut() {
+            NodeMut::Arena(x) => std::mem::take(x).into_tree(),
+            _ => panic!("Expected an arena"),
+        };
+        assert_eq!(
+            StrategyProfile::from_tree(tree.as_ref()).strategies,
+            arena_profile.strategies
+        );
+    }
+
+    #[test]
+    fn test_averaging_schemes() {
+        assert_eq!(
+            "last-3".parse::<AveragingScheme>(),
+            Ok(AveragingScheme::LastK(3))
+        );
+        assert!("last-0".parse::<AveragingScheme>().is_err());
+        let config = SolverConfig {
+            variant: CfrVariant::CfrPlus,
+            ..Default::default()
+        };
+        assert_eq!(config.averaging(), AveragingScheme::Linear);
+
+        // Averaging over the last iteration only tracks the current strategy
+        let config = SolverConfig {
+            averaging: Some(AveragingScheme::LastK(1)),
+            ..Default::default()
+        };
+        let mut root = build_two_action_node();
+        for _ in 0..3 {
+            root.update_probabilities();
+            root.update_ev();
+            root.update_strategy(&config, None);
+            assert_eq!(root.avg_strategy, root.strategy);
+            assert_eq!(root.recent_strategies.len(), 1);
+        }
+
+        // Iteration t adds weight t under linear averaging, and 1 under uniform averaging
+        let total_weight = |averaging| {
+            let mut root = build_two_action_node();
+            let config = SolverConfig {
+                averaging: Some(averaging),
+                ..Default::default()
+            };
+            for _ in 0..3 {
+                root.update_probabilities();
+                root.update_ev();
+                root.update_strategy(&config, None);
+            }
+            root.total_probabilities[0]
+        };
+        assert_eq!(total_weight(AveragingScheme::Uniform), 3.);
+        assert_eq!(total_weight(AveragingScheme::Linear), 6.);
+        assert_eq!(total_weight(AveragingScheme::LastK(2)), 2.);
+    }
+
+    #[test]
+    fn test_purify_strategy() {
+        let strategy = array![[0.6, 0.3, 0.5], [0.38, 0.3, 0.5], [0.02, 0.4, 0.]];
+        let thresholded = purify_strategy(&strategy, Purification::Threshold(0.35));
+        let expected = array![[0.6 / 0.98, 0., 0.5], [0.38 / 0.98, 0., 0.5], [0., 1., 0.]];
+        assert!((thresholded - expected).iter().all(|x| x.abs() < 1e-12));
+
+        // Every action below the threshold: fall back to the most frequent one
+        let thresholded = purify_strategy(&strategy, Purification::Threshold(0.45));
+        assert_eq!(thresholded.column(1), array![0., 0., 1.]);
+
+        let pure = purify_strategy(&strategy, Purification::Pure);
+        assert_eq!(pure, array![[1., 0., 1.], [0., 0., 0.], [0., 1., 0.]]);
+    }
+
+    #[test]
+    fn test_report() {
+        let mut solver = CfrSolver::new(
+            build_dealt_game().build().unwrap(),
+            SolverConfig {
+                max_iterations: 20,
+                ..Default::default()
+            },
+        );
+        solver.solve();
+        let avg_strategy = solver.root.children().unwrap()[0]
+            .avg_strategy()
+            .unwrap()
+            .to_owned();
+        let report = solver.report();
+
+        // Two infosets at each of high and low, one at each response
+        assert_eq!(report.len(), 6);
+        let high = &report[0];
+        assert_eq!(high.node, "deal/high");
+        assert_eq!(high.player, 0);
+        assert_eq!(high.reach, 0.125);
+        assert_eq!(
+            high.actions
+                .iter()
+                .map(|x| x.action.as_str())
+                .collect::<Vec<_>>(),
+            vec!["b", "x"]
+        );
+        assert_eq!(high.actions[0].frequency, avg_strategy[[0, 0]]);
+        assert_eq!(high.actions[1].ev, 1.);
+        let ev = high
+            .actions
+            .iter()
+            .map(|x| x.ev * x.frequency)
+            .sum::<Float>();
+        assert!((high.ev - ev).abs() < 1e-12);
+
+        // Reporting leaves the solver's strategies untouched
+        assert_eq!(
+            solver.root.children().unwrap()[0].avg_strategy().unwrap(),
+            avg_strategy
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_convergence_csv() {
+        let mut solver = CfrSolver::new(
+            build_dealt_game().build().unwrap(),
+            SolverConfig {
+                max_iterations: 10,
+                check_interval: 5,
+                record_convergence: true,
+                ..Default::default()
+            },
+        );
+        solver.solve();
+        assert_eq!(
+            solver
+                .convergence
+                .iter()
+                .map(|x| x.iteration)
+                .collect::<Vec<_>>(),
+            vec![5, 10]
+        );
+
+        let path = std::env::temp_dir().join(format!("cfr_convergence_{}.csv", std::process::id()));
+        solver.write_convergence_csv(&path).unwrap();
+        let csv = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "iteration,elapsed,exploitability");
+        assert!(lines[2].starts_with("10,"));
+        assert_eq!(
+            lines[2]
+                .rsplit(',')
+                .next()
+                .unwrap()
+                .parse::<Float>()
+                .unwrap(),
+            solver.convergence[1].exploitability
+        );
+    }
+
+    #[test]
+    fn test_frozen_player() {
+        let mut root = build_dealt_game().build().unwrap();
+        // Player 1 always calls
+        let profile = StrategyProfile {
+            strategies: ["deal/high/b", "deal/low/b"]
+                .into_iter()
+                .map(|path| (path.to_string(), array![[1.], [0.]]))
+                .collect(),
+        };
+        freeze_strategies(root.as_mut(), &profile, 1);
+        let mut solver = CfrSolver::new(
+            root,
+            SolverConfig {
+                variant: CfrVariant::CfrPlus,
+                max_iterations: 200,
+                frozen_players: vec![1],
+                ..Default::default()
+            },
+        );
+        solver.solve();
+
+        assert_eq!(
+            StrategyProfile::from_tree(solver.root.as_ref()).strategies["deal/low/b"],
+            array![[1.], [0.]]
+        );
+        // Player 0 bets high cards into the call and checks low ones
+        let strategies = StrategyProfile::from_tree(solver.root.as_ref()).strategies;
+        assert!(strategies["deal/high"][[0, 0]] > 0.99);
+        assert!(strategies["deal/low"][[1, 0]] > 0.99);
+
+        let reach = solver.root.state_probabilities().to_owned();
+        let value = solver.root.avg_strategy_payouts().dot(&reach)[0];
+        assert!(best_response_value(solver.root.as_ref(), 0) - value < 1e-2);
+    }
+
+    #[test]
+    fn test_load_strategies() {
+        let mut solver = CfrSolver::new(
+            build_dealt_game().build().unwrap(),
+            SolverConfig {
+                variant: CfrVariant::CfrPlus,
+                max_iterations: 1000,
+                ..Default::default()
+            },
+        );
+        solver.solve();
+        let mut profile = StrategyProfile::from_tree(solver.root.as_ref());
+
+        // A fresh tree playing the solved profile is as close to equilibrium as the solved tree
+        let mut root = build_dealt_game().build().unwrap();
+        load_strategies(root.as_mut(), &profile).unwrap();
+        let gains = unilateral_gains(root.as_ref());
+        assert_eq!(gains, unilateral_gains(solver.root.as_ref()));
+        assert!(gains.iter().all(|x| *x >= -1e-6 && *x < 1e-2), "{}", gains);
+
+        // Always folding to the bet lets player 0 gain by betting every card
+        for path in ["deal/high/b", "deal/low/b"] {
+            profile
+                .strategies
+                .insert(path.to_string(), array![[0.], [1.]]);
+        }
+        load_strategies(root.as_mut(), &profile).unwrap();
+        let gains = unilateral_gains(root.as_ref());
+        assert!(gains[0] > 0.1 && gains[1].abs() < 1e-2, "{}", gains);
+
+        profile
+            .strategies
+            .insert("deal/low".to_string(), array![[0.5, 1.], [0.4, 0.]]);
+        assert_eq!(
+            load_strategies(root.as_mut(), &profile),
+            Err(TreeError::InvalidStrategy {
+                path: "deal/low".to_string(),
+                infoset: 0
+            })
+        );
+        profile.strategies.remove("deal/low");
+        assert_eq!(
+            load_strategies(root.as_mut(), &profile),
+            Err(TreeError::MissingStrategy {
+                path: "deal/low".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_action_mask() {
+        // Three actions in two states; the best action is illegal in the second
+        let build = |mask| {
+            TreeBuilder::action("root", 0)
+                .action_mask(mask)
+                .zero_sum("a", array![0., 0.])
+                .zero_sum("b", array![1., 1.])
+                .zero_sum("c", array![2., 2.])
+                .build()
+        };
+        let mut solver = CfrSolver::new(
+            build(array![[true, true], [true, true], [true, false]]).unwrap(),
+            SolverConfig {
+                max_iterations: 50,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            solver.root.strategy().unwrap().column(1),
+            array![0.5, 0.5, 0.]
+        );
+        solver.solve();
+
+        let strategy = solver.root.avg_strategy().unwrap();
+        assert!(strategy[[2, 0]] > 0.99);
+        assert!(strategy[[1, 1]] > 0.99);
+        assert_eq!(strategy[[2, 1]], 0.);
+        assert!(exploitability(solver.root.as_ref()) < 1e-2);
+
+        assert_eq!(
+            build(array![[true, false], [true, false], [true, false]]).unwrap_err(),
+            TreeError::NoLegalActions {
+                path: "root".to_string(),
+                infoset: 1
+            }
+        );
+    }
+
+    fn build_simultaneous_game() -> TreeBuilder {
+        // The second player picks a column without seeing the first player's row; both pick the
+        // first 2/5 of the time, for a value of 1/5
+        TreeBuilder::action("root", 0)
+            .hidden()
+            .child(
+                TreeBuilder::action("a", 1)
+                    .zero_sum("a", array![2.])
+                    .zero_sum("b", array![-1.]),
+            )
+            .child(
+                TreeBuilder::action("b", 1)
+                    .zero_sum("a", array![-1.])
+                    .zero_sum("b", array![1.]),
+            )
+    }
+
+    #[test]
+    fn test_hidden_action() {
+        let root = build_simultaneous_game().build().unwrap();
+        // Against an even mix no column wins anything, though seeing the row would win 1
+        assert!(best_response_value(root.as_ref(), 1).abs() < 1e-12);
+
+        let config = SolverConfig {
+            variant: CfrVariant::CfrPlus,
+            max_iterations: 10000,
+            ..Default::default()
+        };
+        let mut boxed = CfrSolver::new(root, config.clone());
+        let mut arena = CfrSolver::new(
+            Box::new(ArenaTree::from_tree(
+                build_simultaneous_game().build().unwrap(),
+            )),
+            config,
+        );
+        boxed.solve();
+        arena.solve();
+
+        let children = boxed.root.children().unwrap();
+        assert_eq!(children[0].avg_strategy(), children[1].avg_strategy());
+        assert!((boxed.root.avg_strategy().unwrap()[[0, 0]] - 0.4).abs() < 0.01);
+        assert!((children[0].avg_strategy().unwrap()[[0, 0]] - 0.4).abs() < 0.01);
+        assert!((best_response_value(boxed.root.as_ref(), 0) - 0.2).abs() < 0.01);
+        assert!(exploitability(boxed.root.as_ref()) < 1e-2);
+        assert!(
+            (exploitability(boxed.root.as_ref()) - exploitability(arena.root.as_ref())).abs()
+                < 1e-12
+        );
+
+        let result = TreeBuilder::action("root", 0)
+            .hidden()
+            .child(TreeBuilder::action("a", 1).zero_sum("a", array![1.]))
+            .zero_sum("b", array![0.])
+            .build();
+        assert_eq!(
+            result.unwrap_err(),
+            TreeError::InvalidHidden {
+                path: "root".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_keyed_infosets() {
+        // Infosets keyed by card; the third state is never dealt and left out
+        let mut keyed = KeyedInfosets::new();
+        assert_eq!(keyed.insert(infoset_key("K"), 0), 0);
+        assert_eq!(keyed.insert(infoset_key("Q"), 1), 1);
+        assert_eq!(keyed.len(), 2);
+        // Keys are saved in checkpoints, so they must not change
+        assert_eq!(infoset_key("K"), 0x6441_2f16_f021_7dea);
+        let build = |node: TreeBuilder| {
+            node.state_probabilities(array![0.5, 0.5, 0.])
+                .zero_sum("a", array![1., -1., 5.])
+                .zero_sum("b", array![-1., 1., 5.])
+                .build()
+                .unwrap()
+        };
+        let mut solver = CfrSolver::new(
+            build(TreeBuilder::action("root", 0).keyed_infosets(keyed)),
+            SolverConfig {
+                max_iterations: 100,
+                ..Default::default()
+            },
+        );
+        solver.solve();
+
+        let node = match solver.root.as_node_ref() {
+            NodeRef::Action(x) => x,
+            _ => unreachable!(),
+        };
+        let q = node.infoset_index(infoset_key("Q")).unwrap();
+        assert!(node.infoset_index(infoset_key("J")).is_none());
+        assert!(node.avg_strategy[[1, q]] > 0.99);
+        assert!(exploitability(solver.root.as_ref()) < 1e-2);
+
+        // Dense infosets still have to cover every state
+        assert!(matches!(
+            TreeBuilder::action("root", 0)
+                .infosets(vec![vec![0], vec![1]])
+                .zero_sum("a", array![1., -1., 5.])
+                .zero_sum("b", array![-1., 1., 5.])
+                .build(),
+            Err(TreeError::InvalidInfosets { .. })
+        ));
+    }
+
+    fn random_regrets_node(n_infosets: usize, masked: bool) -> ActionNode {
+        // Scattered regrets, with some infosets left without positive regret
+        let regrets = Array::from_shape_fn((2, n_infosets), |(a, i)| {
+            let x = ((i * 7919 + a * 104729) % 1000) as Float / 1000.;
+            match i % 5 {
+                0 => -x,
+                _ => x - 0.5,
+            }
+        });
+        ActionNode {
+            action_mask: masked
+                .then(|| Array::from_shape_fn((2, n_infosets), |(a, i)| a == 0 || i % 3 != 0)),
+            regrets,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_regret_match() {
+        for masked in [false, true] {
+            let node = random_regrets_node(169, masked);
+            let strategy = node.regret_match();
+            let difference = strategy - regret_match_reference(&node);
+            assert!(difference.iter().all(|x| x.abs() < 1e-12));
+        }
+    }
+
+    #[test]
+    #[ignore = "benchmark; run with --ignored --nocapture"]
+    fn bench_regret_match() {
+        const ITERATIONS: u32 = 10000;
+        // The push/fold big blind node: call or fold in each of 169 hands
+        let node = random_regrets_node(169, false);
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(regret_match_reference(&node));
+        }
+        let reference_time = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(node.regret_match());
+        }
+        let vectorized_time = start.elapsed();
+
+        println!(
+            "{} iterations: per-infoset {:.3}ms, vectorized {:.3}ms",
+            ITERATIONS,
+            reference_time.as_secs_f64() * 1e3,
+            vectorized_time.as_secs_f64() * 1e3
+        );
+        assert!(vectorized_time < reference_time);
+    }
+
+    #[test]
+    fn test_exploration() {
+        let exploration: Exploration = "0.3:0.5".parse().unwrap();
+        assert_eq!(exploration.epsilon_at(1), 0.3);
+        assert_eq!(exploration.epsilon_at(3), 0.075);
+        assert_eq!("0.1".parse::<Exploration>().unwrap().epsilon_at(100), 0.1);
+        assert!("1.5".parse::<Exploration>().is_err());
+        assert!("0.1:x".parse::<Exploration>().is_err());
+
+        // With constant exploration every action keeps at least epsilon / 3 of the current strategy
+        let build = || {
+            TreeBuilder::action("root", 0)
+                .zero_sum("a", array![0.])
+                .zero_sum("b", array![1.])
+                .zero_sum("c", array![2.])
+                .build()
+                .unwrap()
+        };
+        let mut solver = CfrSolver::new(
+            build(),
+            SolverConfig {
+                max_iterations: 50,
+                exploration: Some(Exploration {
+                    epsilon: 0.3,
+                    decay: 1.,
+                }),
+                ..Default::default()
+            },
+        );
+        solver.solve();
+        let strategy = solver.root.strategy().unwrap();
+        assert!((strategy[[0, 0]] - 0.1).abs() < 1e-6);
+        assert!((strategy[[2, 0]] - 0.8).abs() < 1e-6);
+        // The average strategy still converges to the best action
+        assert!(solver.root.avg_strategy().unwrap()[[2, 0]] > 0.9);
+
+        // Decaying exploration vanishes
+        solver = CfrSolver::new(
+            build(),
+            SolverConfig {
+                max_iterations: 50,
+                exploration: Some(Exploration {
+                    epsilon: 0.3,
+                    decay: 0.5,
+                }),
+                ..Default::default()
+            },
+        );
+        solver.solve();
+        assert!(solver.root.strategy().unwrap()[[2, 0]] > 0.99);
     }
 }
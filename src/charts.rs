@@ -0,0 +1,150 @@
+//! Precomputed push/fold charts, looked up without solving or loading the poker equity tables.
+//!
+//! A chart is the CSV printed by `push-fold --sweep`: a header of effective stacks in big blinds,
+//! then one row per hand class giving how often the SB shoves it at each stack:
+//!
+//! ```text
+//! hand,2,3,4
+//! 22,1.000,1.000,1.000
+//! 32o,0.000,0.000,0.000
+//! ```
+//!
+//! [`PushFoldChart::bundled`] is a heads-up solve with no ante, from 2 to 25 big blinds, so that
+//! builds without the `fs` feature (such as the wasm bindings) still have a chart to look up.
+
+use std::str::FromStr;
+
+/// SB shoving frequencies of the heads-up chart bundled with the crate, solved with CFR+ to under
+/// 0.01 mbb/hand at each stack by
+/// `push-fold --sweep 2:25:1 --variant cfr+ --iter 3000 --target 0.01`
+const BUNDLED: &str = include_str!("../data/push_fold_chart.csv");
+
+/// How often each hand class is played at each of a set of effective stacks
+#[derive(Clone, Debug, PartialEq)]
+pub struct PushFoldChart {
+    /// Effective stacks in big blinds, in increasing order
+    pub stacks: Vec<f64>,
+    /// Hand class names, e.g. `AKs`
+    pub hands: Vec<String>,
+    /// Frequency of each hand (outer) at each stack (inner)
+    pub frequencies: Vec<Vec<f64>>,
+}
+
+impl FromStr for PushFoldChart {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().map(str::trim).filter(|x| !x.is_empty());
+        let header = lines.next().ok_or("Empty chart")?;
+        let stacks = header
+            .split(',')
+            .skip(1)
+            .map(|x| x.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| format!("Invalid chart header: {}", header))?;
+        if stacks.is_empty() || stacks.windows(2).any(|x| x[0] >= x[1]) {
+            return Err(format!("Invalid chart header: {}", header));
+        }
+
+        let mut chart = PushFoldChart {
+            stacks,
+            hands: Vec::new(),
+            frequencies: Vec::new(),
+        };
+        for line in lines {
+            let mut fields = line.split(',').map(str::trim);
+            let hand = fields.next().unwrap_or_default();
+            let frequencies = fields
+                .map(|x| x.parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|_| format!("Invalid chart row: {}", line))?;
+            if hand.is_empty()
+                || frequencies.len() != chart.stacks.len()
+                || frequencies.iter().any(|x| !(0. ..=1.).contains(x))
+            {
+                return Err(format!("Invalid chart row: {}", line));
+            }
+            chart.hands.push(hand.to_string());
+            chart.frequencies.push(frequencies);
+        }
+        Ok(chart)
+    }
+}
+
+impl PushFoldChart {
+    /// The heads-up chart bundled with the crate; see the module documentation
+    pub fn bundled() -> Self {
+        BUNDLED.parse().expect("Bundled chart is valid")
+    }
+
+    /// Frequency of `hand` at `stack` big blinds, interpolated linearly between the two nearest
+    /// stacks in the chart and clamped to the first and last. None for hands not in the chart.
+    pub fn frequency(&self, hand: &str, stack: f64) -> Option<f64> {
+        let row = self.hands.iter().position(|x| x == hand)?;
+        Some(self.interpolate(&self.frequencies[row], stack))
+    }
+
+    /// Frequency of every hand at `stack` big blinds, in the order of [`PushFoldChart::hands`]
+    pub fn at_stack(&self, stack: f64) -> Vec<f64> {
+        self.frequencies
+            .iter()
+            .map(|x| self.interpolate(x, stack))
+            .collect()
+    }
+
+    /// Hands played at least `threshold` of the time at `stack` big blinds
+    pub fn range(&self, stack: f64, threshold: f64) -> Vec<&str> {
+        self.hands
+            .iter()
+            .zip(self.at_stack(stack))
+            .filter(|(_, frequency)| *frequency >= threshold)
+            .map(|(hand, _)| hand.as_str())
+            .collect()
+    }
+
+    fn interpolate(&self, frequencies: &[f64], stack: f64) -> f64 {
+        let upper = self.stacks.partition_point(|x| *x < stack);
+        match upper {
+            0 => frequencies[0],
+            x if x == self.stacks.len() => frequencies[x - 1],
+            x => {
+                let weight = (stack - self.stacks[x - 1]) / (self.stacks[x] - self.stacks[x - 1]);
+                frequencies[x - 1] * (1. - weight) + frequencies[x] * weight
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let chart: PushFoldChart = "hand,5,10\nAA,1.000,1.000\n72o,0.500,0.000"
+            .parse()
+            .unwrap();
+        assert_eq!(chart.frequency("72o", 7.5), Some(0.25));
+        assert_eq!(chart.frequency("72o", 2.), Some(0.5));
+        assert_eq!(chart.frequency("72o", 20.), Some(0.));
+        assert_eq!(chart.frequency("AKs", 10.), None);
+        assert_eq!(chart.range(5., 0.5), vec!["AA", "72o"]);
+        assert_eq!(chart.range(8., 0.5), vec!["AA"]);
+
+        assert!("hand,10,5\nAA,1,1".parse::<PushFoldChart>().is_err());
+        assert!("hand,5,10\nAA,1".parse::<PushFoldChart>().is_err());
+        assert!("hand,5\nAA,1.5".parse::<PushFoldChart>().is_err());
+    }
+
+    #[test]
+    fn test_bundled() {
+        let chart = PushFoldChart::bundled();
+        assert_eq!(chart.hands.len(), 169);
+        // Nearly every hand shoves at 2 big blinds, but only strong ones at 25
+        assert_eq!(chart.frequency("AA", 25.), Some(1.));
+        assert_eq!(chart.frequency("K2o", 2.), Some(1.));
+        assert_eq!(chart.frequency("K2o", 25.), Some(0.));
+        assert_eq!(chart.frequency("72o", 2.), Some(0.));
+        assert!(chart.range(25., 0.5).len() < chart.range(10., 0.5).len());
+    }
+}
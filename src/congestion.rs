@@ -0,0 +1,302 @@
+//! Congestion games: each player chooses a set of resources, such as the roads of a route, and
+//! pays the sum of their latencies, each of which grows with the number of players using it.
+//!
+//! Rosenthal's potential, the sum over resources of the latencies at every load up to the
+//! current one, changes by exactly the deviating player's change in cost, so every improving move
+//! decreases it. Best-response dynamics therefore can't cycle and stop at a pure Nash equilibrium.
+//! Equilibria can still cost more in total than the social optimum: the price of anarchy is the
+//! ratio for the worst equilibrium, at most 5/2 with linear latencies, and the price of stability
+//! the ratio for the best. Both are found by enumerating every profile, so only for small games.
+
+use std::str::FromStr;
+
+/// Latency of a resource as a polynomial in its load
+#[derive(Clone, Debug, PartialEq)]
+pub struct Latency {
+    /// Coefficient of each power of the load, starting from the constant
+    pub coefficients: Vec<f64>,
+}
+
+impl Latency {
+    /// Latency `slope * load + constant`
+    pub fn linear(slope: f64, constant: f64) -> Self {
+        Latency {
+            coefficients: vec![constant, slope],
+        }
+    }
+
+    /// Latency with `load` players using the resource
+    pub fn at(&self, load: usize) -> f64 {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(0., |acc, x| acc * load as f64 + x)
+    }
+}
+
+impl FromStr for Latency {
+    type Err = String;
+
+    /// Coefficients separated by commas, starting from the constant, e.g. `0,1` for the load
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let coefficients = s
+            .split(',')
+            .map(|x| x.trim().parse())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| format!("Invalid latency: {}", s))?;
+        Ok(Latency { coefficients })
+    }
+}
+
+/// Strategy index of each player
+pub type Profile = Vec<usize>;
+
+/// Resources with latencies, and the sets of them each player may choose
+#[derive(Clone, Debug, PartialEq)]
+pub struct CongestionGame {
+    pub latencies: Vec<Latency>,
+    /// Resources of each strategy of each player
+    pub strategies: Vec<Vec<Vec<usize>>>,
+}
+
+/// Where best-response dynamics stopped
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dynamics {
+    /// Pure Nash equilibrium reached
+    pub profile: Profile,
+    /// Number of improving moves made
+    pub moves: usize,
+}
+
+/// Social costs of the optimum and of the best and worst pure equilibria
+#[derive(Clone, Debug, PartialEq)]
+pub struct Efficiency {
+    pub optimum: Profile,
+    pub optimal_cost: f64,
+    pub best_equilibrium_cost: f64,
+    pub worst_equilibrium_cost: f64,
+}
+
+impl Efficiency {
+    pub fn price_of_anarchy(&self) -> f64 {
+        self.worst_equilibrium_cost / self.optimal_cost
+    }
+
+    pub fn price_of_stability(&self) -> f64 {
+        self.best_equilibrium_cost / self.optimal_cost
+    }
+}
+
+/// Costs this close count as equal, so rounding can't make a move look improving
+const COST_TOLERANCE: f64 = 1e-9;
+
+impl CongestionGame {
+    /// Number of players using each resource
+    pub fn loads(&self, profile: &[usize]) -> Vec<usize> {
+        let mut loads = vec![0; self.latencies.len()];
+        for (player, strategy) in profile.iter().enumerate() {
+            for resource in &self.strategies[player][*strategy] {
+                loads[*resource] += 1;
+            }
+        }
+        loads
+    }
+
+    /// Sum of the latencies of the player's resources
+    pub fn cost(&self, profile: &[usize], player: usize) -> f64 {
+        let loads = self.loads(profile);
+        self.strategies[player][profile[player]]
+            .iter()
+            .map(|x| self.latencies[*x].at(loads[*x]))
+            .sum()
+    }
+
+    /// Sum of every player's cost
+    pub fn social_cost(&self, profile: &[usize]) -> f64 {
+        self.loads(profile)
+            .iter()
+            .zip(&self.latencies)
+            .map(|(load, latency)| *load as f64 * latency.at(*load))
+            .sum()
+    }
+
+    /// Rosenthal's potential
+    pub fn potential(&self, profile: &[usize]) -> f64 {
+        self.loads(profile)
+            .iter()
+            .zip(&self.latencies)
+            .map(|(load, latency)| (1..=*load).map(|x| latency.at(x)).sum::<f64>())
+            .sum()
+    }
+
+    /// Cheapest strategy of the player with everyone else's fixed, keeping the current one unless
+    /// another is strictly cheaper
+    pub fn best_response(&self, profile: &[usize], player: usize) -> usize {
+        let mut profile = profile.to_vec();
+        let current = profile[player];
+        let mut best = (current, self.cost(&profile, player));
+        for strategy in 0..self.strategies[player].len() {
+            profile[player] = strategy;
+            let cost = self.cost(&profile, player);
+            if cost < best.1 - COST_TOLERANCE {
+                best = (strategy, cost);
+            }
+        }
+        best.0
+    }
+
+    /// Whether no player can lower their cost by switching strategy alone
+    pub fn is_nash(&self, profile: &[usize]) -> bool {
+        (0..profile.len()).all(|player| self.best_response(profile, player) == profile[player])
+    }
+
+    /// Lets players switch to a best response in turn until none wants to, which the potential
+    /// guarantees happens
+    pub fn best_response_dynamics(&self, start: Profile) -> Dynamics {
+        let mut profile = start;
+        let mut moves = 0;
+        loop {
+            let mut moved = false;
+            for player in 0..profile.len() {
+                let best = self.best_response(&profile, player);
+                if best != profile[player] {
+                    profile[player] = best;
+                    moves += 1;
+                    moved = true;
+                }
+            }
+            if !moved {
+                return Dynamics { profile, moves };
+            }
+        }
+    }
+
+    /// Every profile, with the last player's strategy changing fastest
+    pub fn profiles(&self) -> impl Iterator<Item = Profile> + '_ {
+        let total: usize = self.strategies.iter().map(|x| x.len()).product();
+        (0..total).map(|mut index| {
+            let mut profile = vec![0; self.strategies.len()];
+            for (player, strategies) in self.strategies.iter().enumerate().rev() {
+                profile[player] = index % strategies.len();
+                index /= strategies.len();
+            }
+            profile
+        })
+    }
+
+    /// Every pure Nash equilibrium, by enumeration
+    pub fn pure_equilibria(&self) -> Vec<Profile> {
+        self.profiles().filter(|x| self.is_nash(x)).collect()
+    }
+
+    /// Social optimum and the costs of the best and worst equilibria, by enumeration
+    pub fn efficiency(&self) -> Efficiency {
+        let mut optimum = (vec![], f64::INFINITY);
+        let (mut best, mut worst) = (f64::INFINITY, f64::NEG_INFINITY);
+        for profile in self.profiles() {
+            let cost = self.social_cost(&profile);
+            if self.is_nash(&profile) {
+                best = best.min(cost);
+                worst = worst.max(cost);
+            }
+            if cost < optimum.1 {
+                optimum = (profile, cost);
+            }
+        }
+        Efficiency {
+            optimum: optimum.0,
+            optimal_cost: optimum.1,
+            best_equilibrium_cost: best,
+            worst_equilibrium_cost: worst,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_parallel_links() {
+        // Two players choose between a link as slow as its load and one that always takes 2
+        let game = CongestionGame {
+            latencies: vec![Latency::linear(1., 0.), "2".parse().unwrap()],
+            strategies: vec![vec![vec![0], vec![1]]; 2],
+        };
+        assert_eq!(game.cost(&[0, 0], 1), 2.);
+        assert_eq!(game.social_cost(&[0, 1]), 3.);
+        assert_eq!(game.potential(&[0, 0]), 3.);
+        assert_eq!(
+            game.pure_equilibria(),
+            vec![vec![0, 0], vec![0, 1], vec![1, 0]]
+        );
+
+        let dynamics = game.best_response_dynamics(vec![1, 1]);
+        assert_eq!(dynamics.profile, vec![0, 1]);
+        assert_eq!(dynamics.moves, 1);
+
+        let efficiency = game.efficiency();
+        assert_eq!(efficiency.optimal_cost, 3.);
+        assert_eq!(efficiency.price_of_anarchy(), 4. / 3.);
+        assert_eq!(efficiency.price_of_stability(), 1.);
+    }
+
+    #[test]
+    fn test_price_of_anarchy() {
+        // The instance reaching the 5/2 bound: player i either uses h_i and g_i, or h_{i+1},
+        // h_{i+2} and g_{i+1}
+        let (h, g) = (|i: usize| i % 3, |i: usize| 3 + i % 3);
+        let game = CongestionGame {
+            latencies: vec![Latency::linear(1., 0.); 6],
+            strategies: (0..3)
+                .map(|i| vec![vec![h(i), g(i)], vec![h(i + 1), h(i + 2), g(i + 1)]])
+                .collect(),
+        };
+        let efficiency = game.efficiency();
+        assert_eq!(efficiency.optimum, vec![0, 0, 0]);
+        assert_eq!(efficiency.optimal_cost, 6.);
+        assert!(game.is_nash(&[1, 1, 1]));
+        assert_eq!(efficiency.worst_equilibrium_cost, 15.);
+        assert_eq!(efficiency.price_of_anarchy(), 2.5);
+        assert_eq!(efficiency.price_of_stability(), 1.);
+    }
+
+    #[test]
+    fn test_best_response_dynamics() {
+        // Random games with quadratic latencies, players choosing two different resources of five
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let game = CongestionGame {
+                latencies: (0..5)
+                    .map(|_| Latency {
+                        coefficients: vec![rng.gen_range(0., 3.), 0., rng.gen_range(0., 1.)],
+                    })
+                    .collect(),
+                strategies: (0..4)
+                    .map(|_| {
+                        (0..3)
+                            .map(|_| {
+                                let first = rng.gen_range(0, 5);
+                                vec![first, (first + rng.gen_range(1, 5)) % 5]
+                            })
+                            .collect()
+                    })
+                    .collect(),
+            };
+            let start: Profile = (0..4).map(|_| rng.gen_range(0, 3)).collect();
+            let dynamics = game.best_response_dynamics(start.clone());
+            assert!(game.is_nash(&dynamics.profile));
+            assert!(game.potential(&dynamics.profile) <= game.potential(&start));
+            assert!(game.pure_equilibria().contains(&dynamics.profile));
+
+            // A unilateral move changes the potential by the mover's change in cost
+            let mut moved = start.clone();
+            moved[0] = (moved[0] + 1) % 3;
+            let difference = game.potential(&moved) - game.potential(&start);
+            assert!((difference - (game.cost(&moved, 0) - game.cost(&start, 0))).abs() < 1e-9);
+        }
+        assert!("1,x".parse::<Latency>().is_err());
+    }
+}
@@ -0,0 +1,537 @@
+//! Connect Four on the standard 7×6 board, solved by negamax with alpha-beta pruning.
+//!
+//! Positions are bitboards with a column of `HEIGHT + 1` bits per column, the extra bit keeping
+//! columns apart so that four in a row can be found with shifts. Columns are indexed 0 to 6 from
+//! the left, and a game can be written as the sequence of columns played numbered from 1, e.g.
+//! `4453`.
+//!
+//! Scores are from the point of view of the player to move: positive when they can force a win,
+//! more so the sooner it comes, negative when they lose, and 0 for a draw. A win with the player's
+//! `n`th stone from the end of the game scores `(WIDTH * HEIGHT + 1 - moves) / 2`.
+//!
+//! For search that stops short of the end of the game, [`ThreatEvaluator`] scores positions by
+//! their open threats.
+
+pub use crate::error::GameError;
+
+#[cfg(feature = "fs")]
+use crate::error::IoError;
+use crate::search::{Evaluator, GameState, Outcome};
+#[cfg(feature = "fs")]
+use crate::transposition::{load_table, save_table};
+
+use hashbrown::HashMap;
+use std::fmt::Display;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::str::FromStr;
+
+pub const WIDTH: usize = 7;
+pub const HEIGHT: usize = 6;
+/// Number of cells on the board
+pub const BOARD_SIZE: usize = WIDTH * HEIGHT;
+
+/// Columns from the center outwards, which are more often good moves and so searched first
+const COLUMN_ORDER: [usize; WIDTH] = [3, 2, 4, 1, 5, 0, 6];
+
+fn bottom_mask(column: usize) -> u64 {
+    1 << (column * (HEIGHT + 1))
+}
+
+fn top_mask(column: usize) -> u64 {
+    1 << (HEIGHT - 1 + column * (HEIGHT + 1))
+}
+
+fn column_mask(column: usize) -> u64 {
+    ((1 << HEIGHT) - 1) << (column * (HEIGHT + 1))
+}
+
+/// Every cell on the board
+fn board_mask() -> u64 {
+    (0..WIDTH).fold(0, |mask, x| mask | column_mask(x))
+}
+
+/// Empty cells that would give the stones in `stones` four in a row
+fn winning_cells(stones: u64, mask: u64) -> u64 {
+    // Vertical
+    let mut cells = (stones << 1) & (stones << 2) & (stones << 3);
+    // Horizontal and the two diagonals, with the empty cell at either end or in the middle
+    for shift in [HEIGHT + 1, HEIGHT, HEIGHT + 2] {
+        let pairs = (stones << shift) & (stones << (2 * shift));
+        cells |= pairs & (stones << (3 * shift));
+        cells |= pairs & (stones >> shift);
+        let pairs = (stones >> shift) & (stones >> (2 * shift));
+        cells |= pairs & (stones << shift);
+        cells |= pairs & (stones >> (3 * shift));
+    }
+    cells & (board_mask() ^ mask)
+}
+
+/// Whether the stones in `stones` have four in a row in any direction
+fn has_alignment(stones: u64) -> bool {
+    // Vertical, horizontal, and the two diagonals
+    [1, HEIGHT + 1, HEIGHT, HEIGHT + 2].iter().any(|shift| {
+        let pairs = stones & (stones >> shift);
+        pairs & (pairs >> (2 * shift)) != 0
+    })
+}
+
+/// Bitboard representation of a Connect Four position
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Position {
+    /// Stones of the player to move
+    current: u64,
+    /// Every stone on the board
+    mask: u64,
+    moves: usize,
+}
+
+impl Position {
+    /// Number of stones played
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Whether the column has room for another stone
+    pub fn can_play(&self, column: usize) -> bool {
+        column < WIDTH && self.mask & top_mask(column) == 0
+    }
+
+    /// Drops a stone of the player to move in the column
+    pub fn play(&mut self, column: usize) -> Result<(), GameError> {
+        if column >= WIDTH {
+            return Err(GameError::OutOfBounds(column));
+        }
+        if !self.can_play(column) {
+            return Err(GameError::InvalidMove(column));
+        }
+        self.current ^= self.mask;
+        self.mask |= self.mask + bottom_mask(column);
+        self.moves += 1;
+        Ok(())
+    }
+
+    /// Columns with room for another stone, from left to right
+    pub fn valid_moves(&self) -> Vec<usize> {
+        (0..WIDTH).filter(|x| self.can_play(*x)).collect()
+    }
+
+    /// Whether playing in the column wins for the player to move. The column must have room.
+    pub fn is_winning_move(&self, column: usize) -> bool {
+        let stones = self.current | ((self.mask + bottom_mask(column)) & column_mask(column));
+        has_alignment(stones)
+    }
+
+    /// Whether the player who moved last has four in a row
+    pub fn last_move_won(&self) -> bool {
+        has_alignment(self.current ^ self.mask)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.moves == BOARD_SIZE
+    }
+
+    /// Identifies the position for transposition tables: unique for every position on the board
+    pub fn key(&self) -> u64 {
+        self.current + self.mask
+    }
+
+    /// The position reflected left to right, which has the same score
+    pub fn mirror(&self) -> Position {
+        let column_bits = (1 << (HEIGHT + 1)) - 1;
+        let flip = |bits: u64| {
+            (0..WIDTH).fold(0, |acc, x| {
+                let column = (bits >> (x * (HEIGHT + 1))) & column_bits;
+                acc | column << ((WIDTH - 1 - x) * (HEIGHT + 1))
+            })
+        };
+        Position {
+            current: flip(self.current),
+            mask: flip(self.mask),
+            moves: self.moves,
+        }
+    }
+
+    /// The smaller [`Position::key`] of the position and its [`Position::mirror`], the same for
+    /// both so that transposition tables store them once
+    pub fn canonical_key(&self) -> u64 {
+        self.key().min(self.mirror().key())
+    }
+
+    /// Whose stone is at `row` (from the bottom) of `column`: 1 for the first player, 2 for the
+    /// second, and 0 when empty
+    pub fn get(&self, column: usize, row: usize) -> u8 {
+        let bit = 1 << (row + column * (HEIGHT + 1));
+        if self.mask & bit == 0 {
+            return 0;
+        }
+        let first = match self.moves % 2 {
+            0 => self.current,
+            _ => self.current ^ self.mask,
+        };
+        if first & bit != 0 {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+impl GameState for Position {
+    type Move = usize;
+
+    fn current_player(&self) -> usize {
+        self.moves % 2
+    }
+
+    /// Columns with room, from the center outwards
+    fn legal_moves(&self) -> Vec<usize> {
+        match self.last_move_won() {
+            true => vec![],
+            false => COLUMN_ORDER
+                .into_iter()
+                .filter(|x| self.can_play(*x))
+                .collect(),
+        }
+    }
+
+    fn play(&mut self, action: usize) -> Result<(), GameError> {
+        Position::play(self, action)
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        if self.last_move_won() {
+            Some(Outcome::Win(1 - self.moves % 2))
+        } else if self.is_full() {
+            Some(Outcome::Draw)
+        } else {
+            None
+        }
+    }
+}
+
+/// Scores a position by the empty cells that would complete four in a row for the player to move,
+/// less those for the opponent, plus a tenth of a point per stone in the center column
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreatEvaluator;
+
+impl Evaluator<Position> for ThreatEvaluator {
+    fn evaluate(&self, state: &Position) -> f64 {
+        let opponent = state.current ^ state.mask;
+        let threats = winning_cells(state.current, state.mask).count_ones() as f64
+            - winning_cells(opponent, state.mask).count_ones() as f64;
+        let center = (state.current & column_mask(WIDTH / 2)).count_ones() as f64
+            - (opponent & column_mask(WIDTH / 2)).count_ones() as f64;
+        threats + 0.1 * center
+    }
+}
+
+impl FromStr for Position {
+    type Err = GameError;
+
+    /// Parses the columns played, numbered from 1, e.g. `4453`. The game must not already be won
+    /// before the last move.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut position = Position::default();
+        for (index, x) in s.trim().chars().enumerate() {
+            let column = x
+                .to_digit(10)
+                .filter(|x| (1..=WIDTH as u32).contains(x))
+                .ok_or_else(|| GameError::InvalidBoard(format!("unknown column {}", x)))?;
+            if position.last_move_won() {
+                return Err(GameError::InvalidBoard(format!(
+                    "move {} is after the game was won",
+                    index + 1
+                )));
+            }
+            position.play(column as usize - 1)?;
+        }
+        Ok(position)
+    }
+}
+
+impl Display for Position {
+    /// The board from the top row down, with `X` for the first player and `O` for the second
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in (0..HEIGHT).rev() {
+            let cells: String = (0..WIDTH)
+                .map(|column| match self.get(column, row) {
+                    1 => 'X',
+                    2 => 'O',
+                    _ => '.',
+                })
+                .collect();
+            writeln!(f, "{}", cells)?;
+        }
+        writeln!(f, "1234567")
+    }
+}
+
+/// Negamax solver with alpha-beta pruning and a transposition table of upper bounds, shared by
+/// each position and its mirror image
+#[derive(Default)]
+pub struct Solver {
+    table: HashMap<u64, i8>,
+    /// Positions searched so far
+    pub nodes: u64,
+}
+
+impl Solver {
+    /// Reads a transposition table written by [`Solver::save`]
+    #[cfg(feature = "fs")]
+    pub fn load(path: &Path) -> Result<Self, IoError> {
+        Ok(Solver {
+            table: load_table(path, "connect4")?,
+            nodes: 0,
+        })
+    }
+
+    /// Writes the transposition table to `path`, in the [`transposition`](crate::transposition)
+    /// format
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: &Path) -> Result<(), IoError> {
+        save_table(path, "connect4", &self.table)
+    }
+
+    /// Exact score of the position for the player to move; see the module documentation. The
+    /// position must not be won already.
+    ///
+    /// Searches with null windows around a guess that moves towards the edges of the range of
+    /// possible scores, narrowing the range by one search at a time until it closes.
+    pub fn solve(&mut self, position: &Position) -> i32 {
+        let moves = position.moves as i32;
+        if (0..WIDTH).any(|x| position.can_play(x) && position.is_winning_move(x)) {
+            return (BOARD_SIZE as i32 + 1 - moves) / 2;
+        }
+        let mut min = -(BOARD_SIZE as i32 - moves) / 2;
+        let mut max = (BOARD_SIZE as i32 + 1 - moves) / 2;
+        while min < max {
+            let mut guess = min + (max - min) / 2;
+            if guess <= 0 && min / 2 < guess {
+                guess = min / 2;
+            } else if guess >= 0 && max / 2 > guess {
+                guess = max / 2;
+            }
+            let score = self.negamax(position, guess, guess + 1);
+            if score <= guess {
+                max = score;
+            } else {
+                min = score;
+            }
+        }
+        min
+    }
+
+    /// Best column for the player to move and its score, preferring central columns among equals.
+    /// None if the board is full.
+    pub fn best_move(&mut self, position: &Position) -> Option<(usize, i32)> {
+        let moves = position.moves as i32;
+        COLUMN_ORDER
+            .iter()
+            .filter(|x| position.can_play(**x))
+            .map(|column| {
+                if position.is_winning_move(*column) {
+                    return (*column, (BOARD_SIZE as i32 + 1 - moves) / 2);
+                }
+                let mut child = *position;
+                let _ = child.play(*column);
+                (*column, -self.solve(&child))
+            })
+            .fold(None, |best: Option<(usize, i32)>, x| match best {
+                Some(best) if best.1 >= x.1 => Some(best),
+                _ => Some(x),
+            })
+    }
+
+    /// Score of the position if it's within `(alpha, beta)`, and otherwise a bound on it on the
+    /// same side of the window. The player to move can't win immediately.
+    fn negamax(&mut self, position: &Position, mut alpha: i32, mut beta: i32) -> i32 {
+        self.nodes += 1;
+        let moves = position.moves as i32;
+        if position.is_full() {
+            return 0;
+        }
+        if (0..WIDTH).any(|x| position.can_play(x) && position.is_winning_move(x)) {
+            return (BOARD_SIZE as i32 + 1 - moves) / 2;
+        }
+
+        // We can't win next move, so the best we can do is win the move after
+        let max = match self.table.get(&position.canonical_key()) {
+            Some(x) => *x as i32,
+            None => (BOARD_SIZE as i32 - 1 - moves) / 2,
+        };
+        if beta > max {
+            beta = max;
+            if alpha >= beta {
+                return beta;
+            }
+        }
+
+        for column in COLUMN_ORDER {
+            if !position.can_play(column) {
+                continue;
+            }
+            let mut child = *position;
+            let _ = child.play(column);
+            let score = -self.negamax(&child, -beta, -alpha);
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        self.table.insert(position.canonical_key(), alpha as i8);
+        alpha
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::symmetry::Symmetry;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    /// Exact score by searching every move
+    fn brute_force(position: &Position) -> i32 {
+        let moves = position.moves() as i32;
+        if position.is_full() {
+            return 0;
+        }
+        position
+            .valid_moves()
+            .into_iter()
+            .map(|column| {
+                if position.is_winning_move(column) {
+                    return (BOARD_SIZE as i32 + 1 - moves) / 2;
+                }
+                let mut child = *position;
+                child.play(column).unwrap();
+                -brute_force(&child)
+            })
+            .max()
+            .unwrap()
+    }
+
+    /// Random position with `moves` stones where nobody has won
+    fn random_position(moves: usize, rng: &mut StdRng) -> Position {
+        loop {
+            let mut position = Position::default();
+            while position.moves() < moves {
+                let safe: Vec<usize> = position
+                    .valid_moves()
+                    .into_iter()
+                    .filter(|x| !position.is_winning_move(*x))
+                    .collect();
+                match safe.choose(rng) {
+                    Some(column) => position.play(*column).unwrap(),
+                    None => break,
+                }
+            }
+            if position.moves() == moves {
+                return position;
+            }
+        }
+    }
+
+    #[test]
+    fn test_play() {
+        let position: Position = "445566".parse().unwrap();
+        assert_eq!(position.moves(), 6);
+        assert!(position.is_winning_move(6));
+        assert!(position.is_winning_move(2));
+        assert!(!position.is_winning_move(0));
+        assert_eq!(position.get(3, 0), 1);
+        assert_eq!(position.get(3, 1), 2);
+        assert_eq!(position.get(3, 2), 0);
+
+        // Vertical and diagonal wins
+        assert!("1212121".parse::<Position>().unwrap().last_move_won());
+        assert!("12234334464".parse::<Position>().unwrap().last_move_won());
+        assert!("44556677".parse::<Position>().is_err());
+        assert!("48".parse::<Position>().is_err());
+
+        let mut position: Position = "111111".parse().unwrap();
+        assert!(!position.can_play(0));
+        assert_eq!(position.play(0), Err(GameError::InvalidMove(0)));
+        assert_eq!(position.play(7), Err(GameError::OutOfBounds(7)));
+        assert_eq!(position.valid_moves(), [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_mirror() {
+        let position: Position = "4453".parse().unwrap();
+        let mirror: Position = "4435".parse().unwrap();
+        assert_eq!(position.mirror(), mirror);
+        assert_eq!(mirror.mirror(), position);
+        assert_ne!(position.key(), mirror.key());
+        assert_eq!(position.canonical_key(), mirror.canonical_key());
+
+        // The bitboards hold WIDTH columns of HEIGHT + 1 bits, so the mirror reverses the
+        // columns of that layout
+        let symmetry =
+            Symmetry::from_fn(WIDTH, HEIGHT + 1, |column, row| (WIDTH - 1 - column, row));
+        assert_eq!(symmetry.apply_bits(position.mask), mirror.mask);
+        assert_eq!(symmetry.apply_bits(position.current), mirror.current);
+    }
+
+    #[test]
+    fn test_display() {
+        let position: Position = "4453".parse().unwrap();
+        assert_eq!(
+            position.to_string(),
+            ".......\n.......\n.......\n.......\n...O...\n..OXX..\n1234567\n"
+        );
+    }
+
+    #[test]
+    fn test_depth_limited_search() {
+        use crate::search::DepthLimitedSearch;
+
+        // Three in a row along the bottom, open at both ends, for the opponent of the player to move
+        let position: Position = "44556".parse().unwrap();
+        assert_eq!(ThreatEvaluator.evaluate(&position), -2.);
+        assert_eq!(ThreatEvaluator.evaluate(&Position::default()), 0.);
+
+        let mut search = DepthLimitedSearch::new(ThreatEvaluator, 4);
+        let position: Position = "445566".parse().unwrap();
+        let (column, _) = search.best_move(&position).unwrap();
+        assert!(column == 2 || column == 6);
+
+        // Deep enough to reach the end of the game, the search agrees with the solver
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..3 {
+            let position = random_position(36, &mut rng);
+            let expected = Solver::default().solve(&position);
+            let mut search = DepthLimitedSearch::new(ThreatEvaluator, BOARD_SIZE);
+            let score = search.score(&position);
+            assert_eq!(
+                score.partial_cmp(&0.),
+                expected.partial_cmp(&0),
+                "\n{}",
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn test_solver() {
+        let mut solver = Solver::default();
+        // Immediate win with the 7th stone
+        let position: Position = "445566".parse().unwrap();
+        assert_eq!(solver.solve(&position), (BOARD_SIZE as i32 + 1 - 6) / 2);
+        assert_eq!(solver.best_move(&position).unwrap().0, 2);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..5 {
+            let position = random_position(36, &mut rng);
+            let mut solver = Solver::default();
+            assert_eq!(
+                solver.solve(&position),
+                brute_force(&position),
+                "\n{}",
+                position
+            );
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! Dots and Boxes on a grid of boxes with up to 64 edges, such as 5×5.
+//!
+//! Players take turns drawing an edge between two neighboring dots. Whoever draws the fourth side
+//! of a box takes it and moves again, and whoever takes more boxes wins. Edges are kept in a
+//! bitboard, the horizontal edges row by row from the top followed by the vertical edges row by
+//! row, and the boxes each player has taken in a bitboard of their own.
+//!
+//! [`Board`] implements [`GameState`], so MCTS and depth-limited search play it, and
+//! [`ChainEvaluator`] scores positions by the boxes on offer and the long chains left.
+
+use crate::error::GameError;
+use crate::search::{Evaluator, GameState, Outcome};
+
+use std::fmt::Display;
+
+/// Dots and Boxes board
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Board {
+    rows: usize,
+    columns: usize,
+    /// Drawn edges, one bit per edge
+    edges: u64,
+    /// Boxes taken by each player, one bit per box in row-major order
+    boxes: [u64; 2],
+    /// Index of the player to move
+    player: usize,
+}
+
+impl Board {
+    /// Empty board with `rows` by `columns` boxes, which must have at most 64 edges
+    pub fn new(rows: usize, columns: usize) -> Result<Self, GameError> {
+        let edges = (rows + 1) * columns + rows * (columns + 1);
+        if rows == 0 || columns == 0 || edges > 64 {
+            return Err(GameError::InvalidBoard(format!(
+                "{}×{} boxes with {} edges, expected at least one box and at most 64 edges",
+                rows, columns, edges
+            )));
+        }
+        Ok(Board {
+            rows,
+            columns,
+            edges: 0,
+            boxes: [0; 2],
+            player: 0,
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Number of edges, drawn or not
+    pub fn edge_count(&self) -> usize {
+        self.horizontal_edges() + self.rows * (self.columns + 1)
+    }
+
+    /// Index of the player to move
+    pub fn turn(&self) -> usize {
+        self.player
+    }
+
+    /// Boxes taken by the player
+    pub fn score(&self, player: usize) -> usize {
+        self.boxes[player].count_ones() as usize
+    }
+
+    /// Player who took the box, if any
+    pub fn owner(&self, row: usize, column: usize) -> Option<usize> {
+        let bit = 1 << (row * self.columns + column);
+        (0..2).find(|x| self.boxes[*x] & bit != 0)
+    }
+
+    pub fn is_drawn(&self, edge: usize) -> Result<bool, GameError> {
+        if edge >= self.edge_count() {
+            return Err(GameError::OutOfBounds(edge));
+        }
+        Ok(self.edges & (1 << edge) != 0)
+    }
+
+    /// Edges not drawn yet
+    pub fn valid_moves(&self) -> Vec<usize> {
+        (0..self.edge_count())
+            .filter(|x| self.edges & (1 << x) == 0)
+            .collect()
+    }
+
+    /// Whether every edge is drawn
+    pub fn is_over(&self) -> bool {
+        self.edges.count_ones() as usize == self.edge_count()
+    }
+
+    /// Draws an edge for the player to move, who takes every box it completes and moves again if
+    /// there are any
+    pub fn act(&mut self, edge: usize) -> Result<(), GameError> {
+        if self.is_drawn(edge)? {
+            return Err(GameError::InvalidMove(edge));
+        }
+        self.edges |= 1 << edge;
+        let mut completed = false;
+        for (row, column) in self.edge_boxes(edge) {
+            if self.sides(row, column) == 4 {
+                self.boxes[self.player] |= 1 << (row * self.columns + column);
+                completed = true;
+            }
+        }
+        if !completed {
+            self.player = 1 - self.player;
+        }
+        Ok(())
+    }
+
+    /// Number of drawn sides of the box
+    pub fn sides(&self, row: usize, column: usize) -> usize {
+        self.box_edges(row, column)
+            .iter()
+            .filter(|x| self.edges & (1 << **x) != 0)
+            .count()
+    }
+
+    /// An edge completing a box, if there is one
+    pub fn capturing_move(&self) -> Option<usize> {
+        self.valid_moves().into_iter().find(|x| {
+            self.edge_boxes(*x)
+                .into_iter()
+                .any(|(row, column)| self.sides(row, column) == 3)
+        })
+    }
+
+    /// Whether some edge can be drawn without giving the opponent a box
+    pub fn has_safe_move(&self) -> bool {
+        self.valid_moves().into_iter().any(|x| {
+            self.edge_boxes(x)
+                .into_iter()
+                .all(|(row, column)| self.sides(row, column) < 2)
+        })
+    }
+
+    /// Sizes of the chains: groups of boxes with two sides drawn, joined through their undrawn
+    /// sides, which fall to the opponent one after another once one of their edges is drawn
+    pub fn chains(&self) -> Vec<usize> {
+        let in_chain = |row: usize, column: usize| self.sides(row, column) == 2;
+        let mut seen = vec![false; self.rows * self.columns];
+        let mut chains = vec![];
+        for start in 0..self.rows * self.columns {
+            if seen[start] || !in_chain(start / self.columns, start % self.columns) {
+                continue;
+            }
+            seen[start] = true;
+            let mut stack = vec![start];
+            let mut size = 0;
+            while let Some(x) = stack.pop() {
+                size += 1;
+                let (row, column) = (x / self.columns, x % self.columns);
+                for edge in self.box_edges(row, column) {
+                    if self.edges & (1 << edge) != 0 {
+                        continue;
+                    }
+                    for (r, c) in self.edge_boxes(edge) {
+                        let y = r * self.columns + c;
+                        if !seen[y] && in_chain(r, c) {
+                            seen[y] = true;
+                            stack.push(y);
+                        }
+                    }
+                }
+            }
+            chains.push(size);
+        }
+        chains
+    }
+
+    fn horizontal_edges(&self) -> usize {
+        (self.rows + 1) * self.columns
+    }
+
+    /// Top, bottom, left and right edges of the box
+    fn box_edges(&self, row: usize, column: usize) -> [usize; 4] {
+        let vertical = self.horizontal_edges() + row * (self.columns + 1) + column;
+        [
+            row * self.columns + column,
+            (row + 1) * self.columns + column,
+            vertical,
+            vertical + 1,
+        ]
+    }
+
+    /// The one or two boxes the edge is a side of
+    fn edge_boxes(&self, edge: usize) -> Vec<(usize, usize)> {
+        let mut boxes = vec![];
+        if edge < self.horizontal_edges() {
+            let (row, column) = (edge / self.columns, edge % self.columns);
+            if row > 0 {
+                boxes.push((row - 1, column));
+            }
+            if row < self.rows {
+                boxes.push((row, column));
+            }
+        } else {
+            let edge = edge - self.horizontal_edges();
+            let (row, column) = (edge / (self.columns + 1), edge % (self.columns + 1));
+            if column > 0 {
+                boxes.push((row, column - 1));
+            }
+            if column < self.columns {
+                boxes.push((row, column));
+            }
+        }
+        boxes
+    }
+}
+
+impl Display for Board {
+    /// Dots joined by the drawn edges, with each taken box marked `X` or `O` for its owner
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let drawn = |x: usize| self.edges & (1 << x) != 0;
+        for row in 0..=self.rows {
+            let mut line = String::from("+");
+            for column in 0..self.columns {
+                line += match drawn(row * self.columns + column) {
+                    true => "---+",
+                    false => "   +",
+                };
+            }
+            writeln!(f, "{}", line)?;
+            if row == self.rows {
+                break;
+            }
+            let mut line = String::new();
+            for column in 0..=self.columns {
+                let vertical = self.horizontal_edges() + row * (self.columns + 1) + column;
+                line += match drawn(vertical) {
+                    true => "|",
+                    false => " ",
+                };
+                if column < self.columns {
+                    line += match self.owner(row, column) {
+                        Some(0) => " X ",
+                        Some(_) => " O ",
+                        None => "   ",
+                    };
+                }
+            }
+            writeln!(f, "{}", line)?;
+        }
+        write!(f, "X {}, O {}", self.score(0), self.score(1))
+    }
+}
+
+impl GameState for Board {
+    type Move = usize;
+
+    fn current_player(&self) -> usize {
+        self.player
+    }
+
+    fn legal_moves(&self) -> Vec<usize> {
+        self.valid_moves()
+    }
+
+    fn play(&mut self, action: usize) -> Result<(), GameError> {
+        self.act(action)
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        if !self.is_over() {
+            return None;
+        }
+        match self.score(0).cmp(&self.score(1)) {
+            std::cmp::Ordering::Greater => Some(Outcome::Win(0)),
+            std::cmp::Ordering::Less => Some(Outcome::Win(1)),
+            std::cmp::Ordering::Equal => Some(Outcome::Draw),
+        }
+    }
+}
+
+/// Scores a position by counting chains. The player to move first takes every box on offer. If
+/// safe moves are left, the long chain rule decides who will be handed the long chains (three
+/// boxes or more): the first player wants the number of dots plus long chains to be even, the
+/// second player odd, and the favored side is credited half of the boxes in them. If not, the
+/// player to move has to open the chains from the smallest, and the opponent takes each one,
+/// giving the last two boxes of every long chain but the last back to stay in control.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ChainEvaluator;
+
+impl Evaluator<Board> for ChainEvaluator {
+    fn evaluate(&self, state: &Board) -> f64 {
+        let player = state.turn();
+        let mut board = state.clone();
+        while let Some(edge) = board.capturing_move() {
+            let _ = board.act(edge);
+        }
+        let mut score = board.score(player) as f64 - board.score(1 - player) as f64;
+        if board.is_over() {
+            return score;
+        }
+
+        let mut chains = board.chains();
+        if board.has_safe_move() {
+            let long: Vec<usize> = chains.into_iter().filter(|x| *x >= 3).collect();
+            let dots = (board.rows + 1) * (board.columns + 1);
+            let favored = (dots + long.len()) % 2;
+            let stake = long.iter().sum::<usize>() as f64 / 2.;
+            score += match favored == player {
+                true => stake,
+                false => -stake,
+            };
+        } else {
+            chains.sort();
+            let last = chains.len().saturating_sub(1);
+            for (i, size) in chains.into_iter().enumerate() {
+                score -= match size >= 3 && i < last {
+                    true => size as f64 - 4.,
+                    false => size as f64,
+                };
+            }
+        }
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcts::{Mcts, MctsConfig, RandomPlayout};
+    use crate::retrograde::{Tablebase, Value};
+    use crate::search::DepthLimitedSearch;
+
+    #[test]
+    fn test_rules() {
+        assert!(Board::new(0, 3).is_err());
+        assert!(Board::new(6, 6).is_err());
+        assert_eq!(Board::new(5, 5).unwrap().edge_count(), 60);
+
+        // One box: whoever draws the fourth side takes it
+        let mut board = Board::new(1, 1).unwrap();
+        for edge in 0..3 {
+            board.act(edge).unwrap();
+        }
+        assert_eq!(board.act(0), Err(GameError::InvalidMove(0)));
+        assert_eq!(board.act(4), Err(GameError::OutOfBounds(4)));
+        assert_eq!(board.turn(), 1);
+        assert_eq!(board.capturing_move(), Some(3));
+        board.act(3).unwrap();
+        assert_eq!(board.owner(0, 0), Some(1));
+        assert_eq!(board.outcome(), Some(Outcome::Win(1)));
+
+        // Two boxes side by side: taking a box earns another move
+        let mut board = Board::new(1, 2).unwrap();
+        for edge in [0, 2, 4] {
+            board.act(edge).unwrap();
+        }
+        assert_eq!(board.turn(), 1);
+        board.act(5).unwrap();
+        assert_eq!(board.owner(0, 0), Some(1));
+        assert_eq!(board.turn(), 1);
+        assert_eq!(
+            board.to_string(),
+            "+---+   +\n| O |    \n+---+   +\nX 0, O 1"
+        );
+
+        // The second box becomes a chain of one once it has two sides
+        assert!(board.chains().is_empty());
+        assert!(board.has_safe_move());
+        board.act(1).unwrap();
+        assert_eq!(board.turn(), 0);
+        assert_eq!(board.chains(), vec![1]);
+        assert!(!board.has_safe_move());
+    }
+
+    #[test]
+    fn test_search() {
+        // Search to the end of the game, with extra turns, agrees with retrograde analysis
+        let board = Board::new(1, 2).unwrap();
+        let tablebase = Tablebase::build(&board);
+        let mut search = DepthLimitedSearch::new(ChainEvaluator, board.edge_count());
+        for (position, value) in tablebase.iter() {
+            if position.is_over() {
+                continue;
+            }
+            let score = search.score(position);
+            match value {
+                Value::Win(_) => assert!(score > 0.),
+                Value::Loss(_) => assert!(score < 0.),
+                Value::Draw => assert_eq!(score, 0.),
+            }
+        }
+
+        // A box on offer is taken
+        let mut board = Board::new(2, 2).unwrap();
+        for edge in [0, 6, 7] {
+            board.act(edge).unwrap();
+        }
+        assert_eq!(board.capturing_move(), Some(2));
+        assert!(ChainEvaluator.evaluate(&board) >= 1.);
+        let mut search = DepthLimitedSearch::new(ChainEvaluator, 2);
+        assert_eq!(search.best_move(&board).map(|x| x.0), Some(2));
+
+        let config = MctsConfig {
+            iterations: 2000,
+            seed: Some(0),
+            ..Default::default()
+        };
+        let mut mcts = Mcts::new(config, RandomPlayout);
+        assert!(mcts.best_move(&board).is_some());
+    }
+}
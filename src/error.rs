@@ -0,0 +1,136 @@
+//! Error types shared across the crate
+
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub use crate::cfr::TreeError;
+
+/// Illegal operation on a game's state
+#[derive(Debug, Error, PartialEq)]
+pub enum GameError {
+    #[error("tile {0} is off the board")]
+    OutOfBounds(usize),
+    #[error("tile {0} is already occupied")]
+    InvalidMove(usize),
+    #[error("invalid board: {0}")]
+    InvalidBoard(String),
+}
+
+/// Failure to solve a normal-form game
+#[derive(Debug, Error, PartialEq)]
+pub enum NormalFormError {
+    #[error("game is not zero-sum")]
+    NotZeroSum,
+    #[error("linear program failed: {0}")]
+    LinearProgram(String),
+    #[error("label {0} is not an action of either player")]
+    InvalidLabel(usize),
+    #[error("game is degenerate")]
+    Degenerate,
+    #[error("player {0} is not one of the two players")]
+    InvalidPlayer(usize),
+}
+
+/// Failure to solve a game tree in sequence form
+#[derive(Debug, Error, PartialEq)]
+pub enum SequenceFormError {
+    #[error("game has {0} players, not two")]
+    NotTwoPlayers(usize),
+    #[error("{path}: payouts are not zero-sum")]
+    NotZeroSum { path: String },
+    /// Infoset whose states were reached through different actions of the acting player
+    #[error("{path}: infoset {infoset} forgets the player's earlier actions")]
+    ImperfectRecall { path: String, infoset: usize },
+    #[error(transparent)]
+    Tree(#[from] TreeError),
+    #[error("linear program failed: {0}")]
+    LinearProgram(String),
+}
+
+/// Failure to read or write one of the crate's files
+#[derive(Debug, Error)]
+pub enum IoError {
+    #[error("{path:?}: {source}")]
+    File {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("{path:?}: {source}")]
+    Format {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("{path:?}: {reason}")]
+    Header { path: PathBuf, reason: String },
+    #[error("{path:?}: expected {expected} bytes, found {found}")]
+    Size {
+        path: PathBuf,
+        expected: usize,
+        found: usize,
+    },
+}
+
+#[cfg(feature = "fs")]
+impl IoError {
+    /// Attaches `path` to an I/O error, for use with `map_err`
+    pub(crate) fn file(path: &Path) -> impl Fn(std::io::Error) -> IoError + '_ {
+        move |source| IoError::File {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// Attaches `path` to a (de)serialization error, for use with `map_err`
+    pub(crate) fn format(path: &Path) -> impl Fn(serde_json::Error) -> IoError + '_ {
+        move |source| IoError::Format {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+/// Failure to set up or run a solver
+#[derive(Debug, Error)]
+pub enum SolverError {
+    #[error("invalid tree: {0}")]
+    Tree(#[from] TreeError),
+    #[error(transparent)]
+    Io(#[from] IoError),
+    /// rust_poker's simulator error isn't exported, so only its message is kept
+    #[error("equity calculation failed: {0}")]
+    Equity(String),
+}
+
+/// Failure to move an arena tree onto the GPU, see [`crate::gpu::GpuArenaTree`]
+#[cfg(feature = "gpu")]
+#[derive(Debug, Error)]
+pub enum GpuError {
+    #[error("no GPU adapter found")]
+    NoAdapter,
+    #[error("GPU device request failed: {0}")]
+    Device(#[from] wgpu::RequestDeviceError),
+    /// Tree whose per-node arrays don't fit in one storage buffer of the device
+    #[error("tree needs {needed} bytes per buffer, the device allows {limit}")]
+    TooLarge { needed: u64, limit: u64 },
+    #[error("reading results back from the GPU failed: {0}")]
+    Map(#[from] wgpu::BufferAsyncError),
+}
+
+/// Failure of a request to the HTTP API of [`crate::server`]
+#[cfg(feature = "server")]
+#[derive(Debug, Error)]
+pub enum ApiError {
+    /// Invalid parameters, answered with status 400
+    #[error("{0}")]
+    BadRequest(String),
+    #[error(transparent)]
+    Solver(#[from] SolverError),
+    /// The task running the request failed, e.g. by panicking
+    #[error("{0}")]
+    Internal(String),
+}
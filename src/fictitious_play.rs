@@ -0,0 +1,316 @@
+//! Fictitious play for two player normal-form games: each round, both players respond to the
+//! empirical frequencies of the opponent's past actions, and those frequencies are the solution.
+//!
+//! In classic fictitious play the response is a best response, and the frequencies converge to an
+//! equilibrium in zero-sum games, though slower than CFR and not in every general-sum game. Smooth
+//! fictitious play replaces it with the logit response, playing each action with probability
+//! proportional to `exp(payoff / temperature)`, which converges to a quantal response equilibrium
+//! that approaches a Nash equilibrium as the temperature goes to zero. Stochastic fictitious play
+//! samples one action from that response each round instead of counting all of it.
+//!
+//! Exploitability is measured as [`crate::cfr::exploitability`] measures it, so the convergence
+//! curves can be compared with a [`crate::cfr::CfrSolver`] on the [`crate::matrix_game`] tree of
+//! the same game.
+
+use crate::normal_form::NormalFormGame;
+
+use ndarray::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// How each player responds to the opponent's empirical frequencies
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Response {
+    /// Classic fictitious play: the best response, ties going to the first action
+    Best,
+    /// Smooth fictitious play: each action with probability proportional to
+    /// `exp(payoff / temperature)`
+    Logit { temperature: f64 },
+}
+
+/// Options of a fictitious play run
+#[derive(Debug, Clone)]
+pub struct FictitiousPlayConfig {
+    pub response: Response,
+    /// Upper bound on the number of rounds
+    pub max_iterations: u64,
+    /// Stop once exploitability drops below this value, in payoff units
+    pub target_exploitability: Option<f64>,
+    /// Number of rounds between exploitability checks
+    pub check_interval: u64,
+    /// Record exploitability every `check_interval` rounds in [`FictitiousPlay::convergence`]
+    pub record_convergence: bool,
+    /// Count one action sampled from each response instead of the whole response
+    pub sampled: bool,
+    /// Seed of the generator sampling actions
+    pub seed: u64,
+}
+
+impl Default for FictitiousPlayConfig {
+    fn default() -> Self {
+        FictitiousPlayConfig {
+            response: Response::Best,
+            max_iterations: 10000,
+            target_exploitability: None,
+            check_interval: 100,
+            record_convergence: false,
+            sampled: false,
+            seed: 0,
+        }
+    }
+}
+
+/// Exploitability of the empirical frequencies after some number of rounds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrequencyPoint {
+    pub iteration: u64,
+    pub exploitability: f64,
+}
+
+/// Summary of a finished run
+#[derive(Debug, Clone, Copy)]
+pub struct FictitiousPlayResult {
+    pub iterations: u64,
+    pub exploitability: f64,
+    /// Whether the target exploitability was reached before the iteration cap
+    pub converged: bool,
+}
+
+/// Fictitious play on a game, keeping how often each player has played each action
+pub struct FictitiousPlay<'a> {
+    game: &'a NormalFormGame,
+    pub config: FictitiousPlayConfig,
+    /// Total weight of each player's actions over the rounds so far
+    counts: [Array<f64, Ix1>; 2],
+    pub iterations: u64,
+    /// Exploitability series recorded when `config.record_convergence` is set
+    pub convergence: Vec<FrequencyPoint>,
+    rng: StdRng,
+}
+
+impl<'a> FictitiousPlay<'a> {
+    pub fn new(game: &'a NormalFormGame, config: FictitiousPlayConfig) -> Self {
+        let [rows, columns] = game.shape();
+        let rng = StdRng::seed_from_u64(config.seed);
+        FictitiousPlay {
+            game,
+            config,
+            counts: [Array::zeros(rows), Array::zeros(columns)],
+            iterations: 0,
+            convergence: Vec::new(),
+            rng,
+        }
+    }
+
+    /// Empirical frequency of each player's actions, uniform before the first round
+    pub fn strategies(&self) -> [Array<f64, Ix1>; 2] {
+        [0, 1].map(|player| {
+            let counts = &self.counts[player];
+            match self.iterations {
+                0 => Array::from_elem(counts.len(), 1. / counts.len() as f64),
+                _ => counts / counts.sum(),
+            }
+        })
+    }
+
+    /// Exploitability of the empirical frequencies
+    pub fn exploitability(&self) -> f64 {
+        let [first, second] = self.strategies();
+        self.game.exploitability([&first, &second])
+    }
+
+    /// Plays one round, with both players responding to the frequencies before it
+    pub fn step(&mut self) {
+        let [first, second] = self.strategies();
+        let values = [
+            self.game.payoffs.index_axis(Axis(0), 0).dot(&second),
+            self.game.payoffs.index_axis(Axis(0), 1).t().dot(&first),
+        ];
+        for (player, values) in values.iter().enumerate() {
+            let response = self.response(values);
+            match self.config.sampled {
+                true => {
+                    let action = self.sample(&response);
+                    self.counts[player][action] += 1.;
+                }
+                false => self.counts[player] += &response,
+            }
+        }
+        self.iterations += 1;
+    }
+
+    /// Plays rounds until the iteration cap or the target exploitability, checking it every
+    /// `check_interval` rounds
+    pub fn solve(&mut self) -> FictitiousPlayResult {
+        let check_interval = self.config.check_interval.max(1);
+        while self.iterations < self.config.max_iterations {
+            self.step();
+            if !self.iterations.is_multiple_of(check_interval) {
+                continue;
+            }
+            let exploitability = self.exploitability();
+            if self.config.record_convergence {
+                self.convergence.push(FrequencyPoint {
+                    iteration: self.iterations,
+                    exploitability,
+                });
+            }
+            if self
+                .config
+                .target_exploitability
+                .is_some_and(|x| exploitability < x)
+            {
+                return FictitiousPlayResult {
+                    iterations: self.iterations,
+                    exploitability,
+                    converged: true,
+                };
+            }
+        }
+        FictitiousPlayResult {
+            iterations: self.iterations,
+            exploitability: self.exploitability(),
+            converged: false,
+        }
+    }
+
+    /// Mixed response to the expected payoff of each action
+    fn response(&self, values: &Array<f64, Ix1>) -> Array<f64, Ix1> {
+        match self.config.response {
+            Response::Best => {
+                let mut best = 0;
+                for (action, value) in values.iter().enumerate() {
+                    if *value > values[best] {
+                        best = action;
+                    }
+                }
+                let mut response = Array::zeros(values.len());
+                response[best] = 1.;
+                response
+            }
+            Response::Logit { temperature } => {
+                // Subtracting the largest value keeps the exponentials finite
+                let max = values.fold(f64::NEG_INFINITY, |acc, x| acc.max(*x));
+                let weights = values.mapv(|x| ((x - max) / temperature).exp());
+                let total = weights.sum();
+                weights / total
+            }
+        }
+    }
+
+    /// Action drawn from a mixed strategy
+    fn sample(&mut self, strategy: &Array<f64, Ix1>) -> usize {
+        let mut remaining: f64 = self.rng.gen();
+        for (action, probability) in strategy.iter().enumerate() {
+            remaining -= probability;
+            if remaining < 0. {
+                return action;
+            }
+        }
+        strategy.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::*;
+    use crate::matrix_game::{avg_strategies, MatrixGame};
+
+    #[test]
+    fn test_fictitious_play() {
+        // Frequencies approach the equilibrium of a zero-sum game, and exploitability falls
+        let game = NormalFormGame::from(&MatrixGame::biased_rock_paper_scissors());
+        let mut play = FictitiousPlay::new(
+            &game,
+            FictitiousPlayConfig {
+                max_iterations: 20000,
+                check_interval: 1000,
+                record_convergence: true,
+                ..Default::default()
+            },
+        );
+        let result = play.solve();
+        assert!(!result.converged);
+        assert_eq!(play.convergence.len(), 20);
+        assert!(result.exploitability < 0.05, "{}", result.exploitability);
+        assert!(result.exploitability < play.convergence[0].exploitability);
+        let expected = array![1., 10., 1.] / 12.;
+        for strategy in play.strategies() {
+            assert!((&strategy - &expected).iter().all(|x| x.abs() < 0.02));
+        }
+
+        // CFR+ gets closer in the same number of iterations
+        let matrix_game = MatrixGame::biased_rock_paper_scissors();
+        let mut solver = CfrSolver::new(
+            matrix_game.build_tree().unwrap(),
+            SolverConfig {
+                variant: CfrVariant::CfrPlus,
+                max_iterations: 1000,
+                ..Default::default()
+            },
+        );
+        solver.solve();
+        let [first, second] = avg_strategies(solver.root.as_ref()).map(|x| x.mapv(f64::from));
+        let exploitability = game.exploitability([&first, &second]);
+        assert_eq!(play.convergence[0].iteration, 1000);
+        assert!(exploitability < play.convergence[0].exploitability);
+
+        // Stops at the target, and in the prisoner's dilemma both players learn to defect
+        let game: NormalFormGame = "3/3,0/5;5/0,1/1".parse().unwrap();
+        let mut play = FictitiousPlay::new(
+            &game,
+            FictitiousPlayConfig {
+                target_exploitability: Some(1e-2),
+                check_interval: 10,
+                ..Default::default()
+            },
+        );
+        let result = play.solve();
+        assert!(result.converged);
+        assert!(result.iterations < 1000);
+        assert!(play.strategies().iter().all(|x| x[1] > 0.99));
+    }
+
+    #[test]
+    fn test_smooth_fictitious_play() {
+        // The logit equilibrium of matching pennies is the Nash equilibrium, by symmetry
+        let game: NormalFormGame = "1,-1;-1,1".parse().unwrap();
+        for sampled in [false, true] {
+            let mut play = FictitiousPlay::new(
+                &game,
+                FictitiousPlayConfig {
+                    response: Response::Logit { temperature: 0.1 },
+                    max_iterations: 20000,
+                    sampled,
+                    seed: 1,
+                    ..Default::default()
+                },
+            );
+            let result = play.solve();
+            assert!(result.exploitability < 0.05, "{}", result.exploitability);
+            for strategy in play.strategies() {
+                assert!((strategy[0] - 0.5).abs() < 0.03, "{}", strategy);
+            }
+        }
+
+        // Elsewhere it settles near, but not at, the equilibrium, closer at lower temperatures
+        let game = NormalFormGame::from(&MatrixGame::biased_rock_paper_scissors());
+        let exploitability = [1., 0.1].map(|temperature| {
+            let mut play = FictitiousPlay::new(
+                &game,
+                FictitiousPlayConfig {
+                    response: Response::Logit { temperature },
+                    max_iterations: 20000,
+                    ..Default::default()
+                },
+            );
+            play.solve().exploitability
+        });
+        assert!(
+            exploitability[1] < exploitability[0],
+            "{:?}",
+            exploitability
+        );
+    }
+}
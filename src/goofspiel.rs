@@ -0,0 +1,164 @@
+//! Goofspiel, the game of pure strategy: a common CFR benchmark made only of simultaneous moves.
+//!
+//! Each player holds the cards 1 to N, and a prize deck of the same cards is shuffled. Every
+//! round a prize is turned up and both players bid one of their cards at the same time. The higher
+//! bid wins points equal to the prize, and ties win nothing. Bids are revealed at the end of the
+//! round and can't be used again. The game is symmetric, so it's worth 0 to both players.
+//!
+//! There is a single state, since the only thing hidden is the other player's bid in the current
+//! round, so the first player's bids are [hidden](TreeBuilder::hidden) from the second. Rounds are
+//! named by the prize, the first player's bid and the second's, e.g. `3:1-2`, and nodes by the
+//! rounds so far separated by commas.
+
+use crate::cfr::{Float, Node, TreeBuilder, TreeError};
+
+use std::cmp::Ordering;
+
+use ndarray::*;
+
+/// Size and scoring of the game
+#[derive(Clone, Debug, PartialEq)]
+pub struct GoofspielConfig {
+    /// Cards in each player's hand and in the prize deck
+    pub cards: usize,
+    /// Pay 1 for winning more points than the other player, rather than the difference in points
+    pub win_loss: bool,
+}
+
+impl Default for GoofspielConfig {
+    fn default() -> Self {
+        GoofspielConfig {
+            cards: 4,
+            win_loss: false,
+        }
+    }
+}
+
+/// Builds the game tree. Each round multiplies the number of nodes by the cube of the cards left,
+/// so 4 cards make about 14,000 terminal nodes and 5 cards well over a million.
+pub fn build_goofspiel_tree(config: &GoofspielConfig) -> Result<Box<dyn Node>, TreeError> {
+    let cards: Vec<usize> = (1..=config.cards).collect();
+    let builder = Builder { config };
+    builder.deal("", &cards, [&cards, &cards], 0).build()
+}
+
+/// `cards` without `card`
+fn without(cards: &[usize], card: usize) -> Vec<usize> {
+    cards.iter().copied().filter(|x| *x != card).collect()
+}
+
+struct Builder<'a> {
+    config: &'a GoofspielConfig,
+}
+
+impl Builder<'_> {
+    /// Chance node turning up one of `prizes`, with `points` the first player's lead so far
+    fn deal(
+        &self,
+        history: &str,
+        prizes: &[usize],
+        hands: [&[usize]; 2],
+        points: i64,
+    ) -> TreeBuilder {
+        let name = if history.is_empty() { "root" } else { history };
+        prizes
+            .iter()
+            .fold(TreeBuilder::chance(name), |node, prize| {
+                let history = if history.is_empty() {
+                    format!("{}:", prize)
+                } else {
+                    format!("{},{}:", history, prize)
+                };
+                let prizes = without(prizes, *prize);
+                node.child(self.first_bid(&history, *prize, &prizes, hands, points))
+            })
+    }
+
+    /// First player's bid on `prize`, hidden from the second player
+    fn first_bid(
+        &self,
+        history: &str,
+        prize: usize,
+        prizes: &[usize],
+        hands: [&[usize]; 2],
+        points: i64,
+    ) -> TreeBuilder {
+        hands[0]
+            .iter()
+            .fold(TreeBuilder::action(history, 0).hidden(), |node, bid| {
+                let history = format!("{}{}", history, bid);
+                node.child(self.second_bid(&history, prize, prizes, hands, *bid, points))
+            })
+    }
+
+    /// Second player's bid on `prize`, after the first player bid `first`
+    fn second_bid(
+        &self,
+        history: &str,
+        prize: usize,
+        prizes: &[usize],
+        hands: [&[usize]; 2],
+        first: usize,
+        points: i64,
+    ) -> TreeBuilder {
+        let hand = without(hands[0], first);
+        hands[1]
+            .iter()
+            .fold(TreeBuilder::action(history, 1), |node, bid| {
+                let history = format!("{}-{}", history, bid);
+                let points = points
+                    + match first.cmp(bid) {
+                        Ordering::Greater => prize as i64,
+                        Ordering::Less => -(prize as i64),
+                        Ordering::Equal => 0,
+                    };
+                if prizes.is_empty() {
+                    return node.zero_sum(&history, array![self.payout(points)]);
+                }
+                let other = without(hands[1], *bid);
+                node.child(self.deal(&history, prizes, [&hand, &other], points))
+            })
+    }
+
+    /// First player's payout with a final lead of `points`
+    fn payout(&self, points: i64) -> Float {
+        if self.config.win_loss {
+            points.signum() as Float
+        } else {
+            points as Float
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::*;
+
+    #[test]
+    fn test_goofspiel() {
+        for win_loss in [false, true] {
+            let config = GoofspielConfig { cards: 3, win_loss };
+            let root = build_goofspiel_tree(&config).unwrap();
+            assert_eq!(root.state_probabilities().len(), 1);
+            let first_bid = &root.children().unwrap()[0];
+            assert_eq!(first_bid.name(), "1:");
+            assert!(matches!(first_bid.as_node_ref(), NodeRef::Action(x) if x.hidden));
+
+            let mut solver = CfrSolver::new(
+                root,
+                SolverConfig {
+                    variant: CfrVariant::CfrPlus,
+                    max_iterations: 100,
+                    ..Default::default()
+                },
+            );
+            let result = solver.solve();
+            assert!(result.exploitability < 1e-2, "{}", result.exploitability);
+
+            // The game is symmetric
+            let value = solver.root.avg_strategy_payouts()[[0, 0]];
+            assert!(value.abs() < 1e-2, "{}", value);
+        }
+    }
+}
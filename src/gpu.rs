@@ -0,0 +1,581 @@
+//! Probability and EV passes of an [`ArenaTree`] on the GPU, with wgpu compute shaders. Built with
+//! the `gpu` feature.
+//!
+//! The arena's breadth-first layout maps directly onto flat GPU buffers: one dispatch per depth
+//! of the tree computes every node of that depth at once, top-down for the probabilities and
+//! bottom-up for the EVs. Regrets and strategies are still updated on the CPU, so each iteration
+//! uploads the current strategies and reads the probabilities and EVs back into the arena.
+//!
+//! The GPU works in f32 whatever the precision of [`Float`], which limits how closely the
+//! solution can approach zero exploitability.
+
+use crate::cfr::{ArenaNode, ArenaTree, Float, Node, NodeMut, NodeRef, SolverConfig};
+use crate::error::GpuError;
+
+use ndarray::*;
+use std::fmt::Display;
+use std::ops::Range;
+use std::sync::mpsc;
+use wgpu::util::DeviceExt;
+
+/// Precision of every GPU buffer
+type GpuFloat = f32;
+
+/// Marks the children of chance nodes, whose outcomes don't scale any player's own reach
+const NO_PLAYER: u32 = u32::MAX;
+
+/// Invocations per workgroup, as in `gpu.wgsl`
+const WORKGROUP_SIZE: u64 = 64;
+
+/// Range of nodes covered by a dispatch, as laid out in `gpu.wgsl`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    first: u32,
+    end: u32,
+    n_states: u32,
+    n_players: u32,
+}
+
+/// [`ArenaTree`] whose probability and EV passes run on the GPU, with the rest of the [`Node`]
+/// API on the CPU. Terminal payouts are uploaded once, when the tree is moved onto the GPU, so
+/// they shouldn't be changed afterwards.
+///
+/// # Panics
+///
+/// The probability and EV passes panic if their results can't be read back from the GPU, e.g.
+/// after the device is lost, since [`Node`] has no way to return the error.
+#[derive(Debug)]
+pub struct GpuArenaTree {
+    tree: ArenaTree,
+    n_players: usize,
+    n_states: usize,
+    /// Ranges of node indices of each depth of the tree, from the root down
+    levels: Vec<Range<usize>>,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    probabilities: wgpu::ComputePipeline,
+    evs: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    /// Offset between the [`Params`] of consecutive levels in `params`
+    params_stride: u64,
+    max_workgroups: u64,
+    weights: wgpu::Buffer,
+    states: wgpu::Buffer,
+    players: wgpu::Buffer,
+    payouts: wgpu::Buffer,
+    /// Mappable copy of `states` followed by `players`, or of `payouts`
+    staging: wgpu::Buffer,
+}
+
+impl GpuArenaTree {
+    /// Moves `tree` onto the first GPU adapter found, preferring a discrete one
+    pub fn new(tree: ArenaTree) -> Result<Self, GpuError> {
+        pollster::block_on(Self::new_async(tree))
+    }
+
+    async fn new_async(tree: ArenaTree) -> Result<Self, GpuError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            })
+            .await
+            .ok_or(GpuError::NoAdapter)?;
+        let limits = adapter.limits();
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: Some("cfr"),
+                    required_features: wgpu::Features::empty(),
+                    required_limits: limits.clone(),
+                    memory_hints: wgpu::MemoryHints::Performance,
+                },
+                None,
+            )
+            .await?;
+
+        let n_players = tree.nodes[0].payouts().nrows();
+        let n_states = tree.nodes[0].state_probabilities().len();
+        let n_nodes = tree.nodes.len();
+        let needed = (n_nodes * n_players * n_states * size_of::<GpuFloat>()) as u64;
+        let limit = (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+        // Indices into the buffers are u32 in the shaders
+        if needed > limit || n_nodes * n_players * n_states > u32::MAX as usize {
+            return Err(GpuError::TooLarge { needed, limit });
+        }
+
+        let mut parents = vec![[0, NO_PLAYER]; n_nodes];
+        let mut depths = vec![0; n_nodes];
+        for (index, node) in tree.nodes.iter().enumerate() {
+            let player = match node {
+                ArenaNode::Action(x) => x.player as u32,
+                _ => NO_PLAYER,
+            };
+            for child in tree.children[index].clone() {
+                parents[child] = [index as u32, player];
+                depths[child] = depths[index] + 1;
+            }
+        }
+        // Breadth-first order keeps each depth contiguous
+        let mut levels: Vec<Range<usize>> = Vec::new();
+        for (index, depth) in depths.iter().enumerate() {
+            match levels.last_mut() {
+                Some(level) if depths[level.start] == *depth => level.end = index + 1,
+                _ => levels.push(index..index + 1),
+            }
+        }
+        let children: Vec<[u32; 2]> = tree
+            .children
+            .iter()
+            .map(|range| [range.start as u32, range.end as u32])
+            .collect();
+        let mut payouts = vec![0.; n_nodes * n_players * n_states];
+        for (index, node) in tree.nodes.iter().enumerate() {
+            if let ArenaNode::Terminal(x) = node {
+                for (target, payout) in payouts[index * n_players * n_states..]
+                    .iter_mut()
+                    .zip(x.payouts.iter())
+                {
+                    *target = *payout as GpuFloat;
+                }
+            }
+        }
+
+        let params_stride = (size_of::<Params>() as u64)
+            .next_multiple_of(limits.min_uniform_buffer_offset_alignment as u64);
+        let mut params = vec![0u8; levels.len() * params_stride as usize];
+        for (level, range) in levels.iter().enumerate() {
+            let offset = level * params_stride as usize;
+            params[offset..offset + size_of::<Params>()].copy_from_slice(bytemuck::bytes_of(
+                &Params {
+                    first: range.start as u32,
+                    end: range.end as u32,
+                    n_states: n_states as u32,
+                    n_players: n_players as u32,
+                },
+            ));
+        }
+
+        let init = |label, contents: &[u8], usage| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(label),
+                contents,
+                usage,
+            })
+        };
+        let storage = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST;
+        let params = init("params", &params, wgpu::BufferUsages::UNIFORM);
+        let parents = init("parents", bytemuck::cast_slice(&parents), storage);
+        let children = init("children", bytemuck::cast_slice(&children), storage);
+        let payouts = init(
+            "payouts",
+            bytemuck::cast_slice(&payouts),
+            storage | wgpu::BufferUsages::COPY_SRC,
+        );
+        let zeroed = |label, len: usize, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: (len * size_of::<GpuFloat>()) as u64,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let weights = zeroed("weights", n_nodes * n_states, storage);
+        let states = zeroed(
+            "states",
+            n_nodes * n_states,
+            storage | wgpu::BufferUsages::COPY_SRC,
+        );
+        let players = zeroed(
+            "players",
+            n_nodes * n_players * n_states,
+            storage | wgpu::BufferUsages::COPY_SRC,
+        );
+        let staging = zeroed(
+            "staging",
+            n_nodes * (n_players + 1) * n_states,
+            wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+        );
+
+        let storage_entry = |binding, read_only| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("cfr"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(size_of::<Params>() as u64),
+                    },
+                    count: None,
+                },
+                storage_entry(1, true),
+                storage_entry(2, true),
+                storage_entry(3, true),
+                storage_entry(4, false),
+                storage_entry(5, false),
+                storage_entry(6, false),
+            ],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("cfr"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &params,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(size_of::<Params>() as u64),
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: parents.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: children.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: weights.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: states.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: players.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: payouts.as_entire_binding(),
+                },
+            ],
+        });
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("cfr"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("cfr"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point,
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let probabilities = pipeline("probabilities");
+        let evs = pipeline("evs");
+
+        Ok(GpuArenaTree {
+            tree,
+            n_players,
+            n_states,
+            levels,
+            probabilities,
+            evs,
+            bind_group,
+            params_stride,
+            max_workgroups: limits.max_compute_workgroups_per_dimension as u64,
+            weights,
+            states,
+            players,
+            payouts,
+            staging,
+            device,
+            queue,
+        })
+    }
+
+    /// Moves the tree back off the GPU
+    pub fn into_inner(self) -> ArenaTree {
+        self.tree
+    }
+
+    /// Runs `pipeline` over the given levels, `values` invocations per node, then copies each of
+    /// `outputs` into consecutive parts of the staging buffer and reads them back
+    fn run(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        levels: impl Iterator<Item = usize>,
+        values: usize,
+        outputs: &[&wgpu::Buffer],
+    ) -> Result<Vec<GpuFloat>, GpuError> {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("cfr") });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("cfr"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(pipeline);
+            for level in levels {
+                // Every dispatch is its own synchronization scope, so each level sees the
+                // previous one's writes
+                pass.set_bind_group(
+                    0,
+                    &self.bind_group,
+                    &[(level as u64 * self.params_stride) as u32],
+                );
+                let invocations = (self.levels[level].len() * values) as u64;
+                let workgroups = invocations.div_ceil(WORKGROUP_SIZE);
+                let x = workgroups.min(self.max_workgroups);
+                pass.dispatch_workgroups(x as u32, workgroups.div_ceil(x) as u32, 1);
+            }
+        }
+        let mut offset = 0;
+        for output in outputs {
+            encoder.copy_buffer_to_buffer(output, 0, &self.staging, offset, output.size());
+            offset += output.size();
+        }
+        self.queue.submit([encoder.finish()]);
+
+        let slice = self.staging.slice(..offset);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            // Nothing is left to tell if the receiver is gone
+            let _ = sender.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        // A dropped callback means the mapping never completed
+        receiver.recv().unwrap_or(Err(wgpu::BufferAsyncError))?;
+        let result = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        self.staging.unmap();
+        Ok(result)
+    }
+}
+
+impl Display for GpuArenaTree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.tree, f)
+    }
+}
+
+impl Node for GpuArenaTree {
+    fn name(&self) -> String {
+        self.tree.name()
+    }
+
+    fn state_probabilities(&self) -> ArrayView1<'_, Float> {
+        self.tree.state_probabilities()
+    }
+
+    fn payouts(&self) -> ArrayView2<'_, Float> {
+        self.tree.payouts()
+    }
+
+    fn strategy(&self) -> Option<ArrayView2<'_, Float>> {
+        self.tree.strategy()
+    }
+
+    fn avg_strategy(&self) -> Option<ArrayView2<'_, Float>> {
+        self.tree.avg_strategy()
+    }
+
+    fn children(&self) -> Option<&Vec<Box<dyn Node>>> {
+        self.tree.children()
+    }
+
+    fn as_node_ref(&self) -> NodeRef<'_> {
+        self.tree.as_node_ref()
+    }
+
+    fn as_node_mut(&mut self) -> NodeMut<'_> {
+        self.tree.as_node_mut()
+    }
+
+    fn set_state_probabilities(&mut self, p: Array<Float, Ix1>) {
+        self.tree.set_state_probabilities(p)
+    }
+
+    fn set_player_probabilities(&mut self, p: Array<Float, Ix2>) {
+        self.tree.set_player_probabilities(p)
+    }
+
+    fn update_probabilities(&mut self) {
+        let (n_players, n_states) = (self.n_players, self.n_states);
+        let mut weights = vec![0.; self.tree.nodes.len() * n_states];
+        for (index, node) in self.tree.nodes.iter().enumerate() {
+            let child_probabilities = match node {
+                ArenaNode::Action(x) => x.expand_strategy(&x.strategy),
+                ArenaNode::Chance(x) => x.outcome_probabilities.clone(),
+                ArenaNode::Terminal(_) => continue,
+            };
+            for (target, weight) in weights[self.tree.children[index].start * n_states..]
+                .iter_mut()
+                .zip(child_probabilities.iter())
+            {
+                *target = *weight as GpuFloat;
+            }
+        }
+        self.queue
+            .write_buffer(&self.weights, 0, bytemuck::cast_slice(&weights));
+
+        // The root's own reach is empty unless set, which means all ones
+        let root = &self.tree.nodes[0];
+        let states: Vec<GpuFloat> = root
+            .state_probabilities()
+            .iter()
+            .map(|x| *x as GpuFloat)
+            .collect();
+        let players: Vec<GpuFloat> = match root {
+            ArenaNode::Action(x) if !x.player_probabilities.is_empty() => x
+                .player_probabilities
+                .iter()
+                .map(|x| *x as GpuFloat)
+                .collect(),
+            ArenaNode::Chance(x) if !x.player_probabilities.is_empty() => x
+                .player_probabilities
+                .iter()
+                .map(|x| *x as GpuFloat)
+                .collect(),
+            _ => vec![1.; n_players * n_states],
+        };
+        self.queue
+            .write_buffer(&self.states, 0, bytemuck::cast_slice(&states));
+        self.queue
+            .write_buffer(&self.players, 0, bytemuck::cast_slice(&players));
+
+        let result = self
+            .run(
+                &self.probabilities,
+                1..self.levels.len(),
+                n_states,
+                &[&self.states, &self.players],
+            )
+            .unwrap_or_else(|e| panic!("{}", e));
+        let (states, players) = result.split_at(self.tree.nodes.len() * n_states);
+        // The root keeps the probabilities it was given
+        for (index, node) in self.tree.nodes.iter_mut().enumerate().skip(1) {
+            *node.state_probabilities_mut() = states[index * n_states..(index + 1) * n_states]
+                .iter()
+                .map(|x| *x as Float)
+                .collect();
+            if let Some(x) = node.player_probabilities_mut() {
+                *x = players[index * n_players * n_states..(index + 1) * n_players * n_states]
+                    .iter()
+                    .map(|x| *x as Float)
+                    .collect::<Array<Float, Ix1>>()
+                    .into_shape_with_order((n_players, n_states))
+                    .unwrap();
+            }
+        }
+    }
+
+    fn update_ev(&mut self) {
+        let (n_players, n_states) = (self.n_players, self.n_states);
+        let payouts = self
+            .run(
+                &self.evs,
+                (0..self.levels.len()).rev(),
+                n_players * n_states,
+                &[&self.payouts],
+            )
+            .unwrap_or_else(|e| panic!("{}", e));
+        for (index, node) in self.tree.nodes.iter_mut().enumerate() {
+            let evs = match node {
+                ArenaNode::Action(x) => &mut x.evs,
+                ArenaNode::Chance(x) => &mut x.evs,
+                ArenaNode::Terminal(_) => continue,
+            };
+            for (target, ev) in evs.iter_mut().zip(&payouts[index * n_players * n_states..]) {
+                *target = *ev as Float;
+            }
+        }
+    }
+
+    fn update_strategy(&mut self, config: &SolverConfig, player: Option<usize>) {
+        self.tree.update_strategy(config, player)
+    }
+
+    fn best_response(&self, player: usize, reach: &Array<Float, Ix1>) -> Array<Float, Ix1> {
+        self.tree.best_response(player, reach)
+    }
+
+    fn avg_strategy_payouts(&self) -> Array<Float, Ix2> {
+        self.tree.avg_strategy_payouts()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::{exploitability, CfrSolver, StrategyProfile, TreeBuilder};
+
+    #[test]
+    #[ignore = "needs a GPU adapter; run with --ignored"]
+    fn test_gpu_matches_cpu() {
+        // A chance node deals one of two cards to player 0 in each of two states, then player 1
+        // responds without seeing it
+        let build = || {
+            TreeBuilder::chance("deal")
+                .outcome_probabilities(array![[0.25, 0.75], [0.75, 0.25]])
+                .child(
+                    TreeBuilder::action("high", 0)
+                        .child(
+                            TreeBuilder::action("b", 1)
+                                .infosets(vec![vec![0, 1]])
+                                .zero_sum("bc", array![2., 1.])
+                                .zero_sum("bf", array![1., 1.]),
+                        )
+                        .zero_sum("x", array![1., -1.]),
+                )
+                .child(
+                    TreeBuilder::action("low", 0)
+                        .child(
+                            TreeBuilder::action("b", 1)
+                                .infosets(vec![vec![0, 1]])
+                                .zero_sum("bc", array![-2., -1.])
+                                .zero_sum("bf", array![1., 1.]),
+                        )
+                        .zero_sum("x", array![-1., 0.]),
+                )
+                .build()
+                .unwrap()
+        };
+        let gpu = GpuArenaTree::new(ArenaTree::from_tree(build())).unwrap();
+        let config = SolverConfig {
+            max_iterations: 50,
+            ..Default::default()
+        };
+        let mut cpu = CfrSolver::new(Box::new(ArenaTree::from_tree(build())), config.clone());
+        let mut gpu = CfrSolver::new(Box::new(gpu), config);
+        cpu.solve();
+        gpu.solve();
+
+        let cpu_profile = StrategyProfile::from_tree(cpu.root.as_ref());
+        let gpu_profile = StrategyProfile::from_tree(gpu.root.as_ref());
+        for (path, strategy) in &cpu_profile.strategies {
+            let difference = strategy - &gpu_profile.strategies[path];
+            assert!(difference.iter().all(|x| x.abs() < 1e-4), "{}", path);
+        }
+        assert!(
+            (exploitability(cpu.root.as_ref()) - exploitability(gpu.root.as_ref())).abs() < 1e-4
+        );
+    }
+}
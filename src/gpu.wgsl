@@ -0,0 +1,81 @@
+// Probability and EV passes of an arena tree, see gpu.rs. Arrays are flattened per node: `states`
+// and `weights` are indexed by node, state and `players` and `payouts` by node, player, state.
+// Each dispatch covers the nodes first..end of one depth of the tree.
+
+struct Params {
+    first: u32,
+    end: u32,
+    n_states: u32,
+    n_players: u32,
+}
+
+// Acting player at the parent of a chance node's children
+const NO_PLAYER: u32 = 0xffffffffu;
+
+@group(0) @binding(0) var<uniform> params: Params;
+// Parent index and the player acting there, indexed by node
+@group(0) @binding(1) var<storage, read> parents: array<vec2<u32>>;
+// Range of child indices, indexed by node; empty for terminal nodes
+@group(0) @binding(2) var<storage, read> children: array<vec2<u32>>;
+// Probability of the parent's strategy or chance outcome leading to the node
+@group(0) @binding(3) var<storage, read> weights: array<f32>;
+@group(0) @binding(4) var<storage, read_write> states: array<f32>;
+@group(0) @binding(5) var<storage, read_write> players: array<f32>;
+@group(0) @binding(6) var<storage, read_write> payouts: array<f32>;
+
+// Dispatches too large for one dimension spill into y
+fn invocation(id: vec3<u32>, groups: vec3<u32>) -> u32 {
+    return id.x + id.y * groups.x * 64u;
+}
+
+@compute @workgroup_size(64)
+fn probabilities(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let index = invocation(id, groups);
+    let node = params.first + index / params.n_states;
+    if node >= params.end {
+        return;
+    }
+    let state = index % params.n_states;
+    let parent = parents[node];
+    let weight = weights[node * params.n_states + state];
+    states[node * params.n_states + state] = states[parent.x * params.n_states + state] * weight;
+    for (var player = 0u; player < params.n_players; player += 1u) {
+        var reach = players[(parent.x * params.n_players + player) * params.n_states + state];
+        if player == parent.y {
+            reach *= weight;
+        }
+        players[(node * params.n_players + player) * params.n_states + state] = reach;
+    }
+}
+
+@compute @workgroup_size(64)
+fn evs(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let index = invocation(id, groups);
+    let node = params.first + index / (params.n_players * params.n_states);
+    if node >= params.end {
+        return;
+    }
+    let range = children[node];
+    // Terminal payouts are fixed
+    if range.x == range.y {
+        return;
+    }
+    let player = (index / params.n_states) % params.n_players;
+    let state = index % params.n_states;
+    var total = 0.0;
+    for (var child = range.x; child < range.y; child += 1u) {
+        total += payouts[(child * params.n_players + player) * params.n_states + state]
+            * states[child * params.n_states + state];
+    }
+    var reach = states[node * params.n_states + state];
+    if reach == 0.0 {
+        reach = 1.0;
+    }
+    payouts[(node * params.n_players + player) * params.n_states + state] = total / reach;
+}
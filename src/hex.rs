@@ -0,0 +1,547 @@
+//! Hex on an n×n rhombus, from 1×1 up to [`MAX_SIZE`].
+//!
+//! X, the first player, joins the top and bottom edges and O joins the left and right edges.
+//! Hex can't be drawn: once the board is full exactly one player is connected. Each player's
+//! stones are kept in a union-find along with the edges they touch, so a win is detected the
+//! moment a move joins the two edges.
+//!
+//! Cells are numbered row by row from the top left, and named like `c2` for column c of row 2.
+//! [`BridgeEvaluator`] scores positions for depth-limited search, [`Board`] implements
+//! [`GameState`] for MCTS and the agents, and [`Solver`] proves wins on boards up to about 5×5.
+
+use crate::error::GameError;
+use crate::search::{Evaluator, GameState, Outcome};
+
+use std::collections::VecDeque;
+use std::fmt::Display;
+
+use hashbrown::HashMap;
+
+/// Largest board, whose cells fill the 128 bits of a player's stones
+pub const MAX_SIZE: usize = 11;
+
+/// Row and column offset between two cells
+type Offset = (isize, isize);
+
+/// Row and column offsets of the six neighbors of a cell
+const NEIGHBORS: [Offset; 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0)];
+
+/// Row and column offsets of the six cells a bridge away, with the two cells between them
+const BRIDGES: [(Offset, [Offset; 2]); 6] = [
+    ((-1, 2), [(-1, 1), (0, 1)]),
+    ((1, 1), [(0, 1), (1, 0)]),
+    ((2, -1), [(1, 0), (1, -1)]),
+    ((1, -2), [(1, -1), (0, -1)]),
+    ((-1, -1), [(0, -1), (-1, 0)]),
+    ((-2, 1), [(-1, 0), (-1, 1)]),
+];
+
+/// Disjoint sets of cells, joined by size so that finding a set's root stays fast without
+/// modifying the sets
+#[derive(Clone, Debug)]
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        UnionFind {
+            parent: (0..len).collect(),
+            size: vec![1; len],
+        }
+    }
+
+    fn find(&self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            x = self.parent[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+
+/// Hex board
+#[derive(Clone, Debug)]
+pub struct Board {
+    size: usize,
+    /// Stones of X and O, one bit per cell
+    stones: [u128; 2],
+    /// Cells followed by the top, bottom, left and right edges, each stone joined to its
+    /// neighbors of the same color and the edges it touches
+    sets: UnionFind,
+    winner: Option<usize>,
+}
+
+impl Board {
+    /// Empty board with `size` cells a side
+    pub fn new(size: usize) -> Result<Self, GameError> {
+        if !(1..=MAX_SIZE).contains(&size) {
+            return Err(GameError::InvalidBoard(format!(
+                "size {} is not between 1 and {}",
+                size, MAX_SIZE
+            )));
+        }
+        Ok(Board {
+            size,
+            stones: [0; 2],
+            sets: UnionFind::new(size * size + 4),
+            winner: None,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Index of the player to move, 0 for X and 1 for O
+    pub fn turn(&self) -> usize {
+        ((self.stones[0] | self.stones[1]).count_ones() % 2) as usize
+    }
+
+    /// Player with a stone on the cell, if any
+    pub fn get(&self, index: usize) -> Result<Option<usize>, GameError> {
+        if index >= self.size * self.size {
+            return Err(GameError::OutOfBounds(index));
+        }
+        Ok((0..2).find(|x| self.stones[*x] & (1 << index) != 0))
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    /// Empty cells
+    pub fn valid_moves(&self) -> Vec<usize> {
+        let occupied = self.stones[0] | self.stones[1];
+        (0..self.size * self.size)
+            .filter(|x| occupied & (1 << x) == 0)
+            .collect()
+    }
+
+    /// Places a stone for the player to move
+    pub fn act(&mut self, index: usize) -> Result<(), GameError> {
+        if self.get(index)?.is_some() || self.winner.is_some() {
+            return Err(GameError::InvalidMove(index));
+        }
+        let player = self.turn();
+        self.stones[player] |= 1 << index;
+        let neighbors: Vec<usize> = self.neighbors(index).collect();
+        for x in neighbors {
+            if self.stones[player] & (1 << x) != 0 {
+                self.sets.union(index, x);
+            }
+        }
+        for edge in self.edges_touched(player, index) {
+            self.sets.union(index, edge);
+        }
+        let [start, end] = self.edges(player);
+        if self.sets.find(start) == self.sets.find(end) {
+            self.winner = Some(player);
+        }
+        Ok(())
+    }
+
+    /// Whether a stone of `player` on the empty cell would join their edges
+    pub fn is_winning_move(&self, player: usize, index: usize) -> bool {
+        let [start, end] = self.edges(player).map(|x| self.sets.find(x));
+        let mut roots: Vec<usize> = self
+            .neighbors(index)
+            .filter(|x| self.stones[player] & (1 << x) != 0)
+            .map(|x| self.sets.find(x))
+            .collect();
+        roots.extend(
+            self.edges_touched(player, index)
+                .into_iter()
+                .map(|x| self.sets.find(x)),
+        );
+        roots.contains(&start) && roots.contains(&end)
+    }
+
+    /// Name of a cell, e.g. `c2`
+    pub fn cell_name(&self, index: usize) -> String {
+        let column = (b'a' + (index % self.size) as u8) as char;
+        format!("{}{}", column, index / self.size + 1)
+    }
+
+    /// Index of a named cell, e.g. `c2`
+    pub fn cell(&self, name: &str) -> Result<usize, GameError> {
+        let name = name.trim().to_ascii_lowercase();
+        let unknown = || GameError::InvalidBoard(format!("unknown cell {}", name));
+        let mut chars = name.chars();
+        let column = chars
+            .next()
+            .filter(|x| x.is_ascii_lowercase())
+            .ok_or_else(unknown)? as usize
+            - 'a' as usize;
+        let row: usize = chars.as_str().parse().map_err(|_| unknown())?;
+        if column >= self.size || row == 0 || row > self.size {
+            return Err(unknown());
+        }
+        Ok((row - 1) * self.size + column)
+    }
+
+    /// Cells `offset` away from the cell, if on the board
+    fn offset(&self, index: usize, (row, column): Offset) -> Option<usize> {
+        let row = (index / self.size) as isize + row;
+        let column = (index % self.size) as isize + column;
+        let size = self.size as isize;
+        match (0..size).contains(&row) && (0..size).contains(&column) {
+            true => Some((row * size + column) as usize),
+            false => None,
+        }
+    }
+
+    fn neighbors(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        NEIGHBORS
+            .into_iter()
+            .filter_map(move |x| self.offset(index, x))
+    }
+
+    /// Union-find nodes of the two edges the player joins
+    fn edges(&self, player: usize) -> [usize; 2] {
+        let cells = self.size * self.size;
+        match player {
+            0 => [cells, cells + 1],
+            _ => [cells + 2, cells + 3],
+        }
+    }
+
+    /// Union-find nodes of the player's edges next to the cell
+    fn edges_touched(&self, player: usize, index: usize) -> Vec<usize> {
+        let [start, end] = self.edges(player);
+        let position = match player {
+            0 => index / self.size,
+            _ => index % self.size,
+        };
+        let mut edges = vec![];
+        if position == 0 {
+            edges.push(start);
+        }
+        if position == self.size - 1 {
+            edges.push(end);
+        }
+        edges
+    }
+
+    /// Fewest empty cells the player needs to fill to join their edges, counting two of their
+    /// stones a bridge apart as joined while both cells between them are empty. None if the
+    /// opponent has cut them off.
+    pub fn distance(&self, player: usize) -> Option<usize> {
+        let cells = self.size * self.size;
+        let cost = |x: usize| match self.get(x) {
+            Ok(Some(p)) if p == player => Some(0),
+            Ok(Some(_)) => None,
+            _ => Some(1),
+        };
+        let empty = |x: usize| self.get(x).is_ok_and(|x| x.is_none());
+        let [start, end] = self.edges(player);
+
+        // Breadth first search with 0-1 costs: own stones are free, empty cells cost one
+        let mut distances = vec![usize::MAX; cells];
+        let mut queue = VecDeque::new();
+        for (x, distance) in distances.iter_mut().enumerate() {
+            if let Some(c) = cost(x).filter(|_| self.edges_touched(player, x).contains(&start)) {
+                *distance = c;
+                match c {
+                    0 => queue.push_front(x),
+                    _ => queue.push_back(x),
+                }
+            }
+        }
+        while let Some(x) = queue.pop_front() {
+            let mut next: Vec<(usize, usize)> = self
+                .neighbors(x)
+                .filter_map(|y| cost(y).map(|c| (y, c)))
+                .collect();
+            if cost(x) == Some(0) {
+                for (offset, carriers) in BRIDGES {
+                    let bridge = self.offset(x, offset).filter(|y| cost(*y) == Some(0));
+                    let open = carriers
+                        .iter()
+                        .all(|c| self.offset(x, *c).is_some_and(empty));
+                    if let (Some(y), true) = (bridge, open) {
+                        next.push((y, 0));
+                    }
+                }
+            }
+            for (y, c) in next {
+                if distances[x] + c < distances[y] {
+                    distances[y] = distances[x] + c;
+                    match c {
+                        0 => queue.push_front(y),
+                        _ => queue.push_back(y),
+                    }
+                }
+            }
+        }
+        (0..cells)
+            .filter(|x| self.edges_touched(player, *x).contains(&end))
+            .map(|x| distances[x])
+            .min()
+            .filter(|x| *x != usize::MAX)
+    }
+}
+
+impl Display for Board {
+    /// The rhombus with each row shifted right of the one above, `X` joining top and bottom and
+    /// `O` joining left and right
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let columns: Vec<String> = (0..self.size)
+            .map(|x| ((b'a' + x as u8) as char).to_string())
+            .collect();
+        writeln!(f, "    {}", columns.join(" "))?;
+        for row in 0..self.size {
+            let cells: Vec<&str> = (0..self.size)
+                .map(|column| match self.get(row * self.size + column) {
+                    Ok(Some(0)) => "X",
+                    Ok(Some(_)) => "O",
+                    _ => ".",
+                })
+                .collect();
+            writeln!(
+                f,
+                "{:>2}{} {}",
+                row + 1,
+                " ".repeat(row + 1),
+                cells.join(" ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl GameState for Board {
+    type Move = usize;
+
+    fn current_player(&self) -> usize {
+        self.turn()
+    }
+
+    fn legal_moves(&self) -> Vec<usize> {
+        match self.winner {
+            Some(_) => vec![],
+            None => self.valid_moves(),
+        }
+    }
+
+    fn play(&mut self, action: usize) -> Result<(), GameError> {
+        self.act(action)
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        self.winner.map(Outcome::Win)
+    }
+}
+
+/// Scores a position by how many more empty cells the opponent needs to connect than the player to
+/// move, with stones a bridge apart counting as connected
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BridgeEvaluator;
+
+impl Evaluator<Board> for BridgeEvaluator {
+    fn evaluate(&self, state: &Board) -> f64 {
+        // Cut off players need more cells than there are
+        let cut_off = state.size * state.size + 1;
+        let player = state.turn();
+        let own = state.distance(player).unwrap_or(cut_off);
+        let opponent = state.distance(1 - player).unwrap_or(cut_off);
+        opponent as f64 - own as f64
+    }
+}
+
+/// Proves wins and losses by depth-first search with a transposition table. Hex has no draws,
+/// so every position is one or the other; boards larger than about 5×5 take too long.
+#[derive(Default)]
+pub struct Solver {
+    table: HashMap<(usize, [u128; 2]), bool>,
+    /// Positions searched so far
+    pub nodes: u64,
+}
+
+impl Solver {
+    /// Whether the player to move wins. The game must not be over.
+    pub fn solve(&mut self, board: &Board) -> bool {
+        let order = search_order(board.size);
+        self.search(board, &order)
+    }
+
+    /// A winning move for the player to move, preferring cells near the center, or None if the
+    /// position is lost or the game is over
+    pub fn winning_move(&mut self, board: &Board) -> Option<usize> {
+        if board.winner.is_some() {
+            return None;
+        }
+        let order = search_order(board.size);
+        let player = board.turn();
+        order
+            .iter()
+            .filter(|x| board.get(**x) == Ok(None))
+            .find(|x| {
+                if board.is_winning_move(player, **x) {
+                    return true;
+                }
+                let mut child = board.clone();
+                let _ = child.act(**x);
+                !self.search(&child, &order)
+            })
+            .copied()
+    }
+
+    fn search(&mut self, board: &Board, order: &[usize]) -> bool {
+        self.nodes += 1;
+        if let Some(win) = self.table.get(&(board.size, board.stones)) {
+            return *win;
+        }
+        let player = board.turn();
+        let empty: Vec<usize> = order
+            .iter()
+            .copied()
+            .filter(|x| board.get(*x) == Ok(None))
+            .collect();
+        let win = if empty.iter().any(|x| board.is_winning_move(player, *x)) {
+            true
+        } else {
+            // The opponent's winning cells must be blocked, and with two of them we can't
+            let threats: Vec<usize> = empty
+                .iter()
+                .copied()
+                .filter(|x| board.is_winning_move(1 - player, *x))
+                .collect();
+            let candidates = match threats.len() {
+                0 => empty,
+                1 => threats,
+                _ => vec![],
+            };
+            candidates.iter().any(|x| {
+                let mut child = board.clone();
+                let _ = child.act(*x);
+                !self.search(&child, order)
+            })
+        };
+        self.table.insert((board.size, board.stones), win);
+        win
+    }
+}
+
+/// Cells from the center outwards, where the strongest moves usually are
+fn search_order(size: usize) -> Vec<usize> {
+    let center = (size as f64 - 1.) / 2.;
+    let distance = |x: usize| {
+        let row = (x / size) as f64 - center;
+        let column = (x % size) as f64 - center;
+        (row.abs() + column.abs() + (row + column).abs()) / 2.
+    };
+    let mut order: Vec<usize> = (0..size * size).collect();
+    order.sort_by(|a, b| distance(*a).total_cmp(&distance(*b)));
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcts::{Mcts, MctsConfig, RandomPlayout};
+    use crate::search::DepthLimitedSearch;
+
+    /// Plays the named cells in turn
+    fn play(size: usize, cells: &[&str]) -> Board {
+        let mut board = Board::new(size).unwrap();
+        for cell in cells {
+            board.act(board.cell(cell).unwrap()).unwrap();
+        }
+        board
+    }
+
+    #[test]
+    fn test_rules() {
+        assert!(Board::new(0).is_err());
+        assert!(Board::new(MAX_SIZE + 1).is_err());
+        let mut board = Board::new(3).unwrap();
+        assert_eq!(board.cell("c2"), Ok(5));
+        assert_eq!(board.cell_name(5), "c2");
+        assert!(board.cell("d1").is_err());
+        assert_eq!(board.act(9), Err(GameError::OutOfBounds(9)));
+
+        // X joins top and bottom down the b column; O's stones in column a don't join left and
+        // right
+        for cell in ["b1", "a1", "b2", "a2"] {
+            board.act(board.cell(cell).unwrap()).unwrap();
+        }
+        assert_eq!(board.act(4), Err(GameError::InvalidMove(4)));
+        assert!(board.is_winning_move(0, board.cell("b3").unwrap()));
+        assert!(board.is_winning_move(0, board.cell("a3").unwrap()));
+        assert!(!board.is_winning_move(1, board.cell("b3").unwrap()));
+        board.act(board.cell("a3").unwrap()).unwrap();
+        assert_eq!(board.winner(), Some(0));
+        assert_eq!(board.outcome(), Some(Outcome::Win(0)));
+        assert!(board.legal_moves().is_empty());
+
+        // O joins left and right along the middle row
+        let board = play(3, &["a1", "a2", "b1", "b2", "a3", "c2"]);
+        assert_eq!(board.winner(), Some(1));
+    }
+
+    #[test]
+    fn test_distance() {
+        let board = Board::new(5).unwrap();
+        assert_eq!(board.distance(0), Some(5));
+        assert_eq!(board.distance(1), Some(5));
+
+        // Stones a bridge apart count as joined until the opponent takes a cell between them
+        let board = play(5, &["c2", "a1", "b4", "e5"]);
+        assert_eq!(board.distance(0), Some(2));
+        assert_eq!(board.distance(1), Some(4));
+        assert_eq!(BridgeEvaluator.evaluate(&board), 2.);
+        let board = play(5, &["c2", "a1", "b4", "b3", "e5"]);
+        assert_eq!(board.distance(0), Some(3));
+
+        // O is cut off by X's winning column
+        let board = play(3, &["b1", "a1", "b2", "a2", "b3"]);
+        assert_eq!(board.distance(0), Some(0));
+        assert_eq!(board.distance(1), None);
+    }
+
+    #[test]
+    fn test_solver() {
+        // The first player wins on every board, and the center wins on 3×3
+        let mut solver = Solver::default();
+        for size in 1..=4 {
+            assert!(solver.solve(&Board::new(size).unwrap()));
+        }
+        assert_eq!(solver.winning_move(&Board::new(3).unwrap()), Some(4));
+
+        // On 2×2 the acute corners lose, and O answers with the cell next to both of its edges
+        let board = play(2, &["a1"]);
+        assert!(solver.solve(&board));
+        assert_eq!(solver.winning_move(&board), Some(2));
+        assert!(!solver.solve(&play(2, &["b1"])));
+        assert_eq!(solver.winning_move(&play(2, &["b1"])), None);
+    }
+
+    #[test]
+    fn test_search() {
+        // X wins immediately on a3
+        let board = play(3, &["a1", "c1", "a2", "c2"]);
+        let mut search = DepthLimitedSearch::new(BridgeEvaluator, 2);
+        assert_eq!(search.best_move(&board).map(|x| x.0), Some(6));
+
+        let config = MctsConfig {
+            iterations: 2000,
+            seed: Some(0),
+            ..Default::default()
+        };
+        let mut mcts = Mcts::new(config, RandomPlayout);
+        assert_eq!(mcts.best_move(&board), Some(6));
+    }
+}
@@ -1,51 +1,592 @@
-use clap::*;
+//! Independent Chip Model: tournament equity of chip stacks under the Malmuth-Harville model
 
-#[derive(Parser, Debug)]
-struct Args {
-    #[arg(short, required=true, num_args=1.., help = "Stack sizes")]
-    stacks: Vec<f64>,
+#[cfg(feature = "fs")]
+use crate::error::IoError;
 
-    #[arg(short, required=true, num_args=1.., help = "Payouts")]
-    payouts: Vec<f64>,
+use std::collections::HashMap;
+use std::fmt::Display;
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::{BufWriter, Write};
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+use serde::Serialize;
+
+/// Probability of each player finishing in each of the first `n_places` places, indexed by
+/// player, place.
+///
+/// The probability of the next place only depends on which players have already finished, not in
+/// what order, so this works through the sets of finished players one place at a time, summing
+/// the probability of reaching each set. That takes about `n * C(n, n_places)` steps rather than
+/// the `n! / (n - n_places)!` of visiting every finishing order, as
+/// [`place_probabilities_recursive`] does.
+///
+/// # Panics
+///
+/// If there are more than 64 players. [`check_tournament`] checks this and the other
+/// requirements on `stacks`.
+pub fn place_probabilities(stacks: &[f64], n_places: usize) -> Vec<Vec<f64>> {
+    // Sets of finished players are bits of a u64. The practical limit is lower and depends on
+    // `n_places`: the sets of up to `n_places` players number about 2*10^4 for 20 players paying
+    // 5 places, but 10^6 for 20 paying all 20, and 2*10^9 for 32 paying 16.
+    assert!(stacks.len() <= 64, "At most 64 players are supported");
+    let total_chips: f64 = stacks.iter().sum();
+    let mut result = vec![vec![0.0; n_places]; stacks.len()];
+    // Probability of reaching each set of finished players, as a mask with bit `i` set for player
+    // `i`, and the chips they held
+    let mut finished: HashMap<u64, (f64, f64)> = HashMap::from([(0, (1.0, 0.0))]);
+    #[allow(clippy::needless_range_loop)] // `place` indexes the inner vectors of `result`
+    for place in 0..n_places {
+        let mut next: HashMap<u64, (f64, f64)> = HashMap::with_capacity(finished.len());
+        for (mask, (p, chips)) in finished {
+            let remaining = total_chips - chips;
+            for (i, stack) in stacks.iter().enumerate() {
+                if *stack <= 0.0 || mask & (1 << i) != 0 {
+                    continue;
+                }
+                let prob = p * stack / remaining;
+                result[i][place] += prob;
+                next.entry(mask | (1 << i))
+                    .or_insert((0.0, chips + stack))
+                    .0 += prob;
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        finished = next;
+    }
+    result
 }
 
-pub fn main() {
-    let args = Args::parse();
-    let n_players = args.stacks.len();
-    let n_places = args.payouts.len();
+/// Probability of each player finishing in each of the first `n_places` places under the
+/// Malmuth-Weitzman model, indexed by player, place. Rather than first place going to each player
+/// in proportion to their stack, the next player to bust is chosen in inverse proportion to their
+/// stack. The busted player's chips are taken to be won in proportion to the stacks left, which
+/// leaves their ratios unchanged. Heads up the two models agree.
+///
+/// Places are decided from the bottom, so this works through every set of busted players whatever
+/// `n_places` is, and is only practical for up to about 20 players.
+///
+/// # Panics
+///
+/// If there are more than 64 players; see [`check_tournament`].
+pub fn place_probabilities_weitzman(stacks: &[f64], n_places: usize) -> Vec<Vec<f64>> {
+    // Sets of busted players are bits of a u64, but all 2^n of them are visited: about 10^6 sets
+    // for 20 players, and each step doubles the time and memory
+    assert!(stacks.len() <= 64, "At most 64 players are supported");
+    let players: Vec<usize> = (0..stacks.len()).filter(|x| stacks[*x] > 0.0).collect();
+    let mut result = vec![vec![0.0; n_places]; stacks.len()];
+    // Probability of each set of busted players, as a mask with bit `i` set for player `i`
+    let mut busted: HashMap<u64, f64> = HashMap::from([(0, 1.0)]);
+    for place in (0..players.len()).rev() {
+        let mut next: HashMap<u64, f64> = HashMap::with_capacity(busted.len());
+        for (mask, p) in busted {
+            let left: Vec<usize> = players
+                .iter()
+                .copied()
+                .filter(|x| mask & (1 << x) == 0)
+                .collect();
+            let total: f64 = left.iter().map(|x| 1.0 / stacks[*x]).sum();
+            for i in left {
+                let prob = p / stacks[i] / total;
+                if place < n_places {
+                    result[i][place] += prob;
+                }
+                *next.entry(mask | (1 << i)).or_insert(0.0) += prob;
+            }
+        }
+        busted = next;
+    }
+    result
+}
+
+/// Model of how likely each player is to finish in each place given the stacks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IcmModel {
+    /// See [`place_probabilities`]
+    #[default]
+    MalmuthHarville,
+    /// See [`place_probabilities_weitzman`]
+    MalmuthWeitzman,
+}
 
-    let mut place_probabilities = vec![vec![0.0; n_places]; n_players];
-    traverse(&args.stacks, 0, n_places, &mut place_probabilities, 1.0);
+impl IcmModel {
+    pub fn place_probabilities(&self, stacks: &[f64], n_places: usize) -> Vec<Vec<f64>> {
+        match self {
+            IcmModel::MalmuthHarville => place_probabilities(stacks, n_places),
+            IcmModel::MalmuthWeitzman => place_probabilities_weitzman(stacks, n_places),
+        }
+    }
 
-    let icm: Vec<f64> = place_probabilities
+    /// Equity of each player under the model
+    pub fn icm(&self, stacks: &[f64], payouts: &[f64]) -> Vec<f64> {
+        equities(&self.place_probabilities(stacks, payouts.len()), payouts)
+    }
+}
+
+impl std::str::FromStr for IcmModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mh" | "harville" => Ok(IcmModel::MalmuthHarville),
+            "mw" | "weitzman" => Ok(IcmModel::MalmuthWeitzman),
+            _ => Err(format!("Unknown ICM model: {}", s)),
+        }
+    }
+}
+
+/// Same as [`place_probabilities`], by visiting every finishing order. Exponential in the number of
+/// places, so only practical for small fields; kept as a reference.
+pub fn place_probabilities_recursive(stacks: &[f64], n_places: usize) -> Vec<Vec<f64>> {
+    let mut result = vec![vec![0.0; n_places]; stacks.len()];
+    traverse(stacks, 0, n_places, &mut result, 1.0);
+    result
+}
+
+/// Expected payout of each player given their place probabilities
+pub fn equities(place_probabilities: &[Vec<f64>], payouts: &[f64]) -> Vec<f64> {
+    place_probabilities
         .iter()
-        .map(|p| {
-            p.into_iter()
-                .zip(args.payouts.iter())
-                .map(|(a, b)| a * b)
-                .sum()
-        })
-        .collect();
+        .map(|p| p.iter().zip(payouts.iter()).map(|(a, b)| a * b).sum())
+        .collect()
+}
+
+/// Checks that `stacks` and `payouts` can be passed to the functions of this module, which panic
+/// or return nonsense otherwise: 1 to 64 stacks, none negative and at least one positive, and at
+/// most one finite payout per player. Players with no chips have already busted, and are skipped.
+pub fn check_tournament(stacks: &[f64], payouts: &[f64]) -> Result<(), String> {
+    let valid = |x: &f64| x.is_finite() && *x >= 0.;
+    if stacks.len() > 64 || !stacks.iter().all(valid) || !stacks.iter().any(|x| *x > 0.) {
+        return Err("Expected 1 to 64 stacks, none negative and at least one positive".to_string());
+    }
+    if payouts.len() > stacks.len() || !payouts.iter().all(|x| x.is_finite()) {
+        return Err("Expected at most one payout per player".to_string());
+    }
+    Ok(())
+}
+
+/// ICM equity of each player
+pub fn icm(stacks: &[f64], payouts: &[f64]) -> Vec<f64> {
+    equities(&place_probabilities(stacks, payouts.len()), payouts)
+}
+
+/// Bubble factor of `player` going all in against `opponent` for the smaller of their stacks: the
+/// ICM equity lost by losing the chips over the equity gained by winning them
+pub fn bubble_factor(stacks: &[f64], payouts: &[f64], player: usize, opponent: usize) -> f64 {
+    let amount = stacks[player].min(stacks[opponent]);
+    let equity_after = |winner: usize, loser: usize| {
+        let mut stacks = stacks.to_vec();
+        stacks[winner] += amount;
+        stacks[loser] -= amount;
+        icm(&stacks, payouts)[player]
+    };
+    let equity = icm(stacks, payouts)[player];
+    (equity - equity_after(opponent, player)) / (equity_after(player, opponent) - equity)
+}
+
+/// Sensitivity of each player's ICM equity to each player's stack, indexed by player, stack:
+/// `result[i][j]` is the equity player `i` gains per chip added to player `j`'s stack, with the
+/// other stacks unchanged. Estimated by central differences with a step of a millionth of the
+/// chips in play.
+///
+/// A chip moving from `k` to `j` changes `i`'s equity by about `result[i][j] - result[i][k]`, and
+/// `result[i][i]` falls short of what a chip is worth in chip EV terms by the risk premium.
+pub fn equity_gradient(stacks: &[f64], payouts: &[f64]) -> Vec<Vec<f64>> {
+    let n = stacks.len();
+    let step = stacks.iter().sum::<f64>() * 1e-6;
+    let mut result = vec![vec![0.0; n]; n];
+    for j in (0..n).filter(|x| stacks[*x] > 0.0) {
+        let shifted = |delta: f64| {
+            let mut stacks = stacks.to_vec();
+            stacks[j] += delta;
+            icm(&stacks, payouts)
+        };
+        let h = step.min(stacks[j]);
+        let (up, down) = (shifted(h), shifted(-h));
+        for (row, (a, b)) in result.iter_mut().zip(up.iter().zip(&down)) {
+            row[j] = (a - b) / (2.0 * h);
+        }
+    }
+    result
+}
 
-    println!("Place probabilities:");
-    place_probabilities.iter().for_each(|x| println!("{:?}", x));
-    println!("ICM:");
-    println!("{:?}", icm);
+/// Bubble factor of every player against every other
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BubbleFactors {
+    pub stacks: Vec<f64>,
+    pub payouts: Vec<f64>,
+    /// Indexed by player, opponent. None on the diagonal and where either player has no chips.
+    pub factors: Vec<Vec<Option<f64>>>,
+}
+
+impl BubbleFactors {
+    pub fn new(stacks: &[f64], payouts: &[f64]) -> Self {
+        let n = stacks.len();
+        let factors = (0..n)
+            .map(|player| {
+                (0..n)
+                    .map(|opponent| {
+                        let defined =
+                            player != opponent && stacks[player] > 0. && stacks[opponent] > 0.;
+                        defined.then(|| bubble_factor(stacks, payouts, player, opponent))
+                    })
+                    .collect()
+            })
+            .collect();
+        BubbleFactors {
+            stacks: stacks.to_vec(),
+            payouts: payouts.to_vec(),
+            factors,
+        }
+    }
+
+    /// Writes the matrix with a header row and column of player indices, leaving undefined
+    /// factors empty
+    #[cfg(feature = "fs")]
+    pub fn write_csv(&self, path: &Path) -> Result<(), IoError> {
+        let mut writer = BufWriter::new(File::create(path).map_err(IoError::file(path))?);
+        let players: Vec<String> = (0..self.factors.len()).map(|x| x.to_string()).collect();
+        writeln!(writer, ",{}", players.join(",")).map_err(IoError::file(path))?;
+        for (player, row) in self.factors.iter().enumerate() {
+            let row: Vec<String> = row
+                .iter()
+                .map(|x| x.map_or_else(String::new, |x| x.to_string()))
+                .collect();
+            writeln!(writer, "{},{}", player, row.join(",")).map_err(IoError::file(path))?;
+        }
+        writer.flush().map_err(IoError::file(path))
+    }
+}
+
+impl Display for BubbleFactors {
+    /// One row per player, with the factors against each opponent in columns
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:>8}", "")?;
+        for opponent in 0..self.factors.len() {
+            write!(f, "{:>8}", opponent)?;
+        }
+        for (player, row) in self.factors.iter().enumerate() {
+            write!(f, "\n{:>8}", player)?;
+            for factor in row {
+                match factor {
+                    Some(x) => write!(f, "{:>8.3}", x)?,
+                    None => write!(f, "{:>8}", "-")?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Most players [`bounty_equities`] supports; it keeps an entry for each of the 2^n sets of
+/// players left
+pub const MAX_BOUNTY_PLAYERS: usize = 24;
+
+/// Share of a bounty paid in cash to the player who collects it in progressive knockouts, the
+/// rest being added to their own bounty
+pub const PKO_CASH_SHARE: f64 = 0.5;
+
+/// Expected bounty winnings of each player in a progressive knockout, where `bounties` are the
+/// bounties on each player's head. Busting a player pays `cash_share` of their bounty to the
+/// player who busts them and adds the rest to that player's bounty, and the winner collects their
+/// own bounty.
+///
+/// Players bust in the reverse of the finishing order of the Malmuth-Harville model, and each
+/// elimination is credited to one of the players left in proportion to their stack. Works through
+/// every set of players left, so only practical for up to about 20 players; fails for more than
+/// [`MAX_BOUNTY_PLAYERS`], or without a bounty for every player.
+pub fn bounty_equities(
+    stacks: &[f64],
+    bounties: &[f64],
+    cash_share: f64,
+) -> Result<Vec<f64>, String> {
+    let n = stacks.len();
+    if n > MAX_BOUNTY_PLAYERS {
+        return Err(format!(
+            "Bounties of at most {} players are supported",
+            MAX_BOUNTY_PLAYERS
+        ));
+    }
+    if bounties.len() != n {
+        return Err("Expected a bounty for every player".to_string());
+    }
+    let active = (0..n)
+        .filter(|x| stacks[*x] > 0.0)
+        .fold(0_usize, |mask, x| mask | (1 << x));
+    let total_chips: f64 = stacks.iter().sum();
+    let mut chips = vec![0.0; 1 << n];
+    for mask in 1_usize..1 << n {
+        let lowest = mask.trailing_zeros() as usize;
+        chips[mask] = chips[mask & (mask - 1)] + stacks[lowest];
+    }
+    let members = |mask: usize| (0..n).filter(move |x| mask & (1 << x) != 0);
+
+    // Probability of the players in each set taking the top places in some order, which is also
+    // the probability of them being the players left at some point
+    let mut top = vec![0.0; 1 << n];
+    top[0] = 1.0;
+    for (mask, taken) in chips.iter().enumerate() {
+        if top[mask] == 0.0 || mask & !active != 0 {
+            continue;
+        }
+        let remaining = total_chips - taken;
+        for k in members(active & !mask) {
+            top[mask | (1 << k)] += top[mask] * stacks[k] / remaining;
+        }
+    }
+
+    // Bounty on each player's head, summed over the ways of reaching each set of players left
+    // weighted by their probability. Supersets come before their subsets.
+    let mut result = vec![0.0; n];
+    let mut heads: Vec<Vec<f64>> = vec![Vec::new(); 1 << n];
+    heads[active] = bounties.to_vec();
+    for mask in (1..=active).rev() {
+        let head = std::mem::take(&mut heads[mask]);
+        if head.is_empty() {
+            continue;
+        }
+        if mask.count_ones() == 1 {
+            let winner = mask.trailing_zeros() as usize;
+            result[winner] += head[winner];
+            continue;
+        }
+        for k in members(mask) {
+            let rest = mask ^ (1 << k);
+            // Probability of `k` busting next given the players left
+            let q = top[rest] * stacks[k] / (total_chips - chips[rest]) / top[mask];
+            let mut next = std::mem::take(&mut heads[rest]);
+            next.resize(n, 0.0);
+            for j in members(rest) {
+                let captured = q * stacks[j] / chips[rest] * head[k];
+                result[j] += cash_share * captured;
+                next[j] += q * head[j] + (1.0 - cash_share) * captured;
+            }
+            heads[rest] = next;
+        }
+    }
+    Ok(result)
+}
+
+/// Equity of each player in a progressive knockout: their ICM equity in the payouts plus their
+/// expected bounty winnings, see [`bounty_equities`]
+pub fn pko_equities(stacks: &[f64], payouts: &[f64], bounties: &[f64]) -> Result<Vec<f64>, String> {
+    Ok(icm(stacks, payouts)
+        .iter()
+        .zip(bounty_equities(stacks, bounties, PKO_CASH_SHARE)?)
+        .map(|(a, b)| a + b)
+        .collect())
+}
+
+/// Chip chop of the prize pool: every player is guaranteed the payout of the last place left, and
+/// the rest is split in proportion to chips
+pub fn chip_chop(stacks: &[f64], payouts: &[f64]) -> Vec<f64> {
+    let n = stacks.len();
+    let guaranteed = payouts.get(n.saturating_sub(1)).copied().unwrap_or(0.0);
+    let prize_pool: f64 = payouts.iter().take(n).sum();
+    let rest = prize_pool - n as f64 * guaranteed;
+    let total_chips: f64 = stacks.iter().sum();
+    stacks
+        .iter()
+        .map(|x| guaranteed + rest * x / total_chips)
+        .collect()
+}
+
+/// Amounts each player takes in a deal splitting the remaining payouts
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Deal {
+    pub stacks: Vec<f64>,
+    pub payouts: Vec<f64>,
+    /// ICM equities
+    pub icm: Vec<f64>,
+    /// See [`chip_chop`]
+    pub chip_chop: Vec<f64>,
+    /// Weight of the ICM chop in the blend, the rest going to the chip chop
+    pub icm_weight: f64,
+    pub blend: Vec<f64>,
+}
+
+impl Deal {
+    pub fn new(stacks: &[f64], payouts: &[f64], icm_weight: f64) -> Self {
+        let icm = icm(stacks, payouts);
+        let chip_chop = chip_chop(stacks, payouts);
+        let blend = icm
+            .iter()
+            .zip(&chip_chop)
+            .map(|(a, b)| icm_weight * a + (1. - icm_weight) * b)
+            .collect();
+        Deal {
+            stacks: stacks.to_vec(),
+            payouts: payouts.to_vec(),
+            icm,
+            chip_chop,
+            icm_weight,
+            blend,
+        }
+    }
+}
+
+/// Most outcomes [`fgs`] will visit
+pub const MAX_FGS_OUTCOMES: usize = 1_000_000;
+
+/// Blinds and antes of the hands simulated by [`fgs`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FgsConfig {
+    pub sb: f64,
+    pub bb: f64,
+    /// Posted by every player
+    pub ante: f64,
+    /// Player on the button in the first hand
+    pub button: usize,
+    /// Hands to simulate before applying ICM
+    pub hands: usize,
+}
+
+/// Equity of each player under Future Game Simulation: the blinds and antes of the next
+/// `config.hands` hands are posted, moving the button round the players left, and ICM is applied
+/// to the stacks at the end. Each hand's pot goes to a player chosen in proportion to their
+/// stack, so short stacks are blinded down rather than their chips being valued as if they could
+/// wait forever. Players who can't cover a blind or ante post what they have and bust unless they
+/// win the pot.
+///
+/// Every player who posts may win each hand, so this visits up to `n^hands` outcomes for `n`
+/// players with chips, applying ICM to the stacks at the end of each: 10 players and 6 hands
+/// are already 10^6 outcomes. Fails if that's more than [`MAX_FGS_OUTCOMES`].
+pub fn fgs(stacks: &[f64], payouts: &[f64], config: &FgsConfig) -> Result<Vec<f64>, String> {
+    let n = stacks.iter().filter(|x| **x > 0.0).count();
+    let outcomes = u32::try_from(config.hands)
+        .ok()
+        .and_then(|hands| n.checked_pow(hands))
+        .filter(|x| *x <= MAX_FGS_OUTCOMES);
+    if outcomes.is_none() {
+        return Err(format!(
+            "{} hands of {} players have more than {} outcomes to simulate",
+            config.hands, n, MAX_FGS_OUTCOMES
+        ));
+    }
+    let mut result = vec![0.0; stacks.len()];
+    simulate(
+        stacks,
+        payouts,
+        config,
+        config.button,
+        config.hands,
+        1.0,
+        &mut result,
+    );
+    Ok(result)
+}
+
+/// First player after `from` with chips left, going round the table
+fn next_active(stacks: &[f64], from: usize) -> usize {
+    (1..=stacks.len())
+        .map(|x| (from + x) % stacks.len())
+        .find(|x| stacks[*x] > 0.0)
+        .unwrap_or(from)
+}
+
+fn simulate(
+    stacks: &[f64],
+    payouts: &[f64],
+    config: &FgsConfig,
+    button: usize,
+    hands: usize,
+    p: f64,
+    result: &mut [f64],
+) {
+    let players: Vec<usize> = (0..stacks.len()).filter(|x| stacks[*x] > 0.0).collect();
+    if hands == 0 || players.len() < 2 {
+        icm(stacks, payouts)
+            .iter()
+            .zip(result.iter_mut())
+            .for_each(|(equity, x)| *x += p * equity);
+        return;
+    }
+
+    // Heads up the button posts the small blind
+    let sb = match players.len() {
+        2 => button,
+        _ => next_active(stacks, button),
+    };
+    let bb = next_active(stacks, sb);
+    let mut posted = stacks.to_vec();
+    let mut pot = 0.0;
+    for player in &players {
+        let blind = match *player {
+            x if x == sb => config.sb,
+            x if x == bb => config.bb,
+            _ => 0.0,
+        };
+        let amount = (blind + config.ante).min(stacks[*player]);
+        posted[*player] -= amount;
+        pot += amount;
+    }
+
+    let total_chips: f64 = stacks.iter().sum();
+    for winner in &players {
+        let mut after = posted.clone();
+        after[*winner] += pot;
+        let button = next_active(&after, button);
+        let p = p * stacks[*winner] / total_chips;
+        simulate(&after, payouts, config, button, hands - 1, p, result);
+    }
+}
+
+/// ICM equities of `caller` facing an all in from `better`, where each risks the smaller of their
+/// stacks and `pot` chips are already in the middle on top of the stacks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AllInEquities {
+    /// After folding, when the better takes the pot
+    pub fold: f64,
+    /// After calling and winning
+    pub win: f64,
+    /// After calling and losing
+    pub lose: f64,
+}
+
+impl AllInEquities {
+    pub fn new(stacks: &[f64], payouts: &[f64], caller: usize, better: usize, pot: f64) -> Self {
+        let amount = stacks[caller].min(stacks[better]);
+        let equity_after = |winner: usize, loser: usize, lost: f64| {
+            let mut stacks = stacks.to_vec();
+            stacks[winner] += lost + pot;
+            stacks[loser] -= lost;
+            icm(&stacks, payouts)[caller]
+        };
+        AllInEquities {
+            fold: equity_after(better, caller, 0.),
+            win: equity_after(caller, better, amount),
+            lose: equity_after(better, caller, amount),
+        }
+    }
+
+    /// Expected equity of calling with a chance of `equity` to win the all in
+    pub fn call(&self, equity: f64) -> f64 {
+        equity * self.win + (1. - equity) * self.lose
+    }
+
+    /// Chance to win the all in above which calling is worth more than folding
+    pub fn required_equity(&self) -> f64 {
+        (self.fold - self.lose) / (self.win - self.lose)
+    }
 }
 
 fn traverse(
-    stacks: &Vec<f64>,
+    stacks: &[f64],
     place: usize,
     n_places: usize,
-    place_probabilities: &mut Vec<Vec<f64>>,
+    place_probabilities: &mut [Vec<f64>],
     p: f64,
 ) {
     let total_chips: f64 = stacks.iter().sum();
     let players: Vec<usize> = stacks
         .iter()
         .enumerate()
-        .filter(|(i, x)| **x > 0.0)
-        .map(|(i, x)| i)
+        .filter(|(_, x)| **x > 0.0)
+        .map(|(i, _)| i)
         .collect();
     let current_probabilties: Vec<f64> = stacks
         .iter()
@@ -62,7 +603,7 @@ fn traverse(
         let new_stacks: Vec<Vec<f64>> = players
             .iter()
             .map(|i| {
-                let mut result = stacks.clone();
+                let mut result = stacks.to_vec();
                 result[*i] = 0.0;
                 result
             })
@@ -75,3 +616,210 @@ fn traverse(
             });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_place_probabilities() {
+        let stacks = [10., 20., 0., 30., 40., 25., 5.];
+        for n_places in [1, 3, 7, 9] {
+            let fast = place_probabilities(&stacks, n_places);
+            let naive = place_probabilities_recursive(&stacks, n_places);
+            for (a, b) in fast.iter().flatten().zip(naive.iter().flatten()) {
+                assert!((a - b).abs() < 1e-12, "{:?} {:?}", fast, naive);
+            }
+        }
+        // Busted players finish nowhere, and every place is taken by someone
+        let result = place_probabilities(&stacks, 6);
+        assert!(result[2].iter().all(|x| *x == 0.0));
+        for place in 0..6 {
+            let total: f64 = result.iter().map(|x| x[place]).sum();
+            assert!((total - 1.).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_weitzman() {
+        // The smallest stack busts first in inverse proportion to the stacks: 1, 1/2 and 1/3
+        let result = place_probabilities_weitzman(&[1., 2., 3.], 3);
+        assert!((result[0][2] - 6. / 11.).abs() < 1e-12);
+        assert!((result[2][2] - 2. / 11.).abs() < 1e-12);
+        for place in 0..3 {
+            let total: f64 = result.iter().map(|x| x[place]).sum();
+            assert!((total - 1.).abs() < 1e-12);
+        }
+
+        // The models agree heads up and with equal stacks
+        for stacks in [vec![15., 45.], vec![20., 20., 20., 20.]] {
+            let harville = IcmModel::MalmuthHarville.icm(&stacks, &[0.7, 0.3]);
+            let weitzman = IcmModel::MalmuthWeitzman.icm(&stacks, &[0.7, 0.3]);
+            for (a, b) in harville.iter().zip(&weitzman) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+        let stacks = [10., 20., 30., 40.];
+        let payouts = [0.5, 0.3, 0.2];
+        let harville = IcmModel::MalmuthHarville.icm(&stacks, &payouts);
+        let weitzman = IcmModel::MalmuthWeitzman.icm(&stacks, &payouts);
+        assert!(harville
+            .iter()
+            .zip(&weitzman)
+            .any(|(a, b)| (a - b).abs() > 1e-3));
+        let total: f64 = weitzman.iter().sum();
+        assert!((total - 1.).abs() < 1e-12);
+        assert!(weitzman.windows(2).all(|x| x[0] < x[1]));
+        assert_eq!("mw".parse::<IcmModel>(), Ok(IcmModel::MalmuthWeitzman));
+    }
+
+    #[test]
+    fn test_many_players() {
+        // 20 players paying 10 places would be far too many finishing orders to visit
+        let stacks: Vec<f64> = (1..=20).map(|x| x as f64).collect();
+        let payouts: Vec<f64> = (1..=10).rev().map(|x| x as f64).collect();
+        let equities = icm(&stacks, &payouts);
+        let total: f64 = equities.iter().sum();
+        assert!((total - payouts.iter().sum::<f64>()).abs() < 1e-9);
+        assert!(equities.windows(2).all(|x| x[0] < x[1]));
+    }
+
+    #[test]
+    fn test_all_in_equities() {
+        // Winner takes all: ICM is the chip count, so the required equity is pot odds
+        let equities = AllInEquities::new(&[10., 20., 30.], &[1.], 0, 1, 3.);
+        assert!((equities.required_equity() - 10. / 23.).abs() < 1e-9);
+        assert!((equities.call(10. / 23.) - equities.fold).abs() < 1e-9);
+
+        // On the bubble calling off a short stack needs more than pot odds
+        let equities = AllInEquities::new(&[10., 20., 30., 40.], &[0.5, 0.3, 0.2], 0, 3, 3.);
+        assert!(equities.required_equity() > 10. / 23.);
+    }
+
+    #[test]
+    fn test_bounty_equities() {
+        // Heads up the winner takes both bounties
+        let equities = bounty_equities(&[10., 30.], &[4., 2.], PKO_CASH_SHARE).unwrap();
+        assert!((equities[0] - 0.25 * 6.).abs() < 1e-9);
+        assert!((equities[1] - 0.75 * 6.).abs() < 1e-9);
+
+        let stacks = [10., 20., 0., 30., 40.];
+        let bounties = [5., 5., 7., 5., 5.];
+        let equities = bounty_equities(&stacks, &bounties, PKO_CASH_SHARE).unwrap();
+        // Every bounty of a player still in is paid out to someone
+        let total: f64 = equities.iter().sum();
+        assert!((total - 20.).abs() < 1e-9, "{:?}", equities);
+        assert_eq!(equities[2], 0.);
+        assert!(equities[0] < equities[1] && equities[1] < equities[3]);
+        assert!(equities[3] < equities[4]);
+
+        let equities = pko_equities(&[10., 10., 10.], &[0.6, 0.4], &[1., 1., 1.]).unwrap();
+        assert!(equities.iter().all(|x| (x - 4. / 3.).abs() < 1e-9));
+
+        assert!(bounty_equities(&[1.; 25], &[1.; 25], PKO_CASH_SHARE).is_err());
+        assert!(pko_equities(&[10., 10., 10.], &[0.6, 0.4], &[1., 1.]).is_err());
+    }
+
+    #[test]
+    fn test_deal() {
+        let stacks = [50., 30., 20.];
+        let payouts = [50., 30., 20.];
+        // Everyone is guaranteed 20, and the other 40 is split by chips
+        assert_eq!(chip_chop(&stacks, &payouts), [40., 32., 28.]);
+
+        let deal = Deal::new(&stacks, &payouts, 0.25);
+        for (i, blend) in deal.blend.iter().enumerate() {
+            let expected = 0.25 * deal.icm[i] + 0.75 * deal.chip_chop[i];
+            assert!((blend - expected).abs() < 1e-9);
+        }
+        // ICM favours the short stack over chips
+        assert!(deal.icm[2] > deal.chip_chop[2]);
+        let total: f64 = deal.blend.iter().sum();
+        assert!((total - 100.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fgs() {
+        let stacks = [3., 30., 30.];
+        let payouts = [0.5, 0.3, 0.2];
+        let config = |hands| FgsConfig {
+            sb: 1.,
+            bb: 2.,
+            ante: 0.,
+            button: 2,
+            hands,
+        };
+        assert_eq!(
+            fgs(&stacks, &payouts, &config(0)).unwrap(),
+            icm(&stacks, &payouts)
+        );
+        // 3^13 outcomes is too many
+        assert!(fgs(&stacks, &payouts, &config(13)).is_err());
+        assert!(fgs(&stacks, &payouts, &config(usize::MAX)).is_err());
+
+        // The short stack is in the small blind and then the big blind
+        let equities = fgs(&stacks, &payouts, &config(2)).unwrap();
+        assert!(equities[0] < icm(&stacks, &payouts)[0]);
+        let total: f64 = equities.iter().sum();
+        assert!((total - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_equity_gradient() {
+        // Winner takes all: equity is the share of chips, so the gradient is known exactly
+        let stacks = [10., 20., 0., 30.];
+        let gradient = equity_gradient(&stacks, &[1.]);
+        for (i, row) in gradient.iter().enumerate() {
+            for j in [0, 1, 3] {
+                let kronecker = if i == j { 1. } else { 0. };
+                let expected = kronecker / 60. - stacks[i] / 3600.;
+                assert!((row[j] - expected).abs() < 1e-8, "{:?}", gradient);
+            }
+        }
+        // Busted players have no stack to change
+        assert!(gradient.iter().all(|x| x[2] == 0.));
+
+        // With a payout ladder chips are worth less the more a player has
+        let gradient = equity_gradient(&[10., 20., 30., 40.], &[0.5, 0.3, 0.2]);
+        assert!(gradient[0][0] > gradient[3][3]);
+    }
+
+    #[test]
+    fn test_bubble_factors() {
+        let stacks = [10., 20., 0., 40.];
+        let factors = BubbleFactors::new(&stacks, &[0.6, 0.4]);
+        assert_eq!(factors.factors[0][0], None);
+        assert_eq!(factors.factors[2][1], None);
+        assert_eq!(
+            factors.factors[1][3],
+            Some(bubble_factor(&stacks, &[0.6, 0.4], 1, 3))
+        );
+        assert_eq!(factors.to_string().lines().count(), 5);
+        let json = serde_json::to_string(&factors).unwrap();
+        assert!(json.contains("\"factors\":[[null,"));
+    }
+
+    #[test]
+    fn test_bubble_factor() {
+        // Winner takes all: chips are worth the same whether won or lost
+        let factor = bubble_factor(&[10., 20., 30.], &[1.], 0, 1);
+        assert!((factor - 1.).abs() < 1e-9);
+
+        // On the bubble the short stack risks more than the big stack covering it
+        let stacks = [10., 20., 30., 40.];
+        let payouts = [0.5, 0.3, 0.2];
+        assert!(bubble_factor(&stacks, &payouts, 3, 0) > 1.);
+        assert!(bubble_factor(&stacks, &payouts, 1, 2) > bubble_factor(&stacks, &payouts, 2, 1));
+    }
+
+    #[test]
+    fn test_check_tournament() {
+        assert!(check_tournament(&[10., 20.], &[0.7, 0.3]).is_ok());
+        assert!(check_tournament(&[], &[]).is_err());
+        assert!(check_tournament(&[10., 0.], &[1.]).is_ok());
+        assert!(check_tournament(&[0., 0.], &[1.]).is_err());
+        assert!(check_tournament(&[10., -1.], &[1.]).is_err());
+        assert!(check_tournament(&[10., f64::NAN], &[1.]).is_err());
+        assert!(check_tournament(&[10.], &[0.7, 0.3]).is_err());
+    }
+}
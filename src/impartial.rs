@@ -0,0 +1,292 @@
+//! Impartial games, where both players have the same moves from every position and whoever can't
+//! move loses.
+//!
+//! By the Sprague-Grundy theorem every such position is equivalent to a single Nim pile, whose size
+//! is the position's Grundy number: the smallest number not among the Grundy numbers of its
+//! options. A position is lost for the player to move exactly when its Grundy number is 0, and the
+//! Grundy number of a sum of games, where each move is made in one component, is the XOR of the
+//! components' numbers. [`Grundy`] computes these for any [`Impartial`] game, and [`Nim`] is the
+//! game itself, with its closed form solution.
+
+use crate::error::GameError;
+use crate::search::{GameState, Outcome};
+
+use std::fmt::Display;
+use std::hash::Hash;
+use std::str::FromStr;
+
+use hashbrown::HashMap;
+
+/// Position of an impartial game
+pub trait Impartial: Clone + Eq + Hash {
+    /// Positions reachable in one move, empty when the player to move has lost
+    fn options(&self) -> Vec<Self>;
+}
+
+/// Smallest natural number not in `values`
+pub fn mex(values: impl IntoIterator<Item = usize>) -> usize {
+    let mut seen = vec![];
+    for value in values {
+        if value >= seen.len() {
+            seen.resize(value + 1, false);
+        }
+        seen[value] = true;
+    }
+    seen.iter().position(|x| !x).unwrap_or(seen.len())
+}
+
+/// Grundy numbers of the positions of an impartial game, cached across calls
+pub struct Grundy<G> {
+    values: HashMap<G, usize>,
+}
+
+impl<G> Default for Grundy<G> {
+    fn default() -> Self {
+        Grundy {
+            values: HashMap::new(),
+        }
+    }
+}
+
+impl<G: Impartial> Grundy<G> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Grundy number of the position
+    pub fn value(&mut self, position: &G) -> usize {
+        if let Some(value) = self.values.get(position) {
+            return *value;
+        }
+        let options: Vec<usize> = position.options().iter().map(|x| self.value(x)).collect();
+        let value = mex(options);
+        self.values.insert(position.clone(), value);
+        value
+    }
+
+    /// Grundy number of the sum of the positions, where each move is made in one of them
+    pub fn sum(&mut self, positions: &[G]) -> usize {
+        positions.iter().fold(0, |acc, x| acc ^ self.value(x))
+    }
+}
+
+/// A pile from which one of a fixed set of counts can be removed at a time
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Subtraction {
+    pub pile: usize,
+    pub allowed: Vec<usize>,
+}
+
+impl Impartial for Subtraction {
+    fn options(&self) -> Vec<Self> {
+        self.allowed
+            .iter()
+            .filter(|x| **x > 0 && **x <= self.pile)
+            .map(|x| Subtraction {
+                pile: self.pile - x,
+                allowed: self.allowed.clone(),
+            })
+            .collect()
+    }
+}
+
+/// A move in Nim, written like `2:3` for taking 3 from pile 2
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Take {
+    pub pile: usize,
+    pub count: usize,
+}
+
+impl Display for Take {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.pile, self.count)
+    }
+}
+
+impl FromStr for Take {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (pile, count) = s
+            .split_once(':')
+            .ok_or_else(|| format!("Unknown move: {}", s))?;
+        match (pile.trim().parse(), count.trim().parse()) {
+            (Ok(pile), Ok(count)) => Ok(Take { pile, count }),
+            _ => Err(format!("Unknown move: {}", s)),
+        }
+    }
+}
+
+/// Nim: players take turns removing any number of items from a single pile, and whoever takes the
+/// last item wins
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Nim {
+    pub piles: Vec<usize>,
+    /// Index of the player to move
+    player: usize,
+}
+
+impl Nim {
+    pub fn new(piles: Vec<usize>) -> Self {
+        Nim { piles, player: 0 }
+    }
+
+    /// XOR of the pile sizes, the Grundy number of the position
+    pub fn nim_sum(&self) -> usize {
+        self.piles.iter().fold(0, |acc, x| acc ^ x)
+    }
+
+    /// A move leaving a nim sum of 0, which the opponent can't answer with another, or None if the
+    /// position is lost
+    pub fn winning_move(&self) -> Option<Take> {
+        let sum = self.nim_sum();
+        if sum == 0 {
+            return None;
+        }
+        self.piles
+            .iter()
+            .enumerate()
+            .find(|(_, x)| **x ^ sum < **x)
+            .map(|(pile, x)| Take {
+                pile,
+                count: x - (x ^ sum),
+            })
+    }
+}
+
+impl FromStr for Nim {
+    type Err = GameError;
+
+    /// Pile sizes separated by whitespace or commas, e.g. `3 4 5`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let piles = s
+            .split(|x: char| x.is_whitespace() || x == ',')
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                x.parse()
+                    .map_err(|_| GameError::InvalidBoard(format!("unknown pile {}", x)))
+            })
+            .collect::<Result<Vec<usize>, _>>()?;
+        Ok(Nim::new(piles))
+    }
+}
+
+impl Display for Nim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, pile) in self.piles.iter().enumerate() {
+            writeln!(f, "{}: {:<3} {}", i, pile, "|".repeat(*pile))?;
+        }
+        Ok(())
+    }
+}
+
+impl GameState for Nim {
+    type Move = Take;
+
+    fn current_player(&self) -> usize {
+        self.player
+    }
+
+    fn legal_moves(&self) -> Vec<Take> {
+        self.piles
+            .iter()
+            .enumerate()
+            .flat_map(|(pile, x)| (1..=*x).map(move |count| Take { pile, count }))
+            .collect()
+    }
+
+    fn play(&mut self, action: Take) -> Result<(), GameError> {
+        let pile = self
+            .piles
+            .get_mut(action.pile)
+            .ok_or(GameError::OutOfBounds(action.pile))?;
+        if action.count == 0 || action.count > *pile {
+            return Err(GameError::InvalidMove(action.pile));
+        }
+        *pile -= action.count;
+        self.player = 1 - self.player;
+        Ok(())
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.piles.iter().all(|x| *x == 0) {
+            true => Some(Outcome::Win(1 - self.player)),
+            false => None,
+        }
+    }
+}
+
+impl Impartial for Nim {
+    fn options(&self) -> Vec<Self> {
+        self.legal_moves()
+            .into_iter()
+            .map(|x| {
+                let mut next = self.clone();
+                let _ = next.play(x);
+                next
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retrograde::{Tablebase, Value};
+
+    #[test]
+    fn test_mex() {
+        assert_eq!(mex([]), 0);
+        assert_eq!(mex([0, 1, 3]), 2);
+        assert_eq!(mex([2, 1, 0, 1]), 3);
+    }
+
+    #[test]
+    fn test_grundy() {
+        // Sprague-Grundy: the Grundy number of Nim is the nim sum
+        let mut grundy = Grundy::new();
+        for piles in [vec![1], vec![3, 4, 5], vec![1, 2, 3], vec![2, 2, 6]] {
+            let nim = Nim::new(piles);
+            assert_eq!(grundy.value(&nim), nim.nim_sum());
+        }
+
+        // Taking 1, 2 or 3 from a pile has Grundy numbers repeating with period 4
+        let mut grundy = Grundy::new();
+        let pile = |pile| Subtraction {
+            pile,
+            allowed: vec![1, 2, 3],
+        };
+        for n in 0..20 {
+            assert_eq!(grundy.value(&pile(n)), n % 4);
+        }
+        assert_eq!(grundy.sum(&[pile(5), pile(6)]), 1 ^ 2);
+        assert_eq!(grundy.sum(&[pile(5), pile(9)]), 0);
+    }
+
+    #[test]
+    fn test_nim() {
+        let mut nim: Nim = "3, 4 5".parse().unwrap();
+        assert_eq!(nim.piles, vec![3, 4, 5]);
+        assert!("3 x".parse::<Nim>().is_err());
+        assert_eq!("2:3".parse(), Ok(Take { pile: 2, count: 3 }));
+
+        let take = nim.winning_move().unwrap();
+        nim.play(take).unwrap();
+        assert_eq!(nim.nim_sum(), 0);
+        assert_eq!(nim.winning_move(), None);
+        assert_eq!(
+            nim.play(Take { pile: 3, count: 1 }),
+            Err(GameError::OutOfBounds(3))
+        );
+        assert_eq!(
+            nim.play(Take { pile: 0, count: 9 }),
+            Err(GameError::InvalidMove(0))
+        );
+
+        // Positions are won exactly when the nim sum isn't 0
+        let tablebase = Tablebase::build(&Nim::new(vec![2, 3, 4]));
+        for (nim, value) in tablebase.iter() {
+            assert_eq!(matches!(value, Value::Win(_)), nim.nim_sum() != 0);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Leduc Hold'em, a standard CFR benchmark: bigger than Kuhn poker but small enough to solve in
+//! seconds.
+//!
+//! The deck has two suits of three ranks, J, Q and K. Both players ante and are dealt one private
+//! card, then bet in a round of limit betting. A public card is dealt and a second betting round
+//! follows with bets twice as large. At showdown a player pairing the public card wins, and
+//! otherwise the higher card wins. The first player acts first in both rounds.
+//!
+//! States are every ordered pair of distinct private cards, by the first player's card then the
+//! second's, with cards `0..6` of rank `card / 2`. The public card is dealt by a chance node with
+//! one outcome per rank, so each player's infosets in either round are the ranks they hold, from
+//! J to K. Nodes are named by the history of the hand: `x` check, `b` bet, `r` raise, `c` call,
+//! `f` fold, and the rank of the public card between the rounds.
+
+use crate::cfr::{Float, Node, TreeBuilder, TreeError};
+
+use ndarray::*;
+
+/// Rank names, indexed by rank
+pub const RANKS: [&str; 3] = ["J", "Q", "K"];
+/// Cards of each rank in the deck
+const SUITS: usize = 2;
+
+/// Betting structure of the game
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeducConfig {
+    /// Paid by both players before the deal
+    pub ante: Float,
+    /// Size of every bet and raise in each round
+    pub bets: [Float; 2],
+    /// Bets allowed per round, counting the opening bet, so 2 allows one raise
+    pub max_bets: usize,
+}
+
+impl Default for LeducConfig {
+    fn default() -> Self {
+        LeducConfig {
+            ante: 1.,
+            bets: [2., 4.],
+            max_bets: 2,
+        }
+    }
+}
+
+/// Private cards of each state
+pub fn states() -> Vec<(usize, usize)> {
+    let n_cards = RANKS.len() * SUITS;
+    (0..n_cards)
+        .flat_map(|a| (0..n_cards).filter(move |b| *b != a).map(move |b| (a, b)))
+        .collect()
+}
+
+/// Builds the game tree with the given betting structure
+pub fn build_leduc_tree(config: &LeducConfig) -> Result<Box<dyn Node>, TreeError> {
+    let states = states();
+    let infosets = |player: usize| -> Vec<Vec<usize>> {
+        (0..RANKS.len())
+            .map(|rank| {
+                (0..states.len())
+                    .filter(|i| [states[*i].0, states[*i].1][player] / SUITS == rank)
+                    .collect()
+            })
+            .collect()
+    };
+    let builder = Builder {
+        config,
+        infosets: [infosets(0), infosets(1)],
+        states,
+    };
+    builder
+        .betting(String::new(), None, 0, 0, [config.ante; 2])
+        .build()
+}
+
+struct Builder<'a> {
+    config: &'a LeducConfig,
+    states: Vec<(usize, usize)>,
+    infosets: [Vec<Vec<usize>>; 2],
+}
+
+impl Builder<'_> {
+    /// Action node of a betting round, `bets` bets into it, with `contributions` put in so far.
+    /// `public` is the rank of the public card once it is dealt, i.e. in the second round.
+    fn betting(
+        &self,
+        history: String,
+        public: Option<usize>,
+        player: usize,
+        bets: usize,
+        contributions: [Float; 2],
+    ) -> TreeBuilder {
+        let name = if history.is_empty() { "root" } else { &history };
+        let node = TreeBuilder::action(name, player).infosets(self.infosets[player].clone());
+        let bet = self.config.bets[public.is_some() as usize];
+        let other = 1 - player;
+        let facing = contributions[other] - contributions[player];
+
+        if facing > 0. {
+            let mut node = node;
+            if bets < self.config.max_bets {
+                let mut raised = contributions;
+                raised[player] += facing + bet;
+                node = node.child(self.betting(
+                    history.clone() + "r",
+                    public,
+                    other,
+                    bets + 1,
+                    raised,
+                ));
+            }
+            let mut called = contributions;
+            called[player] += facing;
+            self.round_over(node, history.clone() + "c", public, called)
+                .zero_sum(&(history + "f"), self.fold(player, contributions))
+        } else {
+            let mut bet_into = contributions;
+            bet_into[player] += bet;
+            let node =
+                node.child(self.betting(history.clone() + "b", public, other, bets + 1, bet_into));
+            // The round ends when the second player checks behind
+            if player == 0 {
+                node.child(self.betting(history + "x", public, other, bets, contributions))
+            } else {
+                self.round_over(node, history + "x", public, contributions)
+            }
+        }
+    }
+
+    /// Appends to `node` the deal of the public card after the first round, or the showdown
+    /// after the second
+    fn round_over(
+        &self,
+        node: TreeBuilder,
+        history: String,
+        public: Option<usize>,
+        contributions: [Float; 2],
+    ) -> TreeBuilder {
+        if let Some(public) = public {
+            return node.zero_sum(&history, self.showdown(public, contributions[0]));
+        }
+        // Each rank has SUITS cards, less those dealt to the players
+        let probabilities = Array::from_shape_fn((RANKS.len(), self.states.len()), |(rank, i)| {
+            let (a, b) = self.states[i];
+            let dealt = (a / SUITS == rank) as usize + (b / SUITS == rank) as usize;
+            (SUITS - dealt) as Float / (RANKS.len() * SUITS - 2) as Float
+        });
+        let deal = RANKS.iter().enumerate().fold(
+            TreeBuilder::chance(&history).outcome_probabilities(probabilities),
+            |deal, (rank, name)| {
+                deal.child(self.betting(history.clone() + name, Some(rank), 0, 0, contributions))
+            },
+        );
+        node.child(deal)
+    }
+
+    /// First player's payouts when `player` folds
+    fn fold(&self, player: usize, contributions: [Float; 2]) -> Array<Float, Ix1> {
+        let payout = if player == 0 {
+            -contributions[0]
+        } else {
+            contributions[1]
+        };
+        Array::from_elem(self.states.len(), payout)
+    }
+
+    /// First player's payouts at showdown when each player has put in `pot`
+    fn showdown(&self, public: usize, pot: Float) -> Array<Float, Ix1> {
+        // Pairs beat any unpaired card
+        let strength = |card: usize| {
+            let rank = card / SUITS;
+            if rank == public {
+                RANKS.len()
+            } else {
+                rank
+            }
+        };
+        self.states
+            .iter()
+            .map(|(a, b)| match strength(*a).cmp(&strength(*b)) {
+                std::cmp::Ordering::Greater => pot,
+                std::cmp::Ordering::Less => -pot,
+                std::cmp::Ordering::Equal => 0.,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::*;
+
+    #[test]
+    fn test_leduc() {
+        let root = build_leduc_tree(&LeducConfig::default()).unwrap();
+        assert_eq!(root.state_probabilities().len(), 30);
+        assert_eq!(root.children().unwrap().len(), 2);
+
+        let config = SolverConfig {
+            variant: CfrVariant::CfrPlus,
+            max_iterations: 1000,
+            ..Default::default()
+        };
+        let mut solver = CfrSolver::new(root, config);
+        let result = solver.solve();
+        assert!(result.exploitability < 1e-2, "{}", result.exploitability);
+
+        // The game is worth about -0.0856 to the first player
+        let root = &solver.root;
+        let reach = root.state_probabilities().to_owned();
+        let value = root.avg_strategy_payouts().dot(&reach)[0];
+        assert!((value + 0.0856).abs() < 0.01, "{}", value);
+    }
+}
@@ -0,0 +1,193 @@
+//! Liar's Dice (dudo), a classic imperfect information benchmark with many infosets per node.
+//!
+//! Both players roll the same number of dice in secret. Starting with the first player, they take
+//! turns bidding on how many of all the dice show some face, each bid raising the quantity or, at
+//! the same quantity, the face. Instead of raising, a player may call the last bid a lie (dudo):
+//! the dice are revealed, and the caller wins a unit if fewer dice than bid show the face and
+//! loses one otherwise. No face is wild.
+//!
+//! States are every ordered pair of rolls, by the first player's roll then the second's, weighted
+//! by how likely the rolls are. A roll is the number of dice showing each face, since the order
+//! of the dice doesn't matter, and each player's infosets in every node are the rolls they can
+//! see. Nodes are named by the bids so far separated by commas, e.g. `2x3` for two threes, and
+//! calls by `dudo`.
+
+use crate::cfr::{Float, Node, TreeBuilder, TreeError};
+
+use ndarray::*;
+
+/// Size of the game
+#[derive(Clone, Debug, PartialEq)]
+pub struct LiarsDiceConfig {
+    /// Dice rolled by each player
+    pub dice: usize,
+    /// Faces of each die, numbered from 1
+    pub faces: usize,
+}
+
+impl Default for LiarsDiceConfig {
+    fn default() -> Self {
+        LiarsDiceConfig { dice: 1, faces: 6 }
+    }
+}
+
+impl LiarsDiceConfig {
+    /// Number of distinct bids, ordered by quantity then face
+    pub fn n_bids(&self) -> usize {
+        2 * self.dice * self.faces
+    }
+
+    /// Quantity and face of the bid with the given index
+    pub fn bid(&self, index: usize) -> (usize, usize) {
+        (index / self.faces + 1, index % self.faces + 1)
+    }
+}
+
+/// Every roll of one player, as the number of dice showing each face
+pub fn rolls(config: &LiarsDiceConfig) -> Vec<Vec<usize>> {
+    fn compositions(total: usize, parts: usize) -> Vec<Vec<usize>> {
+        if parts == 1 {
+            return vec![vec![total]];
+        }
+        (0..=total)
+            .rev()
+            .flat_map(|first| {
+                compositions(total - first, parts - 1)
+                    .into_iter()
+                    .map(move |rest| [vec![first], rest].concat())
+            })
+            .collect()
+    }
+    compositions(config.dice, config.faces)
+}
+
+/// Dice of a roll in ascending order, e.g. `113` for two ones and a three
+pub fn roll_name(roll: &[usize]) -> String {
+    roll.iter()
+        .enumerate()
+        .flat_map(|(face, count)| std::iter::repeat_n((face + 1).to_string(), *count))
+        .collect()
+}
+
+/// Probability of rolling `roll` with fair dice
+fn roll_probability(roll: &[usize]) -> Float {
+    let factorial = |n: usize| (1..=n).map(|x| x as Float).product::<Float>();
+    let dice: usize = roll.iter().sum();
+    let arrangements = factorial(dice) / roll.iter().map(|x| factorial(*x)).product::<Float>();
+    arrangements / (roll.len() as Float).powi(dice as i32)
+}
+
+/// Builds the game tree. The number of nodes doubles with every bid, so only small games with
+/// up to a dozen or so bids are practical.
+pub fn build_liars_dice_tree(config: &LiarsDiceConfig) -> Result<Box<dyn Node>, TreeError> {
+    let rolls = rolls(config);
+    let n = rolls.len();
+    let infosets: [Vec<Vec<usize>>; 2] = [
+        (0..n)
+            .map(|a| (0..n).map(|b| a * n + b).collect())
+            .collect(),
+        (0..n)
+            .map(|b| (0..n).map(|a| a * n + b).collect())
+            .collect(),
+    ];
+    let state_probabilities = Array::from_shape_fn(n * n, |i| {
+        roll_probability(&rolls[i / n]) * roll_probability(&rolls[i % n])
+    });
+    let counts: Vec<Vec<usize>> = (0..n * n)
+        .map(|i| {
+            let (a, b) = (&rolls[i / n], &rolls[i % n]);
+            a.iter().zip(b).map(|(x, y)| x + y).collect()
+        })
+        .collect();
+    let builder = Builder {
+        config,
+        infosets,
+        counts,
+    };
+    builder
+        .bidding("", None, 0)
+        .state_probabilities(state_probabilities)
+        .build()
+}
+
+struct Builder<'a> {
+    config: &'a LiarsDiceConfig,
+    infosets: [Vec<Vec<usize>>; 2],
+    /// Dice showing each face in each state, indexed by state, face
+    counts: Vec<Vec<usize>>,
+}
+
+impl Builder<'_> {
+    /// Action node where `player` raises `last`, the index of the last bid, or calls it a lie
+    fn bidding(&self, history: &str, last: Option<usize>, player: usize) -> TreeBuilder {
+        let name = if history.is_empty() { "root" } else { history };
+        let node = TreeBuilder::action(name, player).infosets(self.infosets[player].clone());
+        let first = last.map_or(0, |x| x + 1);
+        let node = (first..self.config.n_bids()).fold(node, |node, bid| {
+            let (quantity, face) = self.config.bid(bid);
+            let history = if history.is_empty() {
+                format!("{}x{}", quantity, face)
+            } else {
+                format!("{},{}x{}", history, quantity, face)
+            };
+            node.child(self.bidding(&history, Some(bid), 1 - player))
+        });
+        match last {
+            Some(bid) => node.zero_sum(&format!("{},dudo", history), self.dudo(player, bid)),
+            None => node,
+        }
+    }
+
+    /// First player's payouts when `player` calls the bid with index `bid` a lie
+    fn dudo(&self, player: usize, bid: usize) -> Array<Float, Ix1> {
+        let (quantity, face) = self.config.bid(bid);
+        self.counts
+            .iter()
+            .map(|counts| {
+                let caller = if counts[face - 1] < quantity { 1. } else { -1. };
+                if player == 0 {
+                    caller
+                } else {
+                    -caller
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::*;
+
+    #[test]
+    fn test_rolls() {
+        let config = LiarsDiceConfig { dice: 2, faces: 6 };
+        let rolls = rolls(&config);
+        assert_eq!(rolls.len(), 21);
+        let total: Float = rolls.iter().map(|x| roll_probability(x)).sum();
+        assert!((total - 1.).abs() < 1e-6);
+        assert_eq!(roll_name(&[1, 0, 1, 0, 0, 0]), "13");
+        assert_eq!(config.bid(7), (2, 2));
+    }
+
+    #[test]
+    fn test_liars_dice() {
+        let config = LiarsDiceConfig { dice: 1, faces: 3 };
+        let root = build_liars_dice_tree(&config).unwrap();
+        assert_eq!(root.state_probabilities().len(), 9);
+        // No call before the first bid
+        assert_eq!(root.children().unwrap().len(), 6);
+
+        let mut solver = CfrSolver::new(
+            root,
+            SolverConfig {
+                variant: CfrVariant::CfrPlus,
+                max_iterations: 5000,
+                ..Default::default()
+            },
+        );
+        let result = solver.solve();
+        assert!(result.exploitability < 5e-3, "{}", result.exploitability);
+    }
+}
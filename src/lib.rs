@@ -0,0 +1,51 @@
+//! Solution concepts (minimax, Nash) for some toy games. The binaries in `src/bin` are thin
+//! command line wrappers around these modules.
+
+pub mod agent;
+pub mod arena;
+pub mod auction;
+pub mod backward_induction;
+pub mod bargaining;
+pub mod bayesian;
+pub mod cfr;
+pub mod charts;
+pub mod congestion;
+pub mod connect4;
+pub mod dots_and_boxes;
+pub mod error;
+pub mod fictitious_play;
+pub mod goofspiel;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod hex;
+pub mod icm;
+pub mod impartial;
+pub mod leduc;
+pub mod liars_dice;
+pub mod logging;
+pub mod matching;
+pub mod matrix_game;
+pub mod mcts;
+pub mod normal_form;
+#[cfg(feature = "fs")]
+pub mod poker;
+pub mod repeated;
+pub mod retrograde;
+pub mod search;
+pub mod sequence_form;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod symmetry;
+#[cfg(feature = "fs")]
+pub mod tables;
+pub mod tictactoe;
+#[cfg(feature = "fs")]
+pub mod transposition;
+pub mod ultimate_tictactoe;
+pub mod voting;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+mod parallel;
+#[cfg(feature = "fs")]
+mod utils;
@@ -0,0 +1,74 @@
+//! Logging setup shared by the binaries. Diagnostics (tree builds, solver progress, equity table
+//! progress) are `tracing` events written to stderr, so stdout only carries a binary's results:
+//!
+//! - `-v` adds exploitability at every check of a CFR solve that measures it, `-vv` every iteration
+//! - `-q` keeps only warnings, `-qq` only errors, `-qqq` nothing
+//! - `--log-json` writes one JSON object per event instead of plain text
+
+use clap::{ArgAction, Args};
+use std::io::IsTerminal;
+use tracing::level_filters::LevelFilter;
+
+// Verbosity flags, flattened into each binary's arguments with `#[command(flatten)]`. Not a doc
+// comment, since clap would take it as the about text of every binary.
+#[derive(Args, Clone, Debug, Default)]
+pub struct LogArgs {
+    #[arg(short, long, action = ArgAction::Count, global = true, help = "More log output, repeat for more")]
+    pub verbose: u8,
+
+    #[arg(short, long, action = ArgAction::Count, global = true, conflicts_with = "verbose", help = "Less log output, repeat for less")]
+    pub quiet: u8,
+
+    #[arg(long, global = true, help = "Write log events as JSON lines")]
+    pub log_json: bool,
+}
+
+impl LogArgs {
+    /// Most verbose level logged: info by default, raised by `-v` and lowered by `-q`
+    pub fn level(&self) -> LevelFilter {
+        match self.verbose as i16 - self.quiet as i16 {
+            i16::MIN..=-3 => LevelFilter::OFF,
+            -2 => LevelFilter::ERROR,
+            -1 => LevelFilter::WARN,
+            0 => LevelFilter::INFO,
+            1 => LevelFilter::DEBUG,
+            _ => LevelFilter::TRACE,
+        }
+    }
+
+    /// Installs the global subscriber. Call once, at the start of `main`.
+    pub fn init(&self) {
+        let builder = tracing_subscriber::fmt()
+            .with_max_level(self.level())
+            .with_ansi(std::io::stderr().is_terminal())
+            .with_writer(std::io::stderr);
+        match self.log_json {
+            true => builder.json().init(),
+            false => builder.init(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        log: LogArgs,
+    }
+
+    #[test]
+    fn test_level() {
+        let level = |args: &[&str]| Cli::try_parse_from(args).map(|x| x.log.level());
+        assert_eq!(level(&["cli"]).unwrap(), LevelFilter::INFO);
+        assert_eq!(level(&["cli", "-vv"]).unwrap(), LevelFilter::TRACE);
+        assert_eq!(level(&["cli", "-vvvv"]).unwrap(), LevelFilter::TRACE);
+        assert_eq!(level(&["cli", "-q"]).unwrap(), LevelFilter::WARN);
+        assert_eq!(level(&["cli", "-qqqq"]).unwrap(), LevelFilter::OFF);
+        assert!(level(&["cli", "-v", "-q"]).is_err());
+    }
+}
@@ -0,0 +1,346 @@
+//! Matching markets: two-sided matching by Gale-Shapley deferred acceptance, and the housing
+//! market by top trading cycles.
+//!
+//! In deferred acceptance, unmatched proposers propose to their favourite receiver they haven't
+//! yet been rejected by, and each receiver holds on to the best proposal so far, rejecting the
+//! rest. The result is stable, with no proposer and receiver preferring each other to their
+//! partners, and every proposer does at least as well in it as in any other stable matching
+//! while every receiver does at least as badly. Running it with the sides swapped gives the
+//! other extreme, and the stable matching is unique exactly when the two agree.
+//!
+//! In the housing market, each agent owns a house and ranks the houses. Top trading cycles
+//! repeatedly has every remaining agent point at the owner of their favourite remaining house
+//! and trades along the cycles that form; no coalition can do better by trading among itself,
+//! and no agent gains by misreporting their preferences.
+
+use std::fmt::Display;
+
+/// Each proposer's and receiver's acceptable partners, most preferred first; anyone left out is
+/// worse than staying single
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarriageMarket {
+    pub proposers: Vec<Vec<usize>>,
+    pub receivers: Vec<Vec<usize>>,
+}
+
+/// Receiver matched to each proposer, if any
+pub type Matching = Vec<Option<usize>>;
+
+/// Where deferred acceptance stopped
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeferredAcceptance {
+    pub matching: Matching,
+    /// Number of proposals made
+    pub proposals: usize,
+}
+
+/// The two extreme stable matchings, and how each side ranks its partners in them
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptimalityReport {
+    pub proposer_optimal: Matching,
+    pub receiver_optimal: Matching,
+    /// Rank of each proposer's partner in their preferences (0 for their favourite) in the
+    /// proposer-optimal and receiver-optimal matchings, None if single
+    pub proposer_ranks: [Vec<Option<usize>>; 2],
+    /// Same for the receivers
+    pub receiver_ranks: [Vec<Option<usize>>; 2],
+}
+
+impl OptimalityReport {
+    /// Whether the market has only one stable matching
+    pub fn unique(&self) -> bool {
+        self.proposer_optimal == self.receiver_optimal
+    }
+
+    /// Proposers strictly better off when they propose
+    pub fn proposers_gaining(&self) -> Vec<usize> {
+        gaining(&self.proposer_ranks[0], &self.proposer_ranks[1])
+    }
+
+    /// Receivers strictly better off when they propose
+    pub fn receivers_gaining(&self) -> Vec<usize> {
+        gaining(&self.receiver_ranks[1], &self.receiver_ranks[0])
+    }
+}
+
+/// Agents whose rank in `better` is smaller than in `worse`, being single ranking last
+fn gaining(better: &[Option<usize>], worse: &[Option<usize>]) -> Vec<usize> {
+    let key = |x: Option<usize>| x.unwrap_or(usize::MAX);
+    (0..better.len())
+        .filter(|x| key(better[*x]) < key(worse[*x]))
+        .collect()
+}
+
+impl Display for OptimalityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rank = |x: Option<usize>| match x {
+            Some(x) => (x + 1).to_string(),
+            None => "-".to_string(),
+        };
+        for (side, ranks) in [
+            ("Proposer", &self.proposer_ranks),
+            ("Receiver", &self.receiver_ranks),
+        ] {
+            for (agent, (first, second)) in ranks[0].iter().zip(&ranks[1]).enumerate() {
+                writeln!(
+                    f,
+                    "{} {:>3}: choice {:>3} when proposers propose, {:>3} when receivers do",
+                    side,
+                    agent,
+                    rank(*first),
+                    rank(*second)
+                )?;
+            }
+        }
+        write!(
+            f,
+            "{} stable matching",
+            match self.unique() {
+                true => "Unique",
+                false => "More than one",
+            }
+        )
+    }
+}
+
+impl MarriageMarket {
+    pub fn new(proposers: Vec<Vec<usize>>, receivers: Vec<Vec<usize>>) -> Self {
+        MarriageMarket {
+            proposers,
+            receivers,
+        }
+    }
+
+    /// Same market with the roles of proposers and receivers swapped
+    pub fn reversed(&self) -> Self {
+        MarriageMarket::new(self.receivers.clone(), self.proposers.clone())
+    }
+
+    /// Stable matching best for every proposer
+    pub fn deferred_acceptance(&self) -> DeferredAcceptance {
+        // Rank of each proposer in each receiver's preferences, None if unacceptable
+        let ranks = ranks(&self.receivers, self.proposers.len());
+        let mut held: Vec<Option<usize>> = vec![None; self.receivers.len()];
+        let mut next = vec![0; self.proposers.len()];
+        let mut free: Vec<usize> = (0..self.proposers.len()).rev().collect();
+        let mut proposals = 0;
+        while let Some(proposer) = free.pop() {
+            let Some(&receiver) = self.proposers[proposer].get(next[proposer]) else {
+                continue;
+            };
+            next[proposer] += 1;
+            proposals += 1;
+            let Some(rank) = ranks[receiver][proposer] else {
+                free.push(proposer);
+                continue;
+            };
+            match held[receiver] {
+                Some(current) if ranks[receiver][current] < Some(rank) => free.push(proposer),
+                current => {
+                    free.extend(current);
+                    held[receiver] = Some(proposer);
+                }
+            }
+        }
+        let mut matching = vec![None; self.proposers.len()];
+        for (receiver, proposer) in held.iter().enumerate() {
+            if let Some(proposer) = proposer {
+                matching[*proposer] = Some(receiver);
+            }
+        }
+        DeferredAcceptance {
+            matching,
+            proposals,
+        }
+    }
+
+    /// Proposer matched to each receiver
+    pub fn partners(&self, matching: &[Option<usize>]) -> Matching {
+        let mut result = vec![None; self.receivers.len()];
+        for (proposer, receiver) in matching.iter().enumerate() {
+            if let Some(receiver) = receiver {
+                result[*receiver] = Some(proposer);
+            }
+        }
+        result
+    }
+
+    /// Pairs of a proposer and a receiver who both prefer each other to their partners
+    pub fn blocking_pairs(&self, matching: &[Option<usize>]) -> Vec<(usize, usize)> {
+        let partners = self.partners(matching);
+        let prefers = |preferences: &Vec<usize>, x: usize, current: Option<usize>| {
+            let rank = |y: usize| preferences.iter().position(|z| *z == y);
+            match (rank(x), current) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(new), Some(current)) => rank(current).is_none_or(|current| new < current),
+            }
+        };
+        let mut result = Vec::new();
+        for (proposer, preferences) in self.proposers.iter().enumerate() {
+            for receiver in preferences {
+                if prefers(preferences, *receiver, matching[proposer])
+                    && prefers(&self.receivers[*receiver], proposer, partners[*receiver])
+                {
+                    result.push((proposer, *receiver));
+                }
+            }
+        }
+        result
+    }
+
+    pub fn is_stable(&self, matching: &[Option<usize>]) -> bool {
+        self.blocking_pairs(matching).is_empty()
+    }
+
+    /// Runs deferred acceptance with each side proposing, and compares how everyone fares
+    pub fn optimality_report(&self) -> OptimalityReport {
+        let proposer_optimal = self.deferred_acceptance().matching;
+        let reversed = self.reversed().deferred_acceptance().matching;
+        let receiver_optimal = self.reversed().partners(&reversed);
+        let rank = |preferences: &Vec<Vec<usize>>, matching: &[Option<usize>]| {
+            matching
+                .iter()
+                .enumerate()
+                .map(|(x, y)| y.and_then(|y| preferences[x].iter().position(|z| *z == y)))
+                .collect::<Vec<_>>()
+        };
+        OptimalityReport {
+            proposer_ranks: [
+                rank(&self.proposers, &proposer_optimal),
+                rank(&self.proposers, &receiver_optimal),
+            ],
+            receiver_ranks: [
+                rank(&self.receivers, &self.partners(&proposer_optimal)),
+                rank(&self.receivers, &reversed),
+            ],
+            proposer_optimal,
+            receiver_optimal,
+        }
+    }
+}
+
+/// Rank of each of `n` agents in each list of preferences, None where left out
+fn ranks(preferences: &[Vec<usize>], n: usize) -> Vec<Vec<Option<usize>>> {
+    preferences
+        .iter()
+        .map(|list| {
+            let mut result = vec![None; n];
+            for (rank, x) in list.iter().enumerate() {
+                result[*x] = Some(rank);
+            }
+            result
+        })
+        .collect()
+}
+
+/// Houses allocated by top trading cycles
+#[derive(Clone, Debug, PartialEq)]
+pub struct TradingCycles {
+    /// House of each agent
+    pub allocation: Vec<usize>,
+    /// Agents trading in each cycle, in the order the cycles formed, each getting the next one's
+    /// house
+    pub cycles: Vec<Vec<usize>>,
+}
+
+/// Agent `i` owns house `i`, and ranks the houses most preferred first; houses left out of a
+/// ranking are worse than the agent's own
+pub fn top_trading_cycles(preferences: &[Vec<usize>]) -> TradingCycles {
+    let n = preferences.len();
+    let mut allocation: Vec<Option<usize>> = vec![None; n];
+    let mut cycles = Vec::new();
+    while allocation.iter().any(Option::is_none) {
+        // Owner of each remaining agent's favourite remaining house, keeping their own if every
+        // house they prefer is gone
+        let points = |x: usize| {
+            preferences[x]
+                .iter()
+                .copied()
+                .take_while(|y| *y != x)
+                .find(|y| allocation[*y].is_none())
+                .unwrap_or(x)
+        };
+        // Following the pointers from any remaining agent must end in a cycle
+        let start = (0..n).find(|x| allocation[*x].is_none()).unwrap();
+        let mut path = vec![start];
+        let cycle = loop {
+            let next = points(*path.last().unwrap());
+            if let Some(position) = path.iter().position(|x| *x == next) {
+                break path.split_off(position);
+            }
+            path.push(next);
+        };
+        let houses: Vec<usize> = cycle.iter().map(|x| points(*x)).collect();
+        for (agent, house) in cycle.iter().zip(houses) {
+            allocation[*agent] = Some(house);
+        }
+        cycles.push(cycle);
+    }
+    TradingCycles {
+        allocation: allocation.into_iter().map(Option::unwrap).collect(),
+        cycles,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deferred_acceptance() {
+        // Every proposer's favourite is a different receiver, whose favourite is a different
+        // proposer, so the two sides' optimal matchings differ
+        let market = MarriageMarket::new(
+            vec![vec![0, 1, 2], vec![1, 2, 0], vec![2, 0, 1]],
+            vec![vec![1, 2, 0], vec![2, 0, 1], vec![0, 1, 2]],
+        );
+        let result = market.deferred_acceptance();
+        assert_eq!(result.matching, vec![Some(0), Some(1), Some(2)]);
+        assert_eq!(result.proposals, 3);
+        assert!(market.is_stable(&result.matching));
+
+        let report = market.optimality_report();
+        assert!(!report.unique());
+        assert_eq!(report.receiver_optimal, vec![Some(2), Some(0), Some(1)]);
+        assert!(market.is_stable(&report.receiver_optimal));
+        assert_eq!(report.proposer_ranks[0], vec![Some(0); 3]);
+        assert_eq!(report.proposer_ranks[1], vec![Some(2); 3]);
+        assert_eq!(report.receiver_ranks[1], vec![Some(0); 3]);
+        assert_eq!(report.proposers_gaining(), vec![0, 1, 2]);
+        assert_eq!(report.receivers_gaining(), vec![0, 1, 2]);
+
+        // Proposer 2 and receiver 0 prefer each other to their partners here
+        let blocked = vec![Some(0), Some(2), Some(1)];
+        assert_eq!(market.blocking_pairs(&blocked), vec![(2, 0)]);
+    }
+
+    #[test]
+    fn test_unacceptable_partners() {
+        // Receiver 0 would rather stay single than match proposer 1, who gets no one
+        let market = MarriageMarket::new(vec![vec![0], vec![0, 1]], vec![vec![0], vec![0]]);
+        let report = market.optimality_report();
+        assert_eq!(report.proposer_optimal, vec![Some(0), None]);
+        assert!(report.unique());
+        assert_eq!(report.receiver_ranks[0], vec![Some(0), None]);
+        assert!(market.is_stable(&report.proposer_optimal));
+        assert_eq!(market.blocking_pairs(&[None, None]), vec![(0, 0)]);
+        assert!(report.to_string().ends_with("Unique stable matching"));
+    }
+
+    #[test]
+    fn test_top_trading_cycles() {
+        // Agents 0 and 1 swap, then so do 2 and 3 once their favourites are gone, and 4 keeps
+        // their own house
+        let preferences = vec![
+            vec![1, 0],
+            vec![0, 2],
+            vec![1, 3, 2],
+            vec![0, 2, 3],
+            vec![4, 0],
+        ];
+        let result = top_trading_cycles(&preferences);
+        assert_eq!(result.cycles, vec![vec![0, 1], vec![2, 3], vec![4]]);
+        assert_eq!(result.allocation, vec![1, 0, 3, 2, 4]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Two player normal-form (matrix) games as one-shot CFR trees, for sanity checking CFR on games
+//! with known mixed equilibria such as rock-paper-scissors.
+//!
+//! The first player acts at the root and the second player in each of its children, with the
+//! first player's action [hidden](TreeBuilder::hidden) so that both choose at the same time. There
+//! is a single state, so every node has a single infoset.
+
+use crate::cfr::{Float, Node, TreeBuilder, TreeError};
+
+use std::str::FromStr;
+
+use ndarray::*;
+
+/// Payouts and action names of a two player normal-form game
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatrixGame {
+    /// Indexed by player, the first player's action, the second's
+    pub payouts: Array<Float, Ix3>,
+    /// Action names of each player, which name the nodes of the tree
+    pub actions: [Vec<String>; 2],
+}
+
+impl MatrixGame {
+    /// Zero-sum game given the first player's payouts, with actions named by their index
+    pub fn zero_sum(payouts: Array<Float, Ix2>) -> Self {
+        let (rows, columns) = payouts.dim();
+        let names = |n: usize| -> Vec<String> { (0..n).map(|x| x.to_string()).collect() };
+        MatrixGame {
+            payouts: stack![Axis(0), payouts, -&payouts],
+            actions: [names(rows), names(columns)],
+        }
+    }
+
+    /// Rock-paper-scissors, where both players play each action a third of the time
+    pub fn rock_paper_scissors() -> Self {
+        Self::zero_sum(array![[0., -1., 1.], [1., 0., -1.], [-1., 1., 0.]]).with_rps_names()
+    }
+
+    /// Rock-paper-scissors where winning with rock against scissors pays 10, so both players play
+    /// paper 10/12 of the time and the others 1/12 each
+    pub fn biased_rock_paper_scissors() -> Self {
+        Self::zero_sum(array![[0., -1., 10.], [1., 0., -1.], [-10., 1., 0.]]).with_rps_names()
+    }
+
+    fn with_rps_names(mut self) -> Self {
+        let names: Vec<String> = ["rock", "paper", "scissors"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        self.actions = [names.clone(), names];
+        self
+    }
+
+    /// Builds the one-shot game tree
+    pub fn build_tree(&self) -> Result<Box<dyn Node>, TreeError> {
+        let (_, rows, columns) = self.payouts.dim();
+        let expected = [2, self.actions[0].len(), self.actions[1].len()];
+        if self.payouts.shape() != expected {
+            return Err(TreeError::ShapeMismatch {
+                path: "root".to_string(),
+                expected: expected.to_vec(),
+                found: self.payouts.shape().to_vec(),
+            });
+        }
+        (0..rows)
+            .fold(TreeBuilder::action("root", 0).hidden(), |root, row| {
+                let node = (0..columns).fold(
+                    TreeBuilder::action(&self.actions[0][row], 1),
+                    |node, column| {
+                        let payouts = self.payouts.slice(s![.., row, column..column + 1]);
+                        node.terminal(&self.actions[1][column], payouts.to_owned())
+                    },
+                );
+                root.child(node)
+            })
+            .build()
+    }
+}
+
+impl FromStr for MatrixGame {
+    type Err = String;
+
+    /// Parses `rps`, `biased-rps`, or the first player's payouts in a zero-sum game, with rows
+    /// separated by `;` and columns by `,`, e.g. `1,-1;-1,1` for matching pennies
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rps" => return Ok(MatrixGame::rock_paper_scissors()),
+            "biased-rps" => return Ok(MatrixGame::biased_rock_paper_scissors()),
+            _ => {}
+        }
+        let rows = s
+            .split(';')
+            .map(|row| {
+                row.split(',')
+                    .map(|x| x.trim().parse::<Float>())
+                    .collect::<Result<Vec<Float>, _>>()
+            })
+            .collect::<Result<Vec<Vec<Float>>, _>>()
+            .map_err(|_| format!("Invalid payout matrix: {}", s))?;
+        let columns = rows[0].len();
+        if rows.iter().any(|x| x.len() != columns) {
+            return Err(format!("Rows of different lengths: {}", s));
+        }
+        let payouts = Array::from_shape_vec((rows.len(), columns), rows.concat())
+            .map_err(|e| e.to_string())?;
+        Ok(MatrixGame::zero_sum(payouts))
+    }
+}
+
+/// Average strategy of each player in a tree built by [`MatrixGame::build_tree`]
+pub fn avg_strategies(root: &dyn Node) -> [Array<Float, Ix1>; 2] {
+    let first = root.avg_strategy().unwrap().column(0).to_owned();
+    // The second player's nodes share one strategy
+    let second = root.children().map_or_else(
+        || Array::zeros(0),
+        |children| children[0].avg_strategy().unwrap().column(0).to_owned(),
+    );
+    [first, second]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::*;
+
+    #[test]
+    fn test_rock_paper_scissors() {
+        let expected = [
+            (MatrixGame::rock_paper_scissors(), array![1., 1., 1.] / 3.),
+            (
+                MatrixGame::biased_rock_paper_scissors(),
+                array![1., 10., 1.] / 12.,
+            ),
+        ];
+        for (game, equilibrium) in expected {
+            let mut solver = CfrSolver::new(
+                game.build_tree().unwrap(),
+                SolverConfig {
+                    variant: CfrVariant::CfrPlus,
+                    max_iterations: 5000,
+                    ..Default::default()
+                },
+            );
+            let result = solver.solve();
+            assert!(result.exploitability < 1e-2, "{}", result.exploitability);
+            for strategy in avg_strategies(solver.root.as_ref()) {
+                let difference = &strategy - &equilibrium;
+                assert!(difference.iter().all(|x| x.abs() < 0.01), "{}", strategy);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_matrix_game() {
+        let game: MatrixGame = "1,-1; -1,1".parse().unwrap();
+        assert_eq!(game.payouts.shape(), [2, 2, 2]);
+        assert_eq!(game.payouts[[1, 0, 1]], 1.);
+        assert_eq!(game.actions[1], ["0", "1"]);
+        assert_eq!(
+            "rps".parse::<MatrixGame>(),
+            Ok(MatrixGame::rock_paper_scissors())
+        );
+        assert!("1,2;3".parse::<MatrixGame>().is_err());
+        assert!("1,x".parse::<MatrixGame>().is_err());
+    }
+}
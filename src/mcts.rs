@@ -0,0 +1,307 @@
+//! Monte Carlo tree search with UCT selection, for any [`GameState`].
+//!
+//! Each iteration descends the tree by the UCB1 rule, adds one unexplored move, plays the game out
+//! to the end with a [`Playout`] policy, and credits the result to every node on the way back up.
+//! The move played most often from the root is the best move. Nothing needs to know the game
+//! beyond its rules, so the same search plays tic-tac-toe, Connect Four, or any later game.
+
+use crate::search::{GameState, Outcome};
+
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Search budget and exploration constant
+#[derive(Clone, Copy, Debug)]
+pub struct MctsConfig {
+    /// Weight of the exploration term of UCB1; larger values spread visits more evenly
+    pub exploration: f64,
+    /// Maximum number of iterations per search
+    pub iterations: usize,
+    /// Maximum time per search, if any
+    pub time: Option<Duration>,
+    /// Seed for the playouts; otherwise seeded from entropy
+    pub seed: Option<u64>,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            exploration: std::f64::consts::SQRT_2,
+            iterations: 10000,
+            time: None,
+            seed: None,
+        }
+    }
+}
+
+/// Chooses the moves of a playout
+pub trait Playout<G: GameState> {
+    /// One of `moves`, which are the legal moves of `state` and not empty
+    fn choose(&self, state: &G, moves: &[G::Move], rng: &mut StdRng) -> G::Move;
+}
+
+/// Plays uniformly random moves
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RandomPlayout;
+
+impl<G: GameState> Playout<G> for RandomPlayout {
+    fn choose(&self, _state: &G, moves: &[G::Move], rng: &mut StdRng) -> G::Move {
+        *moves.choose(rng).expect("No legal moves")
+    }
+}
+
+/// Plays a move that wins immediately if there is one, and otherwise a random move
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GreedyPlayout;
+
+impl<G: GameState> Playout<G> for GreedyPlayout {
+    fn choose(&self, state: &G, moves: &[G::Move], rng: &mut StdRng) -> G::Move {
+        let player = state.current_player();
+        moves
+            .iter()
+            .find(|x| {
+                let mut child = state.clone();
+                child.play(**x).is_ok() && child.outcome() == Some(Outcome::Win(player))
+            })
+            .copied()
+            .unwrap_or_else(|| *moves.choose(rng).expect("No legal moves"))
+    }
+}
+
+/// Search statistics of one of the root's moves
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveStats<M> {
+    pub action: M,
+    pub visits: u64,
+    /// Mean result for the player to move at the root: 1 for a win, 0.5 for a draw, 0 for a loss
+    pub value: f64,
+}
+
+struct Node<G: GameState> {
+    state: G,
+    action: Option<G::Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    untried: Vec<G::Move>,
+    visits: u64,
+    /// Total result for the player who moved into this node
+    reward: f64,
+}
+
+impl<G: GameState> Node<G> {
+    fn new(state: G, action: Option<G::Move>, parent: Option<usize>) -> Self {
+        Node {
+            untried: state.legal_moves(),
+            state,
+            action,
+            parent,
+            children: vec![],
+            visits: 0,
+            reward: 0.,
+        }
+    }
+}
+
+/// Result of a finished game for the player with index `player`
+fn reward(outcome: Outcome, player: usize) -> f64 {
+    match outcome {
+        Outcome::Win(x) if x == player => 1.,
+        Outcome::Win(_) => 0.,
+        Outcome::Draw => 0.5,
+    }
+}
+
+/// Monte Carlo tree search with a playout policy
+pub struct Mcts<P> {
+    pub config: MctsConfig,
+    pub playout: P,
+    /// Iterations run by the last search
+    pub iterations: usize,
+    rng: StdRng,
+}
+
+impl<P> Mcts<P> {
+    pub fn new(config: MctsConfig, playout: P) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Mcts {
+            config,
+            playout,
+            iterations: 0,
+            rng,
+        }
+    }
+
+    /// Most visited move from the state, or None if the game is over
+    pub fn best_move<G>(&mut self, state: &G) -> Option<G::Move>
+    where
+        G: GameState,
+        P: Playout<G>,
+    {
+        self.analyze(state)
+            .into_iter()
+            .fold(None, |best: Option<MoveStats<G::Move>>, x| match best {
+                Some(best) if best.visits >= x.visits => Some(best),
+                _ => Some(x),
+            })
+            .map(|x| x.action)
+    }
+
+    /// Searches the state within the budget and returns the statistics of each of its moves, in
+    /// the order they were first tried
+    pub fn analyze<G>(&mut self, state: &G) -> Vec<MoveStats<G::Move>>
+    where
+        G: GameState,
+        P: Playout<G>,
+    {
+        let start = Instant::now();
+        let mut tree = vec![Node::new(state.clone(), None, None)];
+        self.iterations = 0;
+        while self.iterations < self.config.iterations
+            && self.config.time.is_none_or(|x| start.elapsed() < x)
+        {
+            self.iterate(&mut tree);
+            self.iterations += 1;
+        }
+        tree[0]
+            .children
+            .iter()
+            .map(|x| {
+                let node = &tree[*x];
+                MoveStats {
+                    action: node.action.expect("Child without a move"),
+                    visits: node.visits,
+                    value: node.reward / node.visits.max(1) as f64,
+                }
+            })
+            .collect()
+    }
+
+    /// One round of selection, expansion, playout, and backpropagation
+    fn iterate<G>(&mut self, tree: &mut Vec<Node<G>>)
+    where
+        G: GameState,
+        P: Playout<G>,
+    {
+        // Selection
+        let mut index = 0;
+        while tree[index].untried.is_empty() && !tree[index].children.is_empty() {
+            let log_visits = (tree[index].visits as f64).ln();
+            index = *tree[index]
+                .children
+                .iter()
+                .max_by(|a, b| {
+                    let ucb = |x: &usize| {
+                        let node = &tree[*x];
+                        node.reward / node.visits as f64
+                            + self.config.exploration * (log_visits / node.visits as f64).sqrt()
+                    };
+                    ucb(a).total_cmp(&ucb(b))
+                })
+                .expect("No children");
+        }
+
+        // Expansion
+        if !tree[index].untried.is_empty() {
+            let i = self.rng.gen_range(0, tree[index].untried.len());
+            let action = tree[index].untried.swap_remove(i);
+            let mut state = tree[index].state.clone();
+            if state.play(action).is_ok() {
+                tree.push(Node::new(state, Some(action), Some(index)));
+                let child = tree.len() - 1;
+                tree[index].children.push(child);
+                index = child;
+            }
+        }
+
+        // Playout
+        let mut state = tree[index].state.clone();
+        let outcome = loop {
+            if let Some(outcome) = state.outcome() {
+                break outcome;
+            }
+            let moves = state.legal_moves();
+            if moves.is_empty() {
+                break Outcome::Draw;
+            }
+            let action = self.playout.choose(&state, &moves, &mut self.rng);
+            if state.play(action).is_err() {
+                break Outcome::Draw;
+            }
+        };
+
+        // Backpropagation
+        let mut current = Some(index);
+        while let Some(x) = current {
+            let parent = tree[x].parent;
+            tree[x].visits += 1;
+            if let Some(parent) = parent {
+                let mover = tree[parent].state.current_player();
+                tree[x].reward += reward(outcome, mover);
+            }
+            current = parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connect4::Position;
+    use crate::tictactoe::Board;
+
+    fn config() -> MctsConfig {
+        MctsConfig {
+            iterations: 5000,
+            seed: Some(0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_mcts() {
+        let mut mcts = Mcts::new(config(), RandomPlayout);
+        // X completes the top row
+        assert_eq!(
+            mcts.best_move(&"XX2OO5678".parse::<Board>().unwrap()),
+            Some(2)
+        );
+        assert_eq!(mcts.iterations, 5000);
+        // O blocks it
+        assert_eq!(
+            mcts.best_move(&"XX2O45678".parse::<Board>().unwrap()),
+            Some(2)
+        );
+        // Nothing to do in a finished game
+        assert_eq!(mcts.best_move(&"XXXOO5678".parse::<Board>().unwrap()), None);
+
+        let stats = mcts.analyze(&Board::default());
+        assert_eq!(stats.len(), 9);
+        assert_eq!(stats.iter().map(|x| x.visits).sum::<u64>(), 5000);
+
+        let mut mcts = Mcts::new(config(), GreedyPlayout);
+        let column = mcts
+            .best_move(&"445566".parse::<Position>().unwrap())
+            .unwrap();
+        assert!(column == 2 || column == 6);
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let mut mcts = Mcts::new(
+            MctsConfig {
+                iterations: usize::MAX,
+                time: Some(Duration::from_millis(20)),
+                ..config()
+            },
+            RandomPlayout,
+        );
+        assert!(mcts.best_move(&Position::default()).is_some());
+        assert!(mcts.iterations > 0);
+    }
+}
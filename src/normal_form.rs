@@ -0,0 +1,905 @@
+//! Two player normal-form games, where both players choose an action at the same time and read
+//! their payoffs off a table, solved exactly rather than by iteration.
+//!
+//! In a zero-sum game every equilibrium gives the first player the same value, and an optimal
+//! strategy is one guaranteeing that value whatever the opponent does.
+//! [`NormalFormGame::solve_zero_sum`] finds both players' guarantees with a linear program each:
+//! the first player maximizes `v` over mixed strategies `x` such that `x·A[.., j] >= v` for every
+//! column `j`, and the second player does the same for the negated, transposed matrix.
+//! [`MatrixGame`] solves the same games approximately with CFR.
+//!
+//! General-sum games have equilibria with different payoffs, and
+//! [`NormalFormGame::lemke_howson`] finds one of them by complementary pivoting. Each action of
+//! either player is a label, numbered with the first player's actions before the second's. A pair
+//! of strategies is an equilibrium when every label is either an action that player doesn't play
+//! or a best response of the opponent's. Starting from the artificial equilibrium where neither
+//! player plays anything, the algorithm drops one label and pivots along the path of strategy
+//! pairs missing only that label, which ends at an equilibrium. Different starting labels may
+//! reach different equilibria. The path is only well defined in nondegenerate games, so a tie in
+//! any pivot is reported as [`NormalFormError::Degenerate`].
+//!
+//! [`NormalFormGame::support_enumeration`] instead finds every equilibrium of a small game by
+//! trying each pair of equally sized supports. Each player's strategy has to make the opponent
+//! indifferent between the actions in the opponent's support, a linear system with one solution
+//! in a nondegenerate game, and the pair is an equilibrium if both strategies are positive on
+//! their supports and no action outside them does better. The number of pairs grows
+//! exponentially, so this is only practical up to about six actions each.
+//!
+//! A correlated equilibrium is a distribution over pairs of actions, from which a mediator draws a
+//! pair and privately recommends each player their action, such that following the
+//! recommendation is a best response. Its conditions are linear in the distribution, so
+//! [`NormalFormGame::correlated_equilibrium`] finds one by linear programming, maximizing a
+//! weighted sum of the players' expected payoffs. Every Nash equilibrium is one, but mixing over
+//! outcomes can give both players more.
+//!
+//! In a Stackelberg game one player, the leader, commits to a mixed strategy that the follower
+//! sees before best responding, as a defender's patrol schedule in a security game is observed by
+//! attackers. [`NormalFormGame::stackelberg`] finds the strong Stackelberg equilibrium, where the
+//! follower breaks ties in the leader's favour, with a linear program for each follower action:
+//! the leader's best commitment among those making that action a best response.
+
+use crate::error::NormalFormError;
+use crate::matrix_game::MatrixGame;
+
+use std::ops::Range;
+use std::str::FromStr;
+
+use microlp::{ComparisonOp, OptimizationDirection, Problem};
+use ndarray::*;
+
+/// Largest difference between the players' payoffs and their negations in a zero-sum game
+const ZERO_SUM_TOLERANCE: f64 = 1e-9;
+/// Ratios this close in a pivot count as tied, making the game degenerate
+const PIVOT_TOLERANCE: f64 = 1e-9;
+/// Probabilities this small count as not played, and smaller pivots make a system singular
+const SUPPORT_TOLERANCE: f64 = 1e-9;
+
+/// Payoffs and action names of a two player normal-form game
+#[derive(Clone, Debug, PartialEq)]
+pub struct NormalFormGame {
+    /// Indexed by player, the first player's action, the second's
+    pub payoffs: Array<f64, Ix3>,
+    /// Action names of each player
+    pub actions: [Vec<String>; 2],
+}
+
+/// Value and optimal strategies of a zero-sum game
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZeroSumSolution {
+    /// Expected payoff of the first player when both play optimally
+    pub value: f64,
+    /// Mixed strategy of each player, indexed by action
+    pub strategies: [Array<f64, Ix1>; 2],
+}
+
+/// Nash equilibrium of a two player game
+#[derive(Clone, Debug, PartialEq)]
+pub struct Equilibrium {
+    /// Mixed strategy of each player, indexed by action
+    pub strategies: [Array<f64, Ix1>; 2],
+    /// Expected payoff of each player
+    pub payoffs: [f64; 2],
+}
+
+/// Correlated equilibrium of a two player game
+#[derive(Clone, Debug, PartialEq)]
+pub struct CorrelatedEquilibrium {
+    /// Probability of each pair of actions, indexed by the first player's action and the second's
+    pub distribution: Array<f64, Ix2>,
+    /// Expected payoff of each player
+    pub payoffs: [f64; 2],
+}
+
+/// Strong Stackelberg equilibrium of a two player game
+#[derive(Clone, Debug, PartialEq)]
+pub struct StackelbergEquilibrium {
+    /// Mixed strategy the leader commits to
+    pub leader: Array<f64, Ix1>,
+    /// Follower's best response to it
+    pub follower: usize,
+    /// Expected payoff of each player, in the game's order of players
+    pub payoffs: [f64; 2],
+}
+
+impl Equilibrium {
+    /// Actions each player plays with positive probability
+    pub fn supports(&self) -> [Vec<usize>; 2] {
+        [0, 1].map(|player| {
+            self.strategies[player]
+                .iter()
+                .enumerate()
+                .filter(|(_, x)| **x > SUPPORT_TOLERANCE)
+                .map(|(action, _)| action)
+                .collect()
+        })
+    }
+}
+
+impl NormalFormGame {
+    /// Game given each player's payoffs, indexed by the first player's action and the second's,
+    /// with actions named by their index
+    pub fn bimatrix(first: Array<f64, Ix2>, second: Array<f64, Ix2>) -> Self {
+        let (rows, columns) = first.dim();
+        let names = |n: usize| -> Vec<String> { (0..n).map(|x| x.to_string()).collect() };
+        NormalFormGame {
+            payoffs: stack![Axis(0), first, second],
+            actions: [names(rows), names(columns)],
+        }
+    }
+
+    /// Zero-sum game given the first player's payoffs
+    pub fn zero_sum(payoffs: Array<f64, Ix2>) -> Self {
+        let negated = -&payoffs;
+        Self::bimatrix(payoffs, negated)
+    }
+
+    /// Number of actions of each player
+    pub fn shape(&self) -> [usize; 2] {
+        let (_, rows, columns) = self.payoffs.dim();
+        [rows, columns]
+    }
+
+    /// Whether the second player's payoffs are the negation of the first's
+    pub fn is_zero_sum(&self) -> bool {
+        let total = &self.payoffs.index_axis(Axis(0), 0) + &self.payoffs.index_axis(Axis(0), 1);
+        total.iter().all(|x| x.abs() <= ZERO_SUM_TOLERANCE)
+    }
+
+    /// Expected payoff of each player when they play the given mixed strategies
+    pub fn expected_payoffs(&self, strategies: [&Array<f64, Ix1>; 2]) -> [f64; 2] {
+        [0, 1].map(|player| {
+            strategies[0].dot(&self.payoffs.index_axis(Axis(0), player).dot(strategies[1]))
+        })
+    }
+
+    /// Gain of each player from switching alone to a best response against the given mixed
+    /// strategies; they're an epsilon-equilibrium for every epsilon at least the largest
+    pub fn unilateral_gains(&self, strategies: [&Array<f64, Ix1>; 2]) -> [f64; 2] {
+        let payoffs = self.expected_payoffs(strategies);
+        let best = [
+            self.payoffs.index_axis(Axis(0), 0).dot(strategies[1]),
+            self.payoffs.index_axis(Axis(0), 1).t().dot(strategies[0]),
+        ];
+        [0, 1].map(|x| best[x].fold(f64::NEG_INFINITY, |acc, y| acc.max(*y)) - payoffs[x])
+    }
+
+    /// Average gain of each player from switching to a best response against the given mixed
+    /// strategies, measured as [`crate::cfr::exploitability`] measures it; zero exactly at an
+    /// equilibrium
+    pub fn exploitability(&self, strategies: [&Array<f64, Ix1>; 2]) -> f64 {
+        let [first, second] = self.unilateral_gains(strategies);
+        (first + second) / 2.
+    }
+
+    /// Value and optimal strategies of a zero-sum game, by linear programming
+    pub fn solve_zero_sum(&self) -> Result<ZeroSumSolution, NormalFormError> {
+        if !self.is_zero_sum() {
+            return Err(NormalFormError::NotZeroSum);
+        }
+        let (value, first) = maximin(self.payoffs.index_axis(Axis(0), 0))?;
+        let (_, second) = maximin(self.payoffs.index_axis(Axis(0), 1).t())?;
+        Ok(ZeroSumSolution {
+            value,
+            strategies: [first, second],
+        })
+    }
+
+    /// Correlated equilibrium maximizing the sum of each player's expected payoff times their
+    /// weight, by linear programming: `[1., 1.]` maximizes social welfare, and `[0., 0.]` accepts
+    /// any correlated equilibrium
+    pub fn correlated_equilibrium(
+        &self,
+        weights: [f64; 2],
+    ) -> Result<CorrelatedEquilibrium, NormalFormError> {
+        let [rows, columns] = self.shape();
+        let first = self.payoffs.index_axis(Axis(0), 0);
+        let second = self.payoffs.index_axis(Axis(0), 1);
+        let mut problem = Problem::new(OptimizationDirection::Maximize);
+        let probabilities = Array::from_shape_fn((rows, columns), |(i, j)| {
+            let objective = weights[0] * first[[i, j]] + weights[1] * second[[i, j]];
+            problem.add_var(objective, (0., f64::INFINITY))
+        });
+
+        // Told to play `row`, the first player gains nothing by playing `other` instead, and
+        // likewise for the second player's columns
+        for row in 0..rows {
+            for other in (0..rows).filter(|x| *x != row) {
+                let gains: Vec<_> = (0..columns)
+                    .map(|j| {
+                        let gain = first[[row, j]] - first[[other, j]];
+                        (probabilities[[row, j]], gain)
+                    })
+                    .collect();
+                problem.add_constraint(&gains, ComparisonOp::Ge, 0.);
+            }
+        }
+        for column in 0..columns {
+            for other in (0..columns).filter(|x| *x != column) {
+                let gains: Vec<_> = (0..rows)
+                    .map(|i| {
+                        let gain = second[[i, column]] - second[[i, other]];
+                        (probabilities[[i, column]], gain)
+                    })
+                    .collect();
+                problem.add_constraint(&gains, ComparisonOp::Ge, 0.);
+            }
+        }
+        let total: Vec<_> = probabilities.iter().map(|x| (*x, 1.)).collect();
+        problem.add_constraint(&total, ComparisonOp::Eq, 1.);
+
+        let solution = problem
+            .solve()
+            .map_err(|e| NormalFormError::LinearProgram(e.to_string()))?;
+        let distribution = probabilities.mapv(|x| solution[x].max(0.));
+        let payoffs =
+            [0, 1].map(|player| (&self.payoffs.index_axis(Axis(0), player) * &distribution).sum());
+        Ok(CorrelatedEquilibrium {
+            distribution,
+            payoffs,
+        })
+    }
+
+    /// Strong Stackelberg equilibrium with `leader` committing first, the best for the leader of
+    /// the commitments found by a linear program per follower action
+    pub fn stackelberg(&self, leader: usize) -> Result<StackelbergEquilibrium, NormalFormError> {
+        if leader > 1 {
+            return Err(NormalFormError::InvalidPlayer(leader));
+        }
+        // Leader's actions along the rows
+        let [leader_payoffs, follower_payoffs] = [leader, 1 - leader].map(|player| {
+            let payoffs = self.payoffs.index_axis(Axis(0), player);
+            match leader {
+                0 => payoffs,
+                _ => payoffs.reversed_axes(),
+            }
+        });
+
+        let mut best: Option<(f64, Array<f64, Ix1>, usize)> = None;
+        for follower in 0..follower_payoffs.ncols() {
+            let mut problem = Problem::new(OptimizationDirection::Maximize);
+            let strategy: Vec<_> = leader_payoffs
+                .column(follower)
+                .iter()
+                .map(|x| problem.add_var(*x, (0., f64::INFINITY)))
+                .collect();
+            // The follower's action is at least as good as each other
+            for other in (0..follower_payoffs.ncols()).filter(|x| *x != follower) {
+                let gains: Vec<_> = strategy
+                    .iter()
+                    .enumerate()
+                    .map(|(i, x)| {
+                        let gain = follower_payoffs[[i, follower]] - follower_payoffs[[i, other]];
+                        (*x, gain)
+                    })
+                    .collect();
+                problem.add_constraint(&gains, ComparisonOp::Ge, 0.);
+            }
+            let total: Vec<_> = strategy.iter().map(|x| (*x, 1.)).collect();
+            problem.add_constraint(&total, ComparisonOp::Eq, 1.);
+
+            let solution = match problem.solve() {
+                Ok(solution) => solution,
+                // No commitment makes this action a best response
+                Err(microlp::Error::Infeasible) => continue,
+                Err(e) => return Err(NormalFormError::LinearProgram(e.to_string())),
+            };
+            let value = solution.objective();
+            if best.as_ref().is_none_or(|(x, _, _)| value > *x) {
+                let commitment = strategy.iter().map(|x| solution[*x].max(0.)).collect();
+                best = Some((value, commitment, follower));
+            }
+        }
+
+        let (_, commitment, follower) = best.expect("Some follower action is a best response");
+        let payoffs = [&leader_payoffs, &follower_payoffs]
+            .map(|payoffs| commitment.dot(&payoffs.column(follower)));
+        Ok(StackelbergEquilibrium {
+            leader: commitment,
+            follower,
+            payoffs: match leader {
+                0 => payoffs,
+                _ => [payoffs[1], payoffs[0]],
+            },
+        })
+    }
+
+    /// An equilibrium found by the Lemke-Howson algorithm, starting by dropping `label`: the
+    /// first player's action `label` if it's less than their number of actions, and otherwise
+    /// the second player's action `label` less that number
+    pub fn lemke_howson(&self, label: usize) -> Result<Equilibrium, NormalFormError> {
+        let [rows, columns] = self.shape();
+        if label >= rows + columns {
+            return Err(NormalFormError::InvalidLabel(label));
+        }
+        // Positive payoffs keep the best response polytopes bounded without changing best responses
+        let positive = |player: usize| {
+            let payoffs = self.payoffs.index_axis(Axis(0), player);
+            let min = payoffs.fold(f64::INFINITY, |acc, x| acc.min(*x));
+            payoffs.mapv(|x| x - min + 1.)
+        };
+        // The first player's slacks are labelled by their actions and the second player's
+        // strategy by theirs, and the other way around
+        let mut tableaus = [
+            Tableau::new(
+                concatenate![Axis(1), Array::eye(rows), positive(0)],
+                0..rows,
+            ),
+            Tableau::new(
+                concatenate![Axis(1), positive(1).t(), Array::eye(columns)],
+                rows..rows + columns,
+            ),
+        ];
+
+        // The first player's strategy is in the second tableau, so dropping one of their actions
+        // starts there
+        let mut tableau = match label < rows {
+            true => 1,
+            false => 0,
+        };
+        let mut entering = label;
+        loop {
+            let leaving = tableaus[tableau].pivot(entering)?;
+            if leaving == label {
+                break;
+            }
+            entering = leaving;
+            tableau = 1 - tableau;
+        }
+
+        let strategies = [
+            tableaus[1].strategy(0..rows),
+            tableaus[0].strategy(rows..rows + columns),
+        ];
+        let payoffs = self.expected_payoffs([&strategies[0], &strategies[1]]);
+        Ok(Equilibrium {
+            strategies,
+            payoffs,
+        })
+    }
+
+    /// Every equilibrium of the game, by enumerating pairs of supports of the same size, ordered
+    /// by support size. Degenerate games can have equilibria with supports of different sizes, or
+    /// infinitely many, and those aren't found.
+    pub fn support_enumeration(&self) -> Vec<Equilibrium> {
+        let [rows, columns] = self.shape();
+        let first_payoffs = self.payoffs.index_axis(Axis(0), 0);
+        let second_payoffs = self.payoffs.index_axis(Axis(0), 1);
+        let mut equilibria = vec![];
+        for size in 1..=rows.min(columns) {
+            for first in supports(rows, size) {
+                for second in supports(columns, size) {
+                    // Each player's strategy is found from the other's indifference
+                    let strategies = match (
+                        indifferent(second_payoffs.t(), &second, &first),
+                        indifferent(first_payoffs, &first, &second),
+                    ) {
+                        (Some(x), Some(y)) => [x, y],
+                        _ => continue,
+                    };
+                    let payoffs = self.expected_payoffs([&strategies[0], &strategies[1]]);
+                    let best = [
+                        first_payoffs.dot(&strategies[1]),
+                        second_payoffs.t().dot(&strategies[0]),
+                    ];
+                    if (0..2).all(|x| best[x].iter().all(|y| *y <= payoffs[x] + PIVOT_TOLERANCE)) {
+                        equilibria.push(Equilibrium {
+                            strategies,
+                            payoffs,
+                        });
+                    }
+                }
+            }
+        }
+        equilibria
+    }
+}
+
+/// Every set of `size` actions out of `n`, in increasing order
+fn supports(n: usize, size: usize) -> impl Iterator<Item = Vec<usize>> {
+    (0..1usize << n)
+        .filter(move |x| x.count_ones() as usize == size)
+        .map(move |x| (0..n).filter(|y| x >> y & 1 == 1).collect())
+}
+
+/// Mixed strategy of the columns, positive exactly on `columns`, against which the rows in `rows`
+/// all have the same expected payoff, or None if there isn't exactly one
+fn indifferent(
+    payoffs: ArrayView2<f64>,
+    rows: &[usize],
+    columns: &[usize],
+) -> Option<Array<f64, Ix1>> {
+    // Unknowns are the probability of each column followed by the common payoff
+    let n = columns.len() + 1;
+    let mut system = Array::zeros((n, n + 1));
+    for (i, row) in rows.iter().enumerate() {
+        for (j, column) in columns.iter().enumerate() {
+            system[[i, j]] = payoffs[[*row, *column]];
+        }
+        system[[i, n - 1]] = -1.;
+    }
+    for j in 0..n - 1 {
+        system[[n - 1, j]] = 1.;
+    }
+    system[[n - 1, n]] = 1.;
+
+    let solution = solve_linear(system)?;
+    if solution.iter().take(n - 1).any(|x| *x <= SUPPORT_TOLERANCE) {
+        return None;
+    }
+    let mut strategy = Array::zeros(payoffs.ncols());
+    for (j, column) in columns.iter().enumerate() {
+        strategy[*column] = solution[j];
+    }
+    Some(strategy)
+}
+
+/// Solution of the square linear system with the given augmented matrix, by Gaussian elimination
+/// with partial pivoting, or None if it's singular
+fn solve_linear(mut system: Array<f64, Ix2>) -> Option<Array<f64, Ix1>> {
+    let n = system.nrows();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|a, b| {
+            system[[*a, column]]
+                .abs()
+                .total_cmp(&system[[*b, column]].abs())
+        })?;
+        if system[[pivot, column]].abs() <= SUPPORT_TOLERANCE {
+            return None;
+        }
+        for j in 0..=n {
+            system.swap([column, j], [pivot, j]);
+        }
+        let row = system.row(column).to_owned() / system[[column, column]];
+        for (i, mut values) in system.rows_mut().into_iter().enumerate() {
+            if i != column {
+                let factor = values[column];
+                values.scaled_add(-factor, &row);
+            }
+        }
+        system.row_mut(column).assign(&row);
+    }
+    Some(system.column(n).to_owned())
+}
+
+/// Constraints of a player's best response polytope, in a tableau with a column for the variable
+/// of each label followed by the right-hand side, all ones
+struct Tableau {
+    rows: Array<f64, Ix2>,
+    /// Label of the variable basic in each row
+    basis: Vec<usize>,
+}
+
+impl Tableau {
+    fn new(coefficients: Array<f64, Ix2>, basis: Range<usize>) -> Self {
+        let ones = Array::ones((coefficients.nrows(), 1));
+        Tableau {
+            rows: concatenate![Axis(1), coefficients, ones],
+            basis: basis.collect(),
+        }
+    }
+
+    /// Brings the variable of `label` into the basis by the minimum ratio test, returning the
+    /// label of the variable leaving it
+    fn pivot(&mut self, label: usize) -> Result<usize, NormalFormError> {
+        let last = self.rows.ncols() - 1;
+        let mut best: Option<(usize, f64)> = None;
+        let mut tied = false;
+        for (row, values) in self.rows.rows().into_iter().enumerate() {
+            if values[label] <= PIVOT_TOLERANCE {
+                continue;
+            }
+            let ratio = values[last] / values[label];
+            match best {
+                Some((_, x)) if ratio > x + PIVOT_TOLERANCE => {}
+                Some((_, x)) if ratio >= x - PIVOT_TOLERANCE => tied = true,
+                _ => {
+                    best = Some((row, ratio));
+                    tied = false;
+                }
+            }
+        }
+        if tied {
+            return Err(NormalFormError::Degenerate);
+        }
+        let (pivot_row, _) = best.expect("Best response polytopes are bounded");
+
+        let pivot = self.rows.row(pivot_row).to_owned() / self.rows[[pivot_row, label]];
+        for (row, mut values) in self.rows.rows_mut().into_iter().enumerate() {
+            match row == pivot_row {
+                true => values.assign(&pivot),
+                false => {
+                    let factor = values[label];
+                    values.scaled_add(-factor, &pivot);
+                }
+            }
+        }
+        Ok(std::mem::replace(&mut self.basis[pivot_row], label))
+    }
+
+    /// Values of the basic variables labelled by `labels`, scaled into a mixed strategy
+    fn strategy(&self, labels: Range<usize>) -> Array<f64, Ix1> {
+        let last = self.rows.ncols() - 1;
+        let mut result = Array::zeros(labels.len());
+        for (row, label) in self.basis.iter().enumerate() {
+            if labels.contains(label) {
+                result[label - labels.start] = self.rows[[row, last]];
+            }
+        }
+        let total = result.sum();
+        result / total
+    }
+}
+
+/// Mixed strategy over the rows maximizing the least expected payoff against any column, along
+/// with that payoff
+fn maximin(payoffs: ArrayView2<f64>) -> Result<(f64, Array<f64, Ix1>), NormalFormError> {
+    let mut problem = Problem::new(OptimizationDirection::Maximize);
+    let value = problem.add_var(1., (f64::NEG_INFINITY, f64::INFINITY));
+    let strategy: Vec<_> = (0..payoffs.nrows())
+        .map(|_| problem.add_var(0., (0., f64::INFINITY)))
+        .collect();
+    for column in payoffs.columns() {
+        let mut expression: Vec<_> = strategy
+            .iter()
+            .copied()
+            .zip(column.iter().copied())
+            .collect();
+        expression.push((value, -1.));
+        problem.add_constraint(&expression, ComparisonOp::Ge, 0.);
+    }
+    let total: Vec<_> = strategy.iter().map(|x| (*x, 1.)).collect();
+    problem.add_constraint(&total, ComparisonOp::Eq, 1.);
+
+    let solution = problem
+        .solve()
+        .map_err(|e| NormalFormError::LinearProgram(e.to_string()))?;
+    Ok((
+        solution[value],
+        strategy.iter().map(|x| solution[*x].max(0.)).collect(),
+    ))
+}
+
+impl From<&MatrixGame> for NormalFormGame {
+    fn from(game: &MatrixGame) -> Self {
+        NormalFormGame {
+            payoffs: game.payouts.mapv(f64::from),
+            actions: game.actions.clone(),
+        }
+    }
+}
+
+impl FromStr for NormalFormGame {
+    type Err = String;
+
+    /// Parses the games [`MatrixGame`] does, or a table with rows separated by `;` and columns by
+    /// `,` in which every cell is either the first player's payoff in a zero-sum game or both
+    /// players' payoffs separated by `/`, e.g. `3/3,0/5;5/0,1/1` for the prisoner's dilemma
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.contains('/') {
+            return s.parse::<MatrixGame>().map(|x| NormalFormGame::from(&x));
+        }
+        let rows = s
+            .split(';')
+            .map(|row| {
+                row.split(',')
+                    .map(|cell| {
+                        let (first, second) = cell.split_once('/')?;
+                        Some((first.trim().parse().ok()?, second.trim().parse().ok()?))
+                    })
+                    .collect::<Option<Vec<(f64, f64)>>>()
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| format!("Invalid payoff table: {}", s))?;
+        let columns = rows[0].len();
+        if rows.iter().any(|x| x.len() != columns) {
+            return Err(format!("Rows of different lengths: {}", s));
+        }
+        let shape = (rows.len(), columns);
+        let cells: Vec<(f64, f64)> = rows.concat();
+        let first = Array::from_shape_fn(shape, |(i, j)| cells[i * columns + j].0);
+        let second = Array::from_shape_fn(shape, |(i, j)| cells[i * columns + j].1);
+        Ok(NormalFormGame::bimatrix(first, second))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Checks that each strategy guarantees the value against every action of the opponent
+    fn assert_optimal(game: &NormalFormGame, solution: &ZeroSumSolution) {
+        let payoffs = game.payoffs.index_axis(Axis(0), 0);
+        let [first, second] = &solution.strategies;
+        assert!((first.sum() - 1.).abs() < 1e-9 && (second.sum() - 1.).abs() < 1e-9);
+        assert!(payoffs
+            .t()
+            .dot(first)
+            .iter()
+            .all(|x| *x >= solution.value - 1e-9));
+        assert!(payoffs
+            .dot(second)
+            .iter()
+            .all(|x| *x <= solution.value + 1e-9));
+        let [value, _] = game.expected_payoffs([first, second]);
+        assert!((value - solution.value).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_zero_sum() {
+        // Matching pennies is fair, with both players mixing evenly
+        let game: NormalFormGame = "1,-1;-1,1".parse().unwrap();
+        let solution = game.solve_zero_sum().unwrap();
+        assert_optimal(&game, &solution);
+        assert!(solution.value.abs() < 1e-9);
+        assert!((&solution.strategies[0] - 0.5)
+            .iter()
+            .all(|x| x.abs() < 1e-9));
+
+        // Same equilibrium as CFR finds
+        let game = NormalFormGame::from(&MatrixGame::biased_rock_paper_scissors());
+        let solution = game.solve_zero_sum().unwrap();
+        assert_optimal(&game, &solution);
+        let expected = array![1., 10., 1.] / 12.;
+        for strategy in &solution.strategies {
+            assert!((strategy - &expected).iter().all(|x| x.abs() < 1e-9));
+        }
+
+        // A saddle point: the second row dominates, and the second column is the best reply
+        let game = NormalFormGame::zero_sum(array![[3., 1.], [4., 2.]]);
+        let solution = game.solve_zero_sum().unwrap();
+        assert!((solution.value - 2.).abs() < 1e-9);
+        assert!((solution.strategies[0][1] - 1.).abs() < 1e-9);
+        assert!((solution.strategies[1][1] - 1.).abs() < 1e-9);
+
+        // Rectangular, with a value that isn't zero
+        let game = NormalFormGame::zero_sum(array![[2., -1., 0.], [-1., 3., 1.]]);
+        let solution = game.solve_zero_sum().unwrap();
+        assert_optimal(&game, &solution);
+        assert!((solution.value - 0.5).abs() < 1e-9);
+        assert!((&solution.strategies[1] - &array![0.25, 0., 0.75])
+            .iter()
+            .all(|x| x.abs() < 1e-9));
+    }
+
+    /// Checks that neither player gains by switching to any one of their actions
+    fn assert_equilibrium(game: &NormalFormGame, equilibrium: &Equilibrium) {
+        let [first, second] = &equilibrium.strategies;
+        assert_eq!(game.expected_payoffs([first, second]), equilibrium.payoffs);
+        let deviations = [
+            game.payoffs.index_axis(Axis(0), 0).dot(second),
+            game.payoffs.index_axis(Axis(0), 1).t().dot(first),
+        ];
+        for (player, payoffs) in deviations.iter().enumerate() {
+            let payoff = equilibrium.payoffs[player];
+            assert!(
+                payoffs.iter().all(|x| *x <= payoff + 1e-9),
+                "{:?}",
+                equilibrium
+            );
+        }
+    }
+
+    #[test]
+    fn test_lemke_howson() {
+        // Both players defect in the prisoner's dilemma, whatever the starting label
+        let game: NormalFormGame = "3/3,0/5;5/0,1/1".parse().unwrap();
+        for label in 0..4 {
+            let equilibrium = game.lemke_howson(label).unwrap();
+            assert_equilibrium(&game, &equilibrium);
+            assert_eq!(equilibrium.strategies[0], array![0., 1.]);
+            assert_eq!(equilibrium.payoffs, [1., 1.]);
+        }
+
+        // Battle of the sexes has two pure equilibria and a mixed one, and the labels of the
+        // first player lead to different pure ones
+        let game: NormalFormGame = "3/2,0/0;0/0,2/3".parse().unwrap();
+        let equilibria: Vec<Equilibrium> = (0..4).map(|x| game.lemke_howson(x).unwrap()).collect();
+        for equilibrium in &equilibria {
+            assert_equilibrium(&game, equilibrium);
+        }
+        assert_eq!(equilibria[0].payoffs, [3., 2.]);
+        assert_eq!(equilibria[1].payoffs, [2., 3.]);
+
+        // In a zero-sum game it finds the minimax strategies
+        let game = NormalFormGame::from(&MatrixGame::biased_rock_paper_scissors());
+        let equilibrium = game.lemke_howson(0).unwrap();
+        assert_equilibrium(&game, &equilibrium);
+        let expected = array![1., 10., 1.] / 12.;
+        for strategy in &equilibrium.strategies {
+            assert!((strategy - &expected).iter().all(|x| x.abs() < 1e-9));
+        }
+
+        assert_eq!(game.lemke_howson(6), Err(NormalFormError::InvalidLabel(6)));
+        // Every action is a best response to everything
+        let game: NormalFormGame = "1/1,1/1;1/1,1/1".parse().unwrap();
+        assert_eq!(game.lemke_howson(0), Err(NormalFormError::Degenerate));
+    }
+
+    #[test]
+    fn test_support_enumeration() {
+        // Battle of the sexes: the two pure equilibria, then the mixed one
+        let game: NormalFormGame = "3/2,0/0;0/0,2/3".parse().unwrap();
+        let equilibria = game.support_enumeration();
+        assert_eq!(equilibria.len(), 3);
+        for equilibrium in &equilibria {
+            assert_equilibrium(&game, equilibrium);
+        }
+        assert_eq!(equilibria[0].supports(), [vec![0], vec![0]]);
+        assert_eq!(equilibria[1].supports(), [vec![1], vec![1]]);
+        let mixed = &equilibria[2];
+        assert_eq!(mixed.supports(), [vec![0, 1], vec![0, 1]]);
+        assert!((&mixed.strategies[0] - &array![0.6, 0.4])
+            .iter()
+            .all(|x| x.abs() < 1e-9));
+        assert!((&mixed.strategies[1] - &array![0.4, 0.6])
+            .iter()
+            .all(|x| x.abs() < 1e-9));
+        assert!(mixed.payoffs.iter().all(|x| (x - 1.2).abs() < 1e-9));
+
+        // The prisoner's dilemma only has mutual defection
+        let game: NormalFormGame = "3/3,0/5;5/0,1/1".parse().unwrap();
+        let equilibria = game.support_enumeration();
+        assert_eq!(equilibria.len(), 1);
+        assert_eq!(equilibria[0].supports(), [vec![1], vec![1]]);
+
+        // Rock paper scissors only has the fully mixed one
+        let game: NormalFormGame = "rps".parse().unwrap();
+        let equilibria = game.support_enumeration();
+        assert_eq!(equilibria.len(), 1);
+        assert_eq!(equilibria[0].supports(), [vec![0, 1, 2], vec![0, 1, 2]]);
+
+        // A random game is nondegenerate, so has an odd number of equilibria, among them every
+        // one Lemke-Howson finds
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut random = || Array::from_shape_fn((4, 4), |_| rng.gen_range(0., 1.));
+        let game = NormalFormGame::bimatrix(random(), random());
+        let equilibria = game.support_enumeration();
+        assert_eq!(equilibria.len() % 2, 1);
+        for equilibrium in &equilibria {
+            assert_equilibrium(&game, equilibrium);
+        }
+        for label in 0..8 {
+            let found = game.lemke_howson(label).unwrap();
+            assert!(equilibria.iter().any(|x| (0..2).all(|player| {
+                (&x.strategies[player] - &found.strategies[player])
+                    .iter()
+                    .all(|y| y.abs() < 1e-9)
+            })));
+        }
+    }
+
+    /// Checks that no player gains by deviating from any recommendation
+    fn assert_correlated(game: &NormalFormGame, equilibrium: &CorrelatedEquilibrium) {
+        let distribution = &equilibrium.distribution;
+        assert!(distribution.iter().all(|x| *x >= 0.));
+        assert!((distribution.sum() - 1.).abs() < 1e-9);
+        let first = game.payoffs.index_axis(Axis(0), 0);
+        let second = game.payoffs.index_axis(Axis(0), 1);
+        for (row, recommended) in distribution.rows().into_iter().enumerate() {
+            let payoff = recommended.dot(&first.row(row));
+            assert!(first
+                .rows()
+                .into_iter()
+                .all(|x| recommended.dot(&x) <= payoff + 1e-9));
+        }
+        for (column, recommended) in distribution.columns().into_iter().enumerate() {
+            let payoff = recommended.dot(&second.column(column));
+            assert!(second
+                .columns()
+                .into_iter()
+                .all(|x| recommended.dot(&x) <= payoff + 1e-9));
+        }
+    }
+
+    #[test]
+    fn test_correlated_equilibrium() {
+        // In chicken a mediator can avoid the crash while rarely letting either player dare,
+        // giving each player more than in any Nash equilibrium
+        let game: NormalFormGame = "0/0,7/2;2/7,6/6".parse().unwrap();
+        let equilibrium = game.correlated_equilibrium([1., 1.]).unwrap();
+        assert_correlated(&game, &equilibrium);
+        let expected = array![[0., 0.25], [0.25, 0.5]];
+        assert!((&equilibrium.distribution - &expected)
+            .iter()
+            .all(|x| x.abs() < 1e-9));
+        assert!(equilibrium.payoffs.iter().all(|x| (x - 5.25).abs() < 1e-9));
+        let best_nash = game
+            .support_enumeration()
+            .iter()
+            .map(|x| x.payoffs[0] + x.payoffs[1])
+            .fold(f64::NEG_INFINITY, f64::max);
+        assert!(best_nash < 10.5);
+
+        // Favouring one player picks their preferred pure equilibrium
+        let equilibrium = game.correlated_equilibrium([1., 0.]).unwrap();
+        assert_correlated(&game, &equilibrium);
+        assert!((equilibrium.distribution[[0, 1]] - 1.).abs() < 1e-9);
+
+        // Mutual defection is the only correlated equilibrium of the prisoner's dilemma
+        let game: NormalFormGame = "3/3,0/5;5/0,1/1".parse().unwrap();
+        let equilibrium = game.correlated_equilibrium([0., 0.]).unwrap();
+        assert_correlated(&game, &equilibrium);
+        assert!((equilibrium.distribution[[1, 1]] - 1.).abs() < 1e-9);
+
+        // In a zero-sum game every correlated equilibrium gives the value
+        let game = NormalFormGame::from(&MatrixGame::biased_rock_paper_scissors());
+        let equilibrium = game.correlated_equilibrium([1., 0.]).unwrap();
+        assert_correlated(&game, &equilibrium);
+        assert!(equilibrium.payoffs[0].abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stackelberg() {
+        // The leader's first action dominates, but committing to mix evenly makes the follower
+        // indifferent, and they break the tie in the leader's favour
+        let game: NormalFormGame = "2/1,4/0;1/0,3/1".parse().unwrap();
+        let equilibrium = game.stackelberg(0).unwrap();
+        assert!((&equilibrium.leader - &array![0.5, 0.5])
+            .iter()
+            .all(|x| x.abs() < 1e-9));
+        assert_eq!(equilibrium.follower, 1);
+        assert!((equilibrium.payoffs[0] - 3.5).abs() < 1e-9);
+        assert!((equilibrium.payoffs[1] - 0.5).abs() < 1e-9);
+        // Better for the leader than the game's only Nash equilibrium
+        let nash = game.support_enumeration();
+        assert_eq!(nash.len(), 1);
+        assert!((nash[0].payoffs[0] - 2.).abs() < 1e-9);
+
+        // The same game with the players swapped
+        let swapped = NormalFormGame::bimatrix(
+            game.payoffs.index_axis(Axis(0), 1).t().to_owned(),
+            game.payoffs.index_axis(Axis(0), 0).t().to_owned(),
+        );
+        let equilibrium = swapped.stackelberg(1).unwrap();
+        assert_eq!(equilibrium.follower, 1);
+        assert!((equilibrium.payoffs[1] - 3.5).abs() < 1e-9);
+        assert!((equilibrium.payoffs[0] - 0.5).abs() < 1e-9);
+
+        // Committing first gains nothing in a zero-sum game
+        let game = NormalFormGame::from(&MatrixGame::biased_rock_paper_scissors());
+        let equilibrium = game.stackelberg(1).unwrap();
+        assert!(equilibrium.payoffs[1].abs() < 1e-9);
+        let expected = array![1., 10., 1.] / 12.;
+        assert!((&equilibrium.leader - &expected)
+            .iter()
+            .all(|x| x.abs() < 1e-9));
+
+        assert_eq!(game.stackelberg(2), Err(NormalFormError::InvalidPlayer(2)));
+    }
+
+    #[test]
+    fn test_unilateral_gains() {
+        let game: NormalFormGame = "3/3,0/5;5/0,1/1".parse().unwrap();
+        // Both cooperating, each gains 2 by defecting alone
+        let cooperate = array![1., 0.];
+        assert_eq!(game.unilateral_gains([&cooperate, &cooperate]), [2., 2.]);
+        assert_eq!(game.exploitability([&cooperate, &cooperate]), 2.);
+        let defect = array![0., 1.];
+        assert_eq!(game.unilateral_gains([&defect, &defect]), [0., 0.]);
+
+        // Slightly off the rock paper scissors equilibrium is an epsilon-equilibrium
+        let game: NormalFormGame = "rps".parse().unwrap();
+        let uniform = array![1., 1., 1.] / 3.;
+        let skewed = array![0.34, 0.33, 0.33];
+        let gains = game.unilateral_gains([&skewed, &uniform]);
+        assert!(gains[0].abs() < 1e-9);
+        assert!((gains[1] - 0.01).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parse_normal_form() {
+        let game: NormalFormGame = "3/3, 0/5; 5/0, 1/1".parse().unwrap();
+        assert_eq!(game.shape(), [2, 2]);
+        assert_eq!(game.payoffs[[1, 0, 1]], 5.);
+        assert!(!game.is_zero_sum());
+        assert_eq!(game.solve_zero_sum(), Err(NormalFormError::NotZeroSum));
+
+        let game: NormalFormGame = "rps".parse().unwrap();
+        assert!(game.is_zero_sum());
+        assert_eq!(game.actions[0], ["rock", "paper", "scissors"]);
+        assert!("1/2,3;4/5,6/7".parse::<NormalFormGame>().is_err());
+        assert!("1/2,3/4;5/6".parse::<NormalFormGame>().is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//! Rayon's parallel iterators with the `parallel` feature, and otherwise sequential stand-ins with
+//! the same method names, so the solvers build for targets without threads such as wasm32.
+//!
+//! Only the parts of rayon's API used in this crate are covered: `par_iter`, `par_iter_mut` and
+//! `into_par_iter`, followed by `map`, `enumerate`, `zip`, `filter_map`, `flat_map`, `for_each`,
+//! `collect`, `sum`, `reduce_with` and rayon's two-argument `reduce`.
+
+#[cfg(feature = "parallel")]
+pub use rayon::prelude::*;
+
+#[cfg(not(feature = "parallel"))]
+pub use sequential::*;
+
+#[cfg(not(feature = "parallel"))]
+// Which adapters are used depends on the other enabled features
+#[allow(dead_code)]
+mod sequential {
+    use std::iter::Sum;
+
+    /// Iterator run on the current thread, with rayon's method signatures
+    pub struct Sequential<I>(I);
+
+    impl<I: Iterator> Sequential<I> {
+        pub fn map<B, F: FnMut(I::Item) -> B>(self, f: F) -> Sequential<std::iter::Map<I, F>> {
+            Sequential(self.0.map(f))
+        }
+
+        pub fn enumerate(self) -> Sequential<std::iter::Enumerate<I>> {
+            Sequential(self.0.enumerate())
+        }
+
+        pub fn zip<J: IntoIterator>(self, other: J) -> Sequential<std::iter::Zip<I, J::IntoIter>> {
+            Sequential(self.0.zip(other))
+        }
+
+        pub fn filter_map<B, F: FnMut(I::Item) -> Option<B>>(
+            self,
+            f: F,
+        ) -> Sequential<std::iter::FilterMap<I, F>> {
+            Sequential(self.0.filter_map(f))
+        }
+
+        pub fn flat_map<U: IntoIterator, F: FnMut(I::Item) -> U>(
+            self,
+            f: F,
+        ) -> Sequential<std::iter::FlatMap<I, U, F>> {
+            Sequential(self.0.flat_map(f))
+        }
+
+        pub fn for_each<F: FnMut(I::Item)>(self, f: F) {
+            self.0.for_each(f)
+        }
+
+        pub fn collect<C: FromIterator<I::Item>>(self) -> C {
+            self.0.collect()
+        }
+
+        pub fn sum<S: Sum<I::Item>>(self) -> S {
+            self.0.sum()
+        }
+
+        /// Folds the items starting from `identity()`, like rayon's `reduce`
+        pub fn reduce<ID, OP>(self, identity: ID, op: OP) -> I::Item
+        where
+            ID: Fn() -> I::Item,
+            OP: Fn(I::Item, I::Item) -> I::Item,
+        {
+            self.0.fold(identity(), op)
+        }
+
+        pub fn reduce_with<OP: Fn(I::Item, I::Item) -> I::Item>(self, op: OP) -> Option<I::Item> {
+            self.0.reduce(op)
+        }
+    }
+
+    impl<I: Iterator> IntoIterator for Sequential<I> {
+        type Item = I::Item;
+        type IntoIter = I;
+
+        fn into_iter(self) -> I {
+            self.0
+        }
+    }
+
+    pub trait IntoParallelIterator: IntoIterator + Sized {
+        fn into_par_iter(self) -> Sequential<Self::IntoIter> {
+            Sequential(self.into_iter())
+        }
+    }
+
+    impl<T: IntoIterator> IntoParallelIterator for T {}
+
+    pub trait IntoParallelRefIterator<'a> {
+        type Iter: Iterator;
+
+        fn par_iter(&'a self) -> Sequential<Self::Iter>;
+    }
+
+    impl<'a, T: 'a + ?Sized> IntoParallelRefIterator<'a> for T
+    where
+        &'a T: IntoIterator,
+    {
+        type Iter = <&'a T as IntoIterator>::IntoIter;
+
+        fn par_iter(&'a self) -> Sequential<Self::Iter> {
+            Sequential(self.into_iter())
+        }
+    }
+
+    pub trait IntoParallelRefMutIterator<'a> {
+        type Iter: Iterator;
+
+        fn par_iter_mut(&'a mut self) -> Sequential<Self::Iter>;
+    }
+
+    impl<'a, T: 'a + ?Sized> IntoParallelRefMutIterator<'a> for T
+    where
+        &'a mut T: IntoIterator,
+    {
+        type Iter = <&'a mut T as IntoIterator>::IntoIter;
+
+        fn par_iter_mut(&'a mut self) -> Sequential<Self::Iter> {
+            Sequential(self.into_iter())
+        }
+    }
+}
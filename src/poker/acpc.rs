@@ -0,0 +1,423 @@
+//! Client for the dealer protocol of the Annual Computer Poker Competition (ACPC), so that solved
+//! heads-up preflop strategies can play matches against external agents.
+//!
+//! The dealer sends a match state after every action, e.g. `MATCHSTATE:1:30:r200:|9hQc`, and the
+//! agent acting next replies with the same state followed by its action, e.g.
+//! `MATCHSTATE:1:30:r200:|9hQc:c`. The betting so far is mapped onto the nodes of a tree built by
+//! [`PreflopTables::build_tree`](super::push_fold::PreflopTables::build_tree) over hand classes,
+//! the hole cards onto the hand class infoset, and the action is sampled from the average
+//! strategy there.
+//!
+//! The tree only knows a few bet sizes, so a raise by the small blind short of all in is taken to
+//! be the min-raise and any raise after that to be a shove. Spots the tree doesn't cover, such as
+//! the later streets after a limp or a called min-raise, are checked or called.
+
+use crate::cfr::{Float, Node, NodeMut};
+use crate::poker::preflop::card_from_str;
+use crate::poker::push_fold::Hand;
+
+use std::fmt::Display;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+
+use rand::Rng;
+
+/// Sent to the dealer on connecting
+pub const PROTOCOL_VERSION: &str = "VERSION:2.0.0";
+
+/// Chip counts and seating of the match, which the dealer's game definition sets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AcpcConfig {
+    pub big_blind: u64,
+    /// Chips each player starts every hand with
+    pub stack: u64,
+    /// Position of the small blind, who acts first preflop and last after
+    pub sb_position: usize,
+}
+
+impl Default for AcpcConfig {
+    /// As in the competition's `holdem.nolimit.2p.reverse_blinds.game`
+    fn default() -> Self {
+        AcpcConfig {
+            big_blind: 100,
+            stack: 20000,
+            sb_position: 1,
+        }
+    }
+}
+
+/// A betting action. Raises are to a total number of chips put in over the whole hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcpcAction {
+    Fold,
+    /// Also a check
+    Call,
+    Raise(u64),
+}
+
+impl Display for AcpcAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AcpcAction::Fold => write!(f, "f"),
+            AcpcAction::Call => write!(f, "c"),
+            AcpcAction::Raise(x) => write!(f, "r{}", x),
+        }
+    }
+}
+
+/// Parses the actions of one betting round, e.g. `r200r20000c`
+fn parse_round(round: &str) -> Result<Vec<AcpcAction>, String> {
+    let mut result = Vec::new();
+    let mut chars = round.chars().peekable();
+    while let Some(x) = chars.next() {
+        let action = match x {
+            'f' => AcpcAction::Fold,
+            'c' => AcpcAction::Call,
+            'r' => {
+                let mut amount = String::new();
+                while let Some(digit) = chars.next_if(|x| x.is_ascii_digit()) {
+                    amount.push(digit);
+                }
+                let amount = amount
+                    .parse()
+                    .map_err(|_| format!("Invalid raise in betting: {}", round))?;
+                AcpcAction::Raise(amount)
+            }
+            _ => return Err(format!("Unknown action in betting: {}", round)),
+        };
+        result.push(action);
+    }
+    Ok(result)
+}
+
+/// State of a hand as seen by one agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchState {
+    /// Position of the agent in this hand
+    pub position: usize,
+    pub hand_number: u64,
+    /// Actions so far with rounds separated by `/`, e.g. `r200c/c`
+    pub betting: String,
+    /// Hole cards by position separated by `|`, then the board by round, e.g. `|9hQc/8dAs7c`.
+    /// Other agents' hole cards are only shown at showdown.
+    pub cards: String,
+}
+
+impl MatchState {
+    /// Actions of each betting round so far, the last being the current one
+    pub fn rounds(&self) -> Result<Vec<Vec<AcpcAction>>, String> {
+        self.betting.split('/').map(parse_round).collect()
+    }
+
+    /// The agent's hole cards, indexed like [`CARDS`](super::preflop::CARDS)
+    pub fn hole_cards(&self) -> Option<(u8, u8)> {
+        let hole = self
+            .cards
+            .split('/')
+            .next()?
+            .split('|')
+            .nth(self.position)?;
+        match (hole.get(..2), hole.get(2..)) {
+            (Some(a), Some(b)) => Some((card_from_str(a)?, card_from_str(b)?)),
+            _ => None,
+        }
+    }
+
+    /// Whether the hand is over, after a fold or at showdown
+    pub fn is_finished(&self) -> bool {
+        let hands = self.cards.split('/').next().unwrap_or_default();
+        self.betting.ends_with('f') || hands.split('|').all(|x| !x.is_empty())
+    }
+
+    /// Position of the player to act next, or None once the hand is over
+    pub fn to_act(&self, config: &AcpcConfig) -> Result<Option<usize>, String> {
+        if self.is_finished() {
+            return Ok(None);
+        }
+        let rounds = self.rounds()?;
+        // The small blind acts first preflop and the big blind on every later street
+        let first = match rounds.len() {
+            1 => config.sb_position,
+            _ => 1 - config.sb_position,
+        };
+        Ok(Some((first + rounds[rounds.len() - 1].len()) % 2))
+    }
+}
+
+impl FromStr for MatchState {
+    type Err = String;
+
+    /// Parses a state as sent by the dealer, e.g. `MATCHSTATE:0:12:r200:AhKs|`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = s.trim_end().split(':').collect();
+        match fields[..] {
+            ["MATCHSTATE", position, hand_number, betting, cards] => Ok(MatchState {
+                position: position
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|x| *x < 2)
+                    .ok_or_else(|| format!("Invalid position: {}", position))?,
+                hand_number: hand_number
+                    .parse()
+                    .map_err(|_| format!("Invalid hand number: {}", hand_number))?,
+                betting: betting.to_string(),
+                cards: cards.to_string(),
+            }),
+            _ => Err(format!("Invalid match state: {}", s)),
+        }
+    }
+}
+
+impl Display for MatchState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "MATCHSTATE:{}:{}:{}:{}",
+            self.position, self.hand_number, self.betting, self.cards
+        )
+    }
+}
+
+/// Plays the average strategy of a solved preflop tree
+pub struct AcpcPlayer {
+    root: Box<dyn Node>,
+    config: AcpcConfig,
+}
+
+impl AcpcPlayer {
+    pub fn new(mut root: Box<dyn Node>, config: AcpcConfig) -> Self {
+        // The mapping walks the tree by node name, which an arena doesn't expose
+        if let NodeMut::Arena(x) = root.as_node_mut() {
+            let root = std::mem::take(x).into_tree();
+            return AcpcPlayer { root, config };
+        }
+        AcpcPlayer { root, config }
+    }
+
+    /// Name of the node that preflop `actions` lead to, e.g. `rb` after a min-raise and a shove
+    fn history(&self, actions: &[AcpcAction]) -> String {
+        let mut result = String::new();
+        for action in actions {
+            let letter = match action {
+                AcpcAction::Fold => 'f',
+                AcpcAction::Call if result.is_empty() => 'l',
+                AcpcAction::Call if result == "l" => 'k',
+                AcpcAction::Call => 'c',
+                AcpcAction::Raise(x) if result.is_empty() && *x < self.config.stack => 'r',
+                AcpcAction::Raise(_) => 'b',
+            };
+            result.push(letter);
+        }
+        result
+    }
+
+    /// Node that preflop `actions` lead to, if the tree has it
+    fn node(&self, actions: &[AcpcAction]) -> Option<&dyn Node> {
+        let history = self.history(actions);
+        (1..=history.len()).try_fold(self.root.as_ref(), |node, length| {
+            let child = node
+                .children()?
+                .iter()
+                .find(|x| x.name() == &history[..length])?;
+            Some(child.as_ref())
+        })
+    }
+
+    /// Action for the agent to take in `state`, or None if it isn't the agent's turn
+    pub fn act(
+        &self,
+        state: &MatchState,
+        rng: &mut impl Rng,
+    ) -> Result<Option<AcpcAction>, String> {
+        if state.to_act(&self.config)? != Some(state.position) {
+            return Ok(None);
+        }
+        let rounds = state.rounds()?;
+        let node = match rounds.len() {
+            1 => self.node(&rounds[0]),
+            _ => None,
+        };
+        let (strategy, children) = match node.and_then(|x| x.avg_strategy().zip(x.children())) {
+            Some(x) => x,
+            None => return Ok(Some(AcpcAction::Call)),
+        };
+
+        let (a, b) = state
+            .hole_cards()
+            .ok_or_else(|| format!("No hole cards: {}", state))?;
+        let hand = Hand::get_index(a.max(b) as usize, a.min(b) as usize);
+        if strategy.ncols() != 169 {
+            return Err(format!(
+                "Expected a tree over 169 hand classes, found {} infosets",
+                strategy.ncols()
+            ));
+        }
+        let probabilities = strategy.column(hand);
+        let mut sample = rng.gen::<Float>() * probabilities.sum();
+        let index = probabilities
+            .iter()
+            .position(|x| {
+                sample -= x;
+                sample < 0.
+            })
+            .unwrap_or(probabilities.len() - 1);
+
+        let action = match children[index].name().chars().last() {
+            Some('b') => AcpcAction::Raise(self.config.stack),
+            Some('r') => AcpcAction::Raise(2 * self.config.big_blind),
+            Some('f') => AcpcAction::Fold,
+            _ => AcpcAction::Call,
+        };
+        Ok(Some(action))
+    }
+
+    /// Plays a match against the dealer until it closes the connection, returning the number of
+    /// hands played
+    pub fn play(
+        &self,
+        reader: impl BufRead,
+        mut writer: impl Write,
+        rng: &mut impl Rng,
+    ) -> std::io::Result<u64> {
+        let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+        write!(writer, "{}\r\n", PROTOCOL_VERSION)?;
+        writer.flush()?;
+
+        let mut hands = 0;
+        for line in reader.lines() {
+            let line = line?;
+            // Comments and blank lines are ignored
+            if line.trim().is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            let state: MatchState = line.parse().map_err(invalid)?;
+            if state.is_finished() {
+                hands += 1;
+            }
+            if let Some(action) = self.act(&state, rng).map_err(invalid)? {
+                write!(writer, "{}:{}\r\n", state, action)?;
+                writer.flush()?;
+            }
+        }
+        Ok(hands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::push_fold::*;
+
+    use ndarray::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Unsolved tree over every matchup of hand classes, so every action has some probability
+    fn player() -> AcpcPlayer {
+        let n_states = 169 * 169;
+        let tables = PreflopTables::from_states(
+            Array::from_elem(n_states, 1. / n_states as Float),
+            Array::from_elem(n_states, 0.5),
+            [
+                (0..169)
+                    .map(|i| (0..169).map(|j| i * 169 + j).collect())
+                    .collect(),
+                (0..169)
+                    .map(|i| (0..169).map(|j| j * 169 + i).collect())
+                    .collect(),
+            ],
+        );
+        let root = tables
+            .build_tree(
+                200.,
+                0.,
+                AnteStructure::Each,
+                0.5,
+                &SbAction::ALL,
+                &Rake::default(),
+            )
+            .unwrap();
+        AcpcPlayer::new(root, AcpcConfig::default())
+    }
+
+    #[test]
+    fn test_match_state() {
+        let line = "MATCHSTATE:1:30:r200r20000:|9hQc\r\n";
+        let state: MatchState = line.parse().unwrap();
+        assert_eq!(state.position, 1);
+        assert_eq!(state.hand_number, 30);
+        assert_eq!(state.to_string(), line.trim_end());
+        assert_eq!(
+            state.rounds().unwrap(),
+            [[AcpcAction::Raise(200), AcpcAction::Raise(20000)]]
+        );
+        assert_eq!(state.hole_cards(), Some((29, 43)));
+        assert!(!state.is_finished());
+        // The small blind is in position 1 and faces a shove
+        assert_eq!(state.to_act(&AcpcConfig::default()), Ok(Some(1)));
+
+        let showdown: MatchState = "MATCHSTATE:0:31:r20000c///:AhKs|9hQc/8dAs7c/2d/3h"
+            .parse()
+            .unwrap();
+        assert!(showdown.is_finished());
+        assert_eq!(showdown.to_act(&AcpcConfig::default()), Ok(None));
+        let flop: MatchState = "MATCHSTATE:0:32:cc/:AhKs|/8dAs7c".parse().unwrap();
+        assert_eq!(flop.to_act(&AcpcConfig::default()), Ok(Some(0)));
+
+        assert!("MATCHSTATE:2:1::|".parse::<MatchState>().is_err());
+        assert!("MATCHSTATE:0:1:x:|"
+            .parse::<MatchState>()
+            .unwrap()
+            .rounds()
+            .is_err());
+    }
+
+    #[test]
+    fn test_tree_mapping() {
+        let player = player();
+        use AcpcAction::*;
+        let names = |actions: &[AcpcAction]| player.node(actions).map(|x| x.name().to_string());
+        assert_eq!(names(&[]), Some("root".to_string()));
+        assert_eq!(names(&[Raise(20000)]), Some("b".to_string()));
+        assert_eq!(names(&[Raise(250), Raise(900)]), Some("rb".to_string()));
+        assert_eq!(names(&[Call, Raise(300)]), Some("lb".to_string()));
+        assert_eq!(names(&[Call, Call]), Some("lk".to_string()));
+        assert_eq!(names(&[Raise(200), Raise(400), Raise(800)]), None);
+    }
+
+    #[test]
+    fn test_act() {
+        use AcpcAction::*;
+        let player = player();
+        let mut rng = StdRng::seed_from_u64(0);
+        let act = |line: &str, rng: &mut StdRng| player.act(&line.parse().unwrap(), rng).unwrap();
+
+        // Not this agent's turn
+        assert_eq!(act("MATCHSTATE:0:1::AhKs|", &mut rng), None);
+        for _ in 0..20 {
+            let open = act("MATCHSTATE:1:1::|AhKs", &mut rng).unwrap();
+            assert!([Fold, Call, Raise(200), Raise(20000)].contains(&open));
+            let facing_shove = act("MATCHSTATE:0:2:r20000:7c2d|", &mut rng).unwrap();
+            assert!([Fold, Call].contains(&facing_shove));
+        }
+        // Off the tree
+        assert_eq!(act("MATCHSTATE:0:3:cc/:AhKs|/8dAs7c", &mut rng), Some(Call));
+    }
+
+    #[test]
+    fn test_play() {
+        let player = player();
+        let mut rng = StdRng::seed_from_u64(0);
+        let input = "# comment\r\nMATCHSTATE:1:0::|AhKs\r\nMATCHSTATE:0:1:r20000f:AhKs|\r\n";
+        let mut output = Vec::new();
+        let hands = player
+            .play(input.as_bytes(), &mut output, &mut rng)
+            .unwrap();
+        assert_eq!(hands, 1);
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.split("\r\n").collect();
+        assert_eq!(lines[0], PROTOCOL_VERSION);
+        assert!(lines[1].starts_with("MATCHSTATE:1:0::|AhKs:"));
+        assert_eq!(lines.len(), 3);
+    }
+}
@@ -0,0 +1,240 @@
+//! Push/fold over individual combos: states are (SB combo, BB combo) pairs of the 1326 two-card
+//! combos that don't share a card, so suit-specific card removal is exact rather than averaged over
+//! each hand class.
+//!
+//! Equities are computed once per matchup up to suit isomorphism (AsKs vs QhQd is the same matchup
+//! as AhKh vs QsQc), enumerating every board, and cached in [`COMBO_EQUITIES_PATH`]. Trees hold
+//! one entry per state (1.6M of them), so building with the `f32` feature halves their size.
+
+use crate::cfr::Float;
+use crate::error::SolverError;
+use crate::parallel::*;
+use crate::poker::push_fold::{Hand, PreflopTables};
+use crate::tables::Table;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use rust_poker::hand_evaluator::{evaluate, Hand as EvalHand, CARDS};
+
+use ndarray::*;
+
+pub const N_COMBOS: usize = 1326;
+/// Equity of every combo against every other, written by [`combo_equities`]
+pub const COMBO_EQUITIES_PATH: &str = "data/combo_equities.bin";
+
+/// Every combo as (higher card, lower card), with cards indexed 0..52 by rank then suit
+pub fn combos() -> Vec<(u8, u8)> {
+    (1..52u8)
+        .flat_map(|high| (0..high).map(move |low| (high, low)))
+        .collect()
+}
+
+/// Hand class of a combo, in [`Hand::get_index`] order
+pub fn combo_class(combo: (u8, u8)) -> usize {
+    Hand::get_index(combo.0 as usize, combo.1 as usize)
+}
+
+fn conflicts(a: (u8, u8), b: (u8, u8)) -> bool {
+    a.0 == b.0 || a.0 == b.1 || a.1 == b.0 || a.1 == b.1
+}
+
+/// Key shared by all matchups that are the same up to a relabelling of suits: the smallest of the
+/// matchup's cards under all 24 permutations of suits
+fn canonical_matchup(a: (u8, u8), b: (u8, u8)) -> u32 {
+    let mut best = u32::MAX;
+    for p0 in 0..4u8 {
+        for p1 in (0..4).filter(|x| *x != p0) {
+            for p2 in (0..4).filter(|x| *x != p0 && *x != p1) {
+                let p3 = 6 - p0 - p1 - p2;
+                let permutation = [p0, p1, p2, p3];
+                let relabel = |card: u8| card / 4 * 4 + permutation[(card % 4) as usize];
+                let hand = |(x, y): (u8, u8)| {
+                    let (x, y) = (relabel(x), relabel(y));
+                    (x.max(y) as u32) << 6 | x.min(y) as u32
+                };
+                best = best.min(hand(a) << 12 | hand(b));
+            }
+        }
+    }
+    best
+}
+
+/// Equity of `a` against `b`, enumerating every board, with ties split
+pub fn showdown_equity(a: (u8, u8), b: (u8, u8)) -> f64 {
+    let deck: Vec<EvalHand> = (0..52u8)
+        .filter(|x| ![a.0, a.1, b.0, b.1].contains(x))
+        .map(|x| CARDS[x as usize])
+        .collect();
+    let hand_a = EvalHand::from_hole_cards(a.0, a.1);
+    let hand_b = EvalHand::from_hole_cards(b.0, b.1);
+
+    let n = deck.len();
+    let (mut points, mut boards) = (0u64, 0u64);
+    for i in 0..n {
+        for j in i + 1..n {
+            let board_j = EvalHand::default() + deck[i] + deck[j];
+            for k in j + 1..n {
+                let board_k = board_j + deck[k];
+                for l in k + 1..n {
+                    let board_l = board_k + deck[l];
+                    for card in &deck[l + 1..] {
+                        let board = board_l + *card;
+                        let rank_a = evaluate(&(board + hand_a));
+                        let rank_b = evaluate(&(board + hand_b));
+                        // Two points for a win and one for a tie
+                        points += match rank_a.cmp(&rank_b) {
+                            std::cmp::Ordering::Greater => 2,
+                            std::cmp::Ordering::Equal => 1,
+                            std::cmp::Ordering::Less => 0,
+                        };
+                        boards += 1;
+                    }
+                }
+            }
+        }
+    }
+    points as f64 / (2 * boards) as f64
+}
+
+/// Equity of every combo against every other, indexed like [`combos`], with 0 for combos that
+/// share a card. Read from [`COMBO_EQUITIES_PATH`] if present, and otherwise computed and
+/// written there; this enumerates every board for each of the ~50k distinct matchups, so it takes
+/// a while.
+pub fn combo_equities() -> Result<Array<f64, Ix2>, SolverError> {
+    let path = Path::new(COMBO_EQUITIES_PATH);
+    if let Ok(table) = Table::read(path, Some((N_COMBOS, N_COMBOS))) {
+        return Ok(table.into_array());
+    }
+
+    let combos = combos();
+    let mut matchups: HashMap<u32, (u8, u8, u8, u8)> = HashMap::new();
+    for (i, a) in combos.iter().enumerate() {
+        for b in combos[i + 1..].iter().filter(|b| !conflicts(*a, **b)) {
+            matchups
+                .entry(canonical_matchup(*a, *b))
+                .or_insert((a.0, a.1, b.0, b.1));
+        }
+    }
+    let equities: HashMap<u32, f64> = matchups
+        .into_par_iter()
+        .map(|(key, (a0, a1, b0, b1))| (key, showdown_equity((a0, a1), (b0, b1))))
+        .collect();
+
+    let mut result = Array::zeros((N_COMBOS, N_COMBOS));
+    for (i, a) in combos.iter().enumerate() {
+        for (j, b) in combos.iter().enumerate().skip(i + 1) {
+            if !conflicts(*a, *b) {
+                let equity = equities[&canonical_matchup(*a, *b)];
+                result[[i, j]] = equity;
+                result[[j, i]] = 1. - equity;
+            }
+        }
+    }
+    Table::write(&result, path)?;
+    Ok(result)
+}
+
+/// Builds [`PreflopTables`] over (SB combo, BB combo) states from a table of combo equities, such
+/// as the one from [`combo_equities`]. Every state is equally likely.
+pub fn combo_tables(equities: &Array<f64, Ix2>) -> PreflopTables {
+    let combos = combos();
+    let mut states: Vec<(usize, usize)> = Vec::with_capacity(N_COMBOS * (N_COMBOS - 101));
+    let mut infosets: [Vec<Vec<usize>>; 2] =
+        [vec![Vec::new(); N_COMBOS], vec![Vec::new(); N_COMBOS]];
+    for (i, a) in combos.iter().enumerate() {
+        for (j, b) in combos.iter().enumerate() {
+            if !conflicts(*a, *b) {
+                infosets[0][i].push(states.len());
+                infosets[1][j].push(states.len());
+                states.push((i, j));
+            }
+        }
+    }
+
+    let n_states = states.len();
+    PreflopTables::from_states(
+        Array::from_elem(n_states, 1. / n_states as Float),
+        states.iter().map(|(i, j)| equities[[*i, *j]]).collect(),
+        infosets,
+    )
+}
+
+/// Averages a strategy over the combos of each hand class, given the frequency of an action for
+/// every combo, to compare it with (or print it like) a strategy over hand classes
+pub fn class_frequencies(frequencies: ArrayView1<Float>) -> Array<Float, Ix1> {
+    let mut totals: Array<Float, Ix1> = Array::zeros(169);
+    let mut counts: Array<Float, Ix1> = Array::zeros(169);
+    for (combo, frequency) in combos().into_iter().zip(frequencies.iter()) {
+        totals[combo_class(combo)] += *frequency;
+        counts[combo_class(combo)] += 1.;
+    }
+    totals / counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::preflop::card_from_str;
+    use rust_poker::equity_calculator::exact_equity;
+    use rust_poker::hand_range::{get_card_mask, HandRange};
+
+    fn combo(x: &str) -> (u8, u8) {
+        let a = card_from_str(&x[..2]).unwrap();
+        let b = card_from_str(&x[2..]).unwrap();
+        (a.max(b), a.min(b))
+    }
+
+    #[test]
+    fn test_combos() {
+        let combos = combos();
+        assert_eq!(combos.len(), N_COMBOS);
+        let mut counts = [0; 169];
+        combos.iter().for_each(|x| counts[combo_class(*x)] += 1);
+        assert_eq!(counts[168], 6); // AA
+        assert_eq!(counts[Hand::get_index(48, 44)], 4); // AKs
+        assert_eq!(counts[Hand::get_index(48, 45)], 12); // AKo
+
+        let uniform = class_frequencies(Array::from_elem(N_COMBOS, 0.25).view());
+        assert!(uniform.iter().all(|x| (x - 0.25).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_canonical_matchup() {
+        let key = |a: &str, b: &str| canonical_matchup(combo(a), combo(b));
+        assert_eq!(key("AsKs", "QhQd"), key("AhKh", "QsQc"));
+        assert_eq!(key("AsKs", "QhQd"), key("KdAd", "QcQs"));
+        assert_ne!(key("AsKs", "QsQd"), key("AsKs", "QhQd"));
+        assert_ne!(key("AsKs", "QhQd"), key("QhQd", "AsKs"));
+
+        let combos = combos();
+        let mut keys = std::collections::HashSet::new();
+        for (i, a) in combos.iter().enumerate() {
+            for b in combos[i + 1..].iter().filter(|b| !conflicts(*a, **b)) {
+                keys.insert(canonical_matchup(*a, *b));
+            }
+        }
+        assert!(keys.len() < 60000);
+    }
+
+    #[test]
+    #[ignore]
+    fn test_showdown_equity() {
+        // Enumerates 1.7M boards, so slow without optimizations
+        for (a, b) in [("AsAh", "KsKh"), ("AsKs", "QhQd"), ("7h6h", "AdKc")] {
+            let expected = exact_equity(
+                &HandRange::from_strings(vec![a.to_string(), b.to_string()]),
+                get_card_mask(""),
+                1,
+            )
+            .unwrap()[0];
+            assert!((showdown_equity(combo(a), combo(b)) - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_combo_tables() {
+        let tables = combo_tables(&Array::from_elem((N_COMBOS, N_COMBOS), 0.5));
+        assert_eq!(tables.n_states(), 1326 * 1225);
+    }
+}
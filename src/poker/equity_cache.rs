@@ -0,0 +1,197 @@
+//! Persistent cache of equity queries keyed by (hands, board), so that repeated runs don't redo
+//! the same `exact_equity` calls, such as every matchup of hand classes on a given board.
+//!
+//! The file is an 8 byte header, the magic `b"GTEC"` then the format version [`VERSION`],
+//! followed by one record per query, all little-endian:
+//!
+//! | Field             | Encoding                                           |
+//! |-------------------|----------------------------------------------------|
+//! | Board             | `u64` mask with bit `i` set for card `i`           |
+//! | Hands             | `u32` count, then each as a `u32` length and UTF-8 |
+//! | Equities          | `u32` count, then each as an `f64`                 |
+
+use crate::error::{IoError, SolverError};
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use rust_poker::equity_calculator::exact_equity;
+use rust_poker::hand_range::HandRange;
+
+/// Where the solvers keep their cache
+pub const EQUITY_CACHE_PATH: &str = "data/equity_cache.bin";
+
+const MAGIC: &[u8; 4] = b"GTEC";
+/// Current format version; caches written by other versions are rejected
+pub const VERSION: u32 = 1;
+
+type Key = (Vec<String>, u64);
+
+/// Equities of previously computed queries. Lookups and inserts lock, so one cache can be shared
+/// by queries computed in parallel.
+#[derive(Debug, Default)]
+pub struct EquityCache {
+    entries: Mutex<HashMap<Key, Vec<f64>>>,
+    /// Whether queries were added since the cache was loaded
+    modified: AtomicBool,
+}
+
+impl EquityCache {
+    /// Reads the cache at `path`, or starts an empty one if there is no file there
+    pub fn load(path: &Path) -> Result<Self, IoError> {
+        if !path.is_file() {
+            return Ok(EquityCache::default());
+        }
+        let bytes = std::fs::read(path).map_err(IoError::file(path))?;
+        let header_error = |reason: &str| IoError::Header {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+        };
+        if bytes.len() < 8 || &bytes[0..4] != MAGIC {
+            return Err(header_error("not an equity cache"));
+        }
+        if bytes[4..8] != VERSION.to_le_bytes() {
+            return Err(header_error("unsupported version"));
+        }
+
+        let mut reader = Reader { bytes, offset: 8 };
+        let mut entries = HashMap::new();
+        while reader.offset < reader.bytes.len() {
+            let truncated = || header_error("truncated record");
+            let board = reader.u64().ok_or_else(truncated)?;
+            let n_hands = reader.u32().ok_or_else(truncated)?;
+            let hands = (0..n_hands)
+                .map(|_| reader.string())
+                .collect::<Option<Vec<String>>>()
+                .ok_or_else(truncated)?;
+            let n_equities = reader.u32().ok_or_else(truncated)?;
+            let equities = (0..n_equities)
+                .map(|_| reader.u64().map(f64::from_bits))
+                .collect::<Option<Vec<f64>>>()
+                .ok_or_else(truncated)?;
+            entries.insert((hands, board), equities);
+        }
+        Ok(EquityCache {
+            entries: Mutex::new(entries),
+            modified: AtomicBool::new(false),
+        })
+    }
+
+    /// Equity of each of `hands` (ranges like "AKs" or "QQ+") with the cards in `board` already
+    /// dealt, from the cache if it was asked before and otherwise computed exactly and cached
+    pub fn exact_equity(&self, hands: &[String], board: u64) -> Result<Vec<f64>, SolverError> {
+        let key = (hands.to_vec(), board);
+        if let Some(equities) = self.entries.lock().unwrap().get(&key) {
+            return Ok(equities.clone());
+        }
+        // Queries are usually computed in parallel already, so each one gets a single thread
+        let equities = exact_equity(&HandRange::from_strings(hands.to_vec()), board, 1)
+            .map_err(|e| SolverError::Equity(e.to_string()))?;
+        self.entries.lock().unwrap().insert(key, equities.clone());
+        self.modified.store(true, Ordering::Relaxed);
+        Ok(equities)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Writes the cache to `path`, unless nothing was added since it was loaded
+    pub fn save(&self, path: &Path) -> Result<(), IoError> {
+        if !self.modified.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        for ((hands, board), equities) in self.entries.lock().unwrap().iter() {
+            bytes.extend_from_slice(&board.to_le_bytes());
+            bytes.extend_from_slice(&(hands.len() as u32).to_le_bytes());
+            for hand in hands {
+                bytes.extend_from_slice(&(hand.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(hand.as_bytes());
+            }
+            bytes.extend_from_slice(&(equities.len() as u32).to_le_bytes());
+            equities
+                .iter()
+                .for_each(|x| bytes.extend_from_slice(&x.to_le_bytes()));
+        }
+
+        let mut writer = BufWriter::new(File::create(path).map_err(IoError::file(path))?);
+        writer.write_all(&bytes).map_err(IoError::file(path))?;
+        writer.flush().map_err(IoError::file(path))
+    }
+}
+
+/// Reads little-endian fields off the front of a buffer, returning None once it runs out
+struct Reader {
+    bytes: Vec<u8>,
+    offset: usize,
+}
+
+impl Reader {
+    fn take(&mut self, n: usize) -> Option<&[u8]> {
+        let result = self.bytes.get(self.offset..self.offset + n)?;
+        self.offset += n;
+        Some(result)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equity_cache() {
+        let path = std::env::temp_dir().join("game_theory_rs_test_equity_cache.bin");
+        let _ = std::fs::remove_file(&path);
+        let hands = ["AKs".to_string(), "QQ".to_string()];
+        // A flop of 9h5h2c
+        let board = (1 << 29) | (1 << 13) | (1 << 3);
+
+        let cache = EquityCache::load(&path).unwrap();
+        assert!(cache.is_empty());
+        let equities = cache.exact_equity(&hands, board).unwrap();
+        assert!((equities[0] + equities[1] - 1.).abs() < 1e-9);
+        cache.save(&path).unwrap();
+
+        let cache = EquityCache::load(&path).unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.exact_equity(&hands, board).unwrap(), equities);
+
+        // Truncated records and other files are rejected
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            EquityCache::load(&path),
+            Err(IoError::Header { .. })
+        ));
+        std::fs::write(&path, b"GTRT").unwrap();
+        assert!(matches!(
+            EquityCache::load(&path),
+            Err(IoError::Header { .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
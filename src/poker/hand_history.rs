@@ -0,0 +1,406 @@
+//! Hand histories of heads-up push/fold play, scored against the solved strategy.
+//!
+//! Each push/fold decision is a [`Decision`]. They can be written by hand as a JSON array, e.g.
+//!
+//! ```json
+//! [
+//!     {"hand": "1", "seat": "sb", "cards": "AhKs", "stack": 10, "action": "shove"},
+//!     {"hand": "2", "seat": "bb", "cards": "7c2d", "stack": 8.5, "ante": 0.125, "action": "fold"}
+//! ]
+//! ```
+//!
+//! where `stack` is the effective stack and `ante` the ante in big blinds, not counting the ante
+//! in the stack, and the BB always faces a shove. They can also be read from PokerStars hand
+//! histories with [`parse_pokerstars`], which keeps the decisions of the player the cards were
+//! dealt to in heads-up hands.
+//!
+//! A decision's EV loss is the EV of the solved strategy in its infoset less the EV of the action
+//! taken, both against the other player's solved strategy. Following the strategy loses nothing
+//! on average, and losses are only negative through the strategy not being fully converged.
+
+use crate::cfr::{Float, InfosetReport};
+use crate::poker::preflop::card_from_str;
+use crate::poker::push_fold::{Hand, Seat};
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Action taken at a push/fold decision: the SB shoves or folds, and the BB calls or folds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushFoldAction {
+    Shove,
+    Call,
+    Fold,
+}
+
+/// One push/fold decision of a player
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    /// Identifies the hand in reports, e.g. the site's hand number
+    #[serde(default)]
+    pub hand: String,
+    pub seat: Seat,
+    /// Hole cards, e.g. `AhKs`
+    pub cards: String,
+    /// Effective stack in big blinds
+    pub stack: f64,
+    /// Ante in big blinds
+    #[serde(default)]
+    pub ante: f64,
+    pub action: PushFoldAction,
+}
+
+impl Decision {
+    /// Index of the hand class of the hole cards, as in [`Hand::get_index`]
+    pub fn hand_class(&self) -> Option<usize> {
+        let a = card_from_str(self.cards.get(..2)?)? as usize;
+        let b = card_from_str(self.cards.get(2..)?)? as usize;
+        (a != b).then(|| Hand::get_index(a.max(b), a.min(b)))
+    }
+
+    /// Name of the node the action leads to, or None if the seat can't take it
+    fn action_name(&self) -> Option<&'static str> {
+        match (self.seat, self.action) {
+            (Seat::Sb, PushFoldAction::Shove) => Some("b"),
+            (Seat::Sb, PushFoldAction::Fold) => Some("f"),
+            (Seat::Bb, PushFoldAction::Call) => Some("bc"),
+            (Seat::Bb, PushFoldAction::Fold) => Some("bf"),
+            _ => None,
+        }
+    }
+}
+
+/// Parses decisions written as a JSON array
+pub fn parse_json(s: &str) -> Result<Vec<Decision>, String> {
+    serde_json::from_str(s).map_err(|e| format!("Invalid hand history: {}", e))
+}
+
+/// Preflop action of a PokerStars hand: the player, and the action taken
+struct PokerStarsAction<'a> {
+    player: &'a str,
+    action: &'a str,
+}
+
+impl PokerStarsAction<'_> {
+    fn is_all_in(&self) -> bool {
+        self.action.ends_with("and is all-in")
+    }
+}
+
+/// Amount at the end of `text` after `prefix`, e.g. 20 in `posts big blind 20`
+fn amount_after(text: &str, prefix: &str) -> Option<f64> {
+    text.strip_prefix(prefix)?
+        .split_whitespace()
+        .next()?
+        .trim_start_matches('$')
+        .replace(',', "")
+        .parse()
+        .ok()
+}
+
+/// Player and chips of a seat line, e.g. `Seat 1: Hero (1500 in chips)`
+fn seat_chips(line: &str) -> Option<(&str, f64)> {
+    let (name, rest) = line
+        .strip_prefix("Seat ")?
+        .split_once(": ")?
+        .1
+        .rsplit_once(" (")?;
+    let chips = rest.split_once(" in chips")?.0;
+    Some((
+        name,
+        chips
+            .trim_start_matches('$')
+            .replace(',', "")
+            .parse()
+            .ok()?,
+    ))
+}
+
+/// The hero's push/fold decision in one PokerStars hand, if it has one
+fn pokerstars_decision(hand: &[&str]) -> Option<Decision> {
+    let id = hand[0].split_once('#')?.1.split(':').next()?.to_string();
+    let mut chips: HashMap<&str, f64> = HashMap::new();
+    let (mut sb, mut bb, mut ante) = (None, None, 0.);
+    let mut hero = None;
+    let mut actions = Vec::new();
+    let mut preflop = false;
+    for line in &hand[1..] {
+        if line.starts_with("*** ") {
+            preflop = line.starts_with("*** HOLE CARDS ***");
+            if !preflop && hero.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some(dealt) = line.strip_prefix("Dealt to ") {
+            let (name, cards) = dealt.rsplit_once(" [")?;
+            hero = Some((name, cards.trim_end_matches(']').replace(' ', "")));
+            continue;
+        }
+        if let Some((name, stack)) = seat_chips(line).filter(|_| !preflop) {
+            chips.insert(name, stack);
+            continue;
+        }
+        let Some((player, action)) = line.split_once(": ") else {
+            continue;
+        };
+        if action.starts_with("posts small blind ") {
+            sb = Some(player);
+        } else if let Some(x) = amount_after(action, "posts big blind ") {
+            bb = Some((player, x));
+        } else if let Some(x) = amount_after(action, "posts the ante ") {
+            ante = x;
+        } else if preflop {
+            actions.push(PokerStarsAction { player, action });
+        }
+    }
+
+    let (hero, cards) = hero?;
+    let (bb, big_blind) = bb?;
+    let sb = sb?;
+    if chips.len() != 2 || !chips.contains_key(sb) || !chips.contains_key(bb) {
+        return None;
+    }
+    let effective = chips.values().copied().fold(f64::INFINITY, f64::min);
+    let first = actions.first()?;
+    let (seat, action) = if hero == sb && first.player == hero {
+        let action = match first.action {
+            "folds" => PushFoldAction::Fold,
+            _ if first.action.starts_with("raises") && first.is_all_in() => PushFoldAction::Shove,
+            _ => return None,
+        };
+        (Seat::Sb, action)
+    } else if hero == bb {
+        if first.player != sb || !(first.action.starts_with("raises") && first.is_all_in()) {
+            return None;
+        }
+        let response = actions.get(1).filter(|x| x.player == hero)?;
+        let action = match response.action {
+            "folds" => PushFoldAction::Fold,
+            _ if response.action.starts_with("calls") => PushFoldAction::Call,
+            _ => return None,
+        };
+        (Seat::Bb, action)
+    } else {
+        return None;
+    };
+    Some(Decision {
+        hand: id,
+        seat,
+        cards,
+        stack: (effective - ante) / big_blind,
+        ante: ante / big_blind,
+        action,
+    })
+}
+
+/// Push/fold decisions of the player dealt cards in the heads-up hands of PokerStars hand
+/// histories. Hands with more players, or where the hero's action isn't a shove, call or fold
+/// in the push/fold game, are skipped.
+pub fn parse_pokerstars(s: &str) -> Vec<Decision> {
+    let lines: Vec<&str> = s.lines().map(str::trim).collect();
+    let starts: Vec<usize> = (0..lines.len())
+        .filter(|i| lines[*i].starts_with("PokerStars "))
+        .collect();
+    starts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(lines.len());
+            pokerstars_decision(&lines[*start..end])
+        })
+        .collect()
+}
+
+/// EVs of a decision in big blinds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecisionEv {
+    /// EV of the action taken
+    pub ev: Float,
+    /// EV of the solved strategy in the same infoset
+    pub strategy_ev: Float,
+    /// How often the solved strategy takes the action
+    pub frequency: Float,
+}
+
+impl DecisionEv {
+    /// EV lost by taking the action rather than following the strategy
+    pub fn loss(&self) -> Float {
+        self.strategy_ev - self.ev
+    }
+}
+
+/// Scores `decision` against the [`report`](crate::cfr::report) of a push/fold tree solved at
+/// its stack size
+pub fn evaluate(decision: &Decision, reports: &[InfosetReport]) -> Result<DecisionEv, String> {
+    let class = decision
+        .hand_class()
+        .ok_or_else(|| format!("Invalid cards: {}", decision.cards))?;
+    let action = decision.action_name().ok_or_else(|| {
+        format!(
+            "{:?} can't {:?} in the push/fold game",
+            decision.seat, decision.action
+        )
+    })?;
+    let infoset = reports
+        .iter()
+        .find(|x| x.node == decision.seat.node_path() && x.infoset == class)
+        .ok_or_else(|| format!("No infoset for {} in the tree", Hand::index_to_str(class)))?;
+    let taken = infoset
+        .actions
+        .iter()
+        .find(|x| x.action == action)
+        .ok_or_else(|| format!("No action {} in the tree", action))?;
+    Ok(DecisionEv {
+        ev: taken.ev,
+        strategy_ev: infoset.ev,
+        frequency: taken.frequency,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::*;
+    use crate::poker::push_fold::*;
+
+    use ndarray::*;
+
+    const POKERSTARS: &str = "\
+PokerStars Hand #1001: Tournament #5, Hold'em No Limit - Level IV (50/100) - 2024/01/01 12:00:00 ET
+Table '5 1' 2-max Seat #1 is the button
+Seat 1: Hero (1000 in chips)
+Seat 2: Villain (2500 in chips)
+Hero: posts the ante 10
+Villain: posts the ante 10
+Hero: posts small blind 50
+Villain: posts big blind 100
+*** HOLE CARDS ***
+Dealt to Hero [Ah Ks]
+Hero: raises 890 to 990 and is all-in
+Villain: folds
+Uncalled bet (890) returned to Hero
+*** SUMMARY ***
+Seat 1: Hero (button) (small blind) collected (220)
+
+
+PokerStars Hand #1002: Tournament #5, Hold'em No Limit - Level IV (50/100) - 2024/01/01 12:01:00 ET
+Table '5 1' 2-max Seat #2 is the button
+Seat 1: Hero (1220 in chips)
+Seat 2: Villain (2280 in chips)
+Villain: posts small blind 50
+Hero: posts big blind 100
+*** HOLE CARDS ***
+Dealt to Hero [7c 2d]
+Villain: raises 2180 to 2280 and is all-in
+Hero: folds
+*** SUMMARY ***
+
+
+PokerStars Hand #1003: Tournament #5, Hold'em No Limit - Level IV (50/100) - 2024/01/01 12:02:00 ET
+Table '5 1' 2-max Seat #1 is the button
+Seat 1: Hero (1120 in chips)
+Seat 2: Villain (2380 in chips)
+Hero: posts small blind 50
+Villain: posts big blind 100
+*** HOLE CARDS ***
+Dealt to Hero [9h 9c]
+Hero: raises 100 to 200
+Villain: folds
+*** SUMMARY ***
+";
+
+    #[test]
+    fn test_parse_json() {
+        let decisions = parse_json(
+            r#"[{"hand": "1", "seat": "sb", "cards": "AhKs", "stack": 10, "action": "shove"},
+                {"seat": "bb", "cards": "7c2d", "stack": 8.5, "ante": 0.125, "action": "fold"}]"#,
+        )
+        .unwrap();
+        assert_eq!(decisions.len(), 2);
+        assert_eq!(decisions[0].seat, Seat::Sb);
+        assert_eq!(decisions[0].hand_class(), Some(Hand::get_index(49, 44)));
+        assert_eq!(decisions[1].action, PushFoldAction::Fold);
+        assert_eq!(decisions[1].ante, 0.125);
+        assert!(parse_json(r#"[{"seat": "co", "cards": "AhKs", "stack": 10}]"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_pokerstars() {
+        let decisions = parse_pokerstars(POKERSTARS);
+        // The min-raise in the last hand isn't a push/fold decision
+        assert_eq!(decisions.len(), 2);
+        assert_eq!(
+            decisions[0],
+            Decision {
+                hand: "1001".to_string(),
+                seat: Seat::Sb,
+                cards: "AhKs".to_string(),
+                stack: 9.9,
+                ante: 0.1,
+                action: PushFoldAction::Shove,
+            }
+        );
+        assert_eq!(decisions[1].seat, Seat::Bb);
+        assert_eq!(decisions[1].cards, "7c2d");
+        assert_eq!(decisions[1].stack, 12.2);
+        assert_eq!(decisions[1].action, PushFoldAction::Fold);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        // Every hand has even equity against every other
+        let n_states = 169 * 169;
+        let tables = PreflopTables::from_states(
+            Array::from_elem(n_states, 1. / n_states as Float),
+            Array::from_elem(n_states, 0.5),
+            [
+                (0..169)
+                    .map(|i| (0..169).map(|j| i * 169 + j).collect())
+                    .collect(),
+                (0..169)
+                    .map(|i| (0..169).map(|j| j * 169 + i).collect())
+                    .collect(),
+            ],
+        );
+        let root = tables
+            .build_tree(
+                10.,
+                0.,
+                AnteStructure::Each,
+                0.5,
+                &[SbAction::Shove, SbAction::Fold],
+                &Rake::default(),
+            )
+            .unwrap();
+        let mut solver = CfrSolver::new(
+            root,
+            SolverConfig {
+                variant: CfrVariant::CfrPlus,
+                max_iterations: 200,
+                ..Default::default()
+            },
+        );
+        solver.solve();
+        let reports = solver.report();
+
+        let decision = |action| Decision {
+            hand: String::new(),
+            seat: Seat::Sb,
+            cards: "AhKs".to_string(),
+            stack: 10.,
+            ante: 0.,
+            action,
+        };
+        let shove = evaluate(&decision(PushFoldAction::Shove), &reports).unwrap();
+        let fold = evaluate(&decision(PushFoldAction::Fold), &reports).unwrap();
+        // Folding loses the small blind
+        assert!((fold.ev + 0.5).abs() < 1e-6, "{}", fold.ev);
+        // The strategy's EV is the average of its actions' EVs
+        let average = shove.frequency * shove.loss() + fold.frequency * fold.loss();
+        assert!(average.abs() < 1e-6, "{}", average);
+        assert!(evaluate(&decision(PushFoldAction::Call), &reports).is_err());
+    }
+}
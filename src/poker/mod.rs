@@ -0,0 +1,11 @@
+//! No Limit Texas Hold'em
+
+pub mod acpc;
+pub mod combos;
+pub mod equity_cache;
+pub mod hand_history;
+pub mod multiway;
+pub mod preflop;
+pub mod push_fold;
+pub mod range;
+pub mod three_handed;
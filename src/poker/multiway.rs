@@ -0,0 +1,193 @@
+//! Preflop equities of three or more hand classes all in together.
+//!
+//! Enumerating every deal is out of reach with more than two hands, so equities are estimated from
+//! random deals instead: each hand is dealt a random combo of its class, deals where two hands
+//! share a card are rejected, and the board is dealt from the rest of the deck. Hand classes are
+//! indexed into [`HANDS`].
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rust_poker::hand_evaluator::{evaluate, Hand as EvalHand, CARDS};
+
+use ndarray::*;
+
+use crate::error::{IoError, SolverError};
+use crate::parallel::*;
+use crate::poker::preflop::{preflop_hand_from_cards, HANDS};
+use crate::tables::Table;
+
+/// Written by [`build_three_way_equities`]
+pub const THREE_WAY_EQUITIES_PATH: &str = "data/equities_3way.bin";
+
+/// Deals rejected without any accepted after which a matchup is taken to be impossible to deal
+const MAX_REJECTIONS: usize = 10_000;
+
+/// Card combos making up each hand class, indexed like [`HANDS`]
+pub fn class_combos() -> Vec<Vec<(u8, u8)>> {
+    let mut result = vec![Vec::new(); HANDS.len()];
+    for a in 0..52_u8 {
+        for b in 0..a {
+            result[preflop_hand_from_cards(a, b) as usize].push((a, b));
+        }
+    }
+    result
+}
+
+/// Whether the hand classes could be dealt together, i.e. no rank is needed more than four times
+fn enough_cards(hands: &[usize]) -> bool {
+    let mut ranks = [0; 13];
+    for hand in hands {
+        ranks[hand / 13] += 1;
+        ranks[hand % 13] += 1;
+    }
+    ranks.iter().all(|x| *x <= 4)
+}
+
+/// Equity of each of `hands` all in together preflop, splitting ties, estimated from `n_samples`
+/// random deals. `combos` is [`class_combos`]. None if the hands can't be dealt together.
+pub fn sample_equities(
+    hands: &[usize],
+    combos: &[Vec<(u8, u8)>],
+    n_samples: usize,
+    rng: &mut impl Rng,
+) -> Option<Vec<f64>> {
+    if !enough_cards(hands) {
+        return None;
+    }
+
+    let mut totals = vec![0.; hands.len()];
+    let mut hole_cards = Vec::with_capacity(hands.len());
+    let (mut dealt, mut rejected) = (0, 0);
+    while dealt < n_samples {
+        let mut used = 0_u64;
+        hole_cards.clear();
+        for hand in hands {
+            let (a, b) = *combos[*hand].choose(rng).unwrap();
+            let mask = (1 << a) | (1 << b);
+            if used & mask != 0 {
+                break;
+            }
+            used |= mask;
+            hole_cards.push((a, b));
+        }
+        if hole_cards.len() < hands.len() {
+            rejected += 1;
+            if rejected >= MAX_REJECTIONS && dealt == 0 {
+                return None;
+            }
+            continue;
+        }
+
+        let mut deck: Vec<u8> = (0..52).filter(|x| used & (1 << x) == 0).collect();
+        let (board, _) = deck.partial_shuffle(rng, 5);
+        let board = board
+            .iter()
+            .fold(EvalHand::default(), |f, x| f + CARDS[*x as usize]);
+        let ranks: Vec<u16> = hole_cards
+            .iter()
+            .map(|(a, b)| evaluate(&(board + EvalHand::from_hole_cards(*a, *b))))
+            .collect();
+        let best = *ranks.iter().max().unwrap();
+        let n_winners = ranks.iter().filter(|x| **x == best).count();
+        ranks
+            .iter()
+            .zip(totals.iter_mut())
+            .filter(|(rank, _)| **rank == best)
+            .for_each(|(_, total)| *total += 1. / n_winners as f64);
+        dealt += 1;
+    }
+    Some(totals.into_iter().map(|x| x / n_samples as f64).collect())
+}
+
+/// Estimates the equity of every three-way matchup of hand classes from `n_samples` deals each,
+/// reproducibly for a given seed, and writes them to [`THREE_WAY_EQUITIES_PATH`] as a [`Table`]
+/// of the first hand's equity, with hands `i`, `j` and `k` at row `169 * i + j` and column `k`.
+/// Matchups that can't be dealt are NaN. Matchups are computed in parallel, calling `progress`
+/// with the number done so far and the total after each one.
+pub fn build_three_way_equities(
+    n_samples: usize,
+    seed: u64,
+    progress: impl Fn(usize, usize) + Sync,
+) -> Result<(), IoError> {
+    let n = HANDS.len();
+    let combos = class_combos();
+    // Equities don't depend on the order of the hands, so only sorted matchups are sampled
+    let matchups: Vec<[usize; 3]> = (0..n)
+        .flat_map(|i| (i..n).flat_map(move |j| (j..n).map(move |k| [i, j, k])))
+        .collect();
+    let done = AtomicUsize::new(0);
+    let results: Vec<Option<Vec<f64>>> = matchups
+        .par_iter()
+        .enumerate()
+        .map(|(index, hands)| {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(index as u64));
+            let result = sample_equities(hands, &combos, n_samples, &mut rng);
+            progress(done.fetch_add(1, Ordering::Relaxed) + 1, matchups.len());
+            result
+        })
+        .collect();
+
+    let mut equities = Array::from_elem((n * n, n), f64::NAN);
+    for (hands, result) in matchups.iter().zip(results) {
+        let Some(result) = result else {
+            continue;
+        };
+        for [a, b, c] in [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ] {
+            equities[[n * hands[a] + hands[b], hands[c]]] = result[a];
+        }
+    }
+    Table::write(&equities, Path::new(THREE_WAY_EQUITIES_PATH))
+}
+
+/// Three-way equities written by [`build_three_way_equities`], indexed by the three hands and
+/// giving the first hand's equity
+pub fn three_way_equities() -> Result<Array<f64, Ix3>, SolverError> {
+    let n = HANDS.len();
+    let table = Table::<f64>::read(Path::new(THREE_WAY_EQUITIES_PATH), Some((n * n, n)))?;
+    Ok(table
+        .into_array()
+        .into_shape_with_order((n, n, n))
+        .expect("the table has 169³ elements"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::preflop::preflop_hand_from_str;
+
+    #[test]
+    fn test_sample_equities() {
+        let combos = class_combos();
+        let hand = |x: &str| preflop_hand_from_str(x).unwrap() as usize;
+        assert_eq!(combos[hand("AA")].len(), 6);
+        assert_eq!(combos[hand("AKs")].len(), 4);
+        assert_eq!(combos[hand("AKo")].len(), 12);
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let equities = sample_equities(
+            &[hand("AA"), hand("KK"), hand("72o")],
+            &combos,
+            2000,
+            &mut rng,
+        )
+        .unwrap();
+        assert!((equities.iter().sum::<f64>() - 1.).abs() < 1e-9);
+        assert!(equities[0] > 0.6 && equities[0] > equities[1] && equities[1] > equities[2]);
+
+        // Five aces
+        assert!(
+            sample_equities(&[hand("AA"), hand("AA"), hand("AKs")], &combos, 1, &mut rng).is_none()
+        );
+    }
+}
@@ -1,29 +1,40 @@
-mod utils;
+//! Heads-up preflop equity and matchup tables over the 169 hand classes
 
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::OnceLock;
 
 use rust_poker::equity_calculator::*;
 use rust_poker::hand_range::*;
 
-use utils::enumerate_combos;
+use ndarray::*;
 
-const RANKS: &[char; 13] = &[
+use crate::error::{IoError, SolverError};
+use crate::parallel::*;
+use crate::tables::Table;
+use crate::utils::enumerate_combos;
+
+/// Written by [`build_matchup_equities`]
+pub const EQUITIES_PATH: &str = "data/equities.bin";
+/// Written by [`build_matchup_probabilities`]
+pub const MATCHUPS_PATH: &str = "data/matchups.bin";
+
+pub const RANKS: &[char; 13] = &[
     '2', '3', '4', '5', '6', '7', '8', '9', 'T', 'J', 'Q', 'K', 'A',
 ];
 
-static SUITS: &[char; 4] = &['s', 'h', 'd', 'c'];
+pub static SUITS: &[char; 4] = &['s', 'h', 'd', 'c'];
 
-static CARDS: &[&str; 52] = &[
+pub static CARDS: &[&str; 52] = &[
     "2s", "2h", "2d", "2c", "3s", "3h", "3d", "3c", "4s", "4h", "4d", "4c", "5s", "5h", "5d", "5c",
     "6s", "6h", "6d", "6c", "7s", "7h", "7d", "7c", "8s", "8h", "8d", "8c", "9s", "9h", "9d", "9c",
     "Ts", "Th", "Td", "Tc", "Js", "Jh", "Jd", "Jc", "Qs", "Qh", "Qd", "Qc", "Ks", "Kh", "Kd", "Kc",
     "As", "Ah", "Ad", "Ac",
 ];
 
-static HANDS: &[&str; 169] = &[
+/// Hand classes, with suited hands above the diagonal and offsuit hands below it
+pub static HANDS: &[&str; 169] = &[
     "22", "32s", "42s", "52s", "62s", "72s", "82s", "92s", "T2s", "J2s", "Q2s", "K2s", "A2s",
     "32o", "33", "43s", "53s", "63s", "73s", "83s", "93s", "T3s", "J3s", "Q3s", "K3s", "A3s",
     "42o", "43o", "44", "54s", "64s", "74s", "84s", "94s", "T4s", "J4s", "Q4s", "K4s", "A4s",
@@ -39,7 +50,7 @@ static HANDS: &[&str; 169] = &[
     "A2o", "A3o", "A4o", "A5o", "A6o", "A7o", "A8o", "A9o", "ATo", "AJo", "AQo", "AKo", "AA",
 ];
 
-fn card_from_str(card: &str) -> Option<u8> {
+pub fn card_from_str(card: &str) -> Option<u8> {
     static CARD_MAP: OnceLock<HashMap<&str, u8>> = OnceLock::new();
     let card_map = CARD_MAP.get_or_init(|| {
         let mut result = HashMap::new();
@@ -51,7 +62,8 @@ fn card_from_str(card: &str) -> Option<u8> {
     card_map.get(card).copied()
 }
 
-fn preflop_hand_from_cards(a: u8, b: u8) -> u8 {
+/// Index into [`HANDS`] of the hand class of two cards
+pub fn preflop_hand_from_cards(a: u8, b: u8) -> u8 {
     let rank_a = a / 4;
     let suit_a = a % 4;
     let rank_b = b / 4;
@@ -67,7 +79,7 @@ fn preflop_hand_from_cards(a: u8, b: u8) -> u8 {
     }
 }
 
-fn preflop_hand_from_str(hand: &str) -> Option<u8> {
+pub fn preflop_hand_from_str(hand: &str) -> Option<u8> {
     static HAND_MAP: OnceLock<HashMap<&str, u8>> = OnceLock::new();
     let hand_map = HAND_MAP.get_or_init(|| {
         let mut result = HashMap::new();
@@ -79,41 +91,47 @@ fn preflop_hand_from_str(hand: &str) -> Option<u8> {
     hand_map.get(hand).copied()
 }
 
-fn build_matchup_equities() {
-    let mut equities = [[0_f32; 169]; 169];
+/// Computes the equity of every hand class against every other and writes them to
+/// [`EQUITIES_PATH`] as a [`Table`]. Matchups are computed in parallel, calling `progress` with the
+/// number done so far and the total after each one.
+pub fn build_matchup_equities(progress: impl Fn(usize, usize) + Sync) -> Result<(), SolverError> {
+    let mut equities: Array<f64, Ix2> = Array::zeros((169, 169));
 
     for i in 0..169 as usize {
-        equities[i][i] = 0.5;
+        equities[[i, i]] = 0.5;
     }
 
-    for i in 0..168 as usize {
-        for j in i + 1..169 as usize {
+    let matchups: Vec<(usize, usize)> = (0..168_usize)
+        .flat_map(|i| (i + 1..169).map(move |j| (i, j)))
+        .collect();
+    let done = AtomicUsize::new(0);
+    let results = matchups
+        .par_iter()
+        .map(|(i, j)| {
+            // Parallel over matchups already, so each one gets a single thread
             let result = exact_equity(
-                &HandRange::from_strings(vec![String::from(HANDS[i]), String::from(HANDS[j])]),
+                &HandRange::from_strings(vec![String::from(HANDS[*i]), String::from(HANDS[*j])]),
                 get_card_mask(""),
-                12,
+                1,
             )
-            .unwrap();
-
-            equities[i][j] = result[0] as f32;
-            equities[j][i] = result[1] as f32;
-        }
+            .map_err(|e| SolverError::Equity(e.to_string()))?;
+            progress(done.fetch_add(1, Ordering::Relaxed) + 1, matchups.len());
+            Ok(result)
+        })
+        .collect::<Result<Vec<Vec<f64>>, SolverError>>()?;
+
+    for ((i, j), result) in matchups.into_iter().zip(results) {
+        equities[[i, j]] = result[0];
+        equities[[j, i]] = result[1];
     }
 
-    let mut output_buffer: Vec<u8> = Vec::with_capacity(169 * 169 * 4);
-    equities
-        .as_flattened()
-        .into_iter()
-        .for_each(|x| output_buffer.append(&mut Vec::from(x.to_le_bytes())));
-
-    let mut o = File::create("data/equities.bin")
-        .unwrap_or_else(|_| panic!("Could not create preflop equities"));
-    o.write_all(&output_buffer)
-        .unwrap_or_else(|_| panic!("Unable to write preflop equities"));
+    Ok(Table::write(&equities, Path::new(EQUITIES_PATH))?)
 }
 
-fn build_matchup_probabilities() {
-    let mut matchups = [[0u8; 169]; 169];
+/// Counts the card combinations making up every matchup of hand classes and writes them to
+/// [`MATCHUPS_PATH`] as a [`Table`]
+pub fn build_matchup_probabilities() -> Result<(), IoError> {
+    let mut matchups: Array<u64, Ix2> = Array::zeros((169, 169));
 
     let combos = enumerate_combos((0..52 as u8).collect(), 4);
 
@@ -122,39 +140,25 @@ fn build_matchup_probabilities() {
         let hand_1 = preflop_hand_from_cards(x[0], x[1]);
         let hand_2 = preflop_hand_from_cards(x[2], x[3]);
 
-        matchups[hand_1 as usize][hand_2 as usize] += 1;
-        matchups[hand_2 as usize][hand_1 as usize] += 1;
+        matchups[[hand_1 as usize, hand_2 as usize]] += 1;
+        matchups[[hand_2 as usize, hand_1 as usize]] += 1;
 
         // (0, 2) and (1, 3)
         let hand_1 = preflop_hand_from_cards(x[0], x[2]);
         let hand_2 = preflop_hand_from_cards(x[1], x[3]);
 
-        matchups[hand_1 as usize][hand_2 as usize] += 1;
-        matchups[hand_2 as usize][hand_1 as usize] += 1;
+        matchups[[hand_1 as usize, hand_2 as usize]] += 1;
+        matchups[[hand_2 as usize, hand_1 as usize]] += 1;
 
         // (0, 3) and (1, 2)
         let hand_1 = preflop_hand_from_cards(x[0], x[3]);
         let hand_2 = preflop_hand_from_cards(x[1], x[2]);
 
-        matchups[hand_1 as usize][hand_2 as usize] += 1;
-        matchups[hand_2 as usize][hand_1 as usize] += 1;
+        matchups[[hand_1 as usize, hand_2 as usize]] += 1;
+        matchups[[hand_2 as usize, hand_1 as usize]] += 1;
     });
 
-    let mut output_buffer: Vec<u8> = Vec::with_capacity(169 * 169);
-    matchups
-        .as_flattened()
-        .into_iter()
-        .for_each(|x| output_buffer.append(&mut Vec::from(x.to_le_bytes())));
-
-    let mut o = File::create("data/matchups.bin")
-        .unwrap_or_else(|_| panic!("Could not create preflop matchups"));
-    o.write_all(&output_buffer)
-        .unwrap_or_else(|_| panic!("Unable to write preflop matchups"));
-}
-
-fn main() {
-    // build_matchup_equities();
-    build_matchup_probabilities();
+    Table::write(&matchups, Path::new(MATCHUPS_PATH))
 }
 
 #[cfg(test)]
@@ -163,14 +167,11 @@ mod tests {
 
     #[test]
     fn test_matchup_equities() {
-        let bytes = include_bytes!("../data/equities.bin");
-        let equities: Vec<f32> = bytes
-            .chunks_exact(4)
-            .map(|x| f32::from_le_bytes(x.try_into().unwrap()))
-            .collect();
+        let equities = Table::<f64>::read(Path::new(EQUITIES_PATH), Some((169, 169))).unwrap();
+        let equities = equities.view();
 
         for i in 0..169 {
-            assert_eq!(equities[i * 169 + i], 0.5);
+            assert_eq!(equities[[i, i]], 0.5);
         }
 
         for i in 0..168 {
@@ -181,14 +182,15 @@ mod tests {
             )
             .unwrap();
 
-            assert_eq!(equities[i * 169 + i + 1], result[0] as f32);
-            assert_eq!(equities[(i + 1) * 169 + i], result[1] as f32);
+            assert_eq!(equities[[i, i + 1]], result[0]);
+            assert_eq!(equities[[i + 1, i]], result[1]);
         }
     }
 
     #[test]
     fn test_matchup_counts() {
-        let counts = include_bytes!("../data/matchups.bin");
+        let counts = Table::<u64>::read(Path::new(MATCHUPS_PATH), Some((169, 169))).unwrap();
+        let counts = counts.view();
 
         let mut matchups = [[0u32; 169]; 169];
 
@@ -224,10 +226,10 @@ mod tests {
 
         for i in 0..169 {
             for j in 0..169 {
-                assert_eq!(matchups[i][j], counts[i * 169 + j] as u32);
+                assert_eq!(matchups[i][j] as u64, counts[[i, j]]);
             }
         }
 
-        assert_eq!(counts.iter().map(|x| *x as u32).sum::<u32>(), 1624350);
+        assert_eq!(counts.sum(), 1624350);
     }
 }
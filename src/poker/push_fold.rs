@@ -0,0 +1,1022 @@
+//! Heads-up push/fold: the small blind shoves or folds, and the big blind calls or folds.
+//! [`build_preflop_tree`] also lets the small blind minraise or limp.
+
+use crate::cfr::*;
+use crate::error::SolverError;
+use crate::parallel::*;
+use crate::poker::equity_cache::{EquityCache, EQUITY_CACHE_PATH};
+use crate::poker::preflop::{card_from_str, EQUITIES_PATH, MATCHUPS_PATH};
+use crate::poker::range::parse_range;
+use crate::tables::Table;
+use crate::utils::enumerate_combos;
+
+use std::fmt::Display;
+use std::path::Path;
+
+use rust_poker::constants::RANK_TO_CHAR;
+use serde::{Deserialize, Serialize};
+
+use ndarray::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Seat {
+    Sb,
+    Bb,
+}
+
+impl Seat {
+    pub fn player(&self) -> usize {
+        match self {
+            Seat::Sb => 0,
+            Seat::Bb => 1,
+        }
+    }
+
+    /// Path of the seat's only decision node: SB shoves or folds, BB calls or folds
+    pub fn node_path(&self) -> &'static str {
+        match self {
+            Seat::Sb => "root",
+            Seat::Bb => "root/b",
+        }
+    }
+}
+
+impl std::str::FromStr for Seat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sb" => Ok(Seat::Sb),
+            "bb" => Ok(Seat::Bb),
+            _ => Err(format!("Unknown seat: {}", s)),
+        }
+    }
+}
+
+/// Opening actions of the small blind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SbAction {
+    /// All in; BB calls or folds
+    Shove,
+    /// Raise to 2 big blinds; BB calls, folds, or shoves and SB calls or folds
+    MinRaise,
+    /// Complete the small blind; BB checks, or shoves and SB calls or folds
+    Limp,
+    Fold,
+}
+
+impl SbAction {
+    /// Every action, in the order they appear in the tree
+    pub const ALL: [SbAction; 4] = [
+        SbAction::Shove,
+        SbAction::MinRaise,
+        SbAction::Limp,
+        SbAction::Fold,
+    ];
+
+    /// Name of the node the action leads to
+    pub fn node_name(&self) -> &'static str {
+        match self {
+            SbAction::Shove => "b",
+            SbAction::MinRaise => "r",
+            SbAction::Limp => "l",
+            SbAction::Fold => "f",
+        }
+    }
+}
+
+impl std::str::FromStr for SbAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "shove" => Ok(SbAction::Shove),
+            "minraise" => Ok(SbAction::MinRaise),
+            "limp" => Ok(SbAction::Limp),
+            "fold" => Ok(SbAction::Fold),
+            _ => Err(format!("Unknown SB action: {}", s)),
+        }
+    }
+}
+
+/// Evenly spaced effective stacks, parsed from "START:END:STEP" with both ends included
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StackSweep {
+    pub start: f64,
+    pub end: f64,
+    pub step: f64,
+}
+
+impl StackSweep {
+    pub fn stacks(&self) -> Vec<f64> {
+        // Allow for rounding so that the end is included when the step divides the range
+        let n = ((self.end - self.start) / self.step + 1e-9).floor() as usize;
+        (0..=n).map(|i| self.start + i as f64 * self.step).collect()
+    }
+}
+
+impl std::str::FromStr for StackSweep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s
+            .split(':')
+            .map(|x| x.parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .map_err(|_| format!("Invalid sweep: {}", s))?;
+        match fields[..] {
+            [start, end, step] if start > 0. && end >= start && step > 0. => {
+                Ok(StackSweep { start, end, step })
+            }
+            _ => Err(format!(
+                "Invalid sweep: {}, expected START:END:STEP with 0 < START <= END and STEP > 0",
+                s
+            )),
+        }
+    }
+}
+
+/// Who posts the ante: every player, or a single player for the whole table as in big blind ante
+/// and button ante formats. Antes are dead money, so they don't count towards calling a bet.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AnteStructure {
+    #[default]
+    Each,
+    BigBlind,
+    Button,
+}
+
+impl AnteStructure {
+    /// Ante posted by each of `n_seats` seats, ordered from the button to the big blind. Heads up
+    /// the small blind is on the button.
+    pub fn antes(&self, ante: f64, n_seats: usize) -> Vec<f64> {
+        (0..n_seats)
+            .map(|seat| match self {
+                AnteStructure::Each => ante,
+                AnteStructure::BigBlind if seat == n_seats - 1 => ante,
+                AnteStructure::Button if seat == 0 => ante,
+                _ => 0.,
+            })
+            .collect()
+    }
+}
+
+impl std::str::FromStr for AnteStructure {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "each" => Ok(AnteStructure::Each),
+            "bb" => Ok(AnteStructure::BigBlind),
+            "button" => Ok(AnteStructure::Button),
+            _ => Err(format!("Unknown ante structure: {}", s)),
+        }
+    }
+}
+
+/// Rake taken from the pot before it is paid out: `fraction` of the pot up to `cap` big blinds.
+/// Under "no flop, no drop" pots won preflop are not raked; otherwise every pot is. The default
+/// takes no rake.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rake {
+    pub fraction: f64,
+    pub cap: f64,
+    pub no_flop_no_drop: bool,
+}
+
+impl Default for Rake {
+    fn default() -> Self {
+        Rake {
+            fraction: 0.,
+            cap: f64::INFINITY,
+            no_flop_no_drop: false,
+        }
+    }
+}
+
+impl Rake {
+    /// Rake taken from a pot of `pot` big blinds, given whether the hand saw a flop
+    pub fn taken(&self, pot: f64, flop: bool) -> f64 {
+        match self.no_flop_no_drop && !flop {
+            true => 0.,
+            false => (self.fraction * pot).min(self.cap),
+        }
+    }
+}
+
+/// Parses a chart in the format printed by the solver ("AA,KK,AKs:0.500,...") into the frequency
+/// of each hand, with listed hands at 1 unless given a frequency and all others at 0. Any range
+/// accepted by [`parse_range`] works.
+pub fn parse_chart(chart: &str) -> Result<Array<Float, Ix1>, String> {
+    parse_range(chart)
+}
+
+/// Scales the initial probability of every (SB hand, BB hand) state of a heads-up tree by the
+/// weights of the two hands in each player's range, and renormalizes
+pub fn restrict_ranges(
+    root: &mut dyn Node,
+    sb_range: &Array<Float, Ix1>,
+    bb_range: &Array<Float, Ix1>,
+) -> Result<(), String> {
+    let weights = Array::from_shape_fn(169 * 169, |x| sb_range[x / 169] * bb_range[x % 169]);
+    let probabilities = &root.state_probabilities() * &weights;
+    let total = probabilities.sum();
+    if total <= 0. {
+        return Err("The ranges have no hands in common".to_string());
+    }
+    root.set_state_probabilities(probabilities / total);
+    Ok(())
+}
+
+/// Scales each player's losses at every terminal by their bubble factor, indexed by player, so
+/// that the tree is solved for risk-adjusted chips: with a factor of 1.5, losing a chip costs as
+/// much as winning 1.5 chips gains. Applies to trees before they are flattened into an arena.
+pub fn apply_bubble_factors(root: &mut dyn Node, bubble_factors: &[f64]) {
+    let children = match root.as_node_mut() {
+        NodeMut::Action(x) => &mut x.children,
+        NodeMut::Chance(x) => &mut x.children,
+        NodeMut::Terminal(x) => {
+            for (mut payouts, factor) in x.payouts.outer_iter_mut().zip(bubble_factors) {
+                payouts.mapv_inplace(|x| if x < 0. { x * *factor as Float } else { x });
+            }
+            return;
+        }
+        NodeMut::Arena(_) => return,
+    };
+    for child in children.iter_mut() {
+        apply_bubble_factors(child.as_mut(), bubble_factors);
+    }
+}
+
+/// Best response of the SB in a push/fold tree to the BB calling with `call_range`, the calling
+/// frequency of each hand class. Rather than solving, the BB's strategy is fixed and the EVs of
+/// shoving and folding are evaluated once, and the SB's strategy is set to shove the hands where
+/// shoving is worth more. Returns the SB's shoving frequencies.
+pub fn exploit_call_range(
+    root: &mut dyn Node,
+    call_range: &Array<Float, Ix1>,
+) -> Array<Float, Ix1> {
+    let call = StrategyProfile {
+        strategies: [(
+            Seat::Bb.node_path().to_string(),
+            stack![Axis(0), call_range.view(), 1. - call_range],
+        )]
+        .into(),
+    };
+    freeze_strategies(root, &call, Seat::Bb.player());
+
+    let mut shove = Array::zeros(root.avg_strategy().map_or(0, |x| x.ncols()));
+    for infoset in report(root)
+        .iter()
+        .filter(|x| x.node == Seat::Sb.node_path())
+    {
+        if infoset.actions[0].ev > infoset.actions[1].ev {
+            shove[infoset.infoset] = 1.;
+        }
+    }
+    let profile = StrategyProfile {
+        strategies: [(
+            Seat::Sb.node_path().to_string(),
+            stack![Axis(0), shove, 1. - &shove],
+        )]
+        .into(),
+    };
+    freeze_strategies(root, &profile, Seat::Sb.player());
+    shove
+}
+
+/// Two hole cards, indexed 0..52 by rank then suit
+pub struct Hand(pub usize, pub usize);
+
+impl Display for Hand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let r1 = self.0 / 4;
+        let r2 = self.1 / 4;
+        let s1 = self.0 % 4;
+        let s2 = self.1 % 4;
+
+        if r1 == r2 {
+            write!(f, "{}{}", RANK_TO_CHAR[r1], RANK_TO_CHAR[r1])
+        } else if s1 == s2 {
+            if r1 > r2 {
+                write!(f, "{}{}s", RANK_TO_CHAR[r1], RANK_TO_CHAR[r2])
+            } else {
+                write!(f, "{}{}s", RANK_TO_CHAR[r2], RANK_TO_CHAR[r1])
+            }
+        } else if r1 > r2 {
+            write!(f, "{}{}o", RANK_TO_CHAR[r1], RANK_TO_CHAR[r2])
+        } else {
+            write!(f, "{}{}o", RANK_TO_CHAR[r2], RANK_TO_CHAR[r1])
+        }
+    }
+}
+
+impl Hand {
+    /// Name of the hand class with the given index, e.g. "AKs"
+    pub fn index_to_str(x: usize) -> String {
+        let mut result = String::with_capacity(3);
+        let i = x / 13;
+        let j = x % 13;
+
+        use std::cmp::Ordering::*;
+        match i.cmp(&j) {
+            Greater => {
+                result.push(RANK_TO_CHAR[i]);
+                result.push(RANK_TO_CHAR[j]);
+                result.push('s');
+            }
+            Less => {
+                result.push(RANK_TO_CHAR[j]);
+                result.push(RANK_TO_CHAR[i]);
+                result.push('o');
+            }
+            Equal => {
+                result.push(RANK_TO_CHAR[i]);
+                result.push(RANK_TO_CHAR[j]);
+            }
+        }
+
+        result
+    }
+    /// Index of the hand class of two cards, from 0 (22) to 168 (AA)
+    pub fn get_index(c1: usize, c2: usize) -> usize {
+        let r1 = c1 / 4;
+        let s1 = c1 % 4;
+        let r2 = c2 / 4;
+        let s2 = c2 % 4;
+
+        if (r1 == r2) || (s1 == s2) {
+            // Diagonals for pocket pairs, upper triangle for suited combos
+            13 * r1 + r2
+        } else {
+            // Lower triangle for offsuit combos
+            13 * r2 + r1
+        }
+    }
+}
+
+#[derive(Debug)]
+struct MatchupTable {
+    counts: [[u64; 169]; 169],
+}
+
+impl MatchupTable {
+    fn count_matchup(&mut self, hand_index_1: usize, hand_index_2: usize) {
+        self.counts[hand_index_1][hand_index_2] += 1;
+        self.counts[hand_index_2][hand_index_1] += 1;
+    }
+
+    fn sum(&self) -> u64 {
+        self.counts.iter().fold(0, |f, x| f + x.iter().sum::<u64>())
+    }
+}
+
+impl Default for MatchupTable {
+    fn default() -> Self {
+        MatchupTable {
+            counts: [[0; 169]; 169],
+        }
+    }
+}
+
+/// Converts a table indexed like [`HANDS`] to [`Hand::get_index`] order. The two put suited hands
+/// on opposite sides of the diagonal, so each index is transposed.
+fn from_preflop_order<T: Copy>(table: ArrayView2<T>) -> Array<T, Ix2> {
+    let transpose = |x: usize| (x % 13) * 13 + x / 13;
+    Array::from_shape_fn((169, 169), |(i, j)| table[[transpose(i), transpose(j)]])
+}
+
+/// Number of card combinations making up each matchup of hand classes, read from
+/// [`MATCHUPS_PATH`] if present and counted otherwise
+fn matchup_counts() -> Result<Array<u64, Ix2>, SolverError> {
+    let path = Path::new(MATCHUPS_PATH);
+    if path.is_file() {
+        let table = Table::<u64>::read(path, Some((169, 169)))?;
+        return Ok(from_preflop_order(table.view()));
+    }
+
+    Ok(count_matchups(0))
+}
+
+/// Counts the card combinations making up each matchup of hand classes, leaving out the cards in
+/// `board` (a mask with bit `i` set for card `i`)
+fn count_matchups(board: u64) -> Array<u64, Ix2> {
+    let deck: Vec<usize> = (0..52).filter(|x| board & (1 << x) == 0).collect();
+    let mut matchup_table = MatchupTable::default();
+    enumerate_combos(deck, 4)
+        .into_iter()
+        .map(|x| {
+            matchup_table.count_matchup(Hand::get_index(x[1], x[0]), Hand::get_index(x[3], x[2]));
+            matchup_table.count_matchup(Hand::get_index(x[2], x[0]), Hand::get_index(x[3], x[1]));
+            matchup_table.count_matchup(Hand::get_index(x[3], x[0]), Hand::get_index(x[2], x[1]));
+        })
+        .for_each(drop);
+    Array::from_shape_fn((169, 169), |(i, j)| matchup_table.counts[i][j])
+}
+
+/// Equity of each hand class against each other. Uses this module's own cache in
+/// `data/preflop_equities.bin`, then the table at [`EQUITIES_PATH`], and otherwise computes the
+/// equities and writes them to the cache.
+fn matchup_equities() -> Result<Array<f64, Ix2>, SolverError> {
+    let equities_square: Array<f64, Ix2>;
+    let equity_path = Path::new("data/preflop_equities.bin");
+
+    // A cache that can't be read (e.g. one written before tables had headers) is just rebuilt
+    if let Ok(table) = Table::read(equity_path, Some((169, 169))) {
+        equities_square = table.into_array();
+    } else if Path::new(EQUITIES_PATH).is_file() {
+        let table = Table::<f64>::read(Path::new(EQUITIES_PATH), Some((169, 169)))?;
+        equities_square = from_preflop_order(table.view());
+    } else {
+        equities_square = compute_equities(0)?;
+        Table::write(&equities_square, equity_path)?;
+    }
+    Ok(equities_square)
+}
+
+/// Computes the equity of each hand class against each other with the cards in `board` dealt.
+/// Matchups that the board makes impossible are left at 0.5. Each matchup goes through the
+/// [`EquityCache`] at [`EQUITY_CACHE_PATH`], so a board is only computed once.
+fn compute_equities(board: u64) -> Result<Array<f64, Ix2>, SolverError> {
+    let counts = (board != 0).then(|| count_matchups(board));
+    // A cache that can't be read is just rebuilt
+    let cache_path = Path::new(EQUITY_CACHE_PATH);
+    let cache = EquityCache::load(cache_path).unwrap_or_default();
+    let equities: Vec<Vec<f64>> = (0_usize..169)
+        .into_par_iter()
+        .map(|i| {
+            (i..169)
+                .map(|j| {
+                    if counts.as_ref().is_some_and(|x| x[[i, j]] == 0) {
+                        return Ok(0.5);
+                    }
+                    cache
+                        .exact_equity(&[Hand::index_to_str(i), Hand::index_to_str(j)], board)
+                        .map(|x| x[0])
+                })
+                .collect::<Result<Vec<f64>, SolverError>>()
+        })
+        .collect::<Result<Vec<Vec<f64>>, SolverError>>()?;
+    cache.save(cache_path)?;
+
+    let mut equities_square = Array::zeros((169, 169));
+    equities
+        .into_iter()
+        .enumerate()
+        .map(|(i, x)| {
+            equities_square
+                .slice_mut(s![i, i..])
+                .assign(&Array::from(x.clone()));
+            equities_square
+                .slice_mut(s![i.., i])
+                .assign(&(1. - Array::from(x)));
+        })
+        .for_each(drop);
+    Ok(equities_square)
+}
+
+/// Parses board cards like "AhKd7c" into a mask with bit `i` set for card `i`, as used by
+/// [`PreflopTables::on_board`]
+pub fn parse_board(board: &str) -> Result<u64, String> {
+    let chars: Vec<char> = board.chars().filter(|x| !x.is_whitespace()).collect();
+    let mut mask = 0u64;
+    for card in chars.chunks(2) {
+        let card: String = card.iter().collect();
+        let index = card_from_str(&card).ok_or_else(|| format!("Invalid card: {}", card))?;
+        if mask & (1 << index) != 0 {
+            return Err(format!("Duplicate card: {}", card));
+        }
+        mask |= 1 << index;
+    }
+    match mask.count_ones() {
+        3..=5 => Ok(mask),
+        n => Err(format!("A board has 3 to 5 cards, found {}", n)),
+    }
+}
+
+/// Builds the push/fold game tree for effective stacks of `stack_size` big blinds. States are
+/// (SB hand, BB hand) pairs of hand classes, weighted by how many card combinations make them up.
+/// Matchup counts and equities come from the precomputed tables when present.
+pub fn build_push_fold_tree(
+    stack_size: f64,
+    ante: f64,
+    sb: f64,
+) -> Result<Box<dyn Node>, SolverError> {
+    build_preflop_tree(stack_size, ante, sb, &[SbAction::Shove, SbAction::Fold])
+}
+
+/// Builds the game tree where the SB opens with any of `sb_actions`, over the same states as
+/// [`build_push_fold_tree`]. Actions are ordered as in [`SbAction::ALL`] whatever order they are
+/// given in. Hands that see a flop without going all in (limp-check, minraise-call) are paid out
+/// at their equity in the pot, as if they checked it down.
+pub fn build_preflop_tree(
+    stack_size: f64,
+    ante: f64,
+    sb: f64,
+    sb_actions: &[SbAction],
+) -> Result<Box<dyn Node>, SolverError> {
+    PreflopTables::load()?.build_tree(
+        stack_size,
+        ante,
+        AnteStructure::Each,
+        sb,
+        sb_actions,
+        &Rake::default(),
+    )
+}
+
+/// States of the preflop game with their probabilities, equities and infosets, loaded once so
+/// that trees for several stack sizes can be built from them. By default the states are matchups
+/// of hand classes; see [`crate::poker::combos`] for matchups of individual combos.
+#[derive(Debug, Clone)]
+pub struct PreflopTables {
+    state_probabilities: Array<Float, Ix1>,
+    /// SB equity in each state
+    equities: Array<f64, Ix1>,
+    /// SB and BB infosets, indexed by infoset, member(state)
+    infosets: [Vec<Vec<usize>>; 2],
+}
+
+impl PreflopTables {
+    /// Matchups of hand classes: (SB hand, BB hand) pairs, weighted by how many card combinations
+    /// make them up
+    pub fn load() -> Result<Self, SolverError> {
+        Ok(Self::from_matchups(matchup_counts()?, matchup_equities()?))
+    }
+
+    /// Matchups of hand classes with the cards in `board` (see [`parse_board`]) already dealt, so
+    /// that the hands are all in with only the rest of the board to come: "shove or fold on this
+    /// flop". Equities are computed rather than cached, which is quick with a board.
+    pub fn on_board(board: u64) -> Result<Self, SolverError> {
+        Ok(Self::from_matchups(
+            count_matchups(board),
+            compute_equities(board)?,
+        ))
+    }
+
+    fn from_matchups(matchup_counts: Array<u64, Ix2>, equities: Array<f64, Ix2>) -> Self {
+        let total_matchups = matchup_counts.sum();
+        let state_probabilities: Array<Float, Ix1> = matchup_counts
+            .iter()
+            .map(|x| *x as Float / total_matchups as Float)
+            .collect();
+
+        // Compute information sets
+        let infosets: [Vec<Vec<usize>>; 2] = [
+            (0_usize..169)
+                .map(|i| (0_usize..169).map(|j| i * 169 + j).collect::<Vec<usize>>())
+                .collect(),
+            (0_usize..169)
+                .map(|i| (0_usize..169).map(|j| j * 169 + i).collect::<Vec<usize>>())
+                .collect(),
+        ];
+
+        PreflopTables {
+            state_probabilities,
+            equities: equities.flatten().to_owned(),
+            infosets,
+        }
+    }
+
+    /// Tables over any set of states, given the probability of each, the SB's equity in each and
+    /// the SB and BB infosets
+    pub fn from_states(
+        state_probabilities: Array<Float, Ix1>,
+        equities: Array<f64, Ix1>,
+        infosets: [Vec<Vec<usize>>; 2],
+    ) -> Self {
+        PreflopTables {
+            state_probabilities,
+            equities,
+            infosets,
+        }
+    }
+
+    pub fn n_states(&self) -> usize {
+        self.equities.len()
+    }
+
+    /// See [`build_preflop_tree`]. Effective stacks don't count the antes, which `ante_structure`
+    /// says who posts.
+    pub fn build_tree(
+        &self,
+        stack_size: f64,
+        ante: f64,
+        ante_structure: AnteStructure,
+        sb: f64,
+        sb_actions: &[SbAction],
+        rake: &Rake,
+    ) -> Result<Box<dyn Node>, SolverError> {
+        let infosets = &self.infosets;
+        let n_states = self.n_states();
+        let antes = ante_structure.antes(ante, 2);
+
+        // Payouts when the SB and BB have put `invested` in the pot and the SB wins `sb_share` of
+        // what is left after rake. With rake the game is no longer zero-sum.
+        let pot = |sb_share: Array<f64, Ix1>, invested: [f64; 2], flop: bool| {
+            let total = invested[0] + invested[1];
+            let net = total - rake.taken(total, flop);
+            let sb = sb_share.mapv(|x| (x * net - invested[0]) as Float);
+            let bb = sb_share.mapv(|x| ((1. - x) * net - invested[1]) as Float);
+            stack![Axis(0), sb, bb]
+        };
+        // Chips put in by each player once they have matched a bet of `bet`, counting their ante
+        let matched = |bet: f64| [bet + antes[0], bet + antes[1]];
+        // Both players have matched a bet of `bet` and the hand goes to showdown
+        let showdown = |bet: f64| pot(self.equities.clone(), matched(bet), true);
+        // `winner` takes the pot without a showdown
+        let won = |winner: usize, invested: [f64; 2]| {
+            let sb_share = if winner == 0 { 1. } else { 0. };
+            pot(Array::from_elem(n_states, sb_share), invested, false)
+        };
+
+        // `player` faces an all in over a bet of `bet`, and calls ("c") or folds ("f"), in which
+        // case the uncalled part of the shove is returned
+        let facing_shove = |name: &str, player: usize, bet: f64| {
+            TreeBuilder::action(name, player)
+                .infosets(infosets[player].clone())
+                .terminal(&format!("{}c", name), showdown(stack_size))
+                .terminal(&format!("{}f", name), won(1 - player, matched(bet)))
+        };
+        // BB faces a raise to `raise` and shoves ("b"), calls ("c") or folds ("f")
+        let facing_raise = |name: &str, raise: f64| {
+            TreeBuilder::action(name, 1)
+                .infosets(infosets[1].clone())
+                .child(facing_shove(&format!("{}b", name), 0, raise))
+                .terminal(&format!("{}c", name), showdown(raise))
+                .terminal(&format!("{}f", name), won(0, matched(1.)))
+        };
+
+        let mut root = TreeBuilder::action("root", 0)
+            .infosets(infosets[0].clone())
+            .state_probabilities(self.state_probabilities.clone());
+        for action in SbAction::ALL.iter().filter(|x| sb_actions.contains(x)) {
+            let name = action.node_name();
+            root = match action {
+                SbAction::Shove => root.child(facing_shove(name, 1, 1.)),
+                SbAction::MinRaise => root.child(facing_raise(name, 2.)),
+                // A raise to 1 big blind, except that the BB checks rather than calling or folding
+                SbAction::Limp => root.child(
+                    TreeBuilder::action(name, 1)
+                        .infosets(infosets[1].clone())
+                        .child(facing_shove("lb", 0, 1.))
+                        .terminal("lk", showdown(1.)),
+                ),
+                SbAction::Fold => root.terminal(name, won(1, [sb + antes[0], 1. + antes[1]])),
+            };
+        }
+        Ok(root.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_poker::equity_calculator::*;
+    use rust_poker::hand_range::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_flatten() {
+        let a = array![[0, 1, 2], [3, 4, 5]];
+        println!("{}", a.flatten());
+    }
+
+    #[test]
+    fn test_index() {
+        assert_eq!(Hand::get_index(1, 0), 0); // 22
+        assert_eq!(Hand::get_index(0, 1), 0);
+        assert_eq!(Hand::get_index(0, 2), 0);
+        assert_eq!(Hand::get_index(51, 50), 168);
+        assert_eq!(Hand::get_index(50, 51), 168);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Hand(1, 0).to_string(), "22");
+        assert_eq!(Hand(48, 0).to_string(), "A2s");
+        assert_eq!(Hand(48, 1).to_string(), "A2o");
+    }
+
+    #[test]
+    fn test_from_index() {
+        for i in 0..51 {
+            for j in i + 1..52 {
+                assert_eq!(
+                    Hand(i, j).to_string(),
+                    Hand::index_to_str(Hand::get_index(j, i))
+                );
+                assert_eq!(Hand(j, i).to_string(), Hand(i, j).to_string());
+            }
+        }
+    }
+
+    #[test]
+    fn test_equity() {
+        let x = exact_equity(
+            &HandRange::from_strings(vec!["5h4h".to_string(), "9d9s".to_string()]),
+            get_card_mask(""),
+            1,
+        )
+        .unwrap();
+
+        println!("{}", x[0]);
+    }
+
+    #[test]
+    #[ignore = "benchmark; run with --ignored --nocapture"]
+    fn bench_arena_tree() {
+        const ITERATIONS: u64 = 200;
+        let config = SolverConfig {
+            max_iterations: ITERATIONS,
+            ..Default::default()
+        };
+
+        let mut boxed = CfrSolver::new(
+            build_push_fold_tree(10., 0.125, 0.5).unwrap(),
+            config.clone(),
+        );
+        let start = Instant::now();
+        boxed.solve();
+        let boxed_time = start.elapsed();
+
+        let mut arena = CfrSolver::new(
+            Box::new(ArenaTree::from_tree(
+                build_push_fold_tree(10., 0.125, 0.5).unwrap(),
+            )),
+            config,
+        );
+        let start = Instant::now();
+        arena.solve();
+        let arena_time = start.elapsed();
+
+        println!(
+            "{} iterations: boxed {:.3}s, arena {:.3}s",
+            ITERATIONS,
+            boxed_time.as_secs_f64(),
+            arena_time.as_secs_f64()
+        );
+        assert!(
+            (exploitability(boxed.root.as_ref()) - exploitability(arena.root.as_ref())).abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_stack_sweep() {
+        let sweep: StackSweep = "1:25:0.5".parse().unwrap();
+        let stacks = sweep.stacks();
+        assert_eq!(stacks.len(), 49);
+        assert_eq!(stacks[1], 1.5);
+        assert_eq!(stacks[48], 25.);
+        assert_eq!("2:2.9:0.3".parse::<StackSweep>().unwrap().stacks().len(), 4);
+        for invalid in ["1:25", "25:1:0.5", "1:25:0", "0:25:1", "a:b:c"] {
+            assert!(invalid.parse::<StackSweep>().is_err());
+        }
+    }
+
+    #[test]
+    fn test_rake() {
+        let rake = Rake {
+            fraction: 0.05,
+            cap: 1.,
+            no_flop_no_drop: true,
+        };
+        assert_eq!(rake.taken(10., true), 0.5);
+        assert_eq!(rake.taken(30., true), 1.);
+        assert_eq!(rake.taken(10., false), 0.);
+        assert_eq!(Rake::default().taken(100., true), 0.);
+
+        // A single coin flip state
+        let tables =
+            PreflopTables::from_states(array![1.], array![0.5], [vec![vec![0]], vec![vec![0]]]);
+        let payouts = |rake: &Rake, path: &[usize]| {
+            let root = tables
+                .build_tree(
+                    10.,
+                    0.125,
+                    AnteStructure::Each,
+                    0.5,
+                    &[SbAction::Shove, SbAction::Fold],
+                    rake,
+                )
+                .unwrap();
+            let mut node = root.as_ref();
+            for i in path {
+                node = node.children().unwrap()[*i].as_ref();
+            }
+            node.payouts().column(0).to_vec()
+        };
+        // Shove and call: a pot of 20.25 raked by the cap
+        assert_eq!(payouts(&rake, &[0, 0]), [-0.5, -0.5]);
+        assert_eq!(payouts(&Rake::default(), &[0, 0]), [0., 0.]);
+        // SB folds: no flop, so no rake unless every pot is raked
+        assert_eq!(payouts(&rake, &[1]), [-0.625, 0.625]);
+        let every_pot = Rake {
+            no_flop_no_drop: false,
+            ..rake
+        };
+        let bb = payouts(&every_pot, &[1])[1];
+        assert!((bb - (0.625 - 0.05 * 1.75)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ante_structure() {
+        assert_eq!(AnteStructure::Each.antes(0.125, 2), [0.125, 0.125]);
+        assert_eq!(AnteStructure::BigBlind.antes(1., 3), [0., 0., 1.]);
+        assert_eq!(AnteStructure::Button.antes(1., 2), [1., 0.]);
+        assert_eq!("bb".parse(), Ok(AnteStructure::BigBlind));
+        assert!("table".parse::<AnteStructure>().is_err());
+
+        // A single coin flip state with a big blind ante of 1
+        let tables =
+            PreflopTables::from_states(array![1.], array![0.5], [vec![vec![0]], vec![vec![0]]]);
+        let root = tables
+            .build_tree(
+                10.,
+                1.,
+                AnteStructure::BigBlind,
+                0.5,
+                &[SbAction::Shove, SbAction::Fold],
+                &Rake::default(),
+            )
+            .unwrap();
+        let payouts = |path: &[usize]| {
+            let mut node = root.as_ref();
+            for i in path {
+                node = node.children().unwrap()[*i].as_ref();
+            }
+            node.payouts().column(0).to_vec()
+        };
+        // BB folds to a shove and loses the blind and the ante
+        assert_eq!(payouts(&[0, 1]), [2., -2.]);
+        // A coin flip for the pot splits the ante
+        assert_eq!(payouts(&[0, 0]), [0.5, -0.5]);
+        // SB folds and loses only the small blind
+        assert_eq!(payouts(&[1]), [-0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_bubble_factors() {
+        // A single state where the SB wins a showdown 60% of the time
+        let tables =
+            PreflopTables::from_states(array![1.], array![0.6], [vec![vec![0]], vec![vec![0]]]);
+        let mut root = tables
+            .build_tree(
+                10.,
+                0.,
+                AnteStructure::Each,
+                0.5,
+                &[SbAction::Shove, SbAction::Fold],
+                &Rake::default(),
+            )
+            .unwrap();
+        apply_bubble_factors(root.as_mut(), &[2., 1.5]);
+        let children = root.children().unwrap();
+        // Only losses are scaled
+        let call = children[0].children().unwrap()[0]
+            .payouts()
+            .column(0)
+            .to_vec();
+        assert!((call[0] - 2.).abs() < 1e-6 && (call[1] + 3.).abs() < 1e-6);
+        assert_eq!(children[1].payouts().column(0).to_vec(), [-1., 0.5]);
+    }
+
+    #[test]
+    fn test_exploit_call_range() {
+        // The SB holds one of two hands, 80% and 20% against the BB's only hand
+        let tables = PreflopTables::from_states(
+            array![0.5, 0.5],
+            array![0.8, 0.2],
+            [vec![vec![0], vec![1]], vec![vec![0, 1]]],
+        );
+        let build = || {
+            tables
+                .build_tree(
+                    10.,
+                    0.,
+                    AnteStructure::Each,
+                    0.5,
+                    &[SbAction::Shove, SbAction::Fold],
+                    &Rake::default(),
+                )
+                .unwrap()
+        };
+
+        // Against a BB that always calls only the stronger hand shoves
+        let mut root = build();
+        assert_eq!(
+            exploit_call_range(root.as_mut(), &array![1.]),
+            array![1., 0.]
+        );
+        assert_eq!(root.avg_strategy().unwrap().row(0), array![1., 0.]);
+        assert_eq!(
+            root.children().unwrap()[0].avg_strategy().unwrap().row(0),
+            array![1.]
+        );
+        // Against one that never calls everything does
+        let mut root = build();
+        assert_eq!(
+            exploit_call_range(root.as_mut(), &array![0.]),
+            array![1., 1.]
+        );
+    }
+
+    #[test]
+    fn test_sb_actions() {
+        let actions: Vec<SbAction> = "fold,limp,shove,minraise"
+            .split(',')
+            .map(|x| x.parse().unwrap())
+            .collect();
+        assert!("raise".parse::<SbAction>().is_err());
+
+        let mut solver = CfrSolver::new(
+            build_preflop_tree(10., 0.125, 0.5, &actions).unwrap(),
+            SolverConfig {
+                max_iterations: 100,
+                ..Default::default()
+            },
+        );
+        let names: Vec<String> = solver
+            .root
+            .children()
+            .unwrap()
+            .iter()
+            .map(|x| x.name())
+            .collect();
+        assert_eq!(names, ["b", "r", "l", "f"]);
+
+        // After BB shoves over a minraise, SB loses the raise and ante by folding
+        let minraise = &solver.root.children().unwrap()[1];
+        let names: Vec<String> = minraise
+            .children()
+            .unwrap()
+            .iter()
+            .map(|x| x.name())
+            .collect();
+        assert_eq!(names, ["rb", "rc", "rf"]);
+        let fold = &minraise.children().unwrap()[0].children().unwrap()[1];
+        assert_eq!(fold.name(), "rbf");
+        assert!(fold.payouts().row(0).iter().all(|x| *x == -2.125));
+        assert!(fold.payouts().row(1).iter().all(|x| *x == 2.125));
+        solver.solve();
+
+        // The SB can always do at least as well as when restricted to shoving or folding
+        let mut push_fold = CfrSolver::new(
+            build_push_fold_tree(10., 0.125, 0.5).unwrap(),
+            SolverConfig {
+                max_iterations: 100,
+                ..Default::default()
+            },
+        );
+        push_fold.solve();
+        let value = |root: &dyn Node| best_response_value(root, 0);
+        assert!(value(solver.root.as_ref()) >= value(push_fold.root.as_ref()) - 1e-3);
+    }
+
+    #[test]
+    fn test_board() {
+        let board = parse_board("AhKd7c").unwrap();
+        assert_eq!(board.count_ones(), 3);
+        assert!(board & (1 << 49) != 0); // Ah
+        for invalid in ["AhKd", "AhKd7c2s3s4s", "AhAh7c", "AhKd7x", "AhKd7"] {
+            assert!(parse_board(invalid).is_err(), "{}", invalid);
+        }
+
+        // An ace on board leaves three combos of AA, and no AA vs AA
+        let board = parse_board("AsKhQd").unwrap();
+        let counts = count_matchups(board);
+        assert_eq!(counts[[168, 168]], 0);
+        assert_eq!(counts.row(168).sum(), 3 * 1081);
+        let tables = PreflopTables::on_board(board).unwrap();
+        assert_eq!(tables.equities[168 * 169 + 168], 0.5);
+        // A set of aces is far ahead of 22
+        assert!(tables.equities[168 * 169] > 0.9);
+    }
+
+    #[test]
+    fn test_preflop_order() {
+        use crate::poker::preflop::preflop_hand_from_cards;
+
+        let table = Array::from_shape_fn((169, 169), |(i, j)| (i, j));
+        let reordered = from_preflop_order(table.view());
+        for c1 in 1..52 {
+            for c2 in 0..c1 {
+                let (i, _) = reordered[[Hand::get_index(c1, c2), 0]];
+                assert_eq!(i, preflop_hand_from_cards(c1 as u8, c2 as u8) as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_chart() {
+        let chart = parse_chart("AA, AKs,72o:0.25").unwrap();
+        assert_eq!(chart.sum(), 2.25);
+        assert_eq!(chart[168], 1.);
+        assert_eq!(chart[Hand::get_index(48, 44)], 1.); // AKs
+        assert_eq!(chart[Hand::get_index(20, 1)], 0.25); // 72o
+        assert!(parse_chart("AKx").is_err());
+        assert!(parse_chart("AA:x").is_err());
+    }
+}
@@ -0,0 +1,319 @@
+//! Standard range notation ("22+,A2s+,K9s+,QTo+"), with optional weights, and 13x13 grids for
+//! strategies over the 169 hand classes, indexed like
+//! [`Hand::get_index`](crate::poker::push_fold::Hand::get_index)
+
+use crate::cfr::Float;
+use crate::poker::push_fold::Hand;
+
+use rust_poker::constants::RANK_TO_CHAR;
+
+use ndarray::*;
+
+/// Index of a pocket pair
+fn pair(rank: usize) -> usize {
+    13 * rank + rank
+}
+
+/// Index of an unpaired hand, suited or offsuit
+fn unpaired(high: usize, low: usize, suited: bool) -> usize {
+    match suited {
+        true => 13 * high + low,
+        false => 13 * low + high,
+    }
+}
+
+/// Runs of consecutive ranks in `ranks`, which must be in descending order, as (highest, lowest)
+fn runs(ranks: &[usize]) -> Vec<(usize, usize)> {
+    let mut result: Vec<(usize, usize)> = Vec::new();
+    for rank in ranks {
+        match result.last_mut() {
+            Some((_, low)) if *low == rank + 1 => *low = *rank,
+            _ => result.push((*rank, *rank)),
+        }
+    }
+    result
+}
+
+fn parse_rank(c: char) -> Option<usize> {
+    RANK_TO_CHAR.iter().position(|x| *x == c)
+}
+
+/// (high rank, low rank, suitedness) of a hand class like "AKs", "AK" or "TT"; suitedness is
+/// `None` for pairs and for unpaired hands given without a suffix, which stand for both
+fn parse_hand(hand: &str) -> Option<(usize, usize, Option<bool>)> {
+    let chars: Vec<char> = hand.chars().collect();
+    let (a, b) = (parse_rank(*chars.first()?)?, parse_rank(*chars.get(1)?)?);
+    let suited = match chars[2..] {
+        [] => None,
+        ['s'] if a != b => Some(true),
+        ['o'] if a != b => Some(false),
+        _ => return None,
+    };
+    Some((a.max(b), a.min(b), suited))
+}
+
+/// Indices of the hand classes with the given ranks, both suited and offsuit if `suited` is `None`
+fn hand_indices(high: usize, low: usize, suited: Option<bool>) -> Vec<usize> {
+    match suited {
+        _ if high == low => vec![pair(high)],
+        Some(x) => vec![unpaired(high, low, x)],
+        None => vec![unpaired(high, low, true), unpaired(high, low, false)],
+    }
+}
+
+/// Indices of the hand classes of one entry of a range: a hand ("AKs", "AK", "TT"), a hand and
+/// everything above it with the same high card ("A9s+", "TT+"), or an inclusive span of them
+/// ("K9s-K6s", "99-66")
+fn parse_entry(entry: &str) -> Option<Vec<usize>> {
+    if let Some(base) = entry.strip_suffix('+') {
+        let (high, low, suited) = parse_hand(base)?;
+        return Some(match high == low {
+            true => (low..13).map(pair).collect(),
+            false => (low..high)
+                .flat_map(|x| hand_indices(high, x, suited))
+                .collect(),
+        });
+    }
+    if let Some((from, to)) = entry.split_once('-') {
+        let (from, to) = (parse_hand(from)?, parse_hand(to)?);
+        let pairs = from.0 == from.1 && to.0 == to.1;
+        if !pairs && (from.0 != to.0 || from.2 != to.2 || from.1 == from.0 || to.1 == to.0) {
+            return None;
+        }
+        let (high, suited) = (from.0, from.2);
+        let (bottom, top) = (from.1.min(to.1), from.1.max(to.1));
+        return Some(match pairs {
+            true => (bottom..=top).map(pair).collect(),
+            false => (bottom..=top)
+                .flat_map(|x| hand_indices(high, x, suited))
+                .collect(),
+        });
+    }
+    let (high, low, suited) = parse_hand(entry)?;
+    Some(hand_indices(high, low, suited))
+}
+
+/// Parses a range like "22+, ATs+:0.5, KQo" into the weight of every hand class. Each entry has a
+/// weight of 1 unless given one after a colon, later entries override earlier ones, and hands
+/// that aren't listed are 0. Charts printed by the solver ("AA,AKs:0.500") are ranges too.
+pub fn parse_range(range: &str) -> Result<Array<Float, Ix1>, String> {
+    let mut result = Array::zeros(169);
+    for entry in range.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        let (hands, weight) = match entry.split_once(':') {
+            Some((hands, weight)) => (
+                hands,
+                weight
+                    .parse::<Float>()
+                    .ok()
+                    .filter(|x| (0. ..=1.).contains(x))
+                    .ok_or_else(|| format!("Invalid weight: {}", entry))?,
+            ),
+            None => (entry, 1.),
+        };
+        for index in parse_entry(hands).ok_or_else(|| format!("Invalid range entry: {}", entry))? {
+            result[index] = weight;
+        }
+    }
+    Ok(result)
+}
+
+/// Collapses the hands played at least `threshold` of the time into range notation: pairs, then
+/// suited and offsuit hands by descending high card. Runs reaching the top ("TT+", "K9s+") use a
+/// plus, and other runs a dash ("99-66", "K8s-K5s").
+pub fn to_range_notation(frequencies: ArrayView1<Float>, threshold: Float) -> String {
+    let included = |index: usize| frequencies[index] >= threshold;
+    let mut result: Vec<String> = Vec::new();
+
+    let pairs: Vec<usize> = (0..13).rev().filter(|x| included(pair(*x))).collect();
+    for (high, low) in runs(&pairs) {
+        let name = |rank: usize| format!("{}{}", RANK_TO_CHAR[rank], RANK_TO_CHAR[rank]);
+        result.push(if high == low {
+            name(high)
+        } else if high == 12 {
+            format!("{}+", name(low))
+        } else {
+            format!("{}-{}", name(high), name(low))
+        });
+    }
+
+    for suited in [true, false] {
+        let suffix = if suited { 's' } else { 'o' };
+        for high in (1..13).rev() {
+            let kickers: Vec<usize> = (0..high)
+                .rev()
+                .filter(|x| included(unpaired(high, *x, suited)))
+                .collect();
+            for (top, bottom) in runs(&kickers) {
+                let name = |kicker: usize| {
+                    format!("{}{}{}", RANK_TO_CHAR[high], RANK_TO_CHAR[kicker], suffix)
+                };
+                result.push(if top == bottom {
+                    name(top)
+                } else if top == high - 1 {
+                    format!("{}+", name(bottom))
+                } else {
+                    format!("{}-{}", name(top), name(bottom))
+                });
+            }
+        }
+    }
+    result.join(",")
+}
+
+/// Hand class in a row and column of the grid, with aces first: pairs on the diagonal, suited hands
+/// above it and offsuit hands below it. Either way the row gives the first rank of the hand's name.
+fn grid_index(row: usize, col: usize) -> usize {
+    13 * (12 - row) + (12 - col)
+}
+
+/// RGB color of a frequency, from grey at 0 to green at 1
+fn color(frequency: Float) -> (u8, u8, u8) {
+    let x = frequency.clamp(0., 1.);
+    let blend = |from: Float, to: Float| (from + (to - from) * x).round() as u8;
+    (blend(96., 32.), blend(96., 176.), blend(96., 64.))
+}
+
+/// Renders the canonical 13x13 hand grid. With `color`, each cell is the hand's name on a
+/// background shaded by its frequency, using 24-bit terminal colors; otherwise it's the frequency.
+pub fn render_grid(frequencies: ArrayView1<Float>, color_cells: bool) -> String {
+    let mut result = String::new();
+    for row in 0..13 {
+        let cells: Vec<String> = (0..13)
+            .map(|col| {
+                let index = grid_index(row, col);
+                if color_cells {
+                    let (r, g, b) = color(frequencies[index]);
+                    format!(
+                        "\x1b[48;2;{};{};{}m{:<4}\x1b[0m",
+                        r,
+                        g,
+                        b,
+                        Hand::index_to_str(index)
+                    )
+                } else {
+                    format!("{:.2}", frequencies[index])
+                }
+            })
+            .collect();
+        result.push_str(&cells.join(if color_cells { "" } else { " " }));
+        result.push('\n');
+    }
+    result
+}
+
+/// Renders the 13x13 hand grid as an SVG heatmap, laid out like [`render_grid`]
+pub fn render_svg(frequencies: ArrayView1<Float>) -> String {
+    const CELL: usize = 40;
+    let mut result = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" \
+         font-family=\"sans-serif\" font-size=\"12\" text-anchor=\"middle\">\n",
+        13 * CELL
+    );
+    for row in 0..13 {
+        for col in 0..13 {
+            let index = grid_index(row, col);
+            let (r, g, b) = color(frequencies[index]);
+            let (x, y) = (col * CELL, row * CELL);
+            result.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"rgb({},{},{})\" \
+                 stroke=\"black\"><title>{}: {:.3}</title></rect>\n",
+                x,
+                y,
+                CELL,
+                CELL,
+                r,
+                g,
+                b,
+                Hand::index_to_str(index),
+                frequencies[index]
+            ));
+            result.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" fill=\"white\">{}</text>\n",
+                x + CELL / 2,
+                y + CELL / 2 + 4,
+                Hand::index_to_str(index)
+            ));
+        }
+    }
+    result.push_str("</svg>\n");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_notation() {
+        let chart = "22,33,44,55,66,88,99,TT,JJ,QQ,KK,AA,A2s,A3s,A4s,A5s,A6s,A7s,A8s,A9s,ATs,AJs,\
+            AQs,AKs,K9s,KTs,KJs,KQs,Q6s,Q5s,Q2s,AQo,AKo,T9o:0.4,T8o";
+        let frequencies = parse_range(chart).unwrap();
+        assert_eq!(
+            to_range_notation(frequencies.view(), 0.5),
+            "88+,66-22,A2s+,K9s+,Q6s-Q5s,Q2s,AQo+,T8o"
+        );
+        assert_eq!(
+            to_range_notation(frequencies.view(), 0.3),
+            "88+,66-22,A2s+,K9s+,Q6s-Q5s,Q2s,AQo+,T8o+"
+        );
+        assert_eq!(to_range_notation(Array::zeros(169).view(), 0.5), "");
+        let everything = to_range_notation(Array::ones(169).view(), 0.5);
+        assert!(everything.starts_with("22+,A2s+,K2s+"));
+        assert!(everything.ends_with("42o+,32o"));
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let range = parse_range("22+, ATs+:0.5, KQo, 76, K9s-K7s,55:0").unwrap();
+        let weight = |name: &str| range[(0..169).find(|x| Hand::index_to_str(*x) == name).unwrap()];
+        assert_eq!(weight("22"), 1.);
+        assert_eq!(weight("AA"), 1.);
+        assert_eq!(weight("55"), 0.);
+        assert_eq!(weight("ATs"), 0.5);
+        assert_eq!(weight("AKs"), 0.5);
+        assert_eq!(weight("A9s"), 0.);
+        assert_eq!(weight("ATo"), 0.);
+        assert_eq!(weight("KQo"), 1.);
+        assert_eq!(weight("KQs"), 0.);
+        assert_eq!(weight("76s"), 1.);
+        assert_eq!(weight("76o"), 1.);
+        assert_eq!(weight("K8s"), 1.);
+        assert_eq!(weight("K6s"), 0.);
+        assert_eq!(range.sum(), 12. + 2. + 1. + 2. + 3.);
+
+        // The output of to_range_notation parses back to the same hands
+        let chart = parse_range("88+,66-22,A2s+,K9s+,Q6s-Q5s,Q2s,AQo+,T8o+").unwrap();
+        assert_eq!(
+            to_range_notation(chart.view(), 0.5),
+            "88+,66-22,A2s+,K9s+,Q6s-Q5s,Q2s,AQo+,T8o+"
+        );
+        assert_eq!(parse_range("AQ+").unwrap().sum(), 4.);
+
+        for invalid in [
+            "AKx", "AA:x", "AA:2", "KQs-J9s", "AKs-AQo", "1A", "AAs", "A",
+        ] {
+            assert!(parse_range(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_grid() {
+        let names: Vec<Vec<String>> = (0..13)
+            .map(|row| {
+                (0..13)
+                    .map(|col| Hand::index_to_str(grid_index(row, col)))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(names[0][..3], ["AA", "AKs", "AQs"]);
+        assert_eq!(names[1][..3], ["AKo", "KK", "KQs"]);
+        assert_eq!(names[12][12], "22");
+
+        let frequencies = parse_range("AA,AKs:0.5").unwrap();
+        let grid = render_grid(frequencies.view(), false);
+        assert_eq!(grid.lines().count(), 13);
+        assert!(grid.starts_with("1.00 0.50 0.00"));
+        assert!(render_grid(frequencies.view(), true).starts_with("\x1b[48;2;32;176;64mAA  "));
+        assert_eq!(render_svg(frequencies.view()).matches("<rect").count(), 169);
+    }
+}
@@ -0,0 +1,237 @@
+//! Three-handed push/fold: the button shoves or folds, the small blind calls a shove (or shoves
+//! when folded to) or folds, and the big blind calls or folds.
+//!
+//! Enumerating 169³ hand class triples with three-way equities is out of reach, so the tree is
+//! built over a Monte Carlo sample of deals instead. Each state is one deal of three hands and a
+//! board from a single deck, so card removal is accounted for, and states are equally likely. Each
+//! player's infosets group the deals by that player's hand class, as keyed infosets so that
+//! classes missing from the sample take up no space.
+//!
+//! Exploitability is measured against the sample, so a solve over too few deals fits the boards
+//! that happened to be dealt; use enough deals that every hand class sees many of them.
+
+use crate::cfr::*;
+use crate::error::SolverError;
+use crate::poker::push_fold::{AnteStructure, Hand};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rust_poker::hand_evaluator::{evaluate, Hand as EvalHand, CARDS};
+
+use ndarray::*;
+
+pub const BTN: usize = 0;
+pub const SB: usize = 1;
+pub const BB: usize = 2;
+
+/// Hole cards of each player, indexed by seat, and the board
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deal {
+    pub hands: [(u8, u8); 3],
+    pub board: [u8; 5],
+}
+
+impl Deal {
+    /// Hand class of a seat, in [`Hand::get_index`] order
+    pub fn class(&self, seat: usize) -> usize {
+        let (a, b) = self.hands[seat];
+        Hand::get_index(a.max(b) as usize, a.min(b) as usize)
+    }
+
+    /// Share of the pot won by each seat when `seats` go to showdown, splitting ties
+    pub fn showdown(&self, seats: &[usize]) -> [f64; 3] {
+        let board = self
+            .board
+            .iter()
+            .fold(EvalHand::default(), |f, x| f + CARDS[*x as usize]);
+        let ranks: Vec<u16> = seats
+            .iter()
+            .map(|seat| {
+                let (a, b) = self.hands[*seat];
+                evaluate(&(board + EvalHand::from_hole_cards(a, b)))
+            })
+            .collect();
+        let best = *ranks.iter().max().unwrap();
+        let n_winners = ranks.iter().filter(|x| **x == best).count();
+
+        let mut result = [0.; 3];
+        seats
+            .iter()
+            .zip(ranks.iter())
+            .filter(|(_, rank)| **rank == best)
+            .for_each(|(seat, _)| result[*seat] = 1. / n_winners as f64);
+        result
+    }
+}
+
+/// Deals `n` random hands and boards, reproducibly for a given seed
+pub fn sample_deals(n: usize, seed: u64) -> Vec<Deal> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut deck: Vec<u8> = (0..52).collect();
+    (0..n)
+        .map(|_| {
+            let (cards, _) = deck.partial_shuffle(&mut rng, 11);
+            Deal {
+                hands: [
+                    (cards[0], cards[1]),
+                    (cards[2], cards[3]),
+                    (cards[4], cards[5]),
+                ],
+                board: [cards[6], cards[7], cards[8], cards[9], cards[10]],
+            }
+        })
+        .collect()
+}
+
+/// Builds the three-handed push/fold tree over `deals`, with effective stacks of `stack_size` big
+/// blinds not counting the antes, which `ante_structure` says who posts. Players are indexed by
+/// seat ([`BTN`], [`SB`], [`BB`]), and every action node lists the aggressive action first.
+pub fn build_three_handed_tree(
+    deals: &[Deal],
+    stack_size: f64,
+    ante: f64,
+    ante_structure: AnteStructure,
+    sb: f64,
+) -> Result<Box<dyn Node>, SolverError> {
+    // Chips each seat puts in before acting: its ante plus any blind
+    let antes = ante_structure.antes(ante, 3);
+    let posted = [antes[BTN], sb + antes[SB], 1. + antes[BB]];
+    // Chips put in by each seat once `shoved` are all in
+    let all_in = |shoved: &[usize]| {
+        let mut contributions = posted;
+        for seat in shoved {
+            contributions[*seat] = stack_size + antes[*seat];
+        }
+        contributions
+    };
+
+    // Net payouts, indexed by player, deal, given what each seat put in and who goes to showdown
+    let payouts = |contributions: [f64; 3], showdown: &[usize]| -> Array<Float, Ix2> {
+        let pot: f64 = contributions.iter().sum();
+        let mut result = Array::zeros((3, deals.len()));
+        for (i, deal) in deals.iter().enumerate() {
+            let shares = match showdown {
+                [winner] => {
+                    let mut shares = [0.; 3];
+                    shares[*winner] = 1.;
+                    shares
+                }
+                _ => deal.showdown(showdown),
+            };
+            for seat in 0..3 {
+                result[[seat, i]] = (shares[seat] * pot - contributions[seat]) as Float;
+            }
+        }
+        result
+    };
+
+    // Each player's infosets are keyed by their hand class
+    let infosets = |seat: usize| {
+        let mut keyed = KeyedInfosets::new();
+        for (i, deal) in deals.iter().enumerate() {
+            keyed.insert(deal.class(seat) as InfosetKey, i);
+        }
+        keyed
+    };
+    let action =
+        |name: &str, seat: usize| TreeBuilder::action(name, seat).keyed_infosets(infosets(seat));
+
+    Ok(action("root", BTN)
+        .child(
+            action("s", SB)
+                .child(
+                    action("sc", BB)
+                        .terminal("scc", payouts(all_in(&[BTN, SB, BB]), &[BTN, SB, BB]))
+                        .terminal("scf", payouts(all_in(&[BTN, SB]), &[BTN, SB])),
+                )
+                .child(
+                    action("sf", BB)
+                        .terminal("sfc", payouts(all_in(&[BTN, BB]), &[BTN, BB]))
+                        .terminal("sff", payouts(all_in(&[BTN]), &[BTN])),
+                ),
+        )
+        .child(
+            action("f", SB)
+                .child(
+                    action("fs", BB)
+                        .terminal("fsc", payouts(all_in(&[SB, BB]), &[SB, BB]))
+                        .terminal("fsf", payouts(all_in(&[SB]), &[SB])),
+                )
+                .terminal("ff", payouts(posted, &[BB])),
+        )
+        .build()?)
+}
+
+/// Frequency of the first (aggressive) action of each hand class at an action node with keyed
+/// infosets, indexed like [`Hand::get_index`]; classes that were never dealt are 0
+pub fn class_frequencies(node: &dyn Node) -> Option<Array<Float, Ix1>> {
+    let node = match node.as_node_ref() {
+        NodeRef::Action(x) => x,
+        _ => return None,
+    };
+    Some(Array::from_shape_fn(169, |class| {
+        node.infoset_index(class as InfosetKey)
+            .map_or(0., |infoset| node.avg_strategy[[0, infoset]])
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_showdown() {
+        // AcAd and AhAs chop with aces full, KcKd loses
+        let deal = Deal {
+            hands: [(51, 50), (49, 48), (47, 46)],
+            board: [0, 1, 2, 4, 8],
+        };
+        assert_eq!(deal.showdown(&[BTN, SB, BB]), [0.5, 0.5, 0.]);
+        assert_eq!(deal.showdown(&[SB, BB]), [0., 1., 0.]);
+        assert_eq!(deal.class(BTN), 168);
+    }
+
+    #[test]
+    fn test_three_handed_tree() {
+        let deals = sample_deals(500, 0);
+        assert_eq!(deals, sample_deals(500, 0));
+        for deal in &deals {
+            let mut cards: Vec<u8> = deal.hands.iter().flat_map(|(a, b)| [*a, *b]).collect();
+            cards.extend(deal.board);
+            cards.sort();
+            cards.dedup();
+            assert_eq!(cards.len(), 11);
+        }
+
+        let mut solver = CfrSolver::new(
+            build_three_handed_tree(&deals, 10., 0.125, AnteStructure::Each, 0.5).unwrap(),
+            SolverConfig {
+                max_iterations: 50,
+                ..Default::default()
+            },
+        );
+        // Every terminal is zero-sum
+        let mut stack = vec![solver.root.as_ref()];
+        while let Some(node) = stack.pop() {
+            match node.children() {
+                Some(children) => stack.extend(children.iter().map(|x| x.as_ref())),
+                None => assert!(node
+                    .payouts()
+                    .sum_axis(Axis(0))
+                    .iter()
+                    .all(|x| x.abs() < 1e-9)),
+            }
+        }
+
+        let initial = exploitability(solver.root.as_ref());
+        solver.solve();
+        assert!(exploitability(solver.root.as_ref()) < initial);
+
+        // Nobody folds aces
+        let btn = class_frequencies(solver.root.as_ref()).unwrap();
+        if deals.iter().any(|x| x.class(BTN) == 168) {
+            assert!(btn[168] > 0.9);
+        }
+    }
+}
@@ -0,0 +1,408 @@
+//! The repeated prisoner's dilemma and Axelrod-style tournaments between strategies for it.
+//!
+//! Every pair of [`Strategy`]s, including each against itself, plays a match of a fixed number of
+//! rounds, in which each move may be flipped by noise. The average score per round of each
+//! strategy against each other one gives the standings, and also the fitness in the ecological
+//! variant, where the strategies are species whose shares of a population grow in proportion to
+//! how well they score against the current population, so that strategies thriving only on
+//! exploitable ones die out along with them.
+
+use std::fmt::Display;
+
+use ndarray::*;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+/// Move in one round of the prisoner's dilemma
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Move {
+    Cooperate,
+    Defect,
+}
+
+impl Move {
+    fn flipped(self) -> Self {
+        match self {
+            Move::Cooperate => Move::Defect,
+            Move::Defect => Move::Cooperate,
+        }
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Move::Cooperate => write!(f, "C"),
+            Move::Defect => write!(f, "D"),
+        }
+    }
+}
+
+/// Payoffs of one round, with `temptation > reward > punishment > sucker`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Payoffs {
+    /// Both cooperate
+    pub reward: f64,
+    /// Cooperating against a defection
+    pub sucker: f64,
+    /// Defecting against a cooperation
+    pub temptation: f64,
+    /// Both defect
+    pub punishment: f64,
+}
+
+impl Default for Payoffs {
+    /// Axelrod's payoffs
+    fn default() -> Self {
+        Payoffs {
+            reward: 3.,
+            sucker: 0.,
+            temptation: 5.,
+            punishment: 1.,
+        }
+    }
+}
+
+impl Payoffs {
+    /// Payoff of the player making `own` against `opponent`
+    pub fn payoff(&self, own: Move, opponent: Move) -> f64 {
+        match (own, opponent) {
+            (Move::Cooperate, Move::Cooperate) => self.reward,
+            (Move::Cooperate, Move::Defect) => self.sucker,
+            (Move::Defect, Move::Cooperate) => self.temptation,
+            (Move::Defect, Move::Defect) => self.punishment,
+        }
+    }
+}
+
+/// Rule choosing a move from the moves so far in a match
+pub trait Strategy {
+    fn name(&self) -> String;
+
+    /// Next move given the player's own moves and the opponent's, as actually played
+    fn play(&self, own: &[Move], opponent: &[Move], rng: &mut dyn RngCore) -> Move;
+}
+
+pub struct AlwaysCooperate;
+
+impl Strategy for AlwaysCooperate {
+    fn name(&self) -> String {
+        "AlwaysCooperate".to_string()
+    }
+
+    fn play(&self, _own: &[Move], _opponent: &[Move], _rng: &mut dyn RngCore) -> Move {
+        Move::Cooperate
+    }
+}
+
+pub struct AlwaysDefect;
+
+impl Strategy for AlwaysDefect {
+    fn name(&self) -> String {
+        "AlwaysDefect".to_string()
+    }
+
+    fn play(&self, _own: &[Move], _opponent: &[Move], _rng: &mut dyn RngCore) -> Move {
+        Move::Defect
+    }
+}
+
+/// Cooperates first, then repeats the opponent's last move
+pub struct TitForTat;
+
+impl Strategy for TitForTat {
+    fn name(&self) -> String {
+        "TitForTat".to_string()
+    }
+
+    fn play(&self, _own: &[Move], opponent: &[Move], _rng: &mut dyn RngCore) -> Move {
+        opponent.last().copied().unwrap_or(Move::Cooperate)
+    }
+}
+
+/// Cooperates until the opponent defects once, then defects forever
+pub struct GrimTrigger;
+
+impl Strategy for GrimTrigger {
+    fn name(&self) -> String {
+        "GrimTrigger".to_string()
+    }
+
+    fn play(&self, _own: &[Move], opponent: &[Move], _rng: &mut dyn RngCore) -> Move {
+        match opponent.contains(&Move::Defect) {
+            true => Move::Defect,
+            false => Move::Cooperate,
+        }
+    }
+}
+
+/// Win-stay, lose-shift: cooperates first, then repeats its last move after the opponent
+/// cooperated and switches after they defected, so it cooperates exactly when both players made
+/// the same move
+pub struct Pavlov;
+
+impl Strategy for Pavlov {
+    fn name(&self) -> String {
+        "Pavlov".to_string()
+    }
+
+    fn play(&self, own: &[Move], opponent: &[Move], _rng: &mut dyn RngCore) -> Move {
+        match (own.last(), opponent.last()) {
+            (Some(x), Some(y)) if x != y => Move::Defect,
+            _ => Move::Cooperate,
+        }
+    }
+}
+
+/// Cooperates with a fixed probability
+pub struct Random(pub f64);
+
+impl Strategy for Random {
+    fn name(&self) -> String {
+        format!("Random({})", self.0)
+    }
+
+    fn play(&self, _own: &[Move], _opponent: &[Move], rng: &mut dyn RngCore) -> Move {
+        match rng.gen::<f64>() < self.0 {
+            true => Move::Cooperate,
+            false => Move::Defect,
+        }
+    }
+}
+
+/// Strategy given by a function of the moves so far, for user-defined rules
+pub struct Custom<F> {
+    pub name: String,
+    pub rule: F,
+}
+
+impl<F: Fn(&[Move], &[Move]) -> Move> Strategy for Custom<F> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn play(&self, own: &[Move], opponent: &[Move], _rng: &mut dyn RngCore) -> Move {
+        (self.rule)(own, opponent)
+    }
+}
+
+/// Rules of a round-robin tournament
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tournament {
+    pub payoffs: Payoffs,
+    /// Rounds in each match
+    pub rounds: usize,
+    /// Probability that each move is flipped before it's played
+    pub noise: f64,
+    /// Matches between each pair, averaged
+    pub repetitions: usize,
+    pub seed: u64,
+}
+
+impl Default for Tournament {
+    fn default() -> Self {
+        Tournament {
+            payoffs: Payoffs::default(),
+            rounds: 200,
+            noise: 0.,
+            repetitions: 1,
+            seed: 0,
+        }
+    }
+}
+
+/// Average scores per round of every strategy of a tournament against every other
+#[derive(Clone, Debug, PartialEq)]
+pub struct TournamentResult {
+    pub names: Vec<String>,
+    /// Indexed by the scoring strategy, then its opponent
+    pub scores: Array<f64, Ix2>,
+}
+
+impl Tournament {
+    /// Total score of each player over one match
+    pub fn play_match(
+        &self,
+        first: &dyn Strategy,
+        second: &dyn Strategy,
+        rng: &mut dyn RngCore,
+    ) -> [f64; 2] {
+        let mut history: [Vec<Move>; 2] = [vec![], vec![]];
+        let mut scores = [0., 0.];
+        for _ in 0..self.rounds {
+            let moves = [
+                first.play(&history[0], &history[1], rng),
+                second.play(&history[1], &history[0], rng),
+            ]
+            .map(|x| match rng.gen::<f64>() < self.noise {
+                true => x.flipped(),
+                false => x,
+            });
+            scores[0] += self.payoffs.payoff(moves[0], moves[1]);
+            scores[1] += self.payoffs.payoff(moves[1], moves[0]);
+            history[0].push(moves[0]);
+            history[1].push(moves[1]);
+        }
+        scores
+    }
+
+    /// Plays every pair of strategies, each also against itself
+    pub fn round_robin(&self, strategies: &[Box<dyn Strategy>]) -> TournamentResult {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let n = strategies.len();
+        let mut scores = Array::zeros((n, n));
+        let per_match = (self.rounds * self.repetitions) as f64;
+        for i in 0..n {
+            for j in i..n {
+                for _ in 0..self.repetitions {
+                    let [first, second] =
+                        self.play_match(strategies[i].as_ref(), strategies[j].as_ref(), &mut rng);
+                    // Against itself, a strategy scores the average of both sides
+                    match i == j {
+                        true => scores[[i, i]] += (first + second) / 2. / per_match,
+                        false => {
+                            scores[[i, j]] += first / per_match;
+                            scores[[j, i]] += second / per_match;
+                        }
+                    }
+                }
+            }
+        }
+        TournamentResult {
+            names: strategies.iter().map(|x| x.name()).collect(),
+            scores,
+        }
+    }
+}
+
+impl TournamentResult {
+    /// Each strategy with its average score per round over all its opponents, best first
+    pub fn standings(&self) -> Vec<(String, f64)> {
+        let mut standings: Vec<(String, f64)> = self
+            .names
+            .iter()
+            .cloned()
+            .zip(self.scores.mean_axis(Axis(1)).unwrap())
+            .collect();
+        standings.sort_by(|a, b| b.1.total_cmp(&a.1));
+        standings
+    }
+
+    /// Share of a population playing each strategy in each generation, starting from equal
+    /// shares, where a share grows in proportion to the strategy's average score against the
+    /// population. Scores must be positive.
+    pub fn ecological(&self, generations: usize) -> Array<f64, Ix2> {
+        let n = self.names.len();
+        let mut shares = Array::zeros((generations + 1, n));
+        shares.row_mut(0).fill(1. / n as f64);
+        for generation in 0..generations {
+            let current = shares.row(generation).to_owned();
+            let next = &current * &self.scores.dot(&current);
+            let total = next.sum();
+            shares.row_mut(generation + 1).assign(&(next / total));
+        }
+        shares
+    }
+}
+
+impl Display for TournamentResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (rank, (name, score)) in self.standings().iter().enumerate() {
+            writeln!(f, "{:>2}. {:<20} {:.3}", rank + 1, name, score)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match() {
+        let tournament = Tournament {
+            rounds: 10,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        // Tit for tat is exploited once, then defects back
+        assert_eq!(
+            tournament.play_match(&TitForTat, &AlwaysDefect, &mut rng),
+            [9., 14.]
+        );
+        assert_eq!(
+            tournament.play_match(&Pavlov, &TitForTat, &mut rng),
+            [30., 30.]
+        );
+        // Pavlov alternates against a defector, cooperating after each mutual defection
+        assert_eq!(
+            tournament.play_match(&Pavlov, &AlwaysDefect, &mut rng),
+            [5., 30.]
+        );
+        // Suspicious tit for tat, defecting first, never gets out of step with tit for tat
+        let suspicious = Custom {
+            name: "SuspiciousTitForTat".to_string(),
+            rule: |_: &[Move], opponent: &[Move]| opponent.last().copied().unwrap_or(Move::Defect),
+        };
+        assert_eq!(
+            tournament.play_match(&suspicious, &TitForTat, &mut rng),
+            [25., 25.]
+        );
+        assert_eq!(Random(0.5).name(), "Random(0.5)");
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let strategies: Vec<Box<dyn Strategy>> = vec![
+            Box::new(AlwaysCooperate),
+            Box::new(AlwaysDefect),
+            Box::new(TitForTat),
+            Box::new(GrimTrigger),
+        ];
+        let result = Tournament::default().round_robin(&strategies);
+        assert_eq!(result.scores[[1, 2]], (5. + 199.) / 200.);
+        let standings = result.standings();
+        let names: Vec<&str> = standings.iter().map(|x| x.0.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "TitForTat",
+                "GrimTrigger",
+                "AlwaysCooperate",
+                "AlwaysDefect"
+            ]
+        );
+        assert!((standings[0].1 - (3. + 0.995 + 3. + 3.) / 4.).abs() < 1e-9);
+
+        // Defectors die out, after taking some of the unconditional cooperators with them
+        let shares = result.ecological(200);
+        assert!(shares
+            .rows()
+            .into_iter()
+            .all(|x| (x.sum() - 1.).abs() < 1e-9));
+        assert!(shares[[200, 0]] < shares[[200, 2]]);
+        assert!(shares[[200, 1]] < 1e-3);
+        assert!(shares[[200, 2]] > 0.3 && shares[[200, 3]] > 0.3);
+    }
+
+    #[test]
+    fn test_noise() {
+        // A flipped move sets tit for tat retaliating against itself, while Pavlov recovers
+        let tournament = Tournament {
+            rounds: 1000,
+            noise: 0.05,
+            repetitions: 5,
+            ..Default::default()
+        };
+        let strategies: Vec<Box<dyn Strategy>> = vec![Box::new(TitForTat), Box::new(Pavlov)];
+        let result = tournament.round_robin(&strategies);
+        assert!(
+            result.scores[[1, 1]] > result.scores[[0, 0]] + 0.2,
+            "{}",
+            result.scores
+        );
+        assert!(result.scores[[0, 0]] < 2.7);
+        // The same seed plays the same tournament
+        assert_eq!(tournament.round_robin(&strategies), result);
+    }
+}
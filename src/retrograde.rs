@@ -0,0 +1,218 @@
+//! Retrograde analysis: complete solution tables for games small enough to enumerate.
+//!
+//! [`Tablebase::build`] collects every position reachable from a start position, then works
+//! backwards from the finished games. A position is won once any move leads to a position lost for
+//! the opponent, and lost once every move leads to a position won for them. Positions never
+//! resolved this way are draws. Working outward from the end of the game gives every win and loss
+//! with its distance, fastest wins and slowest losses, without any forward minimax search.
+
+use crate::search::{GameState, Outcome};
+
+use std::collections::VecDeque;
+use std::hash::Hash;
+
+use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+/// Result of perfect play for the player to move
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Value {
+    /// Won in this many moves
+    Win(u32),
+    /// Lost in this many moves, holding out as long as possible
+    Loss(u32),
+    Draw,
+}
+
+impl Value {
+    /// Orders values for the player to move: faster wins first, then draws, then slower losses
+    fn rank(&self) -> i64 {
+        match self {
+            Value::Win(d) => i64::MAX - *d as i64,
+            Value::Draw => 0,
+            Value::Loss(d) => i64::MIN + 1 + *d as i64,
+        }
+    }
+}
+
+/// Value of every position reachable from a start position
+pub struct Tablebase<G> {
+    values: HashMap<G, Value>,
+}
+
+impl<G> Tablebase<G>
+where
+    G: GameState + Eq + Hash,
+{
+    /// Solves every position reachable from `root`
+    pub fn build(root: &G) -> Self {
+        // Enumerate positions and the moves between them
+        let mut index: HashMap<G, usize> = HashMap::new();
+        let mut states = vec![root.clone()];
+        let mut parents: Vec<Vec<usize>> = vec![vec![]];
+        let mut children = vec![0usize];
+        index.insert(root.clone(), 0);
+        let mut next = 0;
+        while next < states.len() {
+            let state = states[next].clone();
+            if state.outcome().is_none() {
+                for action in state.legal_moves() {
+                    let mut child = state.clone();
+                    if child.play(action).is_err() {
+                        continue;
+                    }
+                    let child_index = match index.get(&child) {
+                        Some(x) => *x,
+                        None => {
+                            index.insert(child.clone(), states.len());
+                            states.push(child);
+                            parents.push(vec![]);
+                            children.push(0);
+                            states.len() - 1
+                        }
+                    };
+                    parents[child_index].push(next);
+                    children[next] += 1;
+                }
+            }
+            next += 1;
+        }
+
+        // Resolve finished games, then propagate outward in order of distance, so each position
+        // takes the fastest win or slowest loss available
+        let mut values: Vec<Option<Value>> = vec![None; states.len()];
+        let mut queue = VecDeque::new();
+        for (i, state) in states.iter().enumerate() {
+            values[i] = match state.outcome() {
+                Some(Outcome::Win(x)) if x == state.current_player() => Some(Value::Win(0)),
+                Some(Outcome::Win(_)) => Some(Value::Loss(0)),
+                Some(Outcome::Draw) => Some(Value::Draw),
+                None => None,
+            };
+            if matches!(values[i], Some(Value::Win(_) | Value::Loss(_))) {
+                queue.push_back(i);
+            }
+        }
+        // Moves of each position not yet known to lose
+        let mut remaining = children;
+        while let Some(child) = queue.pop_front() {
+            let player = states[child].current_player();
+            for &parent in &parents[child] {
+                if values[parent].is_some() {
+                    continue;
+                }
+                // Value of the child for the player moving into it
+                let value = match (values[child], states[parent].current_player() == player) {
+                    (Some(Value::Win(d)), true) | (Some(Value::Loss(d)), false) => Value::Win(d),
+                    (Some(Value::Win(d)), false) | (Some(Value::Loss(d)), true) => Value::Loss(d),
+                    _ => continue,
+                };
+                match value {
+                    Value::Win(d) => {
+                        values[parent] = Some(Value::Win(d + 1));
+                        queue.push_back(parent);
+                    }
+                    Value::Loss(d) => {
+                        // A parent with several moves into the child counts each of them
+                        remaining[parent] -= 1;
+                        if remaining[parent] == 0 {
+                            values[parent] = Some(Value::Loss(d + 1));
+                            queue.push_back(parent);
+                        }
+                    }
+                    Value::Draw => {}
+                }
+            }
+        }
+
+        Tablebase {
+            values: states
+                .into_iter()
+                .zip(values)
+                .map(|(state, value)| (state, value.unwrap_or(Value::Draw)))
+                .collect(),
+        }
+    }
+
+    /// Value for the player to move, or None if the position isn't reachable from the root
+    pub fn value(&self, state: &G) -> Option<Value> {
+        self.values.get(state).copied()
+    }
+
+    /// A move keeping the value of the position: the fastest win, a draw, or the slowest loss,
+    /// preferring earlier legal moves among equals. None if the game is over or the position isn't
+    /// in the table.
+    pub fn best_move(&self, state: &G) -> Option<G::Move> {
+        self.value(state)?;
+        let mut best: Option<(G::Move, i64)> = None;
+        for action in state.legal_moves() {
+            let mut child = state.clone();
+            if child.play(action).is_err() {
+                continue;
+            }
+            let value = match (
+                self.value(&child)?,
+                child.current_player() == state.current_player(),
+            ) {
+                (value, true) => value,
+                (Value::Win(d), false) => Value::Loss(d),
+                (Value::Loss(d), false) => Value::Win(d),
+                (Value::Draw, false) => Value::Draw,
+            };
+            let rank = value.rank();
+            if best.is_none_or(|(_, x)| rank > x) {
+                best = Some((action, rank));
+            }
+        }
+        best.map(|x| x.0)
+    }
+
+    /// Number of positions in the table
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Every position with its value
+    pub fn iter(&self) -> impl Iterator<Item = (&G, &Value)> {
+        self.values.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tictactoe::{Board, SolutionTable};
+
+    #[test]
+    fn test_tictactoe() {
+        let tablebase = Tablebase::build(&Board::default());
+        assert_eq!(tablebase.len(), 5478);
+        assert_eq!(tablebase.value(&Board::default()), Some(Value::Draw));
+
+        // Agrees with minimax everywhere, including the distance to the end of the game
+        let mut solver = SolutionTable::default();
+        for (board, value) in tablebase.iter() {
+            let minimax = solver.value(board);
+            let empty = board.valid_moves().len() as i8;
+            let sign = match board.current_player() {
+                0 => 1,
+                _ => -1,
+            };
+            match *value {
+                Value::Draw => assert_eq!(minimax, 0),
+                Value::Win(d) => assert_eq!(minimax, sign * (empty - d as i8 + 1)),
+                Value::Loss(d) => assert_eq!(minimax, -sign * (empty - d as i8 + 1)),
+            }
+        }
+
+        let board: Board = "XX2OO5678".parse().unwrap();
+        assert_eq!(tablebase.value(&board), Some(Value::Win(1)));
+        assert_eq!(tablebase.best_move(&board), Some(2));
+        let board: Board = "XX2O45678".parse().unwrap();
+        assert_eq!(tablebase.best_move(&board), Some(2));
+    }
+}
@@ -0,0 +1,271 @@
+//! Game-agnostic search for two player, perfect information games.
+//!
+//! Games implement [`GameState`], and [`DepthLimitedSearch`] searches them with negamax and
+//! alpha-beta pruning to a fixed depth, scoring the positions where it stops with an
+//! [`Evaluator`]. This trades the exact solvers of the individual games for search that works on
+//! boards too large to solve.
+
+use crate::error::GameError;
+
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use hashbrown::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+/// How a finished game ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    /// Won by the player with this index
+    Win(usize),
+    Draw,
+}
+
+/// State of a two player game, with players numbered 0 for the first player and 1 for the second.
+/// Players usually alternate, but a move may give the same player another turn, as completing a box
+/// does in Dots and Boxes.
+pub trait GameState: Clone {
+    type Move: Copy + Debug + PartialEq;
+
+    /// Index of the player to move
+    fn current_player(&self) -> usize;
+
+    /// Moves available to the player to move, empty when the game is over
+    fn legal_moves(&self) -> Vec<Self::Move>;
+
+    /// Plays a move for the player to move
+    fn play(&mut self, action: Self::Move) -> Result<(), GameError>;
+
+    /// How the game ended, or None if it's still going
+    fn outcome(&self) -> Option<Outcome>;
+
+    /// Number of ways to play exactly `depth` more moves, not counting games that end sooner. Move
+    /// generation is checked by comparing these with known counts.
+    fn perft(&self, depth: usize) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if self.outcome().is_some() {
+            return 0;
+        }
+        self.perft_divide(depth).iter().map(|x| x.1).sum()
+    }
+
+    /// [`GameState::perft`] after each legal move, to narrow down which move a wrong count comes
+    /// from
+    fn perft_divide(&self, depth: usize) -> Vec<(Self::Move, u64)> {
+        if depth == 0 || self.outcome().is_some() {
+            return vec![];
+        }
+        self.legal_moves()
+            .into_iter()
+            .filter_map(|action| {
+                let mut child = self.clone();
+                child.play(action).ok()?;
+                Some((action, child.perft(depth - 1)))
+            })
+            .collect()
+    }
+
+    /// Number of distinct positions reachable from this one, itself included, counting positions
+    /// with the same `key` once. The key must give equal keys only to positions with equivalent
+    /// continuations, e.g. a symmetry-invariant hash.
+    fn count_positions<K, F>(&self, key: F) -> usize
+    where
+        K: Eq + Hash,
+        F: Fn(&Self) -> K,
+    {
+        let mut seen = HashSet::new();
+        seen.insert(key(self));
+        let mut queue = VecDeque::from([self.clone()]);
+        while let Some(state) = queue.pop_front() {
+            if state.outcome().is_some() {
+                continue;
+            }
+            for action in state.legal_moves() {
+                let mut child = state.clone();
+                if child.play(action).is_ok() && seen.insert(key(&child)) {
+                    queue.push_back(child);
+                }
+            }
+        }
+        seen.len()
+    }
+}
+
+/// Heuristic value of a position
+pub trait Evaluator<G: GameState> {
+    /// Value of an unfinished game for the player to move, positive when they're ahead. Must be
+    /// smaller in magnitude than [`WIN_SCORE`].
+    fn evaluate(&self, state: &G) -> f64;
+}
+
+/// Evaluates every position as even, so that depth-limited search only sees forced wins
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ZeroEvaluator;
+
+impl<G: GameState> Evaluator<G> for ZeroEvaluator {
+    fn evaluate(&self, _state: &G) -> f64 {
+        0.
+    }
+}
+
+/// Score of a won game, plus the depth left to search so that faster wins score higher
+pub const WIN_SCORE: f64 = 1e6;
+
+/// Negamax with alpha-beta pruning, stopping after a fixed number of moves
+pub struct DepthLimitedSearch<E> {
+    pub evaluator: E,
+    /// Moves searched ahead of the position
+    pub depth: usize,
+    /// Positions searched so far
+    pub nodes: u64,
+}
+
+impl<E> DepthLimitedSearch<E> {
+    pub fn new(evaluator: E, depth: usize) -> Self {
+        DepthLimitedSearch {
+            evaluator,
+            depth,
+            nodes: 0,
+        }
+    }
+
+    /// Best move for the player to move and its score, preferring earlier legal moves among equals.
+    /// None if the game is over.
+    pub fn best_move<G>(&mut self, state: &G) -> Option<(G::Move, f64)>
+    where
+        G: GameState,
+        E: Evaluator<G>,
+    {
+        if state.outcome().is_some() {
+            return None;
+        }
+        let mut best = None;
+        let mut alpha = f64::NEG_INFINITY;
+        for action in state.legal_moves() {
+            let mut child = state.clone();
+            child.play(action).ok()?;
+            let depth = self.depth.saturating_sub(1);
+            let score = match child.current_player() == state.current_player() {
+                true => self.negamax(&child, depth, alpha, f64::INFINITY),
+                false => -self.negamax(&child, depth, f64::NEG_INFINITY, -alpha),
+            };
+            if score > alpha {
+                alpha = score;
+                best = Some((action, score));
+            }
+        }
+        best
+    }
+
+    /// Score of the position for the player to move, searching `depth` more moves
+    pub fn score<G>(&mut self, state: &G) -> f64
+    where
+        G: GameState,
+        E: Evaluator<G>,
+    {
+        self.negamax(state, self.depth, f64::NEG_INFINITY, f64::INFINITY)
+    }
+
+    /// Score of the position if it's strictly between `alpha` and `beta`, and otherwise a bound on
+    /// it on the same side of the window
+    fn negamax<G>(&mut self, state: &G, depth: usize, mut alpha: f64, beta: f64) -> f64
+    where
+        G: GameState,
+        E: Evaluator<G>,
+    {
+        self.nodes += 1;
+        match state.outcome() {
+            Some(Outcome::Draw) => return 0.,
+            Some(Outcome::Win(player)) => {
+                let score = WIN_SCORE + depth as f64;
+                return match player == state.current_player() {
+                    true => score,
+                    false => -score,
+                };
+            }
+            None => {}
+        }
+        if depth == 0 {
+            return self.evaluator.evaluate(state);
+        }
+
+        let mut value = f64::NEG_INFINITY;
+        for action in state.legal_moves() {
+            let mut child = state.clone();
+            if child.play(action).is_err() {
+                continue;
+            }
+            // Scores are for the player to move, who may still be us after an extra turn
+            let score = match child.current_player() == state.current_player() {
+                true => self.negamax(&child, depth - 1, alpha, beta),
+                false => -self.negamax(&child, depth - 1, -beta, -alpha),
+            };
+            value = value.max(score);
+            alpha = alpha.max(value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tictactoe::{Board, SolutionTable};
+
+    #[test]
+    fn test_depth_limited_search() {
+        // Searching to the end of the game finds the exact result
+        let mut search = DepthLimitedSearch::new(ZeroEvaluator, 9);
+        assert_eq!(search.score(&Board::default()), 0.);
+
+        let mut solver = SolutionTable::default();
+        for board in ["X1O3X5678", "XO2345678", "0X2O4567X", "XX2OO5678"] {
+            let board: Board = board.parse().unwrap();
+            let value = solver.value(&board) as f64;
+            let value = match board.current_player() {
+                0 => value,
+                _ => -value,
+            };
+            assert_eq!(
+                search.score(&board).partial_cmp(&0.),
+                value.partial_cmp(&0.)
+            );
+        }
+
+        // X wins by completing the top row
+        let board: Board = "XX2OO5678".parse().unwrap();
+        let (action, score) = search.best_move(&board).unwrap();
+        assert_eq!(action, 2);
+        assert!(score > WIN_SCORE);
+
+        // Too shallow to see the win
+        let mut search = DepthLimitedSearch::new(ZeroEvaluator, 1);
+        assert_eq!(search.score(&"X1O3X5678".parse::<Board>().unwrap()), 0.);
+    }
+
+    #[test]
+    fn test_perft() {
+        // Games of tic-tac-toe still going after each move, less those won along the way
+        let board = Board::default();
+        let counts: Vec<u64> = (0..=9).map(|x| board.perft(x)).collect();
+        assert_eq!(
+            counts,
+            vec![1, 9, 72, 504, 3024, 15120, 54720, 148176, 200448, 127872]
+        );
+        let divide = board.perft_divide(2);
+        assert_eq!(divide.len(), 9);
+        assert!(divide.iter().all(|x| x.1 == 8));
+        assert_eq!(board.count_positions(|x| x.clone()), 5478);
+
+        // Connect Four can't be won or fill a column in the first five moves
+        let position = crate::connect4::Position::default();
+        assert_eq!(position.perft(5), 16807);
+    }
+}
@@ -0,0 +1,486 @@
+//! Exact equilibria of two player zero-sum game trees by linear programming over the sequence
+//! form, for checking what CFR converges to.
+//!
+//! A sequence is a player's own actions along a path, identified by its last (infoset, action)
+//! pair, and a realization plan gives each sequence the probability that the player's own
+//! choices follow it. With perfect recall these plans are exactly the behaviour strategies, and
+//! they satisfy linear constraints: the empty sequence has probability 1, and the actions of an
+//! infoset share the probability of the sequence leading to it. The expected payout is bilinear
+//! in the two plans, through a sparse matrix summing terminal payouts times chance probabilities
+//! over the pairs of sequences reaching them, so each player's maximin plan is a linear program
+//! of the same size as the tree rather than the exponentially larger normal form.
+
+use crate::cfr::{Float, Node, NodeRef, StrategyProfile, TreeError};
+use crate::error::SequenceFormError;
+
+use microlp::{ComparisonOp, OptimizationDirection, Problem, Variable};
+use ndarray::*;
+use std::collections::{BTreeMap, HashMap};
+
+/// Largest sum of the players' payouts that counts as zero
+const ZERO_SUM_TOLERANCE: f64 = 1e-6;
+/// Infosets reached with less probability than this get a uniform strategy
+const REALIZATION_TOLERANCE: f64 = 1e-12;
+
+/// Infoset of one player, whose actions extend the sequence leading to it
+#[derive(Clone, Debug)]
+struct Infoset {
+    /// Sequence leading to the infoset, 0 being the empty sequence
+    parent: usize,
+    /// Sequence ending with the first action, the other actions' following it
+    first: usize,
+    /// Whether each action is legal
+    legal: Vec<bool>,
+}
+
+/// Action node, and the path of the node whose infosets it shares: its own unless a hidden
+/// action leads to it, in which case its first sibling's
+#[derive(Clone, Debug)]
+struct ActionInfo {
+    path: String,
+    owner: String,
+    player: usize,
+    /// Legal actions, indexed by action, infoset
+    legal: Array<bool, Ix2>,
+}
+
+/// Sequences, infosets and payout matrix of a two player zero-sum tree
+#[derive(Clone, Debug, Default)]
+pub struct SequenceForm {
+    /// Infosets of each player
+    infosets: [Vec<Infoset>; 2],
+    /// Index in `infosets` of each player's infosets, keyed by owning node path and infoset
+    keys: [HashMap<(String, usize), usize>; 2],
+    /// Number of sequences of each player, including the empty one
+    sequences: [usize; 2],
+    /// First player's payout summed over the terminal states each pair of sequences reaches,
+    /// weighted by the probability of chance reaching them
+    payoffs: BTreeMap<(usize, usize), f64>,
+    nodes: Vec<ActionInfo>,
+}
+
+/// Equilibrium found by linear programming
+#[derive(Debug)]
+pub struct SequenceFormSolution {
+    /// Expected payout of the first player when both play optimally
+    pub value: f64,
+    /// Probability of each player's sequences in their realization plan
+    pub realization: [Array<f64, Ix1>; 2],
+    /// Behaviour strategy of every action node, keyed like [`StrategyProfile::from_tree`], so
+    /// that [`crate::cfr::load_strategies`] can load it into the tree
+    pub profile: StrategyProfile,
+}
+
+/// States reaching a node, with each player's last sequence and the probability of chance
+/// reaching them
+type Reach = Vec<(usize, [usize; 2], f64)>;
+
+/// Widens a tree's probability or payout for the linear program, which works in f64
+#[allow(clippy::unnecessary_cast)] // Float is f32 with the `f32` feature
+fn widen(x: Float) -> f64 {
+    x as f64
+}
+
+impl SequenceForm {
+    /// Builds the sequence form of a two player zero-sum tree with perfect recall
+    pub fn from_tree(root: &dyn Node) -> Result<Self, SequenceFormError> {
+        let players = root.payouts().nrows();
+        if players != 2 {
+            return Err(SequenceFormError::NotTwoPlayers(players));
+        }
+        let mut result = SequenceForm {
+            sequences: [1, 1],
+            ..Default::default()
+        };
+        let reach = root
+            .state_probabilities()
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| **p > 0.)
+            .map(|(state, p)| (state, [0, 0], widen(*p)))
+            .collect();
+        let path = root.name();
+        result.walk(root, &path, &path, reach)?;
+        Ok(result)
+    }
+
+    /// Number of sequences of each player, including the empty one
+    pub fn sequences(&self) -> [usize; 2] {
+        self.sequences
+    }
+
+    /// Adds the infosets, sequences and payouts of a subtree
+    fn walk(
+        &mut self,
+        node: &dyn Node,
+        path: &str,
+        owner: &str,
+        reach: Reach,
+    ) -> Result<(), SequenceFormError> {
+        let child_path = |child: &dyn Node| format!("{}/{}", path, child.name());
+        match node.as_node_ref() {
+            NodeRef::Terminal(x) => {
+                for (state, sequences, probability) in reach {
+                    let payouts = x.payouts.column(state);
+                    if widen((payouts[0] + payouts[1]).abs()) > ZERO_SUM_TOLERANCE {
+                        return Err(SequenceFormError::NotZeroSum {
+                            path: path.to_string(),
+                        });
+                    }
+                    *self
+                        .payoffs
+                        .entry((sequences[0], sequences[1]))
+                        .or_default() += probability * widen(payouts[0]);
+                }
+                Ok(())
+            }
+            NodeRef::Chance(x) => {
+                for (outcome, child) in x.children.iter().enumerate() {
+                    let child_reach = reach
+                        .iter()
+                        .map(|(state, sequences, probability)| {
+                            let p = widen(x.outcome_probabilities[[outcome, *state]]);
+                            (*state, *sequences, probability * p)
+                        })
+                        .filter(|(_, _, probability)| *probability > 0.)
+                        .collect();
+                    let child_path = child_path(child.as_ref());
+                    self.walk(child.as_ref(), &child_path, &child_path, child_reach)?;
+                }
+                Ok(())
+            }
+            NodeRef::Action(x) => {
+                let player = x.player;
+                let legal = x
+                    .action_mask
+                    .clone()
+                    .unwrap_or_else(|| Array::from_elem(x.avg_strategy.raw_dim(), true));
+                self.nodes.push(ActionInfo {
+                    path: path.to_string(),
+                    owner: owner.to_string(),
+                    player,
+                    legal: legal.clone(),
+                });
+
+                let mut infoset_of = vec![None; x.state_probabilities.len()];
+                for (infoset, states) in x.infosets.iter().enumerate() {
+                    for state in states {
+                        infoset_of[*state] = Some(infoset);
+                    }
+                }
+                // Index in `infosets` of the infoset of each state reaching the node
+                let mut indices = Vec::with_capacity(reach.len());
+                for (state, sequences, _) in &reach {
+                    let Some(infoset) = infoset_of[*state] else {
+                        indices.push(None);
+                        continue;
+                    };
+                    let key = (owner.to_string(), infoset);
+                    let index = match self.keys[player].get(&key) {
+                        Some(x) => *x,
+                        None => {
+                            self.infosets[player].push(Infoset {
+                                parent: sequences[player],
+                                first: self.sequences[player],
+                                legal: legal.column(infoset).to_vec(),
+                            });
+                            self.sequences[player] += x.children.len();
+                            self.keys[player].insert(key, self.infosets[player].len() - 1);
+                            self.infosets[player].len() - 1
+                        }
+                    };
+                    if self.infosets[player][index].parent != sequences[player] {
+                        return Err(SequenceFormError::ImperfectRecall {
+                            path: path.to_string(),
+                            infoset,
+                        });
+                    }
+                    indices.push(Some(index));
+                }
+
+                let first_child = x
+                    .children
+                    .first()
+                    .map(|x| child_path(x.as_ref()))
+                    .unwrap_or_default();
+                for (action, child) in x.children.iter().enumerate() {
+                    let child_reach = reach
+                        .iter()
+                        .zip(&indices)
+                        .filter_map(|((state, sequences, probability), index)| {
+                            let mut sequences = *sequences;
+                            sequences[player] = self.infosets[player][(*index)?].first + action;
+                            Some((*state, sequences, *probability))
+                        })
+                        .collect();
+                    let child_path = child_path(child.as_ref());
+                    let child_owner = match x.hidden {
+                        true => &first_child,
+                        false => &child_path,
+                    };
+                    self.walk(child.as_ref(), &child_path, child_owner, child_reach)?;
+                }
+                Ok(())
+            }
+            NodeRef::Arena(_) => Err(TreeError::Flattened {
+                path: path.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    /// Whether each of the player's sequences ends with a legal action
+    fn legal_sequences(&self, player: usize) -> Vec<bool> {
+        let mut result = vec![true; self.sequences[player]];
+        for infoset in &self.infosets[player] {
+            for (action, legal) in infoset.legal.iter().enumerate() {
+                result[infoset.first + action] = *legal;
+            }
+        }
+        result
+    }
+
+    /// Value to the player of their maximin realization plan, and the plan
+    fn maximin(&self, player: usize) -> Result<(f64, Array<f64, Ix1>), SequenceFormError> {
+        let opponent = 1 - player;
+        let mut problem = Problem::new(OptimizationDirection::Maximize);
+        let realization: Vec<Variable> = self
+            .legal_sequences(player)
+            .into_iter()
+            .map(|legal| match legal {
+                true => problem.add_var(0., (0., f64::INFINITY)),
+                false => problem.add_var(0., (0., 0.)),
+            })
+            .collect();
+        // Value of the game, then of each of the opponent's infosets, to the player when the
+        // opponent best responds
+        let values: Vec<Variable> = (0..=self.infosets[opponent].len())
+            .map(|x| {
+                let objective = match x {
+                    0 => 1.,
+                    _ => 0.,
+                };
+                problem.add_var(objective, (f64::NEG_INFINITY, f64::INFINITY))
+            })
+            .collect();
+
+        problem.add_constraint([(realization[0], 1.)], ComparisonOp::Eq, 1.);
+        for infoset in &self.infosets[player] {
+            let mut terms = vec![(realization[infoset.parent], -1.)];
+            terms.extend((0..infoset.legal.len()).map(|x| (realization[infoset.first + x], 1.)));
+            problem.add_constraint(&terms, ComparisonOp::Eq, 0.);
+        }
+
+        // No sequence of the opponent does better against the plan than the values allow
+        let mut constraints: Vec<Vec<(Variable, f64)>> = vec![Vec::new(); self.sequences[opponent]];
+        constraints[0].push((values[0], 1.));
+        for (index, infoset) in self.infosets[opponent].iter().enumerate() {
+            constraints[infoset.parent].push((values[index + 1], -1.));
+            for action in 0..infoset.legal.len() {
+                constraints[infoset.first + action].push((values[index + 1], 1.));
+            }
+        }
+        for ((first, second), payoff) in &self.payoffs {
+            let (own, other, payoff) = match player {
+                0 => (*first, *second, *payoff),
+                _ => (*second, *first, -payoff),
+            };
+            constraints[other].push((realization[own], -payoff));
+        }
+        for (terms, legal) in constraints.iter().zip(self.legal_sequences(opponent)) {
+            // The opponent can't play an illegal sequence, so it can't be a better response
+            if legal {
+                problem.add_constraint(terms, ComparisonOp::Le, 0.);
+            }
+        }
+
+        let solution = problem
+            .solve()
+            .map_err(|e| SequenceFormError::LinearProgram(e.to_string()))?;
+        let plan = realization.iter().map(|x| solution[*x].max(0.)).collect();
+        Ok((solution.objective(), plan))
+    }
+
+    /// Solves each player's linear program for an equilibrium
+    pub fn solve(&self) -> Result<SequenceFormSolution, SequenceFormError> {
+        let (value, first) = self.maximin(0)?;
+        let (_, second) = self.maximin(1)?;
+        let realization = [first, second];
+
+        let mut profile = StrategyProfile::default();
+        for node in &self.nodes {
+            let (actions, infosets) = node.legal.dim();
+            let mut strategy = Array::zeros((actions, infosets));
+            for infoset in 0..infosets {
+                let legal = node.legal.column(infoset);
+                let key = (node.owner.clone(), infoset);
+                let reached = self.keys[node.player]
+                    .get(&key)
+                    .map(|x| &self.infosets[node.player][*x])
+                    .filter(|x| realization[node.player][x.parent] > REALIZATION_TOLERANCE);
+                let weights: Array<f64, Ix1> = match reached {
+                    Some(x) => realization[node.player]
+                        .slice(s![x.first..x.first + actions])
+                        .to_owned(),
+                    None => legal.mapv(|x| match x {
+                        true => 1.,
+                        false => 0.,
+                    }),
+                };
+                let total = weights.sum();
+                strategy
+                    .column_mut(infoset)
+                    .assign(&weights.mapv(|x| (x / total) as Float));
+            }
+            profile.strategies.insert(node.path.clone(), strategy);
+        }
+        Ok(SequenceFormSolution {
+            value,
+            realization,
+            profile,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cfr::*;
+    use crate::matrix_game::MatrixGame;
+    use crate::normal_form::NormalFormGame;
+
+    /// Kuhn poker with three cards, states being the ordered pairs of different cards
+    fn kuhn_tree() -> Box<dyn Node> {
+        let states: Vec<(usize, usize)> = (0..3)
+            .flat_map(|a| (0..3).filter(move |b| *b != a).map(move |b| (a, b)))
+            .collect();
+        let infosets = |player: usize| -> Vec<Vec<usize>> {
+            (0..3)
+                .map(|card| {
+                    (0..states.len())
+                        .filter(|i| [states[*i].0, states[*i].1][player] == card)
+                        .collect()
+                })
+                .collect()
+        };
+        let showdown = |pot: Float| -> Array<Float, Ix1> {
+            states
+                .iter()
+                .map(|(a, b)| if a < b { pot } else { -pot })
+                .collect()
+        };
+        let constant = |x: Float| Array::from_elem(states.len(), x);
+        TreeBuilder::action("root", 0)
+            .infosets(infosets(0))
+            .child(
+                TreeBuilder::action("b", 1)
+                    .infosets(infosets(1))
+                    .zero_sum("bc", showdown(2.))
+                    .zero_sum("bf", constant(1.)),
+            )
+            .child(
+                TreeBuilder::action("x", 1)
+                    .infosets(infosets(1))
+                    .child(
+                        TreeBuilder::action("xb", 0)
+                            .infosets(infosets(0))
+                            .zero_sum("bc", showdown(2.))
+                            .zero_sum("bf", constant(-1.)),
+                    )
+                    .zero_sum("xx", showdown(1.)),
+            )
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_kuhn() {
+        let mut root = kuhn_tree();
+        let form = SequenceForm::from_tree(root.as_ref()).unwrap();
+        // The empty sequence, then two actions in each of three infosets at each of two nodes
+        assert_eq!(form.sequences(), [13, 13]);
+        let solution = form.solve().unwrap();
+        assert!(
+            (solution.value + 1. / 18.).abs() < 1e-6,
+            "{}",
+            solution.value
+        );
+
+        load_strategies(root.as_mut(), &solution.profile).unwrap();
+        assert!(unilateral_gains(root.as_ref())
+            .iter()
+            .all(|x| x.abs() < 1e-6));
+
+        // CFR+ approaches the same value
+        let mut solver = CfrSolver::new(
+            kuhn_tree(),
+            SolverConfig {
+                variant: CfrVariant::CfrPlus,
+                max_iterations: 2000,
+                ..Default::default()
+            },
+        );
+        solver.solve();
+        let root = &solver.root;
+        let cfr_value = root.avg_strategy_payouts().dot(&root.state_probabilities())[0];
+        assert!((widen(cfr_value) - solution.value).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_matrix_game() {
+        // The second player's nodes share one strategy through the hidden action
+        let game = MatrixGame::biased_rock_paper_scissors();
+        let root = game.build_tree().unwrap();
+        let form = SequenceForm::from_tree(root.as_ref()).unwrap();
+        assert_eq!(form.sequences(), [4, 4]);
+        let solution = form.solve().unwrap();
+        let exact = NormalFormGame::bimatrix(
+            game.payouts.index_axis(Axis(0), 0).mapv(widen),
+            game.payouts.index_axis(Axis(0), 1).mapv(widen),
+        )
+        .solve_zero_sum()
+        .unwrap();
+        assert!((solution.value - exact.value).abs() < 1e-9);
+        let paper = &solution.profile.strategies["root"];
+        assert!((paper[[1, 0]] - 10. / 12.).abs() < 1e-6);
+        assert_eq!(
+            solution.profile.strategies["root/rock"],
+            solution.profile.strategies["root/scissors"]
+        );
+        assert!((solution.realization[1][2] - 10. / 12.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut game = MatrixGame::rock_paper_scissors();
+        game.payouts[[1, 0, 0]] = 1.;
+        let root = game.build_tree().unwrap();
+        assert_eq!(
+            SequenceForm::from_tree(root.as_ref()).unwrap_err(),
+            SequenceFormError::NotZeroSum {
+                path: "root/rock/rock".to_string()
+            }
+        );
+
+        // The first player forgets which state they were in at the root
+        let root = TreeBuilder::action("root", 0)
+            .child(
+                TreeBuilder::action("a", 1).child(
+                    TreeBuilder::action("b", 0)
+                        .infosets(vec![vec![0, 1]])
+                        .zero_sum("c", array![1., -1.])
+                        .zero_sum("d", array![-1., 1.]),
+                ),
+            )
+            .zero_sum("e", array![0., 0.])
+            .build()
+            .unwrap();
+        assert_eq!(
+            SequenceForm::from_tree(root.as_ref()).unwrap_err(),
+            SequenceFormError::ImperfectRecall {
+                path: "root/a/b".to_string(),
+                infoset: 0
+            }
+        );
+    }
+}
@@ -0,0 +1,414 @@
+//! HTTP API for the ICM calculator and the push/fold solver, so that web frontends and other
+//! languages can use them without bindings. Built with the `server` feature and started by
+//! `push-fold serve`.
+//!
+//! Requests and responses are JSON, and failed requests get `{"error": "..."}`:
+//!
+//! - `POST /icm` takes an [`IcmRequest`], e.g. `{"stacks": [50, 30, 20], "payouts": [0.5, 0.3,
+//!   0.2]}`, and returns an [`IcmResponse`]
+//! - `POST /push-fold/solve` takes a [`SolveRequest`], e.g. `{"stack": 10, "ante": 0.1}`, and
+//!   returns a [`SolveResponse`]
+//! - `GET /push-fold/chart?stack=10&threshold=0.5` looks up a [`ChartQuery`] in the bundled
+//!   chart and returns a [`ChartResponse`]
+//!
+//! ICM calculations and solves run on tokio's blocking threads, so a long one doesn't hold up
+//! other requests, and both are capped: ICM by [`MAX_ICM_SETS`] and solves by [`MAX_ITERATIONS`].
+
+use crate::cfr::{CfrSolver, CfrVariant, Float, SolverConfig};
+use crate::charts::PushFoldChart;
+use crate::error::{ApiError, SolverError};
+use crate::icm::{check_tournament, IcmModel};
+use crate::poker::push_fold::{AnteStructure, Hand, PreflopTables, Rake, SbAction};
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tracing::{debug, error};
+
+/// Largest number of CFR iterations a single solve request may ask for
+pub const MAX_ITERATIONS: u64 = 100_000;
+
+/// Most sets of players an ICM request may have the model work through: the sets of finished
+/// players for Malmuth-Harville, which depend on the number of places paid, and all 2^n sets of
+/// busted players for Malmuth-Weitzman, so at most 20 players
+pub const MAX_ICM_SETS: u64 = 1 << 20;
+
+/// Tables loaded once when the server starts and shared by every request
+pub struct ServerState {
+    pub tables: PreflopTables,
+    pub chart: PushFoldChart,
+}
+
+impl ServerState {
+    /// Loads the heads-up preflop tables (see [`PreflopTables::load`]) and the bundled chart
+    pub fn load() -> Result<Self, SolverError> {
+        Ok(ServerState {
+            tables: PreflopTables::load()?,
+            chart: PushFoldChart::bundled(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IcmRequest {
+    pub stacks: Vec<f64>,
+    pub payouts: Vec<f64>,
+    /// `mh` (Malmuth-Harville, the default) or `mw` (Malmuth-Weitzman)
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IcmResponse {
+    /// Indexed by player
+    pub equities: Vec<f64>,
+    /// Indexed by player, place
+    pub place_probabilities: Vec<Vec<f64>>,
+}
+
+/// Heads-up push/fold game to solve: the SB shoves or folds, and the BB calls or folds
+#[derive(Debug, Clone, Deserialize)]
+pub struct SolveRequest {
+    /// Effective stack in big blinds, not counting the antes
+    pub stack: f64,
+    /// Ante posted by each player
+    #[serde(default)]
+    pub ante: f64,
+    #[serde(default = "default_sb")]
+    pub sb: f64,
+    /// `vanilla`, `cfr+` (the default) or `linear`
+    pub variant: Option<String>,
+    #[serde(default = "default_iterations")]
+    pub iterations: u64,
+    /// Stop once exploitability drops below this value, in mbb/hand
+    pub target: Option<Float>,
+}
+
+fn default_sb() -> f64 {
+    0.5
+}
+
+fn default_iterations() -> u64 {
+    1000
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SolveResponse {
+    pub stack: f64,
+    pub iterations: u64,
+    /// In mbb/hand
+    pub exploitability: Float,
+    /// Whether the target exploitability was reached
+    pub converged: bool,
+    /// How often the SB shoves each hand class
+    pub shove: BTreeMap<String, Float>,
+    /// How often the BB calls a shove with each hand class
+    pub call: BTreeMap<String, Float>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChartQuery {
+    /// Effective stack in big blinds, interpolated between the stacks of the chart
+    pub stack: f64,
+    /// Frequency at which a hand counts as part of the range, 0.5 by default
+    pub threshold: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChartResponse {
+    pub stack: f64,
+    /// How often the SB shoves each hand class
+    pub shove: BTreeMap<String, f64>,
+    /// Hands shoved at least `threshold` of the time
+    pub range: Vec<String>,
+}
+
+/// Number of sets of players `model` works through for `n` players and `n_places` places paid,
+/// or more than [`MAX_ICM_SETS`] if it's too large to count
+fn icm_sets(model: IcmModel, n: usize, n_places: usize) -> u64 {
+    match model {
+        IcmModel::MalmuthHarville => {
+            // Sets of up to `n_places` players, adding up C(n, k) one k at a time
+            let (mut total, mut sets) = (1, 1_u64);
+            for k in 1..=n_places.min(n) {
+                sets = sets * (n - k + 1) as u64 / k as u64;
+                total += sets;
+                if total > MAX_ICM_SETS {
+                    break;
+                }
+            }
+            total
+        }
+        IcmModel::MalmuthWeitzman => 1_u64.checked_shl(n as u32).unwrap_or(u64::MAX),
+    }
+}
+
+/// Answers `POST /icm`
+pub fn icm(request: &IcmRequest) -> Result<IcmResponse, ApiError> {
+    check_tournament(&request.stacks, &request.payouts).map_err(ApiError::BadRequest)?;
+    let model = match &request.model {
+        Some(x) => x.parse::<IcmModel>().map_err(ApiError::BadRequest)?,
+        None => IcmModel::default(),
+    };
+    if icm_sets(model, request.stacks.len(), request.payouts.len()) > MAX_ICM_SETS {
+        return Err(ApiError::BadRequest(format!(
+            "too many players for the {:?} model with {} places paid",
+            model,
+            request.payouts.len()
+        )));
+    }
+    let place_probabilities = model.place_probabilities(&request.stacks, request.payouts.len());
+    Ok(IcmResponse {
+        equities: crate::icm::equities(&place_probabilities, &request.payouts),
+        place_probabilities,
+    })
+}
+
+/// Answers `POST /push-fold/solve`
+pub fn solve_push_fold(
+    tables: &PreflopTables,
+    request: &SolveRequest,
+) -> Result<SolveResponse, ApiError> {
+    let invalid = |x: &str| Err(ApiError::BadRequest(x.to_string()));
+    if !(request.stack.is_finite() && request.stack >= 1.) {
+        return invalid("stack must be at least 1 big blind");
+    }
+    if !(request.ante.is_finite() && request.ante >= 0.) {
+        return invalid("ante must be at least 0");
+    }
+    if !(request.sb > 0. && request.sb <= 1.) {
+        return invalid("sb must be in (0, 1]");
+    }
+    if !(1..=MAX_ITERATIONS).contains(&request.iterations) {
+        return Err(ApiError::BadRequest(format!(
+            "iterations must be from 1 to {}",
+            MAX_ITERATIONS
+        )));
+    }
+    let variant = match &request.variant {
+        Some(x) => x.parse::<CfrVariant>().map_err(ApiError::BadRequest)?,
+        None => CfrVariant::CfrPlus,
+    };
+
+    let root = tables.build_tree(
+        request.stack,
+        request.ante,
+        AnteStructure::Each,
+        request.sb,
+        &[SbAction::Shove, SbAction::Fold],
+        &Rake::default(),
+    )?;
+    let config = SolverConfig {
+        variant,
+        max_iterations: request.iterations,
+        // Payouts are in big blinds
+        target_exploitability: request.target.map(|x| x / 1000.),
+        ..Default::default()
+    };
+    let mut solver = CfrSolver::new(root, config);
+    let result = solver.solve();
+
+    // Shoving and calling are the first action of each player, indexed by hand class
+    let by_hand = |strategy: Option<ndarray::ArrayView2<Float>>| -> BTreeMap<String, Float> {
+        strategy
+            .map(|x| x.row(0).to_owned())
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .map(|(hand, frequency)| (Hand::index_to_str(hand), frequency))
+            .collect()
+    };
+    let call = solver
+        .root
+        .children()
+        .and_then(|x| x.first())
+        .map(|x| by_hand(x.avg_strategy()))
+        .unwrap_or_default();
+    Ok(SolveResponse {
+        stack: request.stack,
+        iterations: result.iterations,
+        exploitability: result.exploitability * 1000.,
+        converged: result.converged,
+        shove: by_hand(solver.root.avg_strategy()),
+        call,
+    })
+}
+
+/// Answers `GET /push-fold/chart`
+pub fn chart(chart: &PushFoldChart, query: &ChartQuery) -> Result<ChartResponse, ApiError> {
+    if !(query.stack.is_finite() && query.stack > 0.) {
+        return Err(ApiError::BadRequest("stack must be positive".to_string()));
+    }
+    let threshold = query.threshold.unwrap_or(0.5);
+    Ok(ChartResponse {
+        stack: query.stack,
+        shove: chart
+            .hands
+            .iter()
+            .cloned()
+            .zip(chart.at_stack(query.stack))
+            .collect(),
+        range: chart
+            .range(query.stack, threshold)
+            .into_iter()
+            .map(String::from)
+            .collect(),
+    })
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        match status {
+            StatusCode::BAD_REQUEST => debug!("Bad request: {}", self),
+            _ => error!("Request failed: {}", self),
+        }
+        let body = BTreeMap::from([("error", self.to_string())]);
+        (status, Json(body)).into_response()
+    }
+}
+
+async fn icm_handler(Json(request): Json<IcmRequest>) -> Result<Json<IcmResponse>, ApiError> {
+    tokio::task::spawn_blocking(move || icm(&request))
+        .await
+        .map_err(|x| ApiError::Internal(x.to_string()))?
+        .map(Json)
+}
+
+async fn solve_handler(
+    State(state): State<Arc<ServerState>>,
+    Json(request): Json<SolveRequest>,
+) -> Result<Json<SolveResponse>, ApiError> {
+    tokio::task::spawn_blocking(move || solve_push_fold(&state.tables, &request))
+        .await
+        .map_err(|x| ApiError::Internal(x.to_string()))?
+        .map(Json)
+}
+
+/// Routes of the API; see the module documentation
+pub fn router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/icm", post(icm_handler))
+        .route("/push-fold/solve", post(solve_handler))
+        .route(
+            "/push-fold/chart",
+            get(
+                |State(state): State<Arc<ServerState>>, Query(query): Query<ChartQuery>| async move {
+                    chart(&state.chart, &query).map(Json)
+                },
+            ),
+        )
+        .with_state(state)
+}
+
+/// Serves the API on `addr` until the process is stopped
+pub async fn serve(addr: SocketAddr, state: ServerState) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(Arc::new(state))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn test_icm() {
+        let request = IcmRequest {
+            stacks: vec![50., 30., 20.],
+            payouts: vec![0.5, 0.3, 0.2],
+            model: None,
+        };
+        let response = icm(&request).unwrap();
+        assert_eq!(
+            response.equities,
+            crate::icm::icm(&request.stacks, &request.payouts)
+        );
+        assert_eq!(response.place_probabilities.len(), 3);
+
+        let bad_model = IcmRequest {
+            model: Some("chips".to_string()),
+            ..request.clone()
+        };
+        assert!(matches!(icm(&bad_model), Err(ApiError::BadRequest(_))));
+        let no_stacks = IcmRequest {
+            stacks: Vec::new(),
+            ..request.clone()
+        };
+        assert!(matches!(icm(&no_stacks), Err(ApiError::BadRequest(_))));
+
+        // 20 players are fine for either model, but 21 are too many for Malmuth-Weitzman and
+        // 40 paying 10 places too many for Malmuth-Harville
+        let field = |n: usize, places: usize, model: &str| IcmRequest {
+            stacks: vec![10.; n],
+            payouts: vec![1.; places],
+            model: Some(model.to_string()),
+        };
+        assert_eq!(icm_sets(IcmModel::MalmuthWeitzman, 20, 3), MAX_ICM_SETS);
+        assert!(icm_sets(IcmModel::MalmuthHarville, 20, 20) <= MAX_ICM_SETS);
+        assert!(icm(&field(21, 3, "mw")).is_err());
+        assert!(icm(&field(40, 3, "mh")).is_ok());
+        assert!(icm(&field(40, 10, "mh")).is_err());
+    }
+
+    #[test]
+    fn test_chart() {
+        let query: ChartQuery = serde_json::from_str(r#"{"stack": 10}"#).unwrap();
+        let response = chart(&PushFoldChart::bundled(), &query).unwrap();
+        assert_eq!(response.shove.len(), 169);
+        assert_eq!(response.shove["AA"], 1.);
+        assert!(response.range.contains(&"AA".to_string()));
+        assert!(!response.range.contains(&"72o".to_string()));
+
+        let query = ChartQuery {
+            stack: f64::NAN,
+            threshold: None,
+        };
+        assert!(chart(&PushFoldChart::bundled(), &query).is_err());
+    }
+
+    #[test]
+    fn test_solve() {
+        // Two hand classes each: the SB's first hand wins 90% of the time and the second 10%,
+        // whatever the BB holds
+        let tables = PreflopTables::from_states(
+            array![0.25, 0.25, 0.25, 0.25],
+            array![0.9, 0.9, 0.1, 0.1],
+            [vec![vec![0, 1], vec![2, 3]], vec![vec![0, 2], vec![1, 3]]],
+        );
+        let request: SolveRequest = serde_json::from_str(r#"{"stack": 10}"#).unwrap();
+        assert_eq!(
+            (request.ante, request.sb, request.iterations),
+            (0., 0.5, 1000)
+        );
+        let request = SolveRequest {
+            iterations: 20000,
+            ..request
+        };
+        let response = solve_push_fold(&tables, &request).unwrap();
+        assert_eq!(response.shove.len(), 2);
+        assert!(response.shove["22"] > 0.99);
+        // The SB bluffs with the weak hand just often enough that calling 9 big blinds to win 11
+        // breaks even, 7/9 of the time, and the BB calls 1/6 of the time so that bluffing breaks
+        // even against folding
+        assert!((response.shove["32o"] - 7. / 9.).abs() < 0.02);
+        assert!(response.call.values().all(|x| (x - 1. / 6.).abs() < 0.02));
+        assert!(response.exploitability < 10.);
+
+        for invalid in [r#"{"stack": 0.5}"#, r#"{"stack": 10, "iterations": 0}"#] {
+            let request: SolveRequest = serde_json::from_str(invalid).unwrap();
+            assert!(matches!(
+                solve_push_fold(&tables, &request),
+                Err(ApiError::BadRequest(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,229 @@
+//! Symmetries of rectangular boards, for storing each position once in tables keyed by position.
+//!
+//! A [`Symmetry`] is a permutation of a board's cells, numbered row by row, and a
+//! [`SymmetryGroup`] is the set of symmetries a game's rules don't distinguish: all rotations and
+//! reflections for tic-tac-toe, only the left-right mirror for Connect Four, where gravity picks
+//! out the bottom. [`SymmetryGroup::canonical_key`] gives every position in an orbit the same key.
+
+/// Permutation of the cells of a board with `rows` rows and `columns` columns
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    /// Cell of the original board that ends up in each cell
+    source: Vec<usize>,
+}
+
+impl Symmetry {
+    pub fn identity(cells: usize) -> Self {
+        Symmetry {
+            source: (0..cells).collect(),
+        }
+    }
+
+    /// The symmetry moving the cell at `f(row, column)` to `(row, column)`. `f` must be a
+    /// permutation of the board's cells.
+    pub fn from_fn(
+        rows: usize,
+        columns: usize,
+        f: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Self {
+        let source = (0..rows * columns)
+            .map(|x| {
+                let (row, column) = f(x / columns, x % columns);
+                row * columns + column
+            })
+            .collect();
+        Symmetry { source }
+    }
+
+    /// Cell of the original board that ends up in `cell`
+    pub fn source(&self, cell: usize) -> usize {
+        self.source[cell]
+    }
+
+    /// The board with its cells moved
+    pub fn apply<T: Clone>(&self, cells: &[T]) -> Vec<T> {
+        self.source.iter().map(|x| cells[*x].clone()).collect()
+    }
+
+    /// [`Symmetry::apply`] to a bitboard with one bit per cell
+    pub fn apply_bits(&self, bits: u64) -> u64 {
+        self.source
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (cell, x)| acc | ((bits >> x) & 1) << cell)
+    }
+
+    /// This symmetry followed by `other`
+    pub fn then(&self, other: &Symmetry) -> Symmetry {
+        Symmetry {
+            source: other.source.iter().map(|x| self.source[*x]).collect(),
+        }
+    }
+}
+
+/// Symmetries of a board under which a game's positions are equivalent
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SymmetryGroup {
+    rows: usize,
+    columns: usize,
+    symmetries: Vec<Symmetry>,
+}
+
+impl SymmetryGroup {
+    /// Only the identity, for games without symmetries
+    pub fn trivial(rows: usize, columns: usize) -> Self {
+        SymmetryGroup {
+            rows,
+            columns,
+            symmetries: vec![Symmetry::identity(rows * columns)],
+        }
+    }
+
+    /// The identity and the left-right mirror
+    pub fn mirror(rows: usize, columns: usize) -> Self {
+        let mirror = Symmetry::from_fn(rows, columns, |r, c| (r, columns - 1 - c));
+        SymmetryGroup {
+            rows,
+            columns,
+            symmetries: vec![Symmetry::identity(rows * columns), mirror],
+        }
+    }
+
+    /// Every rotation and reflection: eight on a square board, and on other rectangles the four
+    /// that keep the board's shape
+    pub fn dihedral(rows: usize, columns: usize) -> Self {
+        let (last_row, last_column) = (rows - 1, columns - 1);
+        // Identity, half turn and the two mirrors, then the quarter turns and diagonal reflections
+        // that only a square board has
+        let transform = |kind: usize, row: usize, column: usize| match kind {
+            0 => (row, column),
+            1 => (last_row - row, last_column - column),
+            2 => (row, last_column - column),
+            3 => (last_row - row, column),
+            4 => (column, last_row - row),
+            5 => (last_row - column, row),
+            6 => (column, row),
+            _ => (last_row - column, last_row - row),
+        };
+        let kinds = match rows == columns {
+            true => 8,
+            false => 4,
+        };
+        SymmetryGroup {
+            rows,
+            columns,
+            symmetries: (0..kinds)
+                .map(|kind| Symmetry::from_fn(rows, columns, |r, c| transform(kind, r, c)))
+                .collect(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn len(&self) -> usize {
+        self.symmetries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symmetries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Symmetry> {
+        self.symmetries.iter()
+    }
+
+    /// The board under each symmetry, with repeats if the board is itself symmetric
+    pub fn orbit<T: Clone>(&self, cells: &[T]) -> Vec<Vec<T>> {
+        self.symmetries.iter().map(|x| x.apply(cells)).collect()
+    }
+
+    /// The lexicographically smallest image of the board, the same for every board in its orbit
+    pub fn canonical<T: Clone + Ord>(&self, cells: &[T]) -> Vec<T> {
+        self.orbit(cells)
+            .into_iter()
+            .min()
+            .expect("Empty symmetry group")
+    }
+
+    /// Smallest image of the board read as a number in base `base`, the first cell the most
+    /// significant digit. Cells must be less than `base` and the board small enough to fit.
+    pub fn canonical_key(&self, cells: &[u64], base: u64) -> u64 {
+        self.symmetries
+            .iter()
+            .map(|x| x.source.iter().fold(0, |acc, y| acc * base + cells[*y]))
+            .min()
+            .expect("Empty symmetry group")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups() {
+        assert_eq!(SymmetryGroup::dihedral(3, 3).len(), 8);
+        assert_eq!(SymmetryGroup::dihedral(3, 4).len(), 4);
+        assert_eq!(SymmetryGroup::mirror(6, 7).len(), 2);
+
+        // Every group is closed under composition, with distinct elements
+        for group in [
+            SymmetryGroup::dihedral(3, 3),
+            SymmetryGroup::dihedral(4, 4),
+            SymmetryGroup::dihedral(2, 5),
+            SymmetryGroup::mirror(6, 7),
+        ] {
+            for a in group.iter() {
+                assert_eq!(group.iter().filter(|x| *x == a).count(), 1);
+                for b in group.iter() {
+                    assert!(group.iter().any(|x| *x == a.then(b)));
+                }
+            }
+        }
+
+        // The rotations and reflections of the 3×3 board
+        let expected = [
+            [0, 1, 2, 3, 4, 5, 6, 7, 8],
+            [2, 5, 8, 1, 4, 7, 0, 3, 6],
+            [8, 7, 6, 5, 4, 3, 2, 1, 0],
+            [6, 3, 0, 7, 4, 1, 8, 5, 2],
+            [6, 7, 8, 3, 4, 5, 0, 1, 2],
+            [2, 1, 0, 5, 4, 3, 8, 7, 6],
+            [8, 5, 2, 7, 4, 1, 6, 3, 0],
+            [0, 3, 6, 1, 4, 7, 2, 5, 8],
+        ];
+        let group = SymmetryGroup::dihedral(3, 3);
+        for x in expected {
+            assert!(group.iter().any(|y| y.source == x));
+        }
+    }
+
+    #[test]
+    fn test_canonical() {
+        let group = SymmetryGroup::dihedral(3, 3);
+        // A corner stone in each of the four corners
+        let corners = [0, 2, 6, 8].map(|x| {
+            let mut cells = vec![0; 9];
+            cells[x] = 1;
+            cells
+        });
+        let canonical = group.canonical(&corners[0]);
+        let key = group.canonical_key(&corners[0], 3);
+        for cells in &corners {
+            assert_eq!(group.canonical(cells), canonical);
+            assert_eq!(group.canonical_key(cells, 3), key);
+        }
+        assert_eq!(canonical, vec![0, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(key, 1);
+
+        let mirror = &SymmetryGroup::mirror(2, 3).symmetries[1];
+        assert_eq!(mirror.apply(&[1, 2, 3, 4, 5, 6]), vec![3, 2, 1, 6, 5, 4]);
+        assert_eq!(mirror.apply_bits(0b000_011), 0b000_110);
+    }
+}
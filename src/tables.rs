@@ -0,0 +1,252 @@
+//! Binary format for precomputed 2D tables, such as the preflop equity and matchup tables.
+//!
+//! A table is a 24 byte header followed by its elements in row-major order, all little-endian:
+//!
+//! | Bytes  | Contents                                        |
+//! |--------|-------------------------------------------------|
+//! | 0..4   | Magic, `b"GTRT"`                                |
+//! | 4..8   | Format version, [`VERSION`]                     |
+//! | 8..12  | Byte order mark, `0x01020304`                   |
+//! | 12..16 | Element type, [`TableElement::KIND`]            |
+//! | 16..20 | Number of rows                                  |
+//! | 20..24 | Number of columns                               |
+//!
+//! Tables are memory-mapped, so [`Table::view`] reads the elements straight from the mapping and
+//! only the pages that are touched get loaded; [`Table::into_array`] copies them into memory.
+
+use crate::error::IoError;
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use memmap2::Mmap;
+use ndarray::*;
+
+const MAGIC: &[u8; 4] = b"GTRT";
+/// Current format version; tables written by other versions are rejected
+pub const VERSION: u32 = 1;
+const BYTE_ORDER_MARK: u32 = 0x0102_0304;
+const HEADER_LEN: usize = 24;
+
+/// Element types that can be stored in a table
+///
+/// # Safety
+///
+/// `SIZE` must be the size of `Self`, and any `SIZE` little-endian bytes must be a valid value,
+/// since [`Table::view`] reinterprets the mapped file as elements.
+pub unsafe trait TableElement: Copy {
+    /// Tag stored in the header so a table isn't read back as the wrong type
+    const KIND: u32;
+    const SIZE: usize;
+
+    fn to_le_bytes(self) -> Vec<u8>;
+}
+
+unsafe impl TableElement for f64 {
+    const KIND: u32 = 1;
+    const SIZE: usize = 8;
+
+    fn to_le_bytes(self) -> Vec<u8> {
+        Vec::from(f64::to_le_bytes(self))
+    }
+}
+
+unsafe impl TableElement for u64 {
+    const KIND: u32 = 2;
+    const SIZE: usize = 8;
+
+    fn to_le_bytes(self) -> Vec<u8> {
+        Vec::from(u64::to_le_bytes(self))
+    }
+}
+
+/// A validated, memory-mapped table
+#[derive(Debug)]
+pub struct Table<T> {
+    mmap: Mmap,
+    dim: (usize, usize),
+    element: PhantomData<T>,
+}
+
+impl<T: TableElement> Table<T> {
+    /// Maps a table, checking its header, element type and length. If `shape` is given, the
+    /// table must also have exactly that many rows and columns. The file shouldn't be modified
+    /// while the table is alive.
+    pub fn read(path: &Path, shape: Option<(usize, usize)>) -> Result<Self, IoError> {
+        let file = File::open(path).map_err(IoError::file(path))?;
+        // Safety: the mapping is only ever read, and tables aren't rewritten while they're in use
+        let mmap = unsafe { Mmap::map(&file) }.map_err(IoError::file(path))?;
+        let bytes = &mmap[..];
+        let header_error = |reason: String| IoError::Header {
+            path: path.to_path_buf(),
+            reason,
+        };
+        if bytes.len() < HEADER_LEN {
+            return Err(IoError::Size {
+                path: path.to_path_buf(),
+                expected: HEADER_LEN,
+                found: bytes.len(),
+            });
+        }
+
+        let field = |i: usize| u32::from_le_bytes(bytes[i * 4..(i + 1) * 4].try_into().unwrap());
+        if &bytes[0..4] != MAGIC {
+            return Err(header_error("not a table file".to_string()));
+        }
+        if field(1) != VERSION {
+            return Err(header_error(format!(
+                "version {} is not supported, expected {}",
+                field(1),
+                VERSION
+            )));
+        }
+        if field(2) != BYTE_ORDER_MARK {
+            return Err(header_error("not little-endian".to_string()));
+        }
+        if cfg!(target_endian = "big") {
+            return Err(header_error(
+                "little-endian tables can't be mapped on a big-endian host".to_string(),
+            ));
+        }
+        if field(3) != T::KIND {
+            return Err(header_error(format!(
+                "element type {} does not match the expected {}",
+                field(3),
+                T::KIND
+            )));
+        }
+        let dim = (field(4) as usize, field(5) as usize);
+        if let Some(expected) = shape.filter(|x| *x != dim) {
+            return Err(header_error(format!(
+                "shape {:?} does not match the expected {:?}",
+                dim, expected
+            )));
+        }
+
+        // The shape comes from the file, so it may be large enough to overflow
+        let expected_len = dim
+            .0
+            .checked_mul(dim.1)
+            .and_then(|x| x.checked_mul(T::SIZE))
+            .and_then(|x| x.checked_add(HEADER_LEN))
+            .ok_or_else(|| header_error(format!("shape {:?} is too large", dim)))?;
+        if bytes.len() != expected_len {
+            return Err(IoError::Size {
+                path: path.to_path_buf(),
+                expected: expected_len,
+                found: bytes.len(),
+            });
+        }
+        // The mapping is page-aligned and the header a multiple of the element size
+        assert_eq!(
+            bytes[HEADER_LEN..]
+                .as_ptr()
+                .align_offset(std::mem::align_of::<T>()),
+            0
+        );
+        Ok(Table {
+            mmap,
+            dim,
+            element: PhantomData,
+        })
+    }
+
+    /// Writes `data` as a table
+    pub fn write<S: Data<Elem = T>>(data: &ArrayBase<S, Ix2>, path: &Path) -> Result<(), IoError> {
+        let dimension = |n: usize| {
+            u32::try_from(n).map_err(|_| IoError::Header {
+                path: path.to_path_buf(),
+                reason: format!("{} rows or columns don't fit in the header", n),
+            })
+        };
+        let (rows, columns) = (dimension(data.nrows())?, dimension(data.ncols())?);
+        let mut bytes = Vec::with_capacity(HEADER_LEN + data.len() * T::SIZE);
+        bytes.extend_from_slice(MAGIC);
+        for field in [VERSION, BYTE_ORDER_MARK, T::KIND, rows, columns] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        data.iter().for_each(|x| bytes.append(&mut x.to_le_bytes()));
+
+        let mut writer = BufWriter::new(File::create(path).map_err(IoError::file(path))?);
+        writer.write_all(&bytes).map_err(IoError::file(path))?;
+        writer.flush().map_err(IoError::file(path))
+    }
+
+    /// The elements, borrowed from the mapping
+    pub fn view(&self) -> ArrayView2<'_, T> {
+        let elements = &self.mmap[HEADER_LEN..];
+        // Safety: `read` checked the length and alignment, and TableElement guarantees any bytes
+        // are a valid element
+        let elements = unsafe {
+            std::slice::from_raw_parts(elements.as_ptr().cast::<T>(), self.dim.0 * self.dim.1)
+        };
+        ArrayView2::from_shape(self.dim, elements).unwrap()
+    }
+
+    /// Copies the elements out of the mapping
+    pub fn into_array(self) -> Array<T, Ix2> {
+        self.view().to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("game_theory_rs_table_{}.bin", std::process::id()));
+        let data = array![[0.25, 0.5, 0.75], [1., 1.5, 2.]];
+        Table::write(&data, &path).unwrap();
+
+        let table = Table::<f64>::read(&path, Some((2, 3))).unwrap();
+        assert_eq!(table.view(), data);
+        assert_eq!(table.into_array(), data);
+        assert!(matches!(
+            Table::<f64>::read(&path, Some((3, 2))),
+            Err(IoError::Header { .. })
+        ));
+        assert!(matches!(
+            Table::<u64>::read(&path, None),
+            Err(IoError::Header { .. })
+        ));
+
+        // Truncated, and written by another version
+        let mut bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 1]).unwrap();
+        assert!(matches!(
+            Table::<f64>::read(&path, None),
+            Err(IoError::Size {
+                expected: 72,
+                found: 71,
+                ..
+            })
+        ));
+        bytes[4] = 2;
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            Table::<f64>::read(&path, None),
+            Err(IoError::Header { .. })
+        ));
+
+        // A shape whose size overflows is rejected rather than read out of bounds
+        bytes[4] = 1;
+        bytes[16..24].fill(0xff);
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            Table::<f64>::read(&path, None),
+            Err(IoError::Header { .. })
+        ));
+
+        // Headerless dumps are rejected
+        std::fs::write(&path, [0u8; 169 * 169]).unwrap();
+        assert!(matches!(
+            Table::<u64>::read(&path, None),
+            Err(IoError::Header { .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
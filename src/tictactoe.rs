@@ -1,11 +1,37 @@
+//! Tic-tac-toe solved by tabular minimax.
+//!
+//! Tiles are indexed 0 to 8 row by row from the top left. A [`Board`] can be written in index
+//! notation, one character per tile in index order with `X`, `O`, or the tile's index when it's
+//! empty, e.g. `X1O3X5678`, and parsed back with [`str::parse`]. [`SolutionTable`] caches the
+//! minimax value of every position it visits, so one table can answer any number of queries.
+//!
+//! A game is recorded as a [`MoveList`], each move written as the tile of the player followed by
+//! the index played, e.g. `X4 O0 X8`, and [`Board::replay`] reconstructs the position it reaches.
+//! [`Game`] keeps the moves alongside the board for undo and redo.
+
+pub use crate::error::GameError;
+
+#[cfg(feature = "fs")]
+use crate::error::IoError;
+use crate::parallel::*;
+use crate::search::{self, Outcome};
+use crate::symmetry::SymmetryGroup;
+#[cfg(feature = "fs")]
+use crate::transposition::{load_table, save_table};
+
 use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard, OnceLock};
 
 /// Number of tiles on the board
-const BOARD_SIZE: usize = 9;
+pub const BOARD_SIZE: usize = 9;
 
 /// Possible winning configurations
-const WIN_LINES: [u16; 8] = [
+pub(crate) const WIN_LINES: [u16; 8] = [
     0b111_000_000,
     0b000_111_000,
     0b000_000_111,
@@ -16,21 +42,24 @@ const WIN_LINES: [u16; 8] = [
     0b001_010_100,
 ];
 
-/// Rotations and reflections
-const TRANSFORMATIONS: [[usize; 9]; 8] = [
-    [0, 1, 2, 3, 4, 5, 6, 7, 8], // Rotations
-    [2, 5, 8, 1, 4, 7, 0, 3, 6],
-    [8, 7, 6, 5, 4, 3, 2, 1, 0],
-    [6, 3, 0, 7, 4, 1, 8, 5, 2],
-    [6, 7, 8, 3, 4, 5, 0, 1, 2], // Reflections
-    [2, 1, 0, 5, 4, 3, 8, 7, 6],
-    [8, 5, 2, 7, 4, 1, 6, 3, 0],
-    [0, 3, 6, 1, 4, 7, 2, 5, 8],
-];
+/// Rotations and reflections of the board
+fn symmetries() -> &'static SymmetryGroup {
+    static SYMMETRIES: OnceLock<SymmetryGroup> = OnceLock::new();
+    SYMMETRIES.get_or_init(|| SymmetryGroup::dihedral(3, 3))
+}
+
+/// Whether a game is over, and how
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameState {
+    InProgress,
+    Draw,
+    /// Won by the player, with the indices of the tiles of the winning line
+    Won(Tile, [usize; 3]),
+}
 
 /// Bitboard representation of a tic tac toe board
-#[derive(Clone)]
-struct Board {
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Board {
     /// Whether each tile is empty: 0 = empty, 1 = not empty
     occupied: u16,
     /// If the tile is not empty, which player occupies the tile: 0 = O, 1 = X
@@ -48,8 +77,8 @@ impl Default for Board {
 }
 
 /// Possible values of a tile on the board: occupied by an X, O, or Empty
-#[derive(Debug, PartialEq)]
-enum Tile {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
     X,
     O,
     Empty,
@@ -69,7 +98,7 @@ impl Tile {
     }
 
     /// Computes hash value of the current tile
-    fn hash(&self) -> u16 {
+    fn hash(&self) -> u64 {
         match self {
             Tile::Empty => 0,
             Tile::X => 1,
@@ -78,19 +107,12 @@ impl Tile {
     }
 }
 
-/// Error type for bound checking for statically sized arrays and other data structures
-#[derive(Debug)]
-enum GameError {
-    OutOfBoundsError,
-    InvalidMoveError,
-}
-
 impl Board {
     /// Gets the tile at the specified index
-    fn get(&self, index: usize) -> Result<Tile, GameError> {
+    pub fn get(&self, index: usize) -> Result<Tile, GameError> {
         // Bound checking
-        if index > BOARD_SIZE {
-            return Err(GameError::OutOfBoundsError);
+        if index >= BOARD_SIZE {
+            Err(GameError::OutOfBounds(index))
         } else {
             let occupied = (1 << index) & self.occupied > 0;
             let player = (1 << index) & self.player > 0;
@@ -108,8 +130,8 @@ impl Board {
     /// Sets the tile at the specified index
     fn set(&mut self, index: usize, tile: Tile) -> Result<(), GameError> {
         // Bound checking
-        if index > BOARD_SIZE {
-            return Err(GameError::OutOfBoundsError);
+        if index >= BOARD_SIZE {
+            Err(GameError::OutOfBounds(index))
         } else {
             match tile {
                 Tile::Empty => self.occupied &= !(1 << index),
@@ -127,7 +149,7 @@ impl Board {
     }
 
     /// Determines whose turn it is, X or O
-    fn turn(&self) -> Tile {
+    pub fn turn(&self) -> Tile {
         let moves = self.occupied.count_ones();
         match moves % 2 {
             0 => Tile::X,
@@ -136,7 +158,7 @@ impl Board {
     }
 
     /// Computes the current winner, if there is one
-    fn winner(&self) -> Tile {
+    pub fn winner(&self) -> Tile {
         let x_pos = self.occupied & self.player;
         let o_pos = self.occupied & !self.player;
 
@@ -151,34 +173,84 @@ impl Board {
         Tile::Empty
     }
 
+    /// Indices of the tiles of a completed line, if there is one
+    pub fn winning_line(&self) -> Option<[usize; 3]> {
+        let x_pos = self.occupied & self.player;
+        let o_pos = self.occupied & !self.player;
+        let line = WIN_LINES
+            .into_iter()
+            .find(|line| x_pos & line == *line || o_pos & line == *line)?;
+        let mut tiles = (0..BOARD_SIZE).filter(|x| line & (1 << x) != 0);
+        Some([tiles.next()?, tiles.next()?, tiles.next()?])
+    }
+
+    /// Whether the game is still going, drawn, or won, and by whom along which line
+    pub fn state(&self) -> GameState {
+        match self.winning_line() {
+            Some(line) => match self.get(line[0]) {
+                Ok(tile) => GameState::Won(tile, line),
+                Err(_) => GameState::InProgress,
+            },
+            None if self.is_full() => GameState::Draw,
+            None => GameState::InProgress,
+        }
+    }
+
+    /// Whether every tile is occupied
+    pub fn is_full(&self) -> bool {
+        self.occupied.count_ones() == BOARD_SIZE as u32
+    }
+
     /// Lists indices of valid moves
-    fn valid_moves(&self) -> Vec<usize> {
+    pub fn valid_moves(&self) -> Vec<usize> {
         (0..BOARD_SIZE)
-            .into_iter()
             .filter(|x| self.occupied & (1 << x) == 0)
             .collect()
     }
 
     /// Tries to set the index to the tile of the player whose turn it is to act
-    fn act(&mut self, index: usize) -> Result<(), GameError> {
+    pub fn act(&mut self, index: usize) -> Result<(), GameError> {
         let current_value = self.get(index)?;
         match current_value {
             Tile::Empty => self.set(index, self.turn()),
-            _ => Err(GameError::InvalidMoveError),
+            _ => Err(GameError::InvalidMove(index)),
+        }
+    }
+
+    /// Writes the board in index notation, e.g. `X1O3X5678`
+    pub fn to_index_notation(&self) -> String {
+        (0..BOARD_SIZE)
+            .map(|x| match self.get(x) {
+                Ok(Tile::X) => 'X',
+                Ok(Tile::O) => 'O',
+                _ => char::from_digit(x as u32, 10).unwrap_or(' '),
+            })
+            .collect()
+    }
+
+    /// Plays the moves in order from the empty board. Fails on an illegal move, including any move
+    /// after the game is won.
+    pub fn replay(moves: &[usize]) -> Result<Board, GameError> {
+        let mut board = Board::default();
+        for (i, index) in moves.iter().enumerate() {
+            if board.state() != GameState::InProgress {
+                return Err(GameError::InvalidBoard(format!(
+                    "move {} is after the game was won",
+                    i + 1
+                )));
+            }
+            board.act(*index)?;
         }
+        Ok(board)
     }
 
     /// Computes transformation invariant hash of the current board state
     fn invariant_hash(&self) -> u16 {
-        let hash_values: Vec<u16> = (0..BOARD_SIZE)
-            .into_iter()
+        let hash_values: Vec<u64> = (0..BOARD_SIZE)
             .map(|x| self.get(x).expect("Unable to get tile").hash())
             .collect();
-        TRANSFORMATIONS
-            .iter()
-            .map(|x| x.iter().fold(0, |i, x| i * 3 + hash_values[*x]))
-            .min()
-            .expect("Empty iterator")
+        // 3^9 keys fit in 16 bits
+        symmetries().canonical_key(&hash_values, 3) as u16
     }
 }
 
@@ -188,42 +260,448 @@ impl Display for Board {
         write!(
             f,
             "{}|{}|{}\n-----\n{}|{}|{}\n-----\n{}|{}|{}\n",
-            self.get(0).expect("Couldn't get tile 0").str(Some("0")),
-            self.get(1).expect("Couldn't get tile 1").str(Some("1")),
-            self.get(2).expect("Couldn't get tile 2").str(Some("2")),
-            self.get(3).expect("Couldn't get tile 3").str(Some("3")),
-            self.get(4).expect("Couldn't get tile 4").str(Some("4")),
-            self.get(5).expect("Couldn't get tile 5").str(Some("5")),
-            self.get(6).expect("Couldn't get tile 6").str(Some("6")),
-            self.get(7).expect("Couldn't get tile 7").str(Some("7")),
-            self.get(8).expect("Couldn't get tile 8").str(Some("8")),
+            self.get(0).map_err(|_| std::fmt::Error)?.str(Some("0")),
+            self.get(1).map_err(|_| std::fmt::Error)?.str(Some("1")),
+            self.get(2).map_err(|_| std::fmt::Error)?.str(Some("2")),
+            self.get(3).map_err(|_| std::fmt::Error)?.str(Some("3")),
+            self.get(4).map_err(|_| std::fmt::Error)?.str(Some("4")),
+            self.get(5).map_err(|_| std::fmt::Error)?.str(Some("5")),
+            self.get(6).map_err(|_| std::fmt::Error)?.str(Some("6")),
+            self.get(7).map_err(|_| std::fmt::Error)?.str(Some("7")),
+            self.get(8).map_err(|_| std::fmt::Error)?.str(Some("8")),
         )
     }
 }
 
+impl FromStr for Board {
+    type Err = GameError;
+
+    /// Parses index notation. Whitespace and `|` are ignored, and `.`, `-` or `_` also mark an
+    /// empty tile, so boards can also be written like `X.O|.X.|...`. X moves first, so X must have
+    /// as many tiles as O or one more.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tiles: Vec<char> = s
+            .chars()
+            .filter(|x| !x.is_whitespace() && *x != '|')
+            .collect();
+        if tiles.len() != BOARD_SIZE {
+            return Err(GameError::InvalidBoard(format!(
+                "expected {} tiles, found {}",
+                BOARD_SIZE,
+                tiles.len()
+            )));
+        }
+        let mut board = Board::default();
+        for (index, tile) in tiles.iter().enumerate() {
+            let tile = match tile {
+                'X' | 'x' => Tile::X,
+                'O' | 'o' => Tile::O,
+                '.' | '-' | '_' => Tile::Empty,
+                x if x.to_digit(10) == Some(index as u32) => Tile::Empty,
+                x => return Err(GameError::InvalidBoard(format!("unknown tile {}", x))),
+            };
+            board.set(index, tile)?;
+        }
+        let x_tiles = (board.occupied & board.player).count_ones();
+        let o_tiles = (board.occupied & !board.player).count_ones();
+        if x_tiles != o_tiles && x_tiles != o_tiles + 1 {
+            return Err(GameError::InvalidBoard(format!(
+                "{} X tiles and {} O tiles",
+                x_tiles, o_tiles
+            )));
+        }
+        Ok(board)
+    }
+}
+
+/// Moves of a game in order, written like `X4 O0 X8`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MoveList(pub Vec<usize>);
+
+impl Display for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let moves: Vec<String> = self
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, index)| match i % 2 {
+                0 => format!("X{}", index),
+                _ => format!("O{}", index),
+            })
+            .collect();
+        write!(f, "{}", moves.join(" "))
+    }
+}
+
+impl FromStr for MoveList {
+    type Err = GameError;
+
+    /// Parses moves separated by whitespace. Each tile must be the player whose turn it is, so the
+    /// list starts with X and alternates; the moves themselves are checked by [`Board::replay`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split_whitespace()
+            .enumerate()
+            .map(|(i, token)| {
+                let expected = match i % 2 {
+                    0 => 'X',
+                    _ => 'O',
+                };
+                let mut chars = token.chars();
+                match chars.next().map(|x| x.to_ascii_uppercase()) {
+                    Some(x) if x == expected => chars
+                        .as_str()
+                        .parse::<usize>()
+                        .map_err(|_| GameError::InvalidBoard(format!("unknown move {}", token))),
+                    _ => Err(GameError::InvalidBoard(format!(
+                        "move {} should be played by {}",
+                        token, expected
+                    ))),
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(MoveList)
+    }
+}
+
+/// A board along with the moves that reached it, so moves can be undone and redone
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Game {
+    board: Board,
+    moves: Vec<usize>,
+    /// Undone moves, the most recently undone last
+    undone: Vec<usize>,
+}
+
+impl Game {
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Moves played so far, in order
+    pub fn moves(&self) -> &[usize] {
+        &self.moves
+    }
+
+    /// Plays a move for the player whose turn it is, forgetting any undone moves
+    pub fn play(&mut self, index: usize) -> Result<(), GameError> {
+        if self.board.state() != GameState::InProgress {
+            return Err(GameError::InvalidMove(index));
+        }
+        self.board.act(index)?;
+        self.moves.push(index);
+        self.undone.clear();
+        Ok(())
+    }
+
+    /// Takes back the last move, returning it, or None at the start of the game
+    pub fn undo(&mut self) -> Option<usize> {
+        let index = self.moves.pop()?;
+        self.board.occupied &= !(1 << index);
+        self.board.player &= !(1 << index);
+        self.undone.push(index);
+        Some(index)
+    }
+
+    /// Plays the last undone move again, returning it, or None if there's nothing to redo
+    pub fn redo(&mut self) -> Option<usize> {
+        let index = self.undone.pop()?;
+        self.board.act(index).ok()?;
+        self.moves.push(index);
+        Some(index)
+    }
+
+    /// Moves played so far as a [`MoveList`]
+    pub fn history(&self) -> MoveList {
+        MoveList(self.moves.clone())
+    }
+}
+
+impl search::GameState for Board {
+    type Move = usize;
+
+    fn current_player(&self) -> usize {
+        match self.turn() {
+            Tile::X => 0,
+            _ => 1,
+        }
+    }
+
+    fn legal_moves(&self) -> Vec<usize> {
+        match self.state() {
+            GameState::InProgress => self.valid_moves(),
+            _ => vec![],
+        }
+    }
+
+    fn play(&mut self, action: usize) -> Result<(), GameError> {
+        self.act(action)
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.state() {
+            GameState::Won(Tile::X, _) => Some(Outcome::Win(0)),
+            GameState::Won(_, _) => Some(Outcome::Win(1)),
+            GameState::Draw => Some(Outcome::Draw),
+            GameState::InProgress => None,
+        }
+    }
+}
+
+/// Search options for [`SolutionTable`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchConfig {
+    /// Stop searching a position's moves once one is good enough that the opponent will avoid the
+    /// position, caching bounds instead of exact values for positions cut short
+    pub alpha_beta: bool,
+    /// Search the center first, then corners, then edges, so that cutoffs come sooner
+    pub move_ordering: bool,
+    /// Search each move of [`SolutionTable::solve`]'s board on its own thread, sharing the table
+    pub parallel: bool,
+}
+
+impl SearchConfig {
+    /// Alpha-beta pruning with move ordering
+    pub fn pruned() -> Self {
+        SearchConfig {
+            alpha_beta: true,
+            move_ordering: true,
+            parallel: false,
+        }
+    }
+
+    /// Valid moves in the order they're searched
+    fn ordered_moves(&self, board: &Board) -> Vec<usize> {
+        match self.move_ordering {
+            true => MOVE_ORDER
+                .into_iter()
+                .filter(|x| board.occupied & (1 << x) == 0)
+                .collect(),
+            false => board.valid_moves(),
+        }
+    }
+}
+
+/// Search order when [`SearchConfig::move_ordering`] is set: center, corners, edges
+const MOVE_ORDER: [usize; BOARD_SIZE] = [4, 0, 2, 6, 8, 1, 3, 5, 7];
+
+/// What a cached value says about the minimax value of a position
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum Bound {
+    Exact,
+    /// The minimax value is at least the cached value
+    Lower,
+    /// The minimax value is at most the cached value
+    Upper,
+}
+
+type Entry = (i8, Bound);
+
+/// Number of separately locked parts of a [`SharedTable`]
+const SHARDS: usize = 16;
+
+/// Value table shared by the threads of a parallel search. Positions are split between shards by
+/// hash, each behind its own lock, so threads rarely wait on each other.
+struct SharedTable {
+    shards: Vec<Mutex<HashMap<u16, Entry>>>,
+}
+
+impl SharedTable {
+    fn new(table: HashMap<u16, Entry>) -> Self {
+        let mut shards = vec![HashMap::new(); SHARDS];
+        for (hash, entry) in table {
+            shards[hash as usize % SHARDS].insert(hash, entry);
+        }
+        SharedTable {
+            shards: shards.into_iter().map(Mutex::new).collect(),
+        }
+    }
+
+    fn into_inner(self) -> HashMap<u16, Entry> {
+        self.shards
+            .into_iter()
+            .flat_map(|x| x.into_inner().unwrap_or_else(|e| e.into_inner()))
+            .collect()
+    }
+
+    fn shard(&self, hash: u16) -> MutexGuard<'_, HashMap<u16, Entry>> {
+        let shard = &self.shards[hash as usize % SHARDS];
+        shard.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Where a search caches values: a [`SolutionTable`]'s own table, or a [`SharedTable`]
+trait ValueStore {
+    fn get(&self, hash: u16) -> Option<Entry>;
+    fn insert(&mut self, hash: u16, entry: Entry);
+}
+
+impl ValueStore for &mut HashMap<u16, Entry> {
+    fn get(&self, hash: u16) -> Option<Entry> {
+        (**self).get(&hash).copied()
+    }
+
+    fn insert(&mut self, hash: u16, entry: Entry) {
+        (**self).insert(hash, entry);
+    }
+}
+
+impl ValueStore for &SharedTable {
+    fn get(&self, hash: u16) -> Option<Entry> {
+        self.shard(hash).get(&hash).copied()
+    }
+
+    fn insert(&mut self, hash: u16, entry: Entry) {
+        self.shard(hash).insert(hash, entry);
+    }
+}
+
+/// One search over a value store, counting the positions it visits
+struct Search<S> {
+    store: S,
+    config: SearchConfig,
+    nodes: u64,
+}
+
+impl<S: ValueStore> Search<S> {
+    fn new(store: S, config: SearchConfig) -> Self {
+        Search {
+            store,
+            config,
+            nodes: 0,
+        }
+    }
+
+    /// Computes the minimax value of the board if it's strictly between `alpha` and `beta`, and
+    /// otherwise a bound on the value on the same side of the window. Without
+    /// [`SearchConfig::alpha_beta`] the value is always exact.
+    fn alpha_beta(&mut self, board: &Board, mut alpha: i8, mut beta: i8) -> i8 {
+        use Tile::*;
+        self.nodes += 1;
+        let hash = board.invariant_hash();
+        let (alpha_original, beta_original) = (alpha, beta);
+        // If the current position is in our value table, use the value or bound from the table
+        if let Some((value, bound)) = self.store.get(hash) {
+            match bound {
+                Bound::Exact => return value,
+                Bound::Lower => alpha = alpha.max(value),
+                Bound::Upper => beta = beta.min(value),
+            }
+            if alpha >= beta {
+                return value;
+            }
+        }
+
+        // Otherwise, check if we are in a terminal state
+        let empty = BOARD_SIZE as i8 - board.occupied.count_ones() as i8;
+        let terminal = match board.state() {
+            GameState::Won(X, _) => Some(empty + 1),
+            GameState::Won(_, _) => Some(-(empty + 1)),
+            GameState::Draw => Some(0),
+            GameState::InProgress => None,
+        };
+        if let Some(value) = terminal {
+            self.store.insert(hash, (value, Bound::Exact));
+            return value;
+        }
+
+        // Otherwise, compute values for the children
+        let turn = board.turn();
+        let mut value = match turn {
+            X => i8::MIN,
+            O => i8::MAX,
+            _ => panic!("Impossible branch, invalid turn"),
+        };
+        for i in self.config.ordered_moves(board) {
+            let mut new_board = (*board).clone();
+            let _ = new_board.act(i);
+            let child_value = self.alpha_beta(&new_board, alpha, beta);
+            if turn == X {
+                value = value.max(child_value);
+                alpha = alpha.max(child_value);
+            } else {
+                value = value.min(child_value);
+                beta = beta.min(child_value);
+            }
+            if self.config.alpha_beta && alpha >= beta {
+                break;
+            }
+        }
+
+        let bound = if !self.config.alpha_beta {
+            Bound::Exact
+        } else if value <= alpha_original {
+            Bound::Upper
+        } else if value >= beta_original {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        self.store.insert(hash, (value, bound));
+        value
+    }
+}
+
 /// Minimax solution table
 pub struct SolutionTable {
-    value_table: HashMap<u16, i8>,
+    value_table: HashMap<u16, (i8, Bound)>,
+    config: SearchConfig,
+    /// Positions searched so far, including ones answered from the table
+    pub nodes: u64,
 }
 
 impl SolutionTable {
-    /// Returns the minimax solution for the current board state, for the player whose turn it is
-    fn solve(&mut self, board: &Board) -> usize {
+    pub fn new(config: SearchConfig) -> Self {
+        SolutionTable {
+            value_table: HashMap::new(),
+            config,
+            nodes: 0,
+        }
+    }
+
+    /// Reads a table written by [`SolutionTable::save`], to search with `config`. Bounds cached
+    /// by a pruned search are safe to reuse with any config.
+    #[cfg(feature = "fs")]
+    pub fn load(path: &Path, config: SearchConfig) -> Result<Self, IoError> {
+        Ok(SolutionTable {
+            value_table: load_table(path, "tictactoe")?,
+            config,
+            nodes: 0,
+        })
+    }
+
+    /// Writes the cached values to `path`, in the [`transposition`](crate::transposition) format
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: &Path) -> Result<(), IoError> {
+        save_table(path, "tictactoe", &self.value_table)
+    }
+
+    /// Minimax value of the board: the number of empty tiles plus one when X wins, the negative of
+    /// that when O wins, and 0 for a draw, so faster wins are worth more
+    pub fn value(&mut self, board: &Board) -> i8 {
+        self.eval_recursive(board)
+    }
+
+    /// Returns the minimax solution for the current board state, for the player whose turn it is.
+    /// The board must have at least one empty tile.
+    pub fn solve(&mut self, board: &Board) -> usize {
         use Tile::*;
-        let empty = board.valid_moves();
-        let values: Vec<i8> = empty
-            .iter()
-            .map(|i| {
-                let mut new_board = (*board).clone();
-                let _ = new_board.act(*i);
-                self.eval_recursive(&new_board)
-            })
-            .collect();
+        let empty = self.config.ordered_moves(board);
+        let values: Vec<i8> = match self.config.parallel {
+            true => self.eval_parallel(board, &empty),
+            false => empty
+                .iter()
+                .map(|i| {
+                    let mut new_board = (*board).clone();
+                    let _ = new_board.act(*i);
+                    self.eval_recursive(&new_board)
+                })
+                .collect(),
+        };
         match board.turn() {
             X => {
                 // Argmax
-                let (argmax, _) = empty.into_iter().zip(values.into_iter()).fold(
-                    (0 as usize, i8::MIN),
+                let (argmax, _) = empty.into_iter().zip(values).fold(
+                    (0, i8::MIN),
                     |(argmax, max), (index, value)| match max > value {
                         true => (argmax, max),
                         false => (index, value),
@@ -233,8 +711,8 @@ impl SolutionTable {
             }
             O => {
                 // Argmin
-                let (argmin, _) = empty.into_iter().zip(values.into_iter()).fold(
-                    (0 as usize, i8::MAX),
+                let (argmin, _) = empty.into_iter().zip(values).fold(
+                    (0, i8::MAX),
                     |(argmin, min), (index, value)| match min < value {
                         true => (argmin, min),
                         false => (index, value),
@@ -250,113 +728,44 @@ impl SolutionTable {
 
     /// Computes the minimax value of the current board state
     fn eval_recursive(&mut self, board: &Board) -> i8 {
-        use Tile::*;
-        let hash = board.invariant_hash();
-        match self.value_table.get(&hash) {
-            // If the current position is in our value table, simply return the value from the hash table
-            Some(x) => *x,
-            None => match board.winner() {
-                // Otherwise, check if we are in a terminal state
-                X => {
-                    let value = BOARD_SIZE as i8 - board.occupied.count_ones() as i8 + 1;
-                    self.value_table.insert(hash, value);
-                    value
-                }
-                O => {
-                    let value = -(BOARD_SIZE as i8 - board.occupied.count_ones() as i8 + 1);
-                    self.value_table.insert(hash, value);
-                    value
-                }
-                _ => {
-                    let valid_moves = board.valid_moves();
-                    match valid_moves.is_empty() {
-                        true => {
-                            let value = 0;
-                            self.value_table.insert(hash, value);
-                            value
-                        }
-                        // Otherwise, compute values for all children
-                        false => {
-                            let children: Vec<Board> = valid_moves
-                                .into_iter()
-                                .map(|i| {
-                                    let mut new_board = (*board).clone();
-                                    let _ = new_board.act(i);
-                                    new_board
-                                })
-                                .collect();
-                            let child_values: Vec<i8> = children
-                                .into_iter()
-                                .map(|x| self.eval_recursive(&x))
-                                .collect();
-                            let value = match board.turn() {
-                                X => child_values.into_iter().max().unwrap(),
-                                O => child_values.into_iter().min().unwrap(),
-                                _ => panic!("Impossible branch, invalid turn"),
-                            };
-
-                            self.value_table.insert(hash, value);
-                            value
-                        }
-                    }
-                }
-            },
-        }
+        let mut search = Search::new(&mut self.value_table, self.config);
+        let value = search.alpha_beta(board, i8::MIN, i8::MAX);
+        self.nodes += search.nodes;
+        value
     }
-}
 
-impl Default for SolutionTable {
-    fn default() -> Self {
-        SolutionTable {
-            value_table: HashMap::new(),
-        }
+    /// Computes the minimax values of the boards reached by each of `moves` in parallel, the
+    /// threads sharing the table so each can use what the others have found
+    fn eval_parallel(&mut self, board: &Board, moves: &[usize]) -> Vec<i8> {
+        let table = SharedTable::new(std::mem::take(&mut self.value_table));
+        let config = self.config;
+        let results: Vec<(i8, u64)> = moves
+            .par_iter()
+            .map(|i| {
+                let mut new_board = (*board).clone();
+                let _ = new_board.act(*i);
+                let mut search = Search::new(&table, config);
+                let value = search.alpha_beta(&new_board, i8::MIN, i8::MAX);
+                (value, search.nodes)
+            })
+            .collect();
+        self.value_table = table.into_inner();
+        self.nodes += results.iter().map(|x| x.1).sum::<u64>();
+        results.into_iter().map(|x| x.0).collect()
     }
 }
 
-fn main() {
-    use std::io::stdin;
-    let args: Vec<String> = std::env::args().collect();
-    println!("{:?}", args);
-
-    let player_turn: Tile = match args.get(1) {
-        Some(x) => match x.as_str() {
-            "O" => Tile::O,
-            _ => Tile::X,
-        },
-        None => Tile::X,
-    };
-
-    let mut board = Board::default();
-    let mut solution = SolutionTable::default();
-
-    println!("{board}");
-
-    while board.winner() == Tile::Empty && board.occupied.count_ones() < BOARD_SIZE as u32 {
-        if board.turn() == player_turn {
-            let mut input_buffer = String::new();
-            let _ = stdin().read_line(&mut input_buffer);
-            let i = input_buffer.trim().parse::<usize>();
-            match i {
-                Ok(i) => {
-                    let _ = board.act(i);
-                }
-                _ => {
-                    println!("Invalid move!");
-                }
-            }
-            // Read input
-        } else {
-            let argmin = solution.solve(&board);
-            let _ = board.act(argmin);
-        }
-
-        println!("{board}");
+impl Default for SolutionTable {
+    /// Exhaustive minimax, without pruning
+    fn default() -> Self {
+        SolutionTable::new(SearchConfig::default())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::search::GameState as _;
 
     #[test]
     fn test_board_get() {
@@ -367,7 +776,7 @@ mod tests {
             };
             let board_o = Board {
                 occupied: 1 << index,
-                player: !0 & !(1 << index),
+                player: !(1 << index),
             };
 
             for j in 0..BOARD_SIZE {
@@ -442,6 +851,16 @@ mod tests {
         assert_eq!(board.get(0).expect("Unable to get tile 0"), Tile::O);
     }
 
+    #[test]
+    fn test_board_errors() {
+        let mut board = Board::default();
+        assert_eq!(board.get(9), Err(GameError::OutOfBounds(9)));
+        assert_eq!(board.act(9), Err(GameError::OutOfBounds(9)));
+        assert!(board.act(4).is_ok());
+        assert_eq!(board.act(4), Err(GameError::InvalidMove(4)));
+        assert_eq!(board.turn(), Tile::O);
+    }
+
     #[test]
     fn test_board_invariant_hash() {
         // Default position hashes to 0
@@ -547,6 +966,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_state() {
+        assert_eq!(Board::default().state(), GameState::InProgress);
+        assert_eq!(
+            "XXXOO5678".parse::<Board>().unwrap().state(),
+            GameState::Won(Tile::X, [0, 1, 2])
+        );
+        assert_eq!(
+            "OXXOX5O78".parse::<Board>().unwrap().state(),
+            GameState::Won(Tile::O, [0, 3, 6])
+        );
+        // Full without a line
+        assert_eq!(
+            "XOXXOOOXX".parse::<Board>().unwrap().state(),
+            GameState::Draw
+        );
+        // Full with a line is a win, not a draw
+        assert_eq!(
+            "XXXOOXOXO".parse::<Board>().unwrap().state(),
+            GameState::Won(Tile::X, [0, 1, 2])
+        );
+    }
+
+    #[test]
+    fn test_winning_line() {
+        assert_eq!(Board::default().winning_line(), None);
+        let board: Board = "XX2OO5678".parse().unwrap();
+        assert_eq!(board.winning_line(), None);
+        let board: Board = "XXXOO5678".parse().unwrap();
+        assert_eq!(board.winning_line(), Some([0, 1, 2]));
+        let board: Board = "OXXOX5O78".parse().unwrap();
+        assert_eq!(board.winning_line(), Some([0, 3, 6]));
+    }
+
     #[test]
     fn test_board_valid_moves() {
         assert_eq!(
@@ -565,11 +1018,99 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_index_notation() {
+        let board: Board = "X1O3X5678".parse().unwrap();
+        assert_eq!(board.get(0), Ok(Tile::X));
+        assert_eq!(board.get(2), Ok(Tile::O));
+        assert_eq!(board.turn(), Tile::O);
+        assert_eq!(board.to_index_notation(), "X1O3X5678");
+        assert_eq!("x.o|.x.|...".parse::<Board>(), Ok(board));
+        assert_eq!(
+            Board::default().to_index_notation().parse::<Board>(),
+            Ok(Board::default())
+        );
+
+        assert!("X1O3X567".parse::<Board>().is_err());
+        assert!("X1O3X567Z".parse::<Board>().is_err());
+        assert!("X5O3X5678".parse::<Board>().is_err());
+        // O can't be ahead of X
+        assert!("OO2345678".parse::<Board>().is_err());
+    }
+
+    #[test]
+    fn test_move_list() {
+        let moves: MoveList = "X4 O0 x8 O2".parse().unwrap();
+        assert_eq!(moves, MoveList(vec![4, 0, 8, 2]));
+        assert_eq!(moves.to_string(), "X4 O0 X8 O2");
+        assert_eq!(
+            Board::replay(&moves.0).unwrap().to_index_notation(),
+            "O1O3X567X"
+        );
+        assert_eq!("".parse::<MoveList>(), Ok(MoveList::default()));
+
+        assert!("X4 X0".parse::<MoveList>().is_err());
+        assert!("O4".parse::<MoveList>().is_err());
+        assert!("X4 Oa".parse::<MoveList>().is_err());
+        assert_eq!(Board::replay(&[4, 4]), Err(GameError::InvalidMove(4)));
+        // X wins on the diagonal with its third move
+        assert!(Board::replay(&[0, 1, 4, 2, 8, 3]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_save_load() {
+        let path = std::env::temp_dir().join("game_theory_rs_test_tictactoe_table.json");
+        let mut solver = SolutionTable::new(SearchConfig::pruned());
+        solver.value(&Board::default());
+        solver.save(&path).unwrap();
+
+        let mut loaded = SolutionTable::load(&path, SearchConfig::default()).unwrap();
+        assert_eq!(loaded.value_table, solver.value_table);
+        let board: Board = "XX2OO5678".parse().unwrap();
+        assert_eq!(loaded.value(&board), SolutionTable::default().value(&board));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_game() {
+        let mut game = Game::default();
+        for index in [4, 0, 8] {
+            game.play(index).unwrap();
+        }
+        assert_eq!(game.play(4), Err(GameError::InvalidMove(4)));
+        assert_eq!(game.history().to_string(), "X4 O0 X8");
+
+        assert_eq!(game.undo(), Some(8));
+        assert_eq!(game.undo(), Some(0));
+        assert_eq!(game.board(), &Board::replay(&[4]).unwrap());
+        assert_eq!(game.redo(), Some(0));
+        assert_eq!(game.moves(), [4, 0]);
+
+        // A new move replaces the undone ones
+        game.play(2).unwrap();
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.board(), &Board::replay(&[4, 0, 2]).unwrap());
+
+        while game.undo().is_some() {}
+        assert_eq!(
+            game,
+            Game {
+                undone: vec![2, 0, 4],
+                ..Default::default()
+            }
+        );
+    }
+
     #[test]
     fn test_solver() {
         let mut solver = SolutionTable::default();
         assert_eq!(solver.eval_recursive(&Board::default()), 0); // Theoretical draw
         assert_eq!(solver.value_table.len(), 765);
+        assert_eq!(
+            Board::default().count_positions(|x| x.invariant_hash()),
+            765
+        );
 
         assert_eq!(
             solver.eval_recursive(&Board {
@@ -579,4 +1120,60 @@ mod tests {
             3 // Win for X
         );
     }
+
+    #[test]
+    fn test_alpha_beta() {
+        let mut exhaustive = SolutionTable::default();
+        assert_eq!(exhaustive.value(&Board::default()), 0);
+
+        for config in [
+            SearchConfig {
+                alpha_beta: true,
+                ..Default::default()
+            },
+            SearchConfig::pruned(),
+        ] {
+            let mut pruned = SolutionTable::new(config);
+            assert_eq!(pruned.value(&Board::default()), 0);
+            assert!(pruned.nodes < exhaustive.nodes);
+
+            // Bounds cached by earlier searches still give exact values
+            for board in ["X1O3X5678", "XO2345678", "0X2O4567X", "XX2OO5678"] {
+                let board: Board = board.parse().unwrap();
+                assert_eq!(pruned.value(&board), exhaustive.value(&board));
+            }
+        }
+
+        let mut ordered = SolutionTable::new(SearchConfig::pruned());
+        let mut unordered = SolutionTable::new(SearchConfig {
+            alpha_beta: true,
+            ..Default::default()
+        });
+        ordered.value(&Board::default());
+        unordered.value(&Board::default());
+        assert!(ordered.nodes < unordered.nodes);
+    }
+
+    #[test]
+    fn test_parallel() {
+        let mut sequential = SolutionTable::new(SearchConfig::pruned());
+        let mut parallel = SolutionTable::new(SearchConfig {
+            parallel: true,
+            ..SearchConfig::pruned()
+        });
+        for board in [
+            "012345678",
+            "X1O3X5678",
+            "XO2345678",
+            "0X2O4567X",
+            "XX2OO5678",
+        ] {
+            let board: Board = board.parse().unwrap();
+            assert_eq!(parallel.solve(&board), sequential.solve(&board));
+            assert_eq!(parallel.value(&board), sequential.value(&board));
+        }
+        assert!(parallel.nodes > 0);
+        // The shared table is merged back after each solve
+        assert!(!parallel.value_table.is_empty());
+    }
 }
@@ -0,0 +1,123 @@
+//! Transposition tables saved to disk, so that expensive solves can be reused across runs.
+//!
+//! A table is a JSON document whose header names the game the table belongs to and the format
+//! [`VERSION`], followed by the entries as `[key, value]` pairs:
+//!
+//! ```json
+//! {"format": "connect4", "version": 1, "entries": [[4521, 3], ...]}
+//! ```
+//!
+//! Tables written for another game or by another version are rejected on load rather than
+//! misread.
+
+use crate::error::IoError;
+
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use hashbrown::HashMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Current format version; tables written by other versions are rejected
+pub const VERSION: u32 = 1;
+
+#[derive(Deserialize)]
+struct Header {
+    format: String,
+    version: u32,
+}
+
+#[derive(Serialize)]
+struct TableRef<'a, K, V> {
+    format: &'a str,
+    version: u32,
+    entries: Vec<(&'a K, &'a V)>,
+}
+
+#[derive(Deserialize)]
+struct TableFile<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+/// Writes the table to `path` under the header for `format`
+pub fn save_table<K, V>(path: &Path, format: &str, table: &HashMap<K, V>) -> Result<(), IoError>
+where
+    K: Serialize,
+    V: Serialize,
+{
+    let file = TableRef {
+        format,
+        version: VERSION,
+        entries: table.iter().collect(),
+    };
+    let mut writer = BufWriter::new(File::create(path).map_err(IoError::file(path))?);
+    serde_json::to_writer(&mut writer, &file).map_err(IoError::format(path))?;
+    writer.flush().map_err(IoError::file(path))
+}
+
+/// Reads a table written by [`save_table`], checking that its header matches `format` and
+/// [`VERSION`]
+pub fn load_table<K, V>(path: &Path, format: &str) -> Result<HashMap<K, V>, IoError>
+where
+    K: DeserializeOwned + Eq + Hash,
+    V: DeserializeOwned,
+{
+    let bytes = std::fs::read(path).map_err(IoError::file(path))?;
+    let header_error = |reason: String| IoError::Header {
+        path: path.to_path_buf(),
+        reason,
+    };
+    let header: Header = serde_json::from_slice(&bytes)
+        .map_err(|_| header_error("not a transposition table".to_string()))?;
+    if header.format != format {
+        return Err(header_error(format!(
+            "table for {}, expected {}",
+            header.format, format
+        )));
+    }
+    if header.version != VERSION {
+        return Err(header_error(format!(
+            "version {} is not supported, expected {}",
+            header.version, VERSION
+        )));
+    }
+    let file: TableFile<K, V> = serde_json::from_slice(&bytes).map_err(IoError::format(path))?;
+    Ok(file.entries.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_load() {
+        let path = std::env::temp_dir().join("game_theory_rs_test_transposition.json");
+
+        let table: HashMap<u64, i8> = [(1, -3), (4521, 7)].into_iter().collect();
+        save_table(&path, "connect4", &table).unwrap();
+        assert_eq!(load_table::<u64, i8>(&path, "connect4").unwrap(), table);
+        assert!(matches!(
+            load_table::<u64, i8>(&path, "tictactoe"),
+            Err(IoError::Header { .. })
+        ));
+
+        std::fs::write(
+            &path,
+            r#"{"format": "connect4", "version": 0, "entries": []}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            load_table::<u64, i8>(&path, "connect4"),
+            Err(IoError::Header { .. })
+        ));
+        std::fs::write(&path, "[1, 2, 3]").unwrap();
+        assert!(matches!(
+            load_table::<u64, i8>(&path, "connect4"),
+            Err(IoError::Header { .. })
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,298 @@
+//! Ultimate tic-tac-toe: nine tic-tac-toe boards arranged in a tic-tac-toe board.
+//!
+//! Winning a small board claims its square of the meta-board, and three claimed squares in a row
+//! win the game. The cell of each move sends the opponent to the small board in the same position,
+//! unless that board is already won or full, in which case they may play on any open board.
+//!
+//! Small boards and their cells are indexed 0 to 8 like [`tictactoe`](crate::tictactoe) tiles, and
+//! moves are indexed `board * 9 + cell`. The game is too large to solve by exhaustive minimax, so
+//! it's played with [`DepthLimitedSearch`](crate::search::DepthLimitedSearch) or
+//! [`Mcts`](crate::mcts::Mcts).
+
+pub use crate::error::GameError;
+pub use crate::tictactoe::Tile;
+
+use crate::search::{Evaluator, GameState, Outcome};
+use crate::tictactoe::{BOARD_SIZE, WIN_LINES};
+
+use std::fmt::Display;
+
+/// Number of moves on the whole board
+pub const MOVES: usize = BOARD_SIZE * BOARD_SIZE;
+
+/// Tiles of a full small board
+const FULL: u16 = (1 << BOARD_SIZE) - 1;
+
+fn has_line(tiles: u16) -> bool {
+    WIN_LINES.iter().any(|x| tiles & x == *x)
+}
+
+/// Bitboard representation of an ultimate tic-tac-toe position
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Board {
+    /// Tiles occupied by X on each small board
+    x: [u16; BOARD_SIZE],
+    /// Tiles occupied by O on each small board
+    o: [u16; BOARD_SIZE],
+    /// Small board the player to move was sent to, or None if they may play on any open board
+    next: Option<usize>,
+    moves: usize,
+}
+
+impl Board {
+    /// Determines whose turn it is, X or O
+    pub fn turn(&self) -> Tile {
+        match self.moves % 2 {
+            0 => Tile::X,
+            _ => Tile::O,
+        }
+    }
+
+    /// Gets the tile at a cell of a small board
+    pub fn get(&self, board: usize, cell: usize) -> Result<Tile, GameError> {
+        if board >= BOARD_SIZE || cell >= BOARD_SIZE {
+            return Err(GameError::OutOfBounds(board * BOARD_SIZE + cell));
+        }
+        if self.x[board] & (1 << cell) != 0 {
+            Ok(Tile::X)
+        } else if self.o[board] & (1 << cell) != 0 {
+            Ok(Tile::O)
+        } else {
+            Ok(Tile::Empty)
+        }
+    }
+
+    /// Winner of a small board, if there is one. Only one player can win a board, since play there
+    /// stops once it's won.
+    pub fn board_winner(&self, board: usize) -> Tile {
+        if has_line(self.x[board]) {
+            Tile::X
+        } else if has_line(self.o[board]) {
+            Tile::O
+        } else {
+            Tile::Empty
+        }
+    }
+
+    /// Whether a small board is won or full, so no more moves can be played on it
+    pub fn is_decided(&self, board: usize) -> bool {
+        self.board_winner(board) != Tile::Empty || self.x[board] | self.o[board] == FULL
+    }
+
+    /// Small boards won by the player, as a tic-tac-toe bitboard
+    fn boards_won(&self, player: Tile) -> u16 {
+        (0..BOARD_SIZE)
+            .filter(|x| self.board_winner(*x) == player)
+            .fold(0, |won, x| won | (1 << x))
+    }
+
+    /// Computes the winner of the meta-board, if there is one
+    pub fn winner(&self) -> Tile {
+        if has_line(self.boards_won(Tile::X)) {
+            Tile::X
+        } else if has_line(self.boards_won(Tile::O)) {
+            Tile::O
+        } else {
+            Tile::Empty
+        }
+    }
+
+    /// Small board the player to move must play on, or None if they may play on any open board
+    pub fn active_board(&self) -> Option<usize> {
+        self.next
+    }
+
+    /// Lists indices of valid moves, empty once the game is over
+    pub fn valid_moves(&self) -> Vec<usize> {
+        if self.winner() != Tile::Empty {
+            return vec![];
+        }
+        let boards: Vec<usize> = match self.next {
+            Some(x) => vec![x],
+            None => (0..BOARD_SIZE).filter(|x| !self.is_decided(*x)).collect(),
+        };
+        boards
+            .into_iter()
+            .flat_map(|board| {
+                let occupied = self.x[board] | self.o[board];
+                (0..BOARD_SIZE)
+                    .filter(move |x| occupied & (1 << x) == 0)
+                    .map(move |x| board * BOARD_SIZE + x)
+            })
+            .collect()
+    }
+
+    /// Whether the game is won or every small board is decided
+    pub fn is_over(&self) -> bool {
+        self.valid_moves().is_empty()
+    }
+
+    /// Tries to play the move with index `board * 9 + cell` for the player whose turn it is
+    pub fn act(&mut self, index: usize) -> Result<(), GameError> {
+        if index >= MOVES {
+            return Err(GameError::OutOfBounds(index));
+        }
+        if !self.valid_moves().contains(&index) {
+            return Err(GameError::InvalidMove(index));
+        }
+        let (board, cell) = (index / BOARD_SIZE, index % BOARD_SIZE);
+        match self.turn() {
+            Tile::X => self.x[board] |= 1 << cell,
+            _ => self.o[board] |= 1 << cell,
+        }
+        self.moves += 1;
+        self.next = match self.is_decided(cell) {
+            true => None,
+            false => Some(cell),
+        };
+        Ok(())
+    }
+}
+
+impl Display for Board {
+    /// Prints the nine small boards in a grid, with `.` for empty tiles
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in 0..BOARD_SIZE {
+            if row > 0 && row % 3 == 0 {
+                writeln!(f, "------+-------+------")?;
+            }
+            let cells: Vec<String> = (0..BOARD_SIZE)
+                .map(|column| {
+                    let board = row / 3 * 3 + column / 3;
+                    let cell = row % 3 * 3 + column % 3;
+                    let tile = match self.get(board, cell).map_err(|_| std::fmt::Error)? {
+                        Tile::X => "X",
+                        Tile::O => "O",
+                        Tile::Empty => ".",
+                    };
+                    Ok(match column > 0 && column % 3 == 0 {
+                        true => format!("| {}", tile),
+                        false => tile.to_string(),
+                    })
+                })
+                .collect::<Result<_, std::fmt::Error>>()?;
+            writeln!(f, "{}", cells.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+impl GameState for Board {
+    type Move = usize;
+
+    fn current_player(&self) -> usize {
+        self.moves % 2
+    }
+
+    fn legal_moves(&self) -> Vec<usize> {
+        self.valid_moves()
+    }
+
+    fn play(&mut self, action: usize) -> Result<(), GameError> {
+        self.act(action)
+    }
+
+    fn outcome(&self) -> Option<Outcome> {
+        match self.winner() {
+            Tile::X => Some(Outcome::Win(0)),
+            Tile::O => Some(Outcome::Win(1)),
+            _ if self.is_over() => Some(Outcome::Draw),
+            _ => None,
+        }
+    }
+}
+
+/// Scores a position by the small boards won by the player to move, less those won by the
+/// opponent, with the center board counting double
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BoardsEvaluator;
+
+impl Evaluator<Board> for BoardsEvaluator {
+    fn evaluate(&self, state: &Board) -> f64 {
+        let score = |won: u16| won.count_ones() as f64 + ((won >> 4) & 1) as f64;
+        let (x, o) = (state.boards_won(Tile::X), state.boards_won(Tile::O));
+        match state.turn() {
+            Tile::X => score(x) - score(o),
+            _ => score(o) - score(x),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcts::{GreedyPlayout, Mcts, MctsConfig};
+    use crate::search::DepthLimitedSearch;
+
+    #[test]
+    fn test_rules() {
+        let mut board = Board::default();
+        assert_eq!(board.valid_moves().len(), MOVES);
+        assert_eq!(board.act(MOVES), Err(GameError::OutOfBounds(MOVES)));
+
+        // The center of the center board sends O to the center board
+        board.act(40).unwrap();
+        assert_eq!(board.active_board(), Some(4));
+        assert_eq!(
+            board.valid_moves(),
+            (36..45).filter(|x| *x != 40).collect::<Vec<_>>()
+        );
+        assert_eq!(board.act(0), Err(GameError::InvalidMove(0)));
+        assert_eq!(board.act(40), Err(GameError::InvalidMove(40)));
+
+        // O wins the first board with its middle row
+        let mut board = Board::default();
+        for index in [0, 3, 27, 4, 36, 5] {
+            board.act(index).unwrap();
+        }
+        assert_eq!(board.board_winner(0), Tile::O);
+        assert_eq!(board.get(0, 4), Ok(Tile::O));
+        assert_eq!(board.get(3, 0), Ok(Tile::X));
+        assert_eq!(board.winner(), Tile::Empty);
+
+        // Being sent to a won board allows a move on any other open board
+        board.act(45).unwrap();
+        assert_eq!(board.active_board(), None);
+        let moves = board.valid_moves();
+        assert_eq!(moves.len(), 69);
+        assert!(moves.iter().all(|x| x / BOARD_SIZE != 0));
+    }
+
+    #[test]
+    fn test_meta_board() {
+        let board = Board {
+            x: [0b111, 0b111, 0b111, 0, 0, 0, 0, 0, 0],
+            o: [0b11_000, 0b11_000, 0b11_000, 0, 0, 0, 0, 0, 0],
+            next: None,
+            moves: 15,
+        };
+        assert_eq!(board.winner(), Tile::X);
+        assert!(board.valid_moves().is_empty());
+        assert_eq!(board.outcome(), Some(Outcome::Win(0)));
+        assert_eq!(board.to_string().lines().count(), 11);
+    }
+
+    #[test]
+    fn test_search() {
+        // X has won the first two boards, and is sent to the third with two in a row there
+        let board = Board {
+            x: [0b111, 0b111, 0b11, 0, 0, 0, 0, 0, 0],
+            o: [0, 0, 0, 0b111, 0b11, 0, 0b111, 0, 0],
+            next: Some(2),
+            moves: 16,
+        };
+        assert_eq!(board.turn(), Tile::X);
+        assert_eq!(BoardsEvaluator.evaluate(&board), 0.);
+
+        let mut search = DepthLimitedSearch::new(BoardsEvaluator, 2);
+        assert_eq!(search.best_move(&board).map(|x| x.0), Some(20));
+
+        let config = MctsConfig {
+            iterations: 2000,
+            seed: Some(0),
+            ..Default::default()
+        };
+        let mut mcts = Mcts::new(config, GreedyPlayout);
+        assert_eq!(mcts.best_move(&board), Some(20));
+    }
+}
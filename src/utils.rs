@@ -1,4 +1,4 @@
-use rayon::prelude::*;
+use crate::parallel::*;
 
 pub fn enumerate_combos<T: Clone + Send + Sync>(items: Vec<T>, k: usize) -> Vec<Vec<T>> {
     // Base case: k = 1 or k == length of items
@@ -0,0 +1,358 @@
+//! Social choice from ranked ballots: plurality, Borda count, instant-runoff voting, Condorcet
+//! winners and the Schulze method.
+//!
+//! A ballot ranks some of the candidates from most to least preferred, and prefers every ranked
+//! candidate to every unranked one. The Condorcet winner beats every other candidate in a head to
+//! head majority, but majorities can form a cycle (A beats B beats C beats A), in which case there
+//! isn't one. The Smith set, the smallest set of candidates beating everyone outside it, is then
+//! every candidate on a cycle and whoever they beat on the way, and the Schulze method picks from
+//! it by comparing the strongest chains of majorities between each pair.
+
+use ndarray::*;
+use std::str::FromStr;
+
+/// Candidates ranked by some number of voters
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ballot {
+    /// Candidate indices, most preferred first
+    pub ranking: Vec<usize>,
+    /// Number of voters casting the ballot
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Election {
+    pub candidates: Vec<String>,
+    pub ballots: Vec<Ballot>,
+}
+
+/// Score of every candidate, and the candidates with the highest
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tally {
+    pub scores: Vec<usize>,
+    pub winners: Vec<usize>,
+}
+
+impl Tally {
+    fn new(scores: Vec<usize>) -> Self {
+        let best = scores.iter().max().copied();
+        let winners = (0..scores.len())
+            .filter(|x| Some(scores[*x]) == best)
+            .collect();
+        Tally { scores, winners }
+    }
+}
+
+/// Rounds of an instant-runoff count
+#[derive(Clone, Debug, PartialEq)]
+pub struct Runoff {
+    /// First preferences among the remaining candidates in each round, zero for eliminated ones
+    pub rounds: Vec<Vec<usize>>,
+    /// Candidates in the order they were eliminated
+    pub eliminated: Vec<usize>,
+    pub winner: usize,
+}
+
+impl FromStr for Election {
+    type Err = String;
+
+    /// One ballot per line as candidate names separated by `>`, optionally preceded by a voter
+    /// count and a colon, e.g. `3: Alice > Bob`. Candidates are numbered in order of appearance, and
+    /// there must be at least one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut election = Election {
+            candidates: Vec::new(),
+            ballots: Vec::new(),
+        };
+        for line in s.lines().map(str::trim).filter(|x| !x.is_empty()) {
+            let (count, ranking) = match line.split_once(':') {
+                Some((count, ranking)) => (
+                    count
+                        .trim()
+                        .parse()
+                        .map_err(|_| format!("Invalid ballot: {}", line))?,
+                    ranking,
+                ),
+                None => (1, line),
+            };
+            let mut indices = Vec::new();
+            for name in ranking.split('>').map(str::trim) {
+                if name.is_empty() {
+                    return Err(format!("Invalid ballot: {}", line));
+                }
+                let index = match election.candidates.iter().position(|x| x == name) {
+                    Some(x) => x,
+                    None => {
+                        election.candidates.push(name.to_string());
+                        election.candidates.len() - 1
+                    }
+                };
+                if indices.contains(&index) {
+                    return Err(format!("Invalid ballot: {}", line));
+                }
+                indices.push(index);
+            }
+            election.ballots.push(Ballot {
+                ranking: indices,
+                count,
+            });
+        }
+        if election.candidates.is_empty() {
+            return Err("Expected at least one ballot".to_string());
+        }
+        Ok(election)
+    }
+}
+
+impl Election {
+    /// Number of voters preferring each candidate (rows) to each other (columns)
+    pub fn pairwise(&self) -> Array<usize, Ix2> {
+        let n = self.candidates.len();
+        let mut result = Array::zeros((n, n));
+        for ballot in &self.ballots {
+            for (rank, x) in ballot.ranking.iter().enumerate() {
+                for y in (0..n).filter(|y| !ballot.ranking[..=rank].contains(y)) {
+                    result[[*x, y]] += ballot.count;
+                }
+            }
+        }
+        result
+    }
+
+    /// Number of first preferences
+    pub fn plurality(&self) -> Tally {
+        let mut scores = vec![0; self.candidates.len()];
+        for ballot in &self.ballots {
+            if let Some(first) = ballot.ranking.first() {
+                scores[*first] += ballot.count;
+            }
+        }
+        Tally::new(scores)
+    }
+
+    /// With n candidates, n - 1 points for a first preference, n - 2 for a second and so on, and
+    /// none for unranked candidates
+    pub fn borda(&self) -> Tally {
+        let n = self.candidates.len();
+        let mut scores = vec![0; n];
+        for ballot in &self.ballots {
+            for (rank, x) in ballot.ranking.iter().enumerate() {
+                scores[*x] += (n - 1 - rank) * ballot.count;
+            }
+        }
+        Tally::new(scores)
+    }
+
+    /// Eliminates the candidate with the fewest first preferences among those remaining, the
+    /// last listed on ties, until one has a majority of the ballots still ranking someone
+    /// remaining
+    ///
+    /// # Panics
+    ///
+    /// If there are no candidates, which parsing an election rules out.
+    pub fn instant_runoff(&self) -> Runoff {
+        let n = self.candidates.len();
+        let mut remaining = vec![true; n];
+        let mut rounds = Vec::new();
+        let mut eliminated = Vec::new();
+        loop {
+            let mut tally = vec![0; n];
+            for ballot in &self.ballots {
+                if let Some(x) = ballot.ranking.iter().find(|x| remaining[**x]) {
+                    tally[*x] += ballot.count;
+                }
+            }
+            let total: usize = tally.iter().sum();
+            let candidates: Vec<usize> = (0..n).filter(|x| remaining[*x]).collect();
+            let leader = candidates.iter().copied().max_by_key(|x| tally[*x]);
+            let last = candidates.iter().copied().rev().min_by_key(|x| tally[*x]);
+            rounds.push(tally.clone());
+            match (leader, last) {
+                (Some(leader), _) if 2 * tally[leader] > total || candidates.len() == 1 => {
+                    return Runoff {
+                        rounds,
+                        eliminated,
+                        winner: leader,
+                    };
+                }
+                (_, Some(last)) => {
+                    remaining[last] = false;
+                    eliminated.push(last);
+                }
+                _ => panic!("Election has no candidates"),
+            }
+        }
+    }
+
+    /// Candidate preferred to every other by a majority, if there is one
+    pub fn condorcet_winner(&self) -> Option<usize> {
+        let pairwise = self.pairwise();
+        (0..self.candidates.len()).find(|x| {
+            (0..self.candidates.len()).all(|y| x == &y || pairwise[[*x, y]] > pairwise[[y, *x]])
+        })
+    }
+
+    /// Candidates in a cycle of majority preferences, each preferred to the next and the last to
+    /// the first, if there is one
+    pub fn majority_cycle(&self) -> Option<Vec<usize>> {
+        let pairwise = self.pairwise();
+        let n = self.candidates.len();
+        let beats = |x: usize, y: usize| pairwise[[x, y]] > pairwise[[y, x]];
+        // Depth-first search for an edge back to a candidate on the current path
+        let mut visited = vec![false; n];
+        for start in 0..n {
+            if visited[start] {
+                continue;
+            }
+            let mut path = vec![start];
+            let mut next = vec![0];
+            visited[start] = true;
+            while let (Some(x), Some(y)) = (path.last().copied(), next.last_mut()) {
+                if *y == n {
+                    path.pop();
+                    next.pop();
+                    continue;
+                }
+                let candidate = *y;
+                *y += 1;
+                if !beats(x, candidate) {
+                    continue;
+                }
+                if let Some(position) = path.iter().position(|z| *z == candidate) {
+                    return Some(path[position..].to_vec());
+                }
+                if !visited[candidate] {
+                    visited[candidate] = true;
+                    path.push(candidate);
+                    next.push(0);
+                }
+            }
+        }
+        None
+    }
+
+    /// Smallest set of candidates each preferred by a majority to every candidate outside it
+    pub fn smith_set(&self) -> Vec<usize> {
+        let pairwise = self.pairwise();
+        let n = self.candidates.len();
+        // Whether each candidate reaches each other through majorities or ties
+        let mut reaches =
+            Array::from_shape_fn((n, n), |(x, y)| pairwise[[x, y]] >= pairwise[[y, x]]);
+        for z in 0..n {
+            for x in 0..n {
+                for y in 0..n {
+                    if reaches[[x, z]] && reaches[[z, y]] {
+                        reaches[[x, y]] = true;
+                    }
+                }
+            }
+        }
+        (0..n)
+            .filter(|x| (0..n).all(|y| reaches[[*x, y]]))
+            .collect()
+    }
+
+    /// Strength of the strongest path of majorities from each candidate (rows) to each other
+    /// (columns), a path being as strong as its weakest majority
+    pub fn schulze_strengths(&self) -> Array<usize, Ix2> {
+        let pairwise = self.pairwise();
+        let n = self.candidates.len();
+        let mut strengths =
+            Array::from_shape_fn((n, n), |(x, y)| match pairwise[[x, y]] > pairwise[[y, x]] {
+                true => pairwise[[x, y]],
+                false => 0,
+            });
+        for z in 0..n {
+            for x in (0..n).filter(|x| *x != z) {
+                for y in (0..n).filter(|y| *y != z && *y != x) {
+                    let through = strengths[[x, z]].min(strengths[[z, y]]);
+                    if through > strengths[[x, y]] {
+                        strengths[[x, y]] = through;
+                    }
+                }
+            }
+        }
+        strengths
+    }
+
+    /// Candidates whose strongest path to every other is at least as strong as the way back,
+    /// which always includes someone and is the Condorcet winner alone if there is one
+    pub fn schulze(&self) -> Vec<usize> {
+        let strengths = self.schulze_strengths();
+        let n = self.candidates.len();
+        (0..n)
+            .filter(|x| (0..n).all(|y| strengths[[*x, y]] >= strengths[[y, *x]]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tennessee() {
+        // Voters in each city rank the candidate capitals by distance
+        let election: Election = "
+            42: Memphis > Nashville > Chattanooga > Knoxville
+            26: Nashville > Chattanooga > Knoxville > Memphis
+            15: Chattanooga > Knoxville > Nashville > Memphis
+            17: Knoxville > Chattanooga > Nashville > Memphis"
+            .parse()
+            .unwrap();
+        let (memphis, nashville, knoxville) = (0, 1, 3);
+        assert_eq!(election.pairwise()[[nashville, memphis]], 58);
+        assert_eq!(election.plurality().winners, vec![memphis]);
+        assert_eq!(election.borda().scores, vec![126, 194, 173, 107]);
+        assert_eq!(election.borda().winners, vec![nashville]);
+
+        let runoff = election.instant_runoff();
+        assert_eq!(runoff.eliminated, vec![2, nashville]);
+        assert_eq!(runoff.rounds[1], vec![42, 26, 0, 32]);
+        assert_eq!(runoff.winner, knoxville);
+
+        assert_eq!(election.condorcet_winner(), Some(nashville));
+        assert_eq!(election.majority_cycle(), None);
+        assert_eq!(election.smith_set(), vec![nashville]);
+        assert_eq!(election.schulze(), vec![nashville]);
+    }
+
+    #[test]
+    fn test_condorcet_cycle() {
+        let election: Election = "A > B > C\nB > C > A\nC > A > B".parse().unwrap();
+        assert_eq!(election.condorcet_winner(), None);
+        assert_eq!(election.majority_cycle(), Some(vec![0, 1, 2]));
+        assert_eq!(election.smith_set(), vec![0, 1, 2]);
+        assert_eq!(election.schulze(), vec![0, 1, 2]);
+
+        // Partial ballots prefer ranked candidates to the rest
+        let election: Election = "2: A\n B > C".parse().unwrap();
+        assert_eq!(election.pairwise(), array![[0, 2, 2], [1, 0, 1], [1, 0, 0]]);
+        assert!("A > B > A".parse::<Election>().is_err());
+        assert!("x: A > B".parse::<Election>().is_err());
+        assert!(" \n".parse::<Election>().is_err());
+    }
+
+    #[test]
+    fn test_schulze() {
+        // Schulze's own example, with a cycle among every candidate and E the winner
+        let election: Election = "
+            5: A > C > B > E > D
+            5: A > D > E > C > B
+            8: B > E > D > A > C
+            3: C > A > B > E > D
+            7: C > A > E > B > D
+            2: C > B > A > D > E
+            7: D > C > E > B > A
+            8: E > B > A > D > C"
+            .parse()
+            .unwrap();
+        assert_eq!(election.condorcet_winner(), None);
+        assert!(election.majority_cycle().is_some());
+        assert_eq!(election.smith_set().len(), 5);
+        let e = 3;
+        assert_eq!(election.candidates[e], "E");
+        assert_eq!(election.schulze(), vec![e]);
+        assert_eq!(election.schulze_strengths()[[e, 0]], 25);
+    }
+}
@@ -0,0 +1,199 @@
+//! JavaScript bindings for running the ICM calculator, the bundled push/fold chart and the
+//! tic-tac-toe engine client-side in a browser, built with the `wasm` feature. wasm32 has no
+//! threads or file system, so build without the default features:
+//!
+//! ```text
+//! wasm-pack build --target web --no-default-features --features wasm
+//! ```
+//!
+//! Invalid arguments throw a JavaScript `Error` rather than panicking, which would abort the
+//! module.
+
+use crate::charts::PushFoldChart;
+use crate::icm;
+use crate::tictactoe::{Game, GameState, MoveList, SearchConfig, SolutionTable, Tile};
+
+use wasm_bindgen::prelude::*;
+
+/// Checks the arguments of the ICM functions; see [`icm::check_tournament`]
+fn check_tournament(stacks: &[f64], payouts: &[f64]) -> Result<(), JsError> {
+    icm::check_tournament(stacks, payouts).map_err(|x| JsError::new(&x))
+}
+
+/// ICM equity of each player; see [`icm::icm`]
+#[wasm_bindgen]
+pub fn icm(stacks: &[f64], payouts: &[f64]) -> Result<Vec<f64>, JsError> {
+    check_tournament(stacks, payouts)?;
+    Ok(icm::icm(stacks, payouts))
+}
+
+/// Bubble factor of `player` going all in against `opponent`; see [`icm::bubble_factor`]
+#[wasm_bindgen(js_name = bubbleFactor)]
+pub fn bubble_factor(
+    stacks: &[f64],
+    payouts: &[f64],
+    player: usize,
+    opponent: usize,
+) -> Result<f64, JsError> {
+    check_tournament(stacks, payouts)?;
+    if player >= stacks.len() || opponent >= stacks.len() || player == opponent {
+        return Err(JsError::new("Expected two different players"));
+    }
+    Ok(icm::bubble_factor(stacks, payouts, player, opponent))
+}
+
+/// A push/fold chart; see [`PushFoldChart`]
+#[wasm_bindgen(js_name = PushFoldChart)]
+pub struct JsPushFoldChart(PushFoldChart);
+
+#[wasm_bindgen(js_class = PushFoldChart)]
+impl JsPushFoldChart {
+    /// Parses a chart in the CSV format of `push-fold --sweep`, or uses the bundled chart when
+    /// none is given
+    #[wasm_bindgen(constructor)]
+    pub fn new(csv: Option<String>) -> Result<JsPushFoldChart, JsError> {
+        match csv {
+            Some(csv) => Ok(JsPushFoldChart(
+                csv.parse().map_err(|x: String| JsError::new(&x))?,
+            )),
+            None => Ok(JsPushFoldChart(PushFoldChart::bundled())),
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn stacks(&self) -> Vec<f64> {
+        self.0.stacks.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hands(&self) -> Vec<String> {
+        self.0.hands.clone()
+    }
+
+    /// Frequency of `hand` at `stack` big blinds, or undefined for hands not in the chart
+    pub fn frequency(&self, hand: &str, stack: f64) -> Option<f64> {
+        self.0.frequency(hand, stack)
+    }
+
+    /// Frequency of every hand at `stack` big blinds, in the order of `hands`
+    #[wasm_bindgen(js_name = atStack)]
+    pub fn at_stack(&self, stack: f64) -> Vec<f64> {
+        self.0.at_stack(stack)
+    }
+
+    /// Hands played at least `threshold` of the time at `stack` big blinds
+    pub fn range(&self, stack: f64, threshold: f64) -> Vec<String> {
+        self.0
+            .range(stack, threshold)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+}
+
+/// A game of tic-tac-toe with a minimax engine whose table is kept between moves
+#[wasm_bindgen]
+pub struct TicTacToe {
+    game: Game,
+    table: SolutionTable,
+}
+
+impl Default for TicTacToe {
+    fn default() -> Self {
+        TicTacToe {
+            game: Game::default(),
+            table: SolutionTable::new(SearchConfig::pruned()),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl TicTacToe {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> TicTacToe {
+        TicTacToe::default()
+    }
+
+    /// Game reached by a move list such as `X4 O0 X8`
+    pub fn replay(moves: &str) -> Result<TicTacToe, JsError> {
+        let moves: MoveList = moves.parse()?;
+        let mut result = TicTacToe::default();
+        for index in moves.0 {
+            result.game.play(index)?;
+        }
+        Ok(result)
+    }
+
+    /// Plays tile `index` for the player whose turn it is
+    pub fn play(&mut self, index: usize) -> Result<(), JsError> {
+        Ok(self.game.play(index)?)
+    }
+
+    pub fn undo(&mut self) -> Option<usize> {
+        self.game.undo()
+    }
+
+    pub fn redo(&mut self) -> Option<usize> {
+        self.game.redo()
+    }
+
+    /// Minimax move of the player whose turn it is, or undefined once the game is over
+    #[wasm_bindgen(js_name = bestMove)]
+    pub fn best_move(&mut self) -> Option<usize> {
+        match self.game.board().state() {
+            GameState::InProgress => Some(self.table.solve(self.game.board())),
+            _ => None,
+        }
+    }
+
+    /// Minimax value of the position; see [`SolutionTable::value`]
+    pub fn value(&mut self) -> i8 {
+        self.table.value(self.game.board())
+    }
+
+    /// The board in index notation, e.g. `X1O3X5678`
+    #[wasm_bindgen(getter)]
+    pub fn board(&self) -> String {
+        self.game.board().to_index_notation()
+    }
+
+    /// Moves played so far, e.g. `X4 O0 X8`
+    #[wasm_bindgen(getter)]
+    pub fn history(&self) -> String {
+        self.game.history().to_string()
+    }
+
+    /// `X` or `O`
+    #[wasm_bindgen(getter)]
+    pub fn turn(&self) -> String {
+        tile_name(self.game.board().turn())
+    }
+
+    /// `in progress`, `draw`, or the winner, `X` or `O`
+    #[wasm_bindgen(getter)]
+    pub fn state(&self) -> String {
+        match self.game.board().state() {
+            GameState::InProgress => "in progress".to_string(),
+            GameState::Draw => "draw".to_string(),
+            GameState::Won(tile, _) => tile_name(tile),
+        }
+    }
+
+    /// Tiles of the winning line, empty unless the game is won
+    #[wasm_bindgen(getter, js_name = winningLine)]
+    pub fn winning_line(&self) -> Vec<usize> {
+        self.game
+            .board()
+            .winning_line()
+            .map_or_else(Vec::new, Vec::from)
+    }
+}
+
+fn tile_name(tile: Tile) -> String {
+    match tile {
+        Tile::X => "X",
+        Tile::O => "O",
+        Tile::Empty => "",
+    }
+    .to_string()
+}